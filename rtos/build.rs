@@ -7,14 +7,57 @@
 //! Cargo re-run the build script whenever `memory.x` is changed,
 //! updating `memory.x` ensures a rebuild of the application with the
 //! new memory settings.
+//!
+//! It then compiles the C, C++ and assembly tasks into the `ctasks`
+//! library, as described by `[package.metadata.tasks]` in `Cargo.toml`.
+//! Every C and C++ file is compiled with the `Os.h` generated by
+//! cbindgen included first, so a declaration that does not match the
+//! kernel fails the build.
 
 use std::{
     env,
     fs::{self, File},
     io::Write,
-    path::PathBuf,
+    path::{Path, PathBuf},
 };
 
+/// Language of a source file, by its extension.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Language {
+    C,
+    Cxx,
+    Asm,
+}
+
+impl Language {
+    fn of(path: &Path) -> Option<Language> {
+        match path.extension()?.to_str()? {
+            "c" => Some(Language::C),
+            "cpp" | "cc" | "cxx" => Some(Language::Cxx),
+            "S" | "s" => Some(Language::Asm),
+            _ => None,
+        }
+    }
+}
+
+/// Extra flags of the sources under `path`, a file or a directory.
+struct Override {
+    path: PathBuf,
+    flags: Vec<String>,
+}
+
+/// `[package.metadata.tasks]`, paths relative to the crate root.
+struct TaskBuild {
+    sources: Vec<PathBuf>,
+    include: Vec<PathBuf>,
+    defines: Vec<String>,
+    flags: Vec<String>,
+    cflags: Vec<String>,
+    cxxflags: Vec<String>,
+    asflags: Vec<String>,
+    overrides: Vec<Override>,
+}
+
 fn main() {
     // Put the linker script somewhere the linker can find it
     let out = PathBuf::from(std::env::var_os("OUT_DIR").unwrap());
@@ -28,66 +71,216 @@ fn main() {
     println!("cargo:rerun-if-changed=memory.x");
 
     println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=Cargo.toml");
 
-    // Compiling C tasks
-    // TODO: Remove the hardcoded path to the tasks/do it in a better way
-    let tasks_dir = PathBuf::from(std::env::var_os("CARGO_MANIFEST_DIR").unwrap())
-        .parent()
-        .unwrap()
-        .join("./tasks");
+    // Compiling the tasks
+    let crate_dir = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap());
+    let config = TaskBuild::load(&crate_dir);
+    // Exported by the build script of rtos_core, which runs first
+    let header = PathBuf::from(env::var_os("DEP_RTOS_CORE_INCLUDE").unwrap()).join("Os.h");
+    println!("cargo:rerun-if-changed={}", header.display());
 
-    println!("{tasks_dir:?}");
+    let mut files = Vec::new();
+    for dir in &config.sources {
+        println!("cargo:rerun-if-changed={}", dir.display());
+        visit_dir(dir, &mut files);
+    }
+    files.sort_by(|(a, _), (b, _)| a.cmp(b));
 
-    let mut c_files: Vec<PathBuf> = Vec::new();
-    visit_dir(&tasks_dir, &mut c_files);
+    // Files of one language with the same extra flags build together
+    let mut groups: Vec<(Language, &[String], Vec<PathBuf>)> = Vec::new();
+    for (file, language) in files {
+        let flags = config.overrides_of(&file);
+        match groups
+            .iter_mut()
+            .find(|(other, other_flags, _)| *other == language && *other_flags == flags)
+        {
+            Some((_, _, group)) => group.push(file),
+            None => groups.push((language, flags, vec![file])),
+        }
+    }
 
-    // Tell Cargo when to rerun the build script
-    println!("cargo:rerun-if-changed=build.rs");
-    println!("cargo:rerun-if-changed=tasks");
-    for f in c_files.iter() {
-        println!("cargo:rerun-if-changed={}", f.display());
+    let mut objects = Vec::new();
+    for (language, flags, files) in &groups {
+        let mut build = config.build(*language, &header);
+        build.files(files);
+        for flag in *flags {
+            build.flag(flag);
+        }
+        objects.extend(build.compile_intermediates());
+    }
+    if !objects.is_empty() {
+        // Tasks registered through `.os_tasks` are referenced by no symbol,
+        // so every object is linked rather than those the program calls
+        cc::Build::new()
+            .objects(objects)
+            .link_lib_modifier("+whole-archive")
+            .compile("ctasks");
+    }
+}
+
+impl TaskBuild {
+    fn load(crate_dir: &Path) -> TaskBuild {
+        let manifest = fs::read_to_string(crate_dir.join("Cargo.toml")).unwrap();
+        let manifest: toml::Table = manifest.parse().unwrap();
+        let empty = toml::Table::new();
+        let tasks = manifest
+            .get("package")
+            .and_then(|package| package.get("metadata"))
+            .and_then(|metadata| metadata.get("tasks"))
+            .map(|tasks| {
+                tasks
+                    .as_table()
+                    .expect("[package.metadata.tasks] is a table")
+            })
+            .unwrap_or(&empty);
+        let strings = |table: &toml::Table, key: &str| -> Vec<String> {
+            table.get(key).map_or(Vec::new(), |value| {
+                value
+                    .as_array()
+                    .and_then(|array| array.iter().map(|s| s.as_str().map(String::from)).collect())
+                    .unwrap_or_else(|| panic!("tasks `{key}` is a list of strings"))
+            })
+        };
+        let paths = |table: &toml::Table, key: &str| -> Vec<PathBuf> {
+            strings(table, key)
+                .iter()
+                .map(|path| crate_dir.join(path))
+                .collect()
+        };
+        let overrides = match tasks.get("overrides") {
+            Some(overrides) => overrides
+                .as_array()
+                .expect("tasks `overrides` is an array of tables")
+                .iter()
+                .map(|item| {
+                    let item = item
+                        .as_table()
+                        .expect("tasks `overrides` is an array of tables");
+                    let path = item
+                        .get("path")
+                        .and_then(|path| path.as_str())
+                        .expect("an override has a `path`");
+                    Override {
+                        path: crate_dir.join(path),
+                        flags: strings(item, "flags"),
+                    }
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        TaskBuild {
+            sources: paths(tasks, "sources"),
+            include: paths(tasks, "include"),
+            defines: strings(tasks, "defines"),
+            flags: strings(tasks, "flags"),
+            cflags: strings(tasks, "cflags"),
+            cxxflags: strings(tasks, "cxxflags"),
+            asflags: strings(tasks, "asflags"),
+            overrides,
+        }
     }
 
-    let compiler = env::var("CC_arm")
-        .or_else(|_| env::var("CC"))
-        .unwrap_or_else(|_| "arm-none-eabi-gcc".to_string());
+    /// Flags of the last override covering `file`, if any.
+    fn overrides_of(&self, file: &Path) -> &[String] {
+        self.overrides
+            .iter()
+            .rev()
+            .find(|over| file.starts_with(&over.path))
+            .map_or(&[], |over| &over.flags)
+    }
 
-    let mut build = cc::Build::new();
+    fn build(&self, language: Language, header: &Path) -> cc::Build {
+        let compiler = env::var("CC_arm")
+            .or_else(|_| env::var("CC"))
+            .unwrap_or_else(|_| "arm-none-eabi-gcc".to_string());
 
-    build.compiler(compiler);
-    build.files(c_files);
+        let mut build = cc::Build::new();
 
-    build
-        .flag_if_supported("-ffreestanding")
-        .flag_if_supported("-fno-builtin")
-        .flag_if_supported("-fno-exceptions")
-        .flag_if_supported("-fno-unwind-tables")
-        .flag_if_supported("-fno-asynchronous-unwind-tables")
-        .flag_if_supported("-fdata-sections")
-        .flag_if_supported("-ffunction-sections")
-        .flag_if_supported("-fno-stack-protector")
-        .warnings(true);
+        if language == Language::Cxx {
+            let compiler = env::var("CXX_arm")
+                .or_else(|_| env::var("CXX"))
+                .unwrap_or_else(|_| "arm-none-eabi-g++".to_string());
+            build.cpp(true).cpp_link_stdlib(None).compiler(compiler);
+        } else {
+            // The C compiler preprocesses and assembles the `.S` files
+            build.compiler(compiler);
+        }
+        // The headers of the mini C library shadow those of the toolchain
+        for dir in &self.include {
+            build.include(dir);
+        }
+        for define in &self.defines {
+            match define.split_once('=') {
+                Some((name, value)) => build.define(name, value),
+                None => build.define(define, None),
+            };
+        }
+        // The cargo features of the application, `shell` as FEATURE_SHELL
+        for (var, _) in env::vars() {
+            if let Some(feature) = var.strip_prefix("CARGO_FEATURE_") {
+                build.define(&format!("FEATURE_{feature}"), None);
+            }
+        }
 
-    build
-        .flag_if_supported("-mcpu=cortex-m33")
-        .flag_if_supported("-mthumb")
-        .flag_if_supported("-mfpu=fpv5-sp-d16")
-        .flag_if_supported("-mfloat-abi=hard");
+        build
+            .flag_if_supported("-ffreestanding")
+            .flag_if_supported("-fno-builtin")
+            .flag_if_supported("-fno-exceptions")
+            .flag_if_supported("-fno-unwind-tables")
+            .flag_if_supported("-fno-asynchronous-unwind-tables")
+            .flag_if_supported("-fdata-sections")
+            .flag_if_supported("-ffunction-sections")
+            .flag_if_supported("-fno-stack-protector")
+            .warnings(true);
 
-    build.compile("ctasks");
+        build
+            .flag_if_supported("-mcpu=cortex-m33")
+            .flag_if_supported("-mthumb")
+            .flag_if_supported("-mfpu=fpv5-sp-d16")
+            .flag_if_supported("-mfloat-abi=hard");
 
-    println!("cargo:rustc-link-lib=static=ctasks");
+        if language != Language::Asm {
+            build.flag("-include").flag(header.to_str().unwrap());
+        }
+        match language {
+            Language::C => {
+                build
+                    .flag_if_supported("-Werror=implicit-function-declaration")
+                    .flag_if_supported("-Werror=incompatible-pointer-types")
+                    .flag_if_supported("-Werror=int-conversion");
+            }
+            // No run time for RTTI, exceptions, guarded statics or
+            // destructors at exit; constructors run from `.init_array`
+            Language::Cxx => {
+                build
+                    .flag_if_supported("-fno-rtti")
+                    .flag_if_supported("-fno-threadsafe-statics")
+                    .flag_if_supported("-fno-use-cxa-atexit");
+            }
+            Language::Asm => (),
+        }
+
+        let language_flags = match language {
+            Language::C => &self.cflags,
+            Language::Cxx => &self.cxxflags,
+            Language::Asm => &self.asflags,
+        };
+        for flag in self.flags.iter().chain(language_flags) {
+            build.flag(flag);
+        }
+        build
+    }
 }
 
-fn visit_dir(dir: &PathBuf, c_out: &mut Vec<PathBuf>) {
+/// Collects the sources under `dir` and their language, recursively.
+fn visit_dir(dir: &Path, out: &mut Vec<(PathBuf, Language)>) {
     for entry in fs::read_dir(dir).unwrap() {
-        let entry = entry.unwrap();
-        let path = entry.path();
-        if let Some(ext) = path.extension().and_then(|s| s.to_str()) {
-            match ext {
-                "c" => c_out.push(path),
-                _ => {}
-            }
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            visit_dir(&path, out);
+        } else if let Some(language) = Language::of(&path) {
+            out.push((path, language));
         }
     }
 }
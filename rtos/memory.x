@@ -58,6 +58,36 @@ SECTIONS {
     } > FLASH
 } INSERT AFTER .text;
 
+SECTIONS {
+    /* ### C++ static constructors
+     *
+     * Pointers to the constructors of the static objects of the C++ tasks,
+     * called by `cxx::run_static_constructors` before the OS starts.
+     */
+    .init_array : ALIGN(4)
+    {
+        __init_array_start = .;
+        KEEP(*(SORT(.init_array.*)));
+        KEEP(*(.init_array));
+        __init_array_end = .;
+    } > FLASH
+} INSERT AFTER .rodata;
+
+SECTIONS {
+    /* ### Registered tasks
+     *
+     * Descriptors of the tasks defined with `TASK(name, priority, stack)` or
+     * `#[task(..)]`, each in `.os_tasks.<name>`, sorted by name for
+     * `StartOS`, which gives them their IDs in this order.
+     */
+    .os_tasks : ALIGN(4)
+    {
+        __os_tasks_start = .;
+        KEEP(*(SORT_BY_NAME(.os_tasks.*)));
+        __os_tasks_end = .;
+    } > FLASH
+} INSERT AFTER .rodata;
+
 SECTIONS {
     /* ### Boot ROM extra info
      *
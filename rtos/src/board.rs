@@ -1,18 +1,113 @@
 use core::cell::RefCell;
 
 use critical_section::Mutex;
-use defmt::{debug, info};
+use defmt::info;
+#[cfg(feature = "low-power")]
+use rp235x_hal::powman::{AotClockSource, FractionalFrequency, Powman};
 use rp235x_hal::{
     self as hal,
     fugit::MicrosDurationU32,
     pac::interrupt,
     timer::{Alarm, Alarm0, CopyableTimer0},
 };
-use rtos_core::alarms::*;
+#[cfg(any(feature = "shell-uart", feature = "stdout-uart"))]
+use rp235x_hal::{
+    clocks::Clock,
+    fugit::RateExtU32,
+    gpio::{
+        FunctionUart, Pin, PullDown,
+        bank0::{Gpio0, Gpio1},
+    },
+    uart::{DataBits, Enabled, StopBits, UartConfig, UartPeripheral},
+};
+#[cfg(feature = "low-power")]
+use rtos_core::{
+    alarms::TickType,
+    power::{PowerDriver, PowerState},
+};
+#[cfg(feature = "watchdog")]
+use rtos_core::{
+    task::TaskType,
+    watchdog::{SupervisedEntityType, WatchdogDriver},
+};
+#[cfg(feature = "stdout-usb")]
+use {
+    rp235x_hal::usb::UsbBus,
+    usb_device::{class_prelude::UsbBusAllocator, prelude::*},
+    usbd_serial::SerialPort,
+};
+
+#[cfg(any(
+    all(feature = "stdout-rtt", feature = "stdout-uart"),
+    all(feature = "stdout-rtt", feature = "stdout-usb"),
+    all(feature = "stdout-uart", feature = "stdout-usb"),
+))]
+compile_error!("select at most one of the stdout-rtt, stdout-uart and stdout-usb features");
 
 const XTAL_FREQ_HZ: u32 = 12_000_000u32;
+/// Kernel tick period.
+pub const TICK: MicrosDurationU32 = MicrosDurationU32::secs(1);
 static ALARMS: Mutex<RefCell<Option<Alarm0<CopyableTimer0>>>> = Mutex::new(RefCell::new(None));
 
+/// UART0 on GPIO0 (TX) and GPIO1 (RX), shared by the shell and stdout.
+#[cfg(any(feature = "shell-uart", feature = "stdout-uart"))]
+type Uart0 = UartPeripheral<
+    Enabled,
+    hal::pac::UART0,
+    (
+        Pin<Gpio0, FunctionUart, PullDown>,
+        Pin<Gpio1, FunctionUart, PullDown>,
+    ),
+>;
+#[cfg(any(feature = "shell-uart", feature = "stdout-uart"))]
+static UART: Mutex<RefCell<Option<Uart0>>> = Mutex::new(RefCell::new(None));
+
+/// The hardware watchdog, fed by the watchdog manager of the OS.
+#[cfg(feature = "watchdog")]
+static WATCHDOG: Mutex<RefCell<Option<hal::Watchdog>>> = Mutex::new(RefCell::new(None));
+/// Reset delay of the hardware watchdog, close to its maximum. The first
+/// kernel tick comes 5 s after the start.
+#[cfg(feature = "watchdog")]
+const WATCHDOG_TIMEOUT: MicrosDurationU32 = MicrosDurationU32::secs(8);
+/// Supervision cycle of the watchdog manager, in kernel ticks of 1 s.
+#[cfg(feature = "watchdog")]
+const SUPERVISION_CYCLE: rtos_core::alarms::TickType = 2;
+/// Upper bits of watchdog scratch register 0 when it names the supervised
+/// entity that failed, in its lower 16 bits. Scratch register 1 holds the
+/// task that registered the entity.
+#[cfg(feature = "watchdog")]
+pub const WATCHDOG_CULPRIT: u32 = 0x5744_0000;
+
+/// Kernel tick period, in milliseconds of the always-on timer.
+#[cfg(feature = "low-power")]
+const TICK_MS: u64 = TICK.to_millis() as u64;
+/// The always-on timer, which wakes the idle loop up from its low-power
+/// states, and its time at the next kernel tick.
+#[cfg(feature = "low-power")]
+static POWMAN: Mutex<RefCell<Option<(Powman, u64)>>> = Mutex::new(RefCell::new(None));
+/// WFI with the kernel tick stopped, and deep sleep with all the clocks
+/// gated but those of POWMAN. The latencies are in kernel ticks.
+#[cfg(feature = "low-power")]
+static POWER_STATES: [PowerState; 2] = [
+    PowerState {
+        name: "Tickless",
+        latency: 1,
+    },
+    PowerState {
+        name: "Deep sleep",
+        latency: 2,
+    },
+];
+#[cfg(feature = "low-power")]
+const DEEP_SLEEP: usize = 1;
+/// CLOCKS `SLEEP_EN0` bits of the clocks POWMAN needs in deep sleep.
+#[cfg(feature = "low-power")]
+const SLEEP_EN0_POWMAN: u32 = 1 << 23 | 1 << 24;
+
+#[cfg(feature = "stdout-usb")]
+static USB: Mutex<RefCell<Option<(UsbDevice<'static, UsbBus>, SerialPort<'static, UsbBus>)>>> =
+    Mutex::new(RefCell::new(None));
+
 pub fn init() {
     let mut pac = hal::pac::Peripherals::take().unwrap();
 
@@ -30,15 +125,287 @@ pub fn init() {
     )
     .unwrap();
 
+    #[cfg(feature = "watchdog")]
+    {
+        watchdog.pause_on_debug(true);
+        watchdog.start(WATCHDOG_TIMEOUT);
+        critical_section::with(|cs| WATCHDOG.borrow(cs).replace(Some(watchdog)));
+        rtos_core::watchdog::init(WatchdogDriver {
+            cycle: SUPERVISION_CYCLE,
+            feed: feed_watchdog,
+            failed: watchdog_failed,
+        });
+    }
+
+    #[cfg(feature = "low-power")]
+    {
+        let mut powman = Powman::new(pac.POWMAN, None);
+        // The crystal keeps running in both states
+        powman
+            .aot_set_clock(AotClockSource::Xosc(FractionalFrequency::from_hz(
+                XTAL_FREQ_HZ,
+            )))
+            .unwrap();
+        powman.aot_start();
+        critical_section::with(|cs| POWMAN.borrow(cs).replace(Some((powman, 0))));
+        rtos_core::power::init(PowerDriver {
+            states: &POWER_STATES,
+            sleep,
+        });
+        unsafe {
+            cortex_m::peripheral::NVIC::unmask(hal::pac::Interrupt::POWMAN_IRQ_TIMER);
+        }
+    }
+
     let mut timer = hal::Timer::new_timer0(pac.TIMER0, &mut pac.RESETS, &clocks);
     critical_section::with(|cs| {
         let alarm = timer.alarm_0().unwrap();
         ALARMS.borrow(cs).replace(Some(alarm));
     });
+
+    #[cfg(any(feature = "shell-uart", feature = "stdout-uart"))]
+    {
+        let sio = hal::Sio::new(pac.SIO);
+        let pins = hal::gpio::Pins::new(
+            pac.IO_BANK0,
+            pac.PADS_BANK0,
+            sio.gpio_bank0,
+            &mut pac.RESETS,
+        );
+        #[allow(unused_mut)]
+        let mut uart = UartPeripheral::new(
+            pac.UART0,
+            (pins.gpio0.into_function(), pins.gpio1.into_function()),
+            &mut pac.RESETS,
+        )
+        .enable(
+            UartConfig::new(115200.Hz(), DataBits::Eight, None, StopBits::One),
+            clocks.peripheral_clock.freq(),
+        )
+        .unwrap();
+        #[cfg(feature = "shell-uart")]
+        {
+            uart.enable_rx_interrupt();
+            rtos_core::shell::set_output(shell_write);
+        }
+        critical_section::with(|cs| UART.borrow(cs).replace(Some(uart)));
+        unsafe {
+            cortex_m::peripheral::NVIC::unmask(hal::pac::Interrupt::UART0_IRQ);
+        }
+    }
+
+    // Last, it takes the USB clock
+    #[cfg(feature = "stdout-usb")]
+    {
+        let bus = UsbBusAllocator::new(UsbBus::new(
+            pac.USB,
+            pac.USB_DPRAM,
+            clocks.usb_clock,
+            true,
+            &mut pac.RESETS,
+        ));
+        // The device and the class borrow the bus for good
+        let bus = cortex_m::singleton!(: UsbBusAllocator<UsbBus> = bus).unwrap();
+        let serial = SerialPort::new(bus);
+        let device = UsbDeviceBuilder::new(bus, UsbVidPid(0x16c0, 0x27dd))
+            .strings(&[StringDescriptors::default()
+                .manufacturer("FV-RTOS")
+                .product("Console")
+                .serial_number("0")])
+            .unwrap()
+            .max_packet_size_0(64)
+            .unwrap()
+            .device_class(usbd_serial::USB_CLASS_CDC)
+            .build();
+        critical_section::with(|cs| USB.borrow(cs).replace(Some((device, serial))));
+        unsafe {
+            cortex_m::peripheral::NVIC::unmask(hal::pac::Interrupt::USBCTRL_IRQ);
+        }
+    }
+
+    #[cfg(feature = "stdout-rtt")]
+    rtos_core::stdout::set_backend(rtos_core::stdout::Backend::Rtt);
+    #[cfg(feature = "stdout-uart")]
+    rtos_core::stdout::set_backend(rtos_core::stdout::Backend::Driver(uart_stdout));
+    #[cfg(feature = "stdout-usb")]
+    rtos_core::stdout::set_backend(rtos_core::stdout::Backend::Driver(usb_stdout));
+}
+
+#[cfg(feature = "watchdog")]
+fn feed_watchdog() {
+    critical_section::with(|cs| {
+        if let Some(watchdog) = WATCHDOG.borrow_ref(cs).as_ref() {
+            watchdog.feed();
+        }
+    });
+}
+
+/// Leaves the culprit in the scratch registers, which the watchdog reset
+/// keeps, and lets the watchdog expire.
+#[cfg(feature = "watchdog")]
+fn watchdog_failed(entity: SupervisedEntityType, owner: TaskType) {
+    defmt::error!("Supervised entity {} of task {} failed", entity, owner);
+    critical_section::with(|cs| {
+        if let Some(watchdog) = WATCHDOG.borrow_ref_mut(cs).as_mut() {
+            watchdog.write_scratch(
+                hal::watchdog::ScratchRegister::Scratch0,
+                WATCHDOG_CULPRIT | (entity as u32 & 0xFFFF),
+            );
+            watchdog.write_scratch(hal::watchdog::ScratchRegister::Scratch1, owner as u32);
+        }
+    });
+}
+
+/// Stops the kernel tick and sleeps until the always-on timer reaches
+/// `ticks` ticks after the last one, then restarts the tick in phase with
+/// the ticks slept.
+#[cfg(feature = "low-power")]
+fn sleep(state: usize, ticks: TickType) -> TickType {
+    // A tick already due is counted by its interrupt
+    if cortex_m::peripheral::NVIC::is_pending(hal::pac::Interrupt::TIMER0_IRQ_0) {
+        return 0;
+    }
+    critical_section::with(|cs| {
+        let mut powman = POWMAN.borrow_ref_mut(cs);
+        let mut alarm = ALARMS.borrow_ref_mut(cs);
+        let (Some((powman, next_tick)), Some(alarm)) = (powman.as_mut(), alarm.as_mut()) else {
+            return 0;
+        };
+        let _ = alarm.cancel();
+        alarm.disable_interrupt();
+        powman.aot_set_alarm(*next_tick + (ticks as u64 - 1) * TICK_MS);
+        powman.aot_alarm_interrupt_enable();
+
+        let mut core = unsafe { cortex_m::Peripherals::steal() };
+        let clocks = unsafe { hal::pac::CLOCKS::steal() };
+        if state == DEEP_SLEEP {
+            clocks
+                .sleep_en0()
+                .write(|w| unsafe { w.bits(SLEEP_EN0_POWMAN) });
+            clocks.sleep_en1().write(|w| unsafe { w.bits(0) });
+            core.SCB.set_sleepdeep();
+        }
+        // Woken up by the alarm or by any other interrupt, still masked
+        cortex_m::asm::wfi();
+        if state == DEEP_SLEEP {
+            core.SCB.clear_sleepdeep();
+            clocks.sleep_en0().write(|w| unsafe { w.bits(u32::MAX) });
+            clocks.sleep_en1().write(|w| unsafe { w.bits(u32::MAX) });
+        }
+        powman.aot_alarm_clear();
+
+        let now = powman.aot_get_time();
+        let elapsed = if now >= *next_tick {
+            (now - *next_tick) / TICK_MS + 1
+        } else {
+            0
+        };
+        *next_tick += elapsed * TICK_MS;
+        alarm.clear_interrupt();
+        let _ = alarm.schedule(MicrosDurationU32::millis((*next_tick - now) as u32));
+        alarm.enable_interrupt();
+        elapsed as TickType
+    })
+}
+
+/// The always-on timer alarm only wakes the idle loop up, which clears it.
+#[cfg(feature = "low-power")]
+#[interrupt]
+fn POWMAN_IRQ_TIMER() {
+    Powman::static_aot_alarm_interrupt_disable();
+}
+
+/// Shell output on UART0. Only the bytes that fit in the FIFO are written
+/// with interrupts masked, the shell task waits for room in between.
+#[cfg(feature = "shell-uart")]
+fn shell_write(mut bytes: &[u8]) {
+    while !bytes.is_empty() {
+        bytes = critical_section::with(|cs| match UART.borrow_ref(cs).as_ref() {
+            Some(uart) => uart.write_raw(bytes).unwrap_or(bytes),
+            None => &[],
+        });
+    }
+}
+
+/// Standard output on UART0: fills the FIFO, and asks for the transmit
+/// interrupt to send the rest once there is room.
+#[cfg(feature = "stdout-uart")]
+fn uart_stdout(bytes: &[u8]) -> usize {
+    critical_section::with(|cs| match UART.borrow_ref_mut(cs).as_mut() {
+        Some(uart) => {
+            let rest = uart.write_raw(bytes).unwrap_or(bytes);
+            if !rest.is_empty() {
+                uart.enable_tx_interrupt();
+            }
+            bytes.len() - rest.len()
+        }
+        None => 0,
+    })
+}
+
+/// Passes the bytes received on UART0 to the shell, and the room in the
+/// transmit FIFO to the standard output.
+#[cfg(any(feature = "shell-uart", feature = "stdout-uart"))]
+#[interrupt]
+fn UART0_IRQ() {
+    rtos_core::trace::isr_enter(hal::pac::Interrupt::UART0_IRQ as u8);
+    #[cfg(feature = "shell-uart")]
+    {
+        let mut buf = [0; 32];
+        let len = critical_section::with(|cs| match UART.borrow_ref(cs).as_ref() {
+            Some(uart) => uart.read_raw(&mut buf).unwrap_or(0),
+            None => 0,
+        });
+        rtos_core::shell::feed(&buf[..len]);
+    }
+    #[cfg(feature = "stdout-uart")]
+    {
+        // Enabled again by `uart_stdout` if the FIFO fills up once more
+        critical_section::with(|cs| {
+            if let Some(uart) = UART.borrow_ref_mut(cs).as_mut() {
+                uart.disable_tx_interrupt();
+            }
+        });
+        rtos_core::stdout::flush();
+    }
+    rtos_core::trace::isr_exit(hal::pac::Interrupt::UART0_IRQ as u8);
+}
+
+/// Standard output on the USB serial port. Nothing is taken while no host
+/// has the port open.
+#[cfg(feature = "stdout-usb")]
+fn usb_stdout(bytes: &[u8]) -> usize {
+    critical_section::with(|cs| match USB.borrow_ref_mut(cs).as_mut() {
+        Some((_, serial)) => serial.write(bytes).unwrap_or(0),
+        None => 0,
+    })
+}
+
+/// Runs the USB device, then sends the standard output the host made room
+/// for.
+#[cfg(feature = "stdout-usb")]
+#[interrupt]
+fn USBCTRL_IRQ() {
+    rtos_core::trace::isr_enter(hal::pac::Interrupt::USBCTRL_IRQ as u8);
+    critical_section::with(|cs| {
+        if let Some((device, serial)) = USB.borrow_ref_mut(cs).as_mut()
+            && device.poll(&mut [serial])
+        {
+            // The console is output only, input is dropped
+            let mut buf = [0; 64];
+            let _ = serial.read(&mut buf);
+        }
+    });
+    rtos_core::stdout::flush();
+    rtos_core::trace::isr_exit(hal::pac::Interrupt::USBCTRL_IRQ as u8);
 }
 
 pub fn set_alarm(time: MicrosDurationU32) {
     critical_section::with(|cs| {
+        #[cfg(feature = "low-power")]
+        if let Some((powman, next_tick)) = POWMAN.borrow_ref_mut(cs).as_mut() {
+            *next_tick = powman.aot_get_time() + u64::from(time.to_millis());
+        }
         if let Some(alarm) = ALARMS.borrow_ref_mut(cs).as_mut() {
             alarm.schedule(time).unwrap();
             alarm.enable_interrupt();
@@ -51,48 +418,23 @@ pub fn set_alarm(time: MicrosDurationU32) {
 
 #[interrupt]
 fn TIMER0_IRQ_0() {
+    rtos_core::trace::isr_enter(hal::pac::Interrupt::TIMER0_IRQ_0 as u8);
+    #[cfg(debug_assertions)]
+    info!("Interrupt !");
     critical_section::with(|cs| {
-        #[cfg(debug_assertions)]
-        info!("Interrupt !");
         if let Some(alarm) = ALARMS.borrow_ref_mut(cs).as_mut() {
             alarm.clear_interrupt();
-
-            let mut alarms = rtos_core::alarms::SOFTW_ALARMS.borrow_ref_mut(cs);
-
-            for (i, alarm) in alarms.iter_mut().enumerate() {
-                if alarm.active {
-                    #[cfg(debug_assertions)]
-                    debug!(
-                        "Alarm {} is active, tick = {}, cycle = {}",
-                        i, alarm.tick, alarm.cycle
-                    );
-                    alarm.h_ticks += 1;
-                    if alarm.h_ticks >= alarm.alarm_base.ticksperbase {
-                        alarm.h_ticks = 0;
-                        alarm.tick += 1;
-                    }
-                    if alarm.tick >= alarm.alarm_base.maxallowedvalue {
-                        alarm.tick = alarm.cycle;
-                        if alarm.cycle == 0 {
-                            alarm.active = false;
-                        }
-                        unsafe {
-                            match i {
-                                0 => AlarmCallback0(),
-                                1 => AlarmCallback1(),
-                                2 => AlarmCallback2(),
-                                3 => AlarmCallback3(),
-                                4 => AlarmCallback4(),
-                                _ => (),
-                            }
-                        }
-                    }
-                }
-            }
-
-            //TODO : Change this hardcoded value
-            let _ = alarm.schedule(MicrosDurationU32::secs(1));
+            let _ = alarm.schedule(TICK);
             alarm.enable_interrupt();
         }
-    })
+        #[cfg(feature = "low-power")]
+        if let Some((powman, next_tick)) = POWMAN.borrow_ref_mut(cs).as_mut() {
+            *next_tick = powman.aot_get_time() + TICK_MS;
+        }
+    });
+    rtos_core::alarms::tick();
+    // Picks up the RTT terminal input
+    #[cfg(feature = "shell")]
+    rtos_core::shell::poll();
+    rtos_core::trace::isr_exit(hal::pac::Interrupt::TIMER0_IRQ_0 as u8);
 }
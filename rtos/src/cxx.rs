@@ -0,0 +1,43 @@
+//! Run time of the C++ tasks, built without RTTI, exceptions and a heap.
+//!
+//! The constructors of their static objects are listed in `.init_array`,
+//! laid out by `memory.x`. The compiler may also reference
+//! `__cxa_pure_virtual` and, for virtual destructors, `operator delete`,
+//! which must never be called here.
+
+use core::ffi::c_void;
+
+unsafe extern "C" {
+    static __init_array_start: unsafe extern "C" fn();
+    static __init_array_end: unsafe extern "C" fn();
+}
+
+/// Constructs the static objects of the C++ tasks, in link order. Called
+/// once, before the OS starts.
+pub fn run_static_constructors() {
+    let mut constructor = &raw const __init_array_start;
+    let end = &raw const __init_array_end;
+    while constructor < end {
+        unsafe {
+            (*constructor)();
+            constructor = constructor.add(1);
+        }
+    }
+}
+
+#[unsafe(no_mangle)]
+extern "C" fn __cxa_pure_virtual() -> ! {
+    defmt::panic!("C++ pure virtual function called")
+}
+
+/// `operator delete(void *)`
+#[unsafe(no_mangle)]
+extern "C" fn _ZdlPv(_: *mut c_void) {
+    defmt::panic!("C++ operator delete called, the tasks have no heap")
+}
+
+/// `operator delete(void *, size_t)`
+#[unsafe(no_mangle)]
+extern "C" fn _ZdlPvj(_: *mut c_void, _: usize) {
+    defmt::panic!("C++ operator delete called, the tasks have no heap")
+}
@@ -2,23 +2,48 @@
 #![no_main]
 
 use defmt::*;
-use defmt_rtt as _;
 use panic_probe as _;
 use rp235x_hal::{self as hal, entry, fugit::MicrosDurationU32};
 use rtos_core;
 
 mod board;
+mod cxx;
+mod os_config;
 
 /// Tell the Boot ROM about our application
 #[unsafe(link_section = ".start_block")]
 #[used]
 pub static IMAGE_DEF: hal::block::ImageDef = hal::block::ImageDef::secure_exe();
 
+/// Program information for `picotool info`; the kernel and the tasks add
+/// the OS configuration as program features
+#[unsafe(link_section = ".bi_entries")]
+#[used]
+pub static PICOTOOL_ENTRIES: [hal::binary_info::EntryAddr; 4] = [
+    hal::binary_info::rp_cargo_bin_name!(),
+    hal::binary_info::rp_cargo_version!(),
+    hal::binary_info::rp_program_description!(c"FV-RTOS application"),
+    hal::binary_info::rp_program_build_attribute!(),
+];
+
+/// Tick rate of `board::TICK`, in the binary info next to the OS
+/// configuration
+const TICK_RATE: [u8; 24] =
+    rtos_core::binary_info::format_u32("tick rate ", 1_000_000 / board::TICK.to_micros(), " Hz");
+rtos_core::os_binary_info!(rtos_core::binary_info::c_str(&TICK_RATE));
+
 #[entry]
 fn main() -> ! {
+    rtos_core::rtt::init();
     info!("Program start");
+    #[cfg(feature = "systemview")]
+    rtos_core::systemview::set_isr_names(&[(
+        hal::pac::Interrupt::TIMER0_IRQ_0 as u8,
+        "TIMER0_IRQ_0",
+    )]);
 
     board::init();
+    cxx::run_static_constructors();
     board::set_alarm(MicrosDurationU32::secs(5));
-    rtos_core::start_os();
+    rtos_core::start_os(&os_config::CONFIG);
 }
@@ -0,0 +1,37 @@
+//! Static OS configuration of the application.
+//!
+//! Written by hand until the OIL parser is ready. The tasks below take the
+//! first IDs; the C tasks in `tasks/` register themselves with `TASK` and
+//! `StartOS` gives them the IDs that follow, which C reads as `TASK_ID`.
+
+#[cfg(feature = "shell")]
+use rtos_core::task::{SchedulePolicy, Stack, TaskEntry};
+use rtos_core::{OsConfig, TaskConfig, memory::HeapMemory};
+
+#[cfg(feature = "shell")]
+static SHELL_STACK: Stack<2048> = Stack::new();
+
+static HEAP: HeapMemory<{ 8 * 1024 }> = HeapMemory::new();
+
+const NUM_TASKS: usize = cfg!(feature = "shell") as usize;
+
+static TASKS: [TaskConfig; NUM_TASKS] = [
+    // Lowest priority, activated when input arrives
+    #[cfg(feature = "shell")]
+    rtos_core::task_config!(Shell {
+        priority: 0,
+        entry: rtos_core::shell::ShellTask as TaskEntry,
+        activation: 1,
+        autostart: false,
+        extended: false,
+        schedule: SchedulePolicy::Full,
+        internal_resource: None,
+        deadline: None,
+        stack: SHELL_STACK.region(),
+    }),
+];
+
+pub static CONFIG: OsConfig = OsConfig {
+    heap: Some(HEAP.region()),
+    ..OsConfig::new(&TASKS)
+};
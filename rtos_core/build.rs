@@ -1,11 +1,18 @@
 extern crate cbindgen;
 
-use std::env;
+use std::{env, path::PathBuf};
 
 fn main() {
     let crate_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    let include = PathBuf::from(&crate_dir).join("../tasks");
 
     cbindgen::generate(crate_dir)
         .expect("Unable to generate bindings")
-        .write_to_file("../tasks/rtos_core.h");
+        .write_to_file(include.join("Os.h"));
+
+    // For the build scripts that compile tasks against the header
+    println!(
+        "cargo:include={}",
+        include.canonicalize().unwrap().display()
+    );
 }
@@ -0,0 +1,227 @@
+//! Layout of the types shared with the C tasks.
+//!
+//! The sizes, alignments and field offsets below are those of
+//! `thumbv8m.main-none-eabihf`, whose C enums take the smallest integer
+//! holding their values, as with `arm-none-eabi-gcc`. They are checked here
+//! against the Rust types, with `offset_of!` for the fields, and exported
+//! to `Os.h`, which checks them against the C types, with `offsetof`, when
+//! compiled for Arm. A type changed without its entry here, or C compiled with
+//! `-fno-short-enums`, thus fails the build instead of corrupting the
+//! arguments of the services.
+
+pub const ABI_SIZE_STATUS_TYPE: usize = 1;
+pub const ABI_SIZE_TASK_STATE_TYPE: usize = 1;
+pub const ABI_SIZE_RESET_REASON_TYPE: usize = 1;
+
+pub const ABI_SIZE_ALARM_BASE_TYPE: usize = 12;
+pub const ABI_ALIGN_ALARM_BASE_TYPE: usize = 4;
+pub const ABI_SIZE_CRASH_RECORD_TYPE: usize = 80;
+pub const ABI_ALIGN_CRASH_RECORD_TYPE: usize = 4;
+pub const ABI_SIZE_POOL_INFO_TYPE: usize = 20;
+pub const ABI_ALIGN_POOL_INFO_TYPE: usize = 4;
+pub const ABI_SIZE_RESET_INFO_TYPE: usize = 44;
+pub const ABI_ALIGN_RESET_INFO_TYPE: usize = 4;
+pub const ABI_SIZE_TASK_STATISTICS_TYPE: usize = 32;
+pub const ABI_ALIGN_TASK_STATISTICS_TYPE: usize = 8;
+pub const ABI_SIZE_ISR_STATISTICS_TYPE: usize = 24;
+pub const ABI_ALIGN_ISR_STATISTICS_TYPE: usize = 8;
+pub const ABI_SIZE_SYSTEM_STATISTICS_TYPE: usize = 16;
+pub const ABI_ALIGN_SYSTEM_STATISTICS_TYPE: usize = 8;
+pub const ABI_SIZE_TASK_DESCRIPTOR_TYPE: usize = 24;
+pub const ABI_ALIGN_TASK_DESCRIPTOR_TYPE: usize = 4;
+
+// Field offsets, in declaration order
+pub const ABI_OFFSET_ALARM_BASE_TYPE_MAXALLOWEDVALUE: usize = 0;
+pub const ABI_OFFSET_ALARM_BASE_TYPE_TICKSPERBASE: usize = 4;
+pub const ABI_OFFSET_ALARM_BASE_TYPE_MINCYCLE: usize = 8;
+
+pub const ABI_OFFSET_CRASH_RECORD_TYPE_EXCEPTION: usize = 0;
+pub const ABI_OFFSET_CRASH_RECORD_TYPE_R0: usize = 4;
+pub const ABI_OFFSET_CRASH_RECORD_TYPE_R1: usize = 8;
+pub const ABI_OFFSET_CRASH_RECORD_TYPE_R2: usize = 12;
+pub const ABI_OFFSET_CRASH_RECORD_TYPE_R3: usize = 16;
+pub const ABI_OFFSET_CRASH_RECORD_TYPE_R12: usize = 20;
+pub const ABI_OFFSET_CRASH_RECORD_TYPE_LR: usize = 24;
+pub const ABI_OFFSET_CRASH_RECORD_TYPE_PC: usize = 28;
+pub const ABI_OFFSET_CRASH_RECORD_TYPE_XPSR: usize = 32;
+pub const ABI_OFFSET_CRASH_RECORD_TYPE_SP: usize = 36;
+pub const ABI_OFFSET_CRASH_RECORD_TYPE_EXC_RETURN: usize = 40;
+pub const ABI_OFFSET_CRASH_RECORD_TYPE_CFSR: usize = 44;
+pub const ABI_OFFSET_CRASH_RECORD_TYPE_HFSR: usize = 48;
+pub const ABI_OFFSET_CRASH_RECORD_TYPE_MMFAR: usize = 52;
+pub const ABI_OFFSET_CRASH_RECORD_TYPE_BFAR: usize = 56;
+pub const ABI_OFFSET_CRASH_RECORD_TYPE_SFSR: usize = 60;
+pub const ABI_OFFSET_CRASH_RECORD_TYPE_SFAR: usize = 64;
+pub const ABI_OFFSET_CRASH_RECORD_TYPE_TASK: usize = 68;
+pub const ABI_OFFSET_CRASH_RECORD_TYPE_ISR: usize = 72;
+pub const ABI_OFFSET_CRASH_RECORD_TYPE_TICKS: usize = 76;
+
+pub const ABI_OFFSET_POOL_INFO_TYPE_BLOCK_SIZE: usize = 0;
+pub const ABI_OFFSET_POOL_INFO_TYPE_BLOCKS: usize = 4;
+pub const ABI_OFFSET_POOL_INFO_TYPE_FREE: usize = 8;
+pub const ABI_OFFSET_POOL_INFO_TYPE_MIN_FREE: usize = 12;
+pub const ABI_OFFSET_POOL_INFO_TYPE_FAILURES: usize = 16;
+
+pub const ABI_OFFSET_RESET_INFO_TYPE_REASON: usize = 0;
+pub const ABI_OFFSET_RESET_INFO_TYPE_CHIP_RESET: usize = 4;
+pub const ABI_OFFSET_RESET_INFO_TYPE_WATCHDOG_REASON: usize = 8;
+pub const ABI_OFFSET_RESET_INFO_TYPE_SCRATCH: usize = 12;
+
+pub const ABI_OFFSET_TASK_DESCRIPTOR_TYPE_ID: usize = 0;
+pub const ABI_OFFSET_TASK_DESCRIPTOR_TYPE_NAME: usize = 4;
+pub const ABI_OFFSET_TASK_DESCRIPTOR_TYPE_ENTRY: usize = 8;
+pub const ABI_OFFSET_TASK_DESCRIPTOR_TYPE_PRIORITY: usize = 12;
+pub const ABI_OFFSET_TASK_DESCRIPTOR_TYPE_ACTIVATION: usize = 13;
+pub const ABI_OFFSET_TASK_DESCRIPTOR_TYPE_FLAGS: usize = 14;
+pub const ABI_OFFSET_TASK_DESCRIPTOR_TYPE_STACK: usize = 16;
+pub const ABI_OFFSET_TASK_DESCRIPTOR_TYPE_STACK_SIZE: usize = 20;
+
+pub const ABI_OFFSET_TASK_STATISTICS_TYPE_EXECUTION_TIME: usize = 0;
+pub const ABI_OFFSET_TASK_STATISTICS_TYPE_RESPONSE_TIME_MAX: usize = 8;
+pub const ABI_OFFSET_TASK_STATISTICS_TYPE_ACTIVATIONS: usize = 16;
+pub const ABI_OFFSET_TASK_STATISTICS_TYPE_PREEMPTIONS: usize = 20;
+pub const ABI_OFFSET_TASK_STATISTICS_TYPE_OVERRUNS: usize = 24;
+pub const ABI_OFFSET_TASK_STATISTICS_TYPE_DEADLINE_MISSES: usize = 28;
+
+pub const ABI_OFFSET_ISR_STATISTICS_TYPE_EXECUTION_TIME: usize = 0;
+pub const ABI_OFFSET_ISR_STATISTICS_TYPE_DURATION_MAX: usize = 8;
+pub const ABI_OFFSET_ISR_STATISTICS_TYPE_ACTIVATIONS: usize = 16;
+
+pub const ABI_OFFSET_SYSTEM_STATISTICS_TYPE_ELAPSED_TIME: usize = 0;
+pub const ABI_OFFSET_SYSTEM_STATISTICS_TYPE_IDLE_TIME: usize = 8;
+
+#[cfg(target_os = "none")]
+const _: () = {
+    use core::mem::offset_of;
+
+    use crate::{
+        StatusType,
+        alarms::AlarmBaseType,
+        crash::CrashRecordType,
+        memory::PoolInfoType,
+        registry::TaskDescriptorType,
+        reset::{ResetInfoType, ResetReasonType},
+        task::TaskStateType,
+    };
+
+    assert!(size_of::<StatusType>() == ABI_SIZE_STATUS_TYPE);
+    assert!(size_of::<TaskStateType>() == ABI_SIZE_TASK_STATE_TYPE);
+    assert!(size_of::<ResetReasonType>() == ABI_SIZE_RESET_REASON_TYPE);
+
+    assert!(size_of::<AlarmBaseType>() == ABI_SIZE_ALARM_BASE_TYPE);
+    assert!(align_of::<AlarmBaseType>() == ABI_ALIGN_ALARM_BASE_TYPE);
+    assert!(size_of::<CrashRecordType>() == ABI_SIZE_CRASH_RECORD_TYPE);
+    assert!(align_of::<CrashRecordType>() == ABI_ALIGN_CRASH_RECORD_TYPE);
+    assert!(size_of::<PoolInfoType>() == ABI_SIZE_POOL_INFO_TYPE);
+    assert!(align_of::<PoolInfoType>() == ABI_ALIGN_POOL_INFO_TYPE);
+    assert!(size_of::<ResetInfoType>() == ABI_SIZE_RESET_INFO_TYPE);
+    assert!(align_of::<ResetInfoType>() == ABI_ALIGN_RESET_INFO_TYPE);
+    assert!(size_of::<TaskDescriptorType>() == ABI_SIZE_TASK_DESCRIPTOR_TYPE);
+    assert!(align_of::<TaskDescriptorType>() == ABI_ALIGN_TASK_DESCRIPTOR_TYPE);
+
+    assert!(
+        offset_of!(AlarmBaseType, maxallowedvalue) == ABI_OFFSET_ALARM_BASE_TYPE_MAXALLOWEDVALUE
+    );
+    assert!(offset_of!(AlarmBaseType, ticksperbase) == ABI_OFFSET_ALARM_BASE_TYPE_TICKSPERBASE);
+    assert!(offset_of!(AlarmBaseType, mincycle) == ABI_OFFSET_ALARM_BASE_TYPE_MINCYCLE);
+
+    assert!(offset_of!(CrashRecordType, exception) == ABI_OFFSET_CRASH_RECORD_TYPE_EXCEPTION);
+    assert!(offset_of!(CrashRecordType, r0) == ABI_OFFSET_CRASH_RECORD_TYPE_R0);
+    assert!(offset_of!(CrashRecordType, r1) == ABI_OFFSET_CRASH_RECORD_TYPE_R1);
+    assert!(offset_of!(CrashRecordType, r2) == ABI_OFFSET_CRASH_RECORD_TYPE_R2);
+    assert!(offset_of!(CrashRecordType, r3) == ABI_OFFSET_CRASH_RECORD_TYPE_R3);
+    assert!(offset_of!(CrashRecordType, r12) == ABI_OFFSET_CRASH_RECORD_TYPE_R12);
+    assert!(offset_of!(CrashRecordType, lr) == ABI_OFFSET_CRASH_RECORD_TYPE_LR);
+    assert!(offset_of!(CrashRecordType, pc) == ABI_OFFSET_CRASH_RECORD_TYPE_PC);
+    assert!(offset_of!(CrashRecordType, xpsr) == ABI_OFFSET_CRASH_RECORD_TYPE_XPSR);
+    assert!(offset_of!(CrashRecordType, sp) == ABI_OFFSET_CRASH_RECORD_TYPE_SP);
+    assert!(offset_of!(CrashRecordType, exc_return) == ABI_OFFSET_CRASH_RECORD_TYPE_EXC_RETURN);
+    assert!(offset_of!(CrashRecordType, cfsr) == ABI_OFFSET_CRASH_RECORD_TYPE_CFSR);
+    assert!(offset_of!(CrashRecordType, hfsr) == ABI_OFFSET_CRASH_RECORD_TYPE_HFSR);
+    assert!(offset_of!(CrashRecordType, mmfar) == ABI_OFFSET_CRASH_RECORD_TYPE_MMFAR);
+    assert!(offset_of!(CrashRecordType, bfar) == ABI_OFFSET_CRASH_RECORD_TYPE_BFAR);
+    assert!(offset_of!(CrashRecordType, sfsr) == ABI_OFFSET_CRASH_RECORD_TYPE_SFSR);
+    assert!(offset_of!(CrashRecordType, sfar) == ABI_OFFSET_CRASH_RECORD_TYPE_SFAR);
+    assert!(offset_of!(CrashRecordType, task) == ABI_OFFSET_CRASH_RECORD_TYPE_TASK);
+    assert!(offset_of!(CrashRecordType, isr) == ABI_OFFSET_CRASH_RECORD_TYPE_ISR);
+    assert!(offset_of!(CrashRecordType, ticks) == ABI_OFFSET_CRASH_RECORD_TYPE_TICKS);
+
+    assert!(offset_of!(PoolInfoType, block_size) == ABI_OFFSET_POOL_INFO_TYPE_BLOCK_SIZE);
+    assert!(offset_of!(PoolInfoType, blocks) == ABI_OFFSET_POOL_INFO_TYPE_BLOCKS);
+    assert!(offset_of!(PoolInfoType, free) == ABI_OFFSET_POOL_INFO_TYPE_FREE);
+    assert!(offset_of!(PoolInfoType, min_free) == ABI_OFFSET_POOL_INFO_TYPE_MIN_FREE);
+    assert!(offset_of!(PoolInfoType, failures) == ABI_OFFSET_POOL_INFO_TYPE_FAILURES);
+
+    assert!(offset_of!(ResetInfoType, reason) == ABI_OFFSET_RESET_INFO_TYPE_REASON);
+    assert!(offset_of!(ResetInfoType, chip_reset) == ABI_OFFSET_RESET_INFO_TYPE_CHIP_RESET);
+    assert!(
+        offset_of!(ResetInfoType, watchdog_reason) == ABI_OFFSET_RESET_INFO_TYPE_WATCHDOG_REASON
+    );
+    assert!(offset_of!(ResetInfoType, scratch) == ABI_OFFSET_RESET_INFO_TYPE_SCRATCH);
+
+    assert!(offset_of!(TaskDescriptorType, id) == ABI_OFFSET_TASK_DESCRIPTOR_TYPE_ID);
+    assert!(offset_of!(TaskDescriptorType, name) == ABI_OFFSET_TASK_DESCRIPTOR_TYPE_NAME);
+    assert!(offset_of!(TaskDescriptorType, entry) == ABI_OFFSET_TASK_DESCRIPTOR_TYPE_ENTRY);
+    assert!(offset_of!(TaskDescriptorType, priority) == ABI_OFFSET_TASK_DESCRIPTOR_TYPE_PRIORITY);
+    assert!(
+        offset_of!(TaskDescriptorType, activation) == ABI_OFFSET_TASK_DESCRIPTOR_TYPE_ACTIVATION
+    );
+    assert!(offset_of!(TaskDescriptorType, flags) == ABI_OFFSET_TASK_DESCRIPTOR_TYPE_FLAGS);
+    assert!(offset_of!(TaskDescriptorType, stack) == ABI_OFFSET_TASK_DESCRIPTOR_TYPE_STACK);
+    assert!(
+        offset_of!(TaskDescriptorType, stack_size) == ABI_OFFSET_TASK_DESCRIPTOR_TYPE_STACK_SIZE
+    );
+};
+
+#[cfg(all(target_os = "none", feature = "stats"))]
+const _: () = {
+    use core::mem::offset_of;
+
+    use crate::stats::{IsrStatisticsType, SystemStatisticsType, TaskStatisticsType};
+
+    assert!(size_of::<TaskStatisticsType>() == ABI_SIZE_TASK_STATISTICS_TYPE);
+    assert!(align_of::<TaskStatisticsType>() == ABI_ALIGN_TASK_STATISTICS_TYPE);
+    assert!(size_of::<IsrStatisticsType>() == ABI_SIZE_ISR_STATISTICS_TYPE);
+    assert!(align_of::<IsrStatisticsType>() == ABI_ALIGN_ISR_STATISTICS_TYPE);
+    assert!(size_of::<SystemStatisticsType>() == ABI_SIZE_SYSTEM_STATISTICS_TYPE);
+    assert!(align_of::<SystemStatisticsType>() == ABI_ALIGN_SYSTEM_STATISTICS_TYPE);
+
+    assert!(
+        offset_of!(TaskStatisticsType, execution_time)
+            == ABI_OFFSET_TASK_STATISTICS_TYPE_EXECUTION_TIME
+    );
+    assert!(
+        offset_of!(TaskStatisticsType, response_time_max)
+            == ABI_OFFSET_TASK_STATISTICS_TYPE_RESPONSE_TIME_MAX
+    );
+    assert!(
+        offset_of!(TaskStatisticsType, activations) == ABI_OFFSET_TASK_STATISTICS_TYPE_ACTIVATIONS
+    );
+    assert!(
+        offset_of!(TaskStatisticsType, preemptions) == ABI_OFFSET_TASK_STATISTICS_TYPE_PREEMPTIONS
+    );
+    assert!(offset_of!(TaskStatisticsType, overruns) == ABI_OFFSET_TASK_STATISTICS_TYPE_OVERRUNS);
+    assert!(
+        offset_of!(TaskStatisticsType, deadline_misses)
+            == ABI_OFFSET_TASK_STATISTICS_TYPE_DEADLINE_MISSES
+    );
+
+    assert!(
+        offset_of!(IsrStatisticsType, execution_time)
+            == ABI_OFFSET_ISR_STATISTICS_TYPE_EXECUTION_TIME
+    );
+    assert!(
+        offset_of!(IsrStatisticsType, duration_max) == ABI_OFFSET_ISR_STATISTICS_TYPE_DURATION_MAX
+    );
+    assert!(
+        offset_of!(IsrStatisticsType, activations) == ABI_OFFSET_ISR_STATISTICS_TYPE_ACTIVATIONS
+    );
+
+    assert!(
+        offset_of!(SystemStatisticsType, elapsed_time)
+            == ABI_OFFSET_SYSTEM_STATISTICS_TYPE_ELAPSED_TIME
+    );
+    assert!(
+        offset_of!(SystemStatisticsType, idle_time) == ABI_OFFSET_SYSTEM_STATISTICS_TYPE_IDLE_TIME
+    );
+};
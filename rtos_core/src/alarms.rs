@@ -1,10 +1,20 @@
-use core::cell::RefCell;
+use core::cell::{Cell, RefCell};
 
-use crate::StatusType;
+#[cfg(feature = "events")]
+use crate::events::{self, EventMaskType};
+use crate::{
+    OsConfig, StatusType, hooks,
+    task::{self, TaskType},
+    trace::{self, TraceKind},
+};
 use critical_section::Mutex;
-use defmt::info;
+use defmt::{debug, info};
 
-const NUM_ALARMS: usize = 5;
+/// Maximum number of alarms in a configuration.
+pub const MAX_ALARMS: usize = 16;
+
+/// Maximum number of counters in a configuration.
+pub const MAX_COUNTERS: usize = 8;
 
 pub type TickType = i32;
 pub type TickRefType = *mut TickType;
@@ -14,42 +24,213 @@ pub type TickRefType = *mut TickType;
 pub struct AlarmBaseType {
     pub maxallowedvalue: TickType, // Maximum possible allowed count value in ticks
     pub ticksperbase: TickType,    // Number of ticks required to reach counter specific unit
-    mincycle: TickType,
-}
-
-impl AlarmBaseType {
-    pub const fn defaultinit() -> Self {
-        AlarmBaseType {
-            maxallowedvalue: 10,
-            ticksperbase: 1,
-            mincycle: 10,
-        }
-    }
+    pub mincycle: TickType,        // Smallest allowed cycle of a cyclic alarm
 }
 
 pub type AlarmBaseRefType = *mut AlarmBaseType;
 
 pub type AlarmType = usize;
 
-//TODO : Add an option
+pub type CounterType = usize;
+
+/// Static description of a counter, as found in the OIL `COUNTER` object.
+/// Every counter is driven by the timer tick.
+#[derive(Clone, Copy)]
+pub struct CounterConfig {
+    pub name: &'static str,
+    pub base: AlarmBaseType,
+}
+
+/// What an alarm does when it expires (`ACTION` in OIL).
+#[derive(Clone, Copy)]
+pub enum AlarmAction {
+    ActivateTask(TaskType),
+    #[cfg(feature = "events")]
+    SetEvent(TaskType, EventMaskType),
+    /// Alarm callback routine, run on ISR level.
+    Callback(extern "C" fn()),
+}
+
+/// Expiry of an alarm set by `StartOS`, as `SetRelAlarm` would
+/// (`AUTOSTART` in OIL).
+#[derive(Clone, Copy)]
+pub struct AlarmAutostart {
+    pub alarm_time: TickType,
+    /// Cycle in ticks, 0 for a single-shot alarm.
+    pub cycle_time: TickType,
+}
+
+/// Static description of an alarm, as found in the OIL `ALARM` object.
+#[derive(Clone, Copy)]
+pub struct AlarmConfig {
+    pub name: &'static str,
+    /// Index into [`OsConfig::counters`].
+    pub counter: CounterType,
+    pub action: AlarmAction,
+    pub autostart: Option<AlarmAutostart>,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Alarm {
     pub alarm_base: AlarmBaseType,
     pub tick: TickType,
     pub h_ticks: i32,
+    /// Cycle in ticks, 0 for a single-shot alarm.
     pub cycle: i32,
     pub active: bool,
 }
 
-pub static SOFTW_ALARMS: Mutex<RefCell<[Alarm; NUM_ALARMS]>> = Mutex::new(RefCell::new(
-    [Alarm {
-        alarm_base: AlarmBaseType::defaultinit(),
-        tick: 0,
-        h_ticks: 0,
-        cycle: 0,
-        active: false,
-    }; NUM_ALARMS],
-));
+impl Alarm {
+    pub const fn new() -> Self {
+        Alarm {
+            alarm_base: AlarmBaseType {
+                maxallowedvalue: 0,
+                ticksperbase: 1,
+                mincycle: 1,
+            },
+            tick: 0,
+            h_ticks: 0,
+            cycle: 0,
+            active: false,
+        }
+    }
+}
+
+impl Default for Alarm {
+    fn default() -> Self {
+        Alarm::new()
+    }
+}
+
+pub static SOFTW_ALARMS: Mutex<RefCell<[Alarm; MAX_ALARMS]>> =
+    Mutex::new(RefCell::new([Alarm::new(); MAX_ALARMS]));
+
+/// Current value of a counter, from 0 up to `maxallowedvalue` and back.
+#[derive(Clone, Copy, Debug)]
+struct Counter {
+    base: AlarmBaseType,
+    value: TickType,
+    h_ticks: i32,
+}
+
+impl Counter {
+    const fn new() -> Self {
+        Counter {
+            base: AlarmBaseType {
+                maxallowedvalue: 0,
+                ticksperbase: 1,
+                mincycle: 1,
+            },
+            value: 0,
+            h_ticks: 0,
+        }
+    }
+}
+
+/// Counters of the configuration, indexed by `CounterType`.
+static COUNTERS: Mutex<RefCell<[Counter; MAX_COUNTERS]>> =
+    Mutex::new(RefCell::new([Counter::new(); MAX_COUNTERS]));
+
+/// Alarms of the configuration, indexed by `AlarmType`.
+static ALARMS: Mutex<Cell<&'static [AlarmConfig]>> = Mutex::new(Cell::new(&[]));
+
+/// Checks the alarms and counters of `config`. Called by `StartOS`.
+pub(crate) fn check_config(config: &OsConfig) -> Result<(), &'static str> {
+    if config.alarms.len() > MAX_ALARMS {
+        return Err("too many alarms");
+    }
+    if config.counters.len() > MAX_COUNTERS {
+        return Err("too many counters");
+    }
+    for counter in config.counters {
+        let base = counter.base;
+        if base.maxallowedvalue <= 0 || base.ticksperbase <= 0 {
+            return Err("counter constants must be positive");
+        }
+        if base.mincycle <= 0 || base.mincycle > base.maxallowedvalue {
+            return Err("counter mincycle out of range");
+        }
+    }
+    for alarm in config.alarms {
+        let Some(counter) = config.counters.get(alarm.counter) else {
+            return Err("alarm counter does not exist");
+        };
+        match alarm.action {
+            AlarmAction::ActivateTask(task) if task >= config.tasks.len() => {
+                return Err("alarm task does not exist");
+            }
+            #[cfg(feature = "events")]
+            AlarmAction::SetEvent(task, _) => match config.tasks.get(task) {
+                None => return Err("alarm task does not exist"),
+                Some(task) if !task.extended => return Err("alarm sets an event of a basic task"),
+                Some(_) => (),
+            },
+            _ => (),
+        }
+        if let Some(autostart) = alarm.autostart {
+            let base = counter.base;
+            if autostart.alarm_time <= 0 || autostart.alarm_time > base.maxallowedvalue {
+                return Err("alarm time out of range");
+            }
+            if autostart.cycle_time != 0
+                && (autostart.cycle_time < base.mincycle
+                    || autostart.cycle_time > base.maxallowedvalue)
+            {
+                return Err("alarm cycle time out of range");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Loads the alarms of `config`, inactive but for the autostart ones, and
+/// starts its counters from 0.
+pub(crate) fn init(config: &'static OsConfig) {
+    critical_section::with(|cs| {
+        let mut counters = COUNTERS.borrow_ref_mut(cs);
+        *counters = [Counter::new(); MAX_COUNTERS];
+        for (counter, config_counter) in counters.iter_mut().zip(config.counters) {
+            counter.base = config_counter.base;
+        }
+        let mut alarms = SOFTW_ALARMS.borrow_ref_mut(cs);
+        *alarms = [Alarm::new(); MAX_ALARMS];
+        for (alarm, config_alarm) in alarms.iter_mut().zip(config.alarms) {
+            alarm.alarm_base = config.counters[config_alarm.counter].base;
+            if let Some(autostart) = config_alarm.autostart {
+                alarm.tick = alarm.alarm_base.maxallowedvalue - autostart.alarm_time;
+                alarm.cycle = autostart.cycle_time;
+                alarm.active = true;
+            }
+        }
+        ALARMS.borrow(cs).set(config.alarms);
+    });
+}
+
+/// Alarms of the configuration.
+pub(crate) fn configured() -> &'static [AlarmConfig] {
+    critical_section::with(|cs| ALARMS.borrow(cs).get())
+}
+
+fn count() -> usize {
+    configured().len()
+}
+
+/// Ticks until the first active alarm expires, `None` if no alarm is
+/// active.
+#[cfg(feature = "low-power")]
+pub(crate) fn next_expiry() -> Option<TickType> {
+    critical_section::with(|cs| {
+        SOFTW_ALARMS
+            .borrow_ref(cs)
+            .iter()
+            .filter(|alarm| alarm.active)
+            .map(|alarm| {
+                (alarm.alarm_base.maxallowedvalue - alarm.tick) * alarm.alarm_base.ticksperbase
+                    - alarm.h_ticks
+            })
+            .min()
+    })
+}
 
 /// Reads the alarm base characteristics.
 ///
@@ -79,20 +260,31 @@ pub static SOFTW_ALARMS: Mutex<RefCell<[Alarm; NUM_ALARMS]>> = Mutex::new(RefCel
 ///   * `E_OK` — No error.
 /// * **Extended:**
 ///   * `E_OS_ID` — Alarm `alarm_id` is invalid.
+///
+/// # Safety
+///
+/// `info` must be valid for a write.
 #[unsafe(no_mangle)]
-pub extern "C" fn GetAlarmBase(alarm_id: AlarmType, info: AlarmBaseRefType) -> StatusType {
-    if alarm_id >= NUM_ALARMS {
+pub unsafe extern "C" fn GetAlarmBase(alarm_id: AlarmType, info: AlarmBaseRefType) -> StatusType {
+    let status = match alarm_base(alarm_id) {
+        Ok(base) => {
+            unsafe { *info = base };
+            StatusType::EOk
+        }
+        Err(status) => status,
+    };
+    hooks::report(status)
+}
+
+fn alarm_base(alarm_id: AlarmType) -> Result<AlarmBaseType, StatusType> {
+    if alarm_id >= count() {
         #[cfg(debug_assertions)]
         info!("Invalid Alarm ID");
-        return StatusType::EOsId;
+        return Err(StatusType::EOsId);
     }
-    critical_section::with(|cs| {
-        let alarm_ref = &SOFTW_ALARMS.borrow_ref(cs)[alarm_id];
-        unsafe {
-            *info = alarm_ref.alarm_base;
-        }
-        StatusType::EOk
-    })
+    Ok(critical_section::with(|cs| {
+        SOFTW_ALARMS.borrow_ref(cs)[alarm_id].alarm_base
+    }))
 }
 
 /// Returns the relative value in ticks before an alarm expires.
@@ -124,32 +316,52 @@ pub extern "C" fn GetAlarmBase(alarm_id: AlarmType, info: AlarmBaseRefType) -> S
 ///   * `E_OK` — No error.
 ///   * `E_OS_NOFUNC` — Alarm `alarm_id` is not used.
 ///
+/// # Safety
+///
+/// `tick` must be valid for a write.
 #[unsafe(no_mangle)]
-pub extern "C" fn GetAlarm(alarm_id: AlarmType, tick: TickRefType) -> StatusType {
-    if alarm_id >= NUM_ALARMS {
+pub unsafe extern "C" fn GetAlarm(alarm_id: AlarmType, tick: TickRefType) -> StatusType {
+    let status = match ticks_left(alarm_id) {
+        Ok(ticks) => {
+            unsafe { *tick = ticks };
+            StatusType::EOk
+        }
+        Err(status) => status,
+    };
+    hooks::report(status)
+}
+
+fn ticks_left(alarm_id: AlarmType) -> Result<TickType, StatusType> {
+    if alarm_id >= count() {
         #[cfg(debug_assertions)]
         info!("Invalid Alarm ID");
 
-        return StatusType::EOsId;
+        return Err(StatusType::EOsId);
     }
     critical_section::with(|cs| {
         let alarm_ref = &SOFTW_ALARMS.borrow_ref_mut(cs)[alarm_id];
         if !alarm_ref.active {
-            return StatusType::EOsNoFunc;
+            return Err(StatusType::EOsNofunc);
         }
-        unsafe { *tick = alarm_ref.alarm_base.maxallowedvalue - alarm_ref.tick };
-        StatusType::EOk
+        Ok(alarm_ref.alarm_base.maxallowedvalue - alarm_ref.tick)
     })
 }
 
-//TODO:: design cyclic alarms.
 #[unsafe(no_mangle)]
 pub extern "C" fn SetRelAlarm(
     alarm_id: AlarmType,
     increment: TickType,
     cycle: TickType,
 ) -> StatusType {
-    if alarm_id >= NUM_ALARMS {
+    hooks::report(set_rel_alarm(alarm_id, increment, cycle))
+}
+
+pub(crate) fn set_rel_alarm(
+    alarm_id: AlarmType,
+    increment: TickType,
+    cycle: TickType,
+) -> StatusType {
+    if alarm_id >= count() {
         #[cfg(debug_assertions)]
         info!("Invalid Alarm ID");
 
@@ -177,14 +389,14 @@ pub extern "C" fn SetRelAlarm(
             return StatusType::EOsValue;
         }
 
+        // The increment counts from the current counter value, so the
+        // alarm takes the phase of its counter within the base
+        let counter = COUNTERS.borrow_ref(cs)[configured()[alarm_id].counter];
         alarm_ref.tick = alarm_ref.alarm_base.maxallowedvalue - increment;
+        alarm_ref.h_ticks = counter.h_ticks;
         alarm_ref.active = true;
 
-        alarm_ref.cycle = if cycle != 0 {
-            alarm_ref.alarm_base.maxallowedvalue - cycle
-        } else {
-            0
-        };
+        alarm_ref.cycle = cycle;
         StatusType::EOk
     })
 }
@@ -221,7 +433,11 @@ pub extern "C" fn SetRelAlarm(
 ///
 #[unsafe(no_mangle)]
 pub extern "C" fn SetAbsAlarm(alarm_id: AlarmType, start: TickType, cycle: TickType) -> StatusType {
-    if alarm_id >= NUM_ALARMS {
+    hooks::report(set_abs_alarm(alarm_id, start, cycle))
+}
+
+fn set_abs_alarm(alarm_id: AlarmType, start: TickType, cycle: TickType) -> StatusType {
+    if alarm_id >= count() {
         return StatusType::EOsId;
     }
     critical_section::with(|cs| {
@@ -232,7 +448,7 @@ pub extern "C" fn SetAbsAlarm(alarm_id: AlarmType, start: TickType, cycle: TickT
             return StatusType::EOsState;
         }
 
-        if start > alarm_ref.alarm_base.maxallowedvalue {
+        if start < 0 || start > alarm_ref.alarm_base.maxallowedvalue {
             return StatusType::EOsValue;
         }
 
@@ -243,12 +459,17 @@ pub extern "C" fn SetAbsAlarm(alarm_id: AlarmType, start: TickType, cycle: TickT
             return StatusType::EOsValue;
         }
 
-        alarm_ref.cycle = if cycle != 0 {
-            alarm_ref.alarm_base.maxallowedvalue - cycle
-        } else {
-            0
+        // The alarm counts the ticks left until its counter reaches
+        // `start`, a whole counter round if it is there already
+        let counter = COUNTERS.borrow_ref(cs)[configured()[alarm_id].counter];
+        let round = alarm_ref.alarm_base.maxallowedvalue + 1;
+        let increment = match (start - counter.value).rem_euclid(round) {
+            0 => round,
+            increment => increment,
         };
-        alarm_ref.tick = start;
+        alarm_ref.cycle = cycle;
+        alarm_ref.tick = alarm_ref.alarm_base.maxallowedvalue - increment;
+        alarm_ref.h_ticks = counter.h_ticks;
         alarm_ref.active = true;
 
         StatusType::EOk
@@ -257,26 +478,95 @@ pub extern "C" fn SetAbsAlarm(alarm_id: AlarmType, start: TickType, cycle: TickT
 
 #[unsafe(no_mangle)]
 pub extern "C" fn CancelAlarm(alarm_id: AlarmType) -> StatusType {
-    if alarm_id >= NUM_ALARMS {
+    hooks::report(cancel_alarm(alarm_id))
+}
+
+pub(crate) fn cancel_alarm(alarm_id: AlarmType) -> StatusType {
+    if alarm_id >= count() {
         return StatusType::EOsId;
     }
     critical_section::with(|cs| {
-        let alarms = &SOFTW_ALARMS.borrow_ref_mut(cs);
-        let mut alarm_ref = alarms[alarm_id];
+        let mut alarms = SOFTW_ALARMS.borrow_ref_mut(cs);
+        let alarm_ref = &mut alarms[alarm_id];
         if !alarm_ref.active {
-            return StatusType::EOsNoFunc;
+            return StatusType::EOsNofunc;
         }
         alarm_ref.active = false;
         alarm_ref.tick = 0;
+        alarm_ref.h_ticks = 0;
         StatusType::EOk
     })
 }
 
-unsafe extern "C" {
-    // hardcoding alarm callbacks until OIL parser is ready
-    pub fn AlarmCallback0();
-    pub fn AlarmCallback1();
-    pub fn AlarmCallback2();
-    pub fn AlarmCallback3();
-    pub fn AlarmCallback4();
+/// Advances every active alarm by one timer interrupt and runs the
+/// callbacks of the alarms that expired.
+///
+/// Called from the timer ISR. The callbacks run after the alarm table is
+/// released, so they may use the alarm services themselves.
+pub fn tick() {
+    critical_section::with(|cs| {
+        let mut kernel = task::KERNEL.borrow_ref_mut(cs);
+        kernel.ticks = kernel.ticks.wrapping_add(1);
+    });
+    task::check_deadlines();
+    #[cfg(feature = "watchdog")]
+    crate::watchdog::tick();
+    critical_section::with(|cs| {
+        for counter in COUNTERS.borrow_ref_mut(cs).iter_mut() {
+            counter.h_ticks += 1;
+            if counter.h_ticks >= counter.base.ticksperbase {
+                counter.h_ticks = 0;
+                counter.value = if counter.value >= counter.base.maxallowedvalue {
+                    0
+                } else {
+                    counter.value + 1
+                };
+            }
+        }
+    });
+    let expired = critical_section::with(|cs| {
+        let mut expired = [false; MAX_ALARMS];
+        let mut alarms = SOFTW_ALARMS.borrow_ref_mut(cs);
+
+        for (i, alarm) in alarms.iter_mut().enumerate() {
+            if alarm.active {
+                #[cfg(debug_assertions)]
+                debug!(
+                    "Alarm {} is active, tick = {}, cycle = {}",
+                    i, alarm.tick, alarm.cycle
+                );
+                alarm.h_ticks += 1;
+                if alarm.h_ticks >= alarm.alarm_base.ticksperbase {
+                    alarm.h_ticks = 0;
+                    alarm.tick += 1;
+                }
+                if alarm.tick >= alarm.alarm_base.maxallowedvalue {
+                    if alarm.cycle == 0 {
+                        alarm.active = false;
+                        alarm.tick = 0;
+                    } else {
+                        alarm.tick = alarm.alarm_base.maxallowedvalue - alarm.cycle;
+                    }
+                    expired[i] = true;
+                }
+            }
+        }
+        expired
+    });
+
+    let configured = configured();
+    for (i, _) in expired.iter().enumerate().filter(|(_, expired)| **expired) {
+        trace::record(TraceKind::AlarmExpire, i, 0);
+        // Errors of the actions go to the ErrorHook, as for any service
+        match configured[i].action {
+            AlarmAction::ActivateTask(task_id) => {
+                let _ = task::ActivateTask(task_id);
+            }
+            #[cfg(feature = "events")]
+            AlarmAction::SetEvent(task_id, mask) => {
+                let _ = events::SetEvent(task_id, mask);
+            }
+            AlarmAction::Callback(callback) => callback(),
+        }
+    }
 }
@@ -0,0 +1,146 @@
+//! OS configuration in the picotool binary info.
+//!
+//! Every entry is a program feature string in the `.bi_entries` section,
+//! which `picotool info` lists for an ELF or a UF2 file alike. The kernel
+//! publishes its version and conformance class; `rtos_config!`,
+//! `task_config!`, `#[task(..)]` and `TASK` add a `task <name>,
+//! priority <n>` or `alarm <name>, counter <counter>` entry per object, and
+//! the application its own entries, such as the tick rate, with
+//! [`os_binary_info!`] and [`format_u32`].
+//!
+//! [`os_binary_info!`]: crate::os_binary_info
+
+use core::ffi::CStr;
+
+pub use rp_binary_info::{self, EntryAddr};
+
+use crate::{CONFORMANCE_CLASS, ConformanceClass};
+
+/// Name and version of the kernel.
+pub const VERSION: &CStr = match CStr::from_bytes_with_nul(
+    concat!("FV-RTOS ", env!("CARGO_PKG_VERSION"), "\0").as_bytes(),
+) {
+    Ok(version) => version,
+    Err(_) => panic!("version is not a C string"),
+};
+
+/// Conformance class the kernel was built for.
+pub const CLASS: &CStr = match CONFORMANCE_CLASS {
+    ConformanceClass::Bcc1 => c"OSEK conformance class BCC1",
+    ConformanceClass::Bcc2 => c"OSEK conformance class BCC2",
+    ConformanceClass::Ecc1 => c"OSEK conformance class ECC1",
+    ConformanceClass::Ecc2 => c"OSEK conformance class ECC2",
+};
+
+/// `prefix`, `value` in decimal and `suffix`, NUL-terminated in `N` bytes,
+/// for an entry that follows a constant of the application. Fails to build
+/// if the text does not fit.
+///
+/// ```ignore
+/// const TICK_RATE: [u8; 24] = format_u32("tick rate ", 1_000_000 / TICK_US, " Hz");
+/// rtos_core::os_binary_info!(rtos_core::binary_info::c_str(&TICK_RATE));
+/// ```
+pub const fn format_u32<const N: usize>(prefix: &str, value: u32, suffix: &str) -> [u8; N] {
+    let mut digits = [0; 10];
+    let mut count = 0;
+    let mut rest = value;
+    loop {
+        digits[count] = b'0' + (rest % 10) as u8;
+        count += 1;
+        rest /= 10;
+        if rest == 0 {
+            break;
+        }
+    }
+    assert!(
+        prefix.len() + count + suffix.len() < N,
+        "binary info entry too long"
+    );
+    let mut text = [0; N];
+    let mut len = 0;
+    let mut i = 0;
+    while i < prefix.len() {
+        text[len] = prefix.as_bytes()[i];
+        len += 1;
+        i += 1;
+    }
+    while count > 0 {
+        count -= 1;
+        text[len] = digits[count];
+        len += 1;
+    }
+    i = 0;
+    while i < suffix.len() {
+        text[len] = suffix.as_bytes()[i];
+        len += 1;
+        i += 1;
+    }
+    text
+}
+
+/// The string of `bytes` up to its first NUL, as built by [`format_u32`].
+pub const fn c_str(bytes: &'static [u8]) -> &'static CStr {
+    match CStr::from_bytes_until_nul(bytes) {
+        Ok(text) => text,
+        Err(_) => panic!("binary info entry is not NUL-terminated"),
+    }
+}
+
+/// Puts each `&'static CStr` in the picotool binary info as a program
+/// feature.
+///
+/// Without the `binary-info` feature, the macro expands to nothing, so that
+/// `rtos_config!` and `#[task]` can use it either way.
+///
+/// ```ignore
+/// rtos_core::os_binary_info!(c"tick rate 1 Hz");
+/// ```
+#[macro_export]
+macro_rules! os_binary_info {
+    ($($feature:expr),* $(,)?) => {
+        $(
+            const _: () = {
+                #[used]
+                #[unsafe(link_section = ".bi_entries")]
+                static ENTRY: $crate::binary_info::EntryAddr =
+                    $crate::binary_info::rp_binary_info::str!(
+                        $crate::binary_info::rp_binary_info::consts::TAG_RASPBERRY_PI,
+                        $crate::binary_info::rp_binary_info::consts::ID_RP_PROGRAM_FEATURE,
+                        $feature
+                    );
+            };
+        )*
+    };
+}
+
+crate::os_binary_info!(VERSION, CLASS);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_has_one_digit() {
+        static TEXT: [u8; 8] = format_u32("n ", 0, " s");
+        assert_eq!(&TEXT, b"n 0 s\0\0\0");
+        assert_eq!(c_str(&TEXT), c"n 0 s");
+    }
+
+    #[test]
+    fn largest_value() {
+        let text: [u8; 11] = format_u32("", u32::MAX, "");
+        assert_eq!(&text, b"4294967295\0");
+    }
+
+    #[test]
+    fn exact_fit_keeps_the_nul() {
+        let text: [u8; 13] = format_u32("tick ", 1000, " Hz");
+        assert_eq!(&text, b"tick 1000 Hz\0");
+    }
+
+    #[test]
+    #[should_panic(expected = "binary info entry too long")]
+    fn no_room_for_the_nul() {
+        let _: [u8; 12] = format_u32("tick ", 1000, " Hz");
+    }
+}
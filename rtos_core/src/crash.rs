@@ -0,0 +1,221 @@
+//! Post-mortem crash records.
+//!
+//! The fault handlers of the port (HardFault, MemManage, BusFault,
+//! UsageFault and SecureFault) save the registers stacked by the fault, the
+//! fault status registers and the state of the OS into a [`CrashRecordType`]
+//! kept in the `.uninit` RAM section, then reset the chip. RAM survives the
+//! reset, so the next `StartOS` finds the record, reports `E_OS_SYS_FAULT`
+//! to the ErrorHook and keeps it for [`GetCrashRecord`] until the following
+//! reset.
+//!
+//! A magic word and a checksum tell a record apart from whatever RAM holds
+//! after a power-on.
+
+use core::{cell::RefCell, mem::MaybeUninit};
+
+use critical_section::Mutex;
+#[cfg(debug_assertions)]
+use defmt::info;
+
+use crate::{
+    StatusType,
+    alarms::TickType,
+    hooks,
+    task::{INVALID_TASK, KERNEL, TaskType},
+};
+
+/// Marks a record written by a fault handler, "CRSH".
+const CRASH_MAGIC: u32 = 0x4352_5348;
+
+/// Exception numbers of the faults.
+pub const HARD_FAULT: u32 = 3;
+pub const MEM_MANAGE_FAULT: u32 = 4;
+pub const BUS_FAULT: u32 = 5;
+pub const USAGE_FAULT: u32 = 6;
+pub const SECURE_FAULT: u32 = 7;
+
+pub type CrashRecordRefType = *mut CrashRecordType;
+
+/// What a fault left behind, as returned by `GetCrashRecord`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CrashRecordType {
+    /// Exception number of the fault, `HARD_FAULT` to `SECURE_FAULT`.
+    pub exception: u32,
+    /// Registers stacked on exception entry, `0` if the stack pointer was
+    /// unusable.
+    pub r0: u32,
+    pub r1: u32,
+    pub r2: u32,
+    pub r3: u32,
+    pub r12: u32,
+    pub lr: u32,
+    /// Faulting instruction, or the one after it for imprecise faults.
+    pub pc: u32,
+    pub xpsr: u32,
+    /// Stack pointer of the faulting context before the exception entry.
+    pub sp: u32,
+    /// `EXC_RETURN` of the fault, telling the stack and mode of the
+    /// faulting context.
+    pub exc_return: u32,
+    /// Configurable, HardFault and SecureFault status registers and the
+    /// fault addresses they validate.
+    pub cfsr: u32,
+    pub hfsr: u32,
+    pub mmfar: u32,
+    pub bfar: u32,
+    pub sfsr: u32,
+    pub sfar: u32,
+    /// Task running when the fault happened, `INVALID_TASK` if none.
+    pub task: TaskType,
+    /// Exception number of the interrupted context, `0` in thread mode.
+    pub isr: u32,
+    /// Timer ticks since the start-up when the fault happened.
+    pub ticks: TickType,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct StoredRecord {
+    magic: u32,
+    record: CrashRecordType,
+    checksum: u32,
+}
+
+impl StoredRecord {
+    fn new(record: CrashRecordType) -> Self {
+        StoredRecord {
+            magic: CRASH_MAGIC,
+            record,
+            checksum: checksum(&record),
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        self.magic == CRASH_MAGIC && self.checksum == checksum(&self.record)
+    }
+}
+
+fn checksum(record: &CrashRecordType) -> u32 {
+    [
+        record.exception,
+        record.r0,
+        record.r1,
+        record.r2,
+        record.r3,
+        record.r12,
+        record.lr,
+        record.pc,
+        record.xpsr,
+        record.sp,
+        record.exc_return,
+        record.cfsr,
+        record.hfsr,
+        record.mmfar,
+        record.bfar,
+        record.sfsr,
+        record.sfar,
+        record.task as u32,
+        record.isr,
+        record.ticks as u32,
+    ]
+    .iter()
+    .fold(CRASH_MAGIC, |sum, &word| sum.rotate_left(5) ^ word)
+}
+
+/// Written by the fault handlers, read back by the next start-up. Not
+/// initialized by the start-up code, so that it survives a reset.
+#[cfg_attr(target_os = "none", unsafe(link_section = ".uninit.rtos_crash"))]
+static mut STORED: MaybeUninit<StoredRecord> = MaybeUninit::uninit();
+
+/// Record found by the last start-up.
+static LAST_CRASH: Mutex<RefCell<Option<CrashRecordType>>> = Mutex::new(RefCell::new(None));
+
+/// Fills in the state of the OS and stores `record` for the next start-up.
+/// Called by the fault handlers, which reset the chip right after.
+pub(crate) fn save(mut record: CrashRecordType) {
+    // The fault may have hit the kernel with its state borrowed
+    let state = critical_section::with(|cs| {
+        KERNEL
+            .borrow(cs)
+            .try_borrow()
+            .ok()
+            .map(|kernel| (kernel.task_id(), kernel.ticks))
+    });
+    (record.task, record.ticks) = state.unwrap_or((INVALID_TASK, 0));
+    unsafe {
+        core::ptr::addr_of_mut!(STORED)
+            .cast::<StoredRecord>()
+            .write_volatile(StoredRecord::new(record));
+    }
+}
+
+/// Takes the record left by a fault before the last reset, if any, and
+/// reports it to the ErrorHook. Returns whether there was one. Called by
+/// `StartOS` once the hooks are known.
+pub(crate) fn init() -> bool {
+    let stored = unsafe {
+        core::ptr::addr_of!(STORED)
+            .cast::<StoredRecord>()
+            .read_volatile()
+    };
+    // Only reported once
+    unsafe {
+        core::ptr::addr_of_mut!(STORED)
+            .cast::<u32>()
+            .write_volatile(0);
+    }
+    let record = stored.is_valid().then_some(stored.record);
+    critical_section::with(|cs| *LAST_CRASH.borrow_ref_mut(cs) = record);
+    if let Some(_record) = record {
+        #[cfg(debug_assertions)]
+        info!(
+            "Crash before the reset: exception {}, pc {=u32:#x}, task {}",
+            _record.exception, _record.pc, _record.task
+        );
+        hooks::report(StatusType::EOsSysFault);
+    }
+    record.is_some()
+}
+
+/// Returns the crash record left by a fault before the last reset.
+///
+/// # Syntax
+/// ```ignore
+/// StatusType GetCrashRecord(CrashRecordRefType Record)
+/// ```
+///
+/// # Parameters
+///
+/// * `record` (out) — Exception, stacked registers, fault status registers,
+///   running task and tick count of the fault.
+///
+/// # Description
+///
+/// The record is the one `StartOS` found, and stays available until the
+/// next reset.
+///
+/// # Particularities
+///
+/// Allowed on task level, ISR level and in the hook routines, the
+/// ErrorHook reporting `E_OS_SYS_FAULT` included.
+///
+/// # Status
+///
+/// * **Standard:**
+///   * `E_OK` — No error.
+///   * `E_OS_NOFUNC` — The last reset was not caused by a fault.
+///
+/// # Safety
+///
+/// `record` must be valid for a write.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn GetCrashRecord(record: CrashRecordRefType) -> StatusType {
+    match critical_section::with(|cs| *LAST_CRASH.borrow_ref(cs)) {
+        Some(crash) => {
+            unsafe { *record = crash };
+            StatusType::EOk
+        }
+        None => hooks::report(StatusType::EOsNofunc),
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{
+    StatusType, hooks, port,
+    task::{KERNEL, Kernel, TaskStateType, TaskType},
+    trace::{self, TraceKind},
+};
+
+pub type EventMaskType = u32;
+pub type EventMaskRefType = *mut EventMaskType;
+
+impl Kernel {
+    /// Checks that `task_id` names an extended task that is not suspended.
+    fn event_target(&self, task_id: TaskType) -> Result<(), StatusType> {
+        let Some(task) = self.tasks.get(task_id) else {
+            return Err(StatusType::EOsId);
+        };
+        if !task.extended {
+            return Err(StatusType::EOsAccess);
+        }
+        if self.tcbs[task_id].state == TaskStateType::Suspended {
+            return Err(StatusType::EOsState);
+        }
+        Ok(())
+    }
+
+    /// The running extended task, for the services that act on the caller.
+    fn event_caller(&self) -> Result<TaskType, StatusType> {
+        if port::in_isr() {
+            return Err(StatusType::EOsCallevel);
+        }
+        let Some(id) = self.running else {
+            return Err(StatusType::EOsCallevel);
+        };
+        if !self.tasks[id].extended {
+            return Err(StatusType::EOsAccess);
+        }
+        Ok(id)
+    }
+
+    pub fn set_event(&mut self, task_id: TaskType, mask: EventMaskType) -> StatusType {
+        if let Err(status) = self.event_target(task_id) {
+            return status;
+        }
+        trace::record(TraceKind::SetEvent, task_id, mask);
+        let tcb = &mut self.tcbs[task_id];
+        tcb.events |= mask;
+        if tcb.state == TaskStateType::Waiting && tcb.events & tcb.wait_mask != 0 {
+            trace::record(TraceKind::Release, task_id, 0);
+            tcb.state = TaskStateType::Ready;
+            tcb.wait_mask = 0;
+            // A waiting task holds no queue slot, so one is free for it
+            let _ = self.ready.release(task_id, tcb.priority);
+            self.schedule();
+        }
+        StatusType::EOk
+    }
+
+    pub fn clear_event(&mut self, mask: EventMaskType) -> StatusType {
+        match self.event_caller() {
+            Ok(id) => {
+                self.tcbs[id].events &= !mask;
+                StatusType::EOk
+            }
+            Err(status) => status,
+        }
+    }
+
+    pub fn get_event(&self, task_id: TaskType) -> Result<EventMaskType, StatusType> {
+        self.event_target(task_id)?;
+        Ok(self.tcbs[task_id].events)
+    }
+
+    pub fn wait_event(&mut self, mask: EventMaskType) -> StatusType {
+        let id = match self.event_caller() {
+            Ok(id) => id,
+            Err(status) => return status,
+        };
+        let tcb = &mut self.tcbs[id];
+        if tcb.last_resource.is_some() {
+            return StatusType::EOsResource;
+        }
+        if tcb.events & mask == 0 {
+            trace::record(TraceKind::WaitEvent, id, mask);
+            tcb.wait_mask = mask;
+            tcb.state = TaskStateType::Waiting;
+            // The internal resource is released while waiting
+            tcb.priority = self.tasks[id].priority;
+            self.schedule();
+        }
+        StatusType::EOk
+    }
+}
+
+/// Sets events of an extended task.
+///
+/// # Syntax
+/// ```ignore
+/// StatusType SetEvent(TaskType TaskID, EventMaskType Mask)
+/// ```
+///
+/// # Parameters
+///
+/// * `task_id` (in) — Reference to the task for which one or several events
+///   are to be set.
+/// * `mask` (in) — Mask of the events to be set.
+///
+/// # Description
+///
+/// The events of task `task_id` are set according to `mask`. If the task
+/// was waiting for at least one of them, it is transferred into the ready
+/// state.
+///
+/// # Particularities
+///
+/// Allowed on task level and ISR level, but not in hook routines.
+///
+/// # Status
+///
+/// * **Standard:**
+///   * `E_OK` — No error.
+/// * **Extended:**
+///   * `E_OS_ID` — `task_id` is invalid.
+///   * `E_OS_ACCESS` — `task_id` is not an extended task.
+///   * `E_OS_STATE` — Task `task_id` is in the suspended state.
+#[unsafe(no_mangle)]
+pub extern "C" fn SetEvent(task_id: TaskType, mask: EventMaskType) -> StatusType {
+    hooks::report(critical_section::with(|cs| {
+        KERNEL.borrow_ref_mut(cs).set_event(task_id, mask)
+    }))
+}
+
+/// Clears events of the calling task.
+///
+/// # Syntax
+/// ```ignore
+/// StatusType ClearEvent(EventMaskType Mask)
+/// ```
+///
+/// # Parameters
+///
+/// * `mask` (in) — Mask of the events to be cleared.
+///
+/// # Particularities
+///
+/// Restricted to extended tasks.
+///
+/// # Status
+///
+/// * **Standard:**
+///   * `E_OK` — No error.
+/// * **Extended:**
+///   * `E_OS_ACCESS` — Call not from an extended task.
+///   * `E_OS_CALLEVEL` — Call at interrupt level.
+#[unsafe(no_mangle)]
+pub extern "C" fn ClearEvent(mask: EventMaskType) -> StatusType {
+    hooks::report(critical_section::with(|cs| {
+        KERNEL.borrow_ref_mut(cs).clear_event(mask)
+    }))
+}
+
+/// Reads the current events of an extended task.
+///
+/// # Syntax
+/// ```ignore
+/// StatusType GetEvent(TaskType TaskID, EventMaskRefType Event)
+/// ```
+///
+/// # Parameters
+///
+/// * `task_id` (in) — Task whose event mask is to be returned.
+/// * `event` (out) — Reference to the memory of the return data.
+///
+/// # Particularities
+///
+/// Allowed on task level, ISR level and in several hook routines.
+///
+/// # Status
+///
+/// * **Standard:**
+///   * `E_OK` — No error.
+/// * **Extended:**
+///   * `E_OS_ID` — `task_id` is invalid.
+///   * `E_OS_ACCESS` — `task_id` is not an extended task.
+///   * `E_OS_STATE` — Task `task_id` is in the suspended state.
+///
+/// # Safety
+///
+/// `event` must be valid for a write.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn GetEvent(task_id: TaskType, event: EventMaskRefType) -> StatusType {
+    let status = match critical_section::with(|cs| KERNEL.borrow_ref(cs).get_event(task_id)) {
+        Ok(mask) => {
+            unsafe { *event = mask };
+            StatusType::EOk
+        }
+        Err(status) => status,
+    };
+    hooks::report(status)
+}
+
+/// Waits until at least one of the events in `mask` is set.
+///
+/// # Syntax
+/// ```ignore
+/// StatusType WaitEvent(EventMaskType Mask)
+/// ```
+///
+/// # Parameters
+///
+/// * `mask` (in) — Mask of the events waited for.
+///
+/// # Description
+///
+/// The calling task is transferred into the waiting state, unless at least
+/// one of the events in `mask` is already set. The task returns to the
+/// ready state as soon as one of them is set.
+///
+/// # Particularities
+///
+/// Restricted to extended tasks. A rescheduling takes place if the task
+/// starts waiting, its internal resource being released until it runs again.
+///
+/// # Status
+///
+/// * **Standard:**
+///   * `E_OK` — No error.
+/// * **Extended:**
+///   * `E_OS_ACCESS` — Call not from an extended task.
+///   * `E_OS_RESOURCE` — Calling task occupies resources.
+///   * `E_OS_CALLEVEL` — Call at interrupt level.
+#[unsafe(no_mangle)]
+pub extern "C" fn WaitEvent(mask: EventMaskType) -> StatusType {
+    hooks::report(critical_section::with(|cs| {
+        KERNEL.borrow_ref_mut(cs).wait_event(mask)
+    }))
+}
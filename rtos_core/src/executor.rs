@@ -0,0 +1,329 @@
+//! Async executor running inside an extended task.
+//!
+//! An [`Executor`] polls futures on behalf of the extended task that calls
+//! it. Its wakers set an OSEK event of that task, so when no future can make
+//! progress the task waits in `WaitEvent` and lower-priority tasks run, and
+//! a waker called from an ISR or from another task makes it ready again.
+//! Drivers taking a `Waker`, such as those of `embedded-hal-async`, thus work
+//! unchanged next to C and Rust tasks.
+//!
+//! [`Timer`] futures are built on an alarm of the executor, configured to
+//! set the same event. The pending timers of the task share that alarm,
+//! which is always set to expire at the first of their deadlines.
+//!
+//! ```ignore
+//! static EXECUTOR: Executor = Executor::new(WAKE, POLL_ALARM, Duration::from_millis(1));
+//!
+//! #[task]
+//! fn poller() -> ! {
+//!     EXECUTOR
+//!         .run([pin!(blink()), pin!(read_sensor())])
+//!         .unwrap();
+//!     loop {}
+//! }
+//!
+//! async fn blink() {
+//!     loop {
+//!         LED.toggle();
+//!         Timer::after(Duration::from_millis(500)).await;
+//!     }
+//! }
+//! ```
+
+use core::{
+    cell::RefCell,
+    future::Future,
+    pin::{Pin, pin},
+    ptr,
+    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+    time::Duration,
+};
+
+use critical_section::{CriticalSection, Mutex};
+#[cfg(debug_assertions)]
+use defmt::info;
+
+use crate::{
+    alarms::{self, AlarmAction, AlarmType, SOFTW_ALARMS, TickType},
+    os::{Alarm, Event, OsError, Task},
+    task::{KERNEL, MAX_TASKS, TaskType},
+};
+
+/// Maximum number of timers pending at once, all executors together.
+pub const MAX_TIMERS: usize = 16;
+
+/// Runs futures in the calling extended task, see the module documentation.
+pub struct Executor {
+    /// Single event of the task, set by the wakers and by `alarm`.
+    event: Event,
+    /// Alarm of the timers, whose action sets `event` for the task.
+    alarm: Alarm,
+    /// Duration of a kernel tick.
+    tick: Duration,
+}
+
+/// Alarm and tick duration of the executor running in a task.
+#[derive(Clone, Copy)]
+struct Running {
+    alarm: AlarmType,
+    tick: Duration,
+}
+
+/// The entry of a timer: its task, deadline in kernel ticks and waker,
+/// taken when the deadline passes. The entry lives as long as the timer.
+struct Pending {
+    task: TaskType,
+    deadline: TickType,
+    waker: Option<Waker>,
+}
+
+static RUNNING: Mutex<RefCell<[Option<Running>; MAX_TASKS]>> =
+    Mutex::new(RefCell::new([None; MAX_TASKS]));
+
+static TIMERS: Mutex<RefCell<[Option<Pending>; MAX_TIMERS]>> =
+    Mutex::new(RefCell::new([const { None }; MAX_TIMERS]));
+
+impl Executor {
+    /// An executor woken up by `event`, a single event. `alarm` must set
+    /// `event` for the task the executor runs in, and `tick` is the
+    /// duration of a kernel tick.
+    pub const fn new(event: Event, alarm: Alarm, tick: Duration) -> Self {
+        Executor { event, alarm, tick }
+    }
+
+    /// Polls `future` until it completes, waiting for the event in between,
+    /// and returns its output.
+    ///
+    /// Fails with `E_OS_CALLEVEL` outside of a task, `E_OS_STATE` if the
+    /// task already runs an executor, `E_OS_VALUE` if the event is not a
+    /// single event or is not what the alarm sets for the task, and with
+    /// the errors of `WaitEvent` and `ClearEvent`.
+    pub fn block_on<F: Future>(&self, future: F) -> Result<F::Output, OsError> {
+        let task = self.enter()?;
+        let waker = waker(task, self.event);
+        let mut cx = Context::from_waker(&waker);
+        let mut future = pin!(future);
+        let output = loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                break Ok(output);
+            }
+            if let Err(error) = self.park(task) {
+                break Err(error);
+            }
+        };
+        leave(task);
+        output
+    }
+
+    /// Polls all of `futures` concurrently until they have all completed.
+    /// Fails as [`Executor::block_on`] does.
+    pub fn run<const N: usize>(
+        &self,
+        mut futures: [Pin<&mut dyn Future<Output = ()>>; N],
+    ) -> Result<(), OsError> {
+        let mut done = [false; N];
+        self.block_on(core::future::poll_fn(|cx| {
+            for (future, done) in futures.iter_mut().zip(&mut done) {
+                if !*done {
+                    *done = future.as_mut().poll(cx).is_ready();
+                }
+            }
+            if done.iter().all(|done| *done) {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        }))
+    }
+
+    /// Checks the executor and makes it the one of the calling task.
+    fn enter(&self) -> Result<TaskType, OsError> {
+        let task = Task::current().ok_or(OsError::CallLevel)?.id();
+        let mask = self.event.mask();
+        if !mask.is_power_of_two() {
+            return Err(OsError::Value);
+        }
+        let alarm = self.alarm.id();
+        match alarms::configured().get(alarm).map(|alarm| alarm.action) {
+            Some(AlarmAction::SetEvent(target, event)) if target == task && event == mask => (),
+            Some(_) => return Err(OsError::Value),
+            None => return Err(OsError::Id),
+        }
+        critical_section::with(|cs| {
+            let mut running = RUNNING.borrow_ref_mut(cs);
+            if running[task].is_some() {
+                return Err(OsError::State);
+            }
+            running[task] = Some(Running {
+                alarm,
+                tick: self.tick,
+            });
+            Ok(task)
+        })
+    }
+
+    /// Waits for the event, then wakes the timers that expired meanwhile.
+    fn park(&self, task: TaskType) -> Result<(), OsError> {
+        self.event.wait()?;
+        expire(task);
+        // Set again by any wake from here on, for the next round
+        self.event.clear()
+    }
+}
+
+/// Forgets the executor of `task`. Its timers keep their entries until they
+/// are dropped.
+fn leave(task: TaskType) {
+    critical_section::with(|cs| {
+        if let Some(running) = RUNNING.borrow_ref_mut(cs)[task].take() {
+            let _ = alarms::cancel_alarm(running.alarm);
+        }
+    });
+}
+
+/// Wakes the expired timers of `task`, outside of the critical section as
+/// a waker may run anything, and sets the alarm for the others.
+fn expire(task: TaskType) {
+    let mut expired = [const { None }; MAX_TIMERS];
+    critical_section::with(|cs| {
+        let now = KERNEL.borrow_ref(cs).ticks;
+        let mut timers = TIMERS.borrow_ref_mut(cs);
+        for (timer, expired) in timers.iter_mut().flatten().zip(&mut expired) {
+            if timer.task == task && timer.deadline.wrapping_sub(now) <= 0 {
+                *expired = timer.waker.take();
+            }
+        }
+        arm(cs, task, &timers);
+    });
+    for waker in expired.into_iter().flatten() {
+        waker.wake();
+    }
+}
+
+/// Sets the alarm of the executor of `task` to expire at the first deadline
+/// of its timers, or cancels it.
+fn arm(cs: CriticalSection, task: TaskType, timers: &[Option<Pending>; MAX_TIMERS]) {
+    let Some(running) = RUNNING.borrow_ref(cs)[task] else {
+        return;
+    };
+    let now = KERNEL.borrow_ref(cs).ticks;
+    let _ = alarms::cancel_alarm(running.alarm);
+    let Some(ticks) = timers
+        .iter()
+        .flatten()
+        .filter(|timer| timer.task == task && timer.waker.is_some())
+        .map(|timer| timer.deadline.wrapping_sub(now).max(1))
+        .min()
+    else {
+        return;
+    };
+    let base = SOFTW_ALARMS.borrow_ref(cs)[running.alarm].alarm_base;
+    // Too far away for the counter, the alarm expires early and is set
+    // again for the rest
+    let increment = ((ticks + base.ticksperbase - 1) / base.ticksperbase).min(base.maxallowedvalue);
+    let _ = alarms::set_rel_alarm(running.alarm, increment, 0);
+}
+
+/// Completes a given duration after its first poll. Only polled by an
+/// [`Executor`], on the task of which it must stay.
+pub struct Timer {
+    duration: Duration,
+    /// Deadline in kernel ticks, set by the first poll.
+    deadline: Option<TickType>,
+    /// Entry in `TIMERS` while pending.
+    slot: Option<usize>,
+}
+
+impl Timer {
+    pub const fn after(duration: Duration) -> Timer {
+        Timer {
+            duration,
+            deadline: None,
+            slot: None,
+        }
+    }
+}
+
+impl Future for Timer {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let timer = self.get_mut();
+        let task = Task::current()
+            .map(Task::id)
+            .expect("timer polled outside of a task");
+        critical_section::with(|cs| {
+            let Some(running) = RUNNING.borrow_ref(cs)[task] else {
+                panic!("timer polled outside of an executor");
+            };
+            let now = KERNEL.borrow_ref(cs).ticks;
+            let deadline = *timer.deadline.get_or_insert_with(|| {
+                let ticks = timer
+                    .duration
+                    .as_nanos()
+                    .div_ceil(running.tick.as_nanos().max(1));
+                now.wrapping_add(ticks.min(TickType::MAX as u128 / 2) as TickType)
+            });
+            let mut timers = TIMERS.borrow_ref_mut(cs);
+            if deadline.wrapping_sub(now) <= 0 {
+                if let Some(slot) = timer.slot.take() {
+                    timers[slot] = None;
+                }
+                return Poll::Ready(());
+            }
+            let slot = match timer.slot {
+                Some(slot) => slot,
+                None => {
+                    let Some(slot) = timers.iter().position(Option::is_none) else {
+                        panic!("more than {} timers pending", MAX_TIMERS);
+                    };
+                    #[cfg(debug_assertions)]
+                    info!("Timer {} of task {} due at tick {}", slot, task, deadline);
+                    timer.slot = Some(slot);
+                    slot
+                }
+            };
+            timers[slot] = Some(Pending {
+                task,
+                deadline,
+                waker: Some(cx.waker().clone()),
+            });
+            arm(cs, task, &timers);
+            Poll::Pending
+        })
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        if let Some(slot) = self.slot {
+            critical_section::with(|cs| TIMERS.borrow_ref_mut(cs)[slot] = None);
+        }
+    }
+}
+
+/// A waker setting `event` for `task`, both packed into its data pointer:
+/// the event number in the low 5 bits, the task above.
+fn waker(task: TaskType, event: Event) -> Waker {
+    let data = (task << 5) | event.mask().trailing_zeros() as usize;
+    unsafe { Waker::from_raw(RawWaker::new(ptr::without_provenance(data), &VTABLE)) }
+}
+
+static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake, forget);
+
+unsafe fn clone(data: *const ()) -> RawWaker {
+    RawWaker::new(data, &VTABLE)
+}
+
+unsafe fn wake(data: *const ()) {
+    let data = data.addr();
+    // Once the executor is gone its task may be suspended, the wake is
+    // then lost on purpose rather than reported to the ErrorHook
+    let _ = critical_section::with(|cs| {
+        KERNEL
+            .borrow_ref_mut(cs)
+            .set_event(data >> 5, 1 << (data & 31))
+    });
+}
+
+unsafe fn forget(_: *const ()) {}
@@ -0,0 +1,65 @@
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{
+    StatusType,
+    alarms::TickType,
+    reset::ResetReasonType,
+    task::{KERNEL, TaskType},
+};
+
+/// Hook routines of the application, as selected by the `USE*HOOK`
+/// attributes of the OIL `OS` object. `None` disables a hook.
+#[derive(Clone, Copy)]
+pub struct Hooks {
+    /// Called at the end of the OS start-up, before any task runs, with the
+    /// cause of the last reset.
+    pub startup: Option<extern "C" fn(ResetReasonType)>,
+    /// Called by `ShutdownOS` with the error it was given.
+    pub shutdown: Option<extern "C" fn(StatusType)>,
+    /// Called whenever a system service returns something else than `E_OK`.
+    pub error: Option<extern "C" fn(StatusType)>,
+    /// Called after a task entered the running state.
+    pub pre_task: Option<extern "C" fn()>,
+    /// Called before a task leaves the running state.
+    pub post_task: Option<extern "C" fn()>,
+    /// Called once per activation the task has not terminated by its
+    /// deadline, from the first tick that finds it late, with the task and
+    /// the lateness in ticks. Without it, the ErrorHook gets `E_OS_LIMIT`.
+    pub deadline_miss: Option<extern "C" fn(TaskType, TickType)>,
+}
+
+impl Hooks {
+    pub const NONE: Hooks = Hooks {
+        startup: None,
+        shutdown: None,
+        error: None,
+        pre_task: None,
+        post_task: None,
+        deadline_miss: None,
+    };
+}
+
+impl Default for Hooks {
+    fn default() -> Self {
+        Hooks::NONE
+    }
+}
+
+static IN_ERROR_HOOK: AtomicBool = AtomicBool::new(false);
+
+/// Passes the result of a system service through the ErrorHook.
+///
+/// The ErrorHook is not called for services it calls itself.
+pub(crate) fn report(status: StatusType) -> StatusType {
+    if status == StatusType::EOk {
+        return status;
+    }
+    let hook = critical_section::with(|cs| KERNEL.borrow_ref(cs).hooks.error);
+    if let Some(hook) = hook
+        && !IN_ERROR_HOOK.swap(true, Ordering::Acquire)
+    {
+        hook(status);
+        IN_ERROR_HOOK.store(false, Ordering::Release);
+    }
+    status
+}
@@ -1,85 +1,174 @@
 #![no_std]
 #![feature(c_variadic)]
-// Use C-compatible core types
-use core::ffi::{CStr, c_char, c_int, c_void};
 use defmt::*;
-use defmt_rtt as _;
-use embedded_alloc::LlffHeap as Heap;
+#[cfg(target_os = "none")]
 use panic_probe as _;
 
-extern crate alloc;
-use alloc::string::String;
+pub mod abi;
 pub mod alarms;
+#[cfg(feature = "binary-info")]
+pub mod binary_info;
+pub mod crash;
+#[cfg(feature = "events")]
+pub mod events;
+/// cbindgen:ignore
+#[cfg(feature = "async")]
+pub mod executor;
+pub mod hooks;
+pub mod libc;
+pub mod memory;
+/// cbindgen:ignore
+pub mod os;
+mod port;
+#[cfg(feature = "low-power")]
+pub mod power;
+pub mod ready_queue;
+pub mod registry;
+pub mod reset;
+pub mod resources;
+#[cfg(target_os = "none")]
+pub mod rtt;
+#[cfg(feature = "shell")]
+pub mod shell;
+#[cfg(feature = "stats")]
+pub mod stats;
+pub mod stdio;
+pub mod stdout;
+#[cfg(feature = "systemview")]
+pub mod systemview;
+pub mod task;
+pub mod trace;
+#[cfg(feature = "watchdog")]
+pub mod watchdog;
 
+#[cfg(not(target_os = "none"))]
+pub use port::sim;
+
+/// Without the `binary-info` feature, the configuration is left out of the
+/// picotool binary info.
+#[cfg(not(feature = "binary-info"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! os_binary_info {
+    ($($feature:expr),* $(,)?) => {};
+}
+
+pub use hooks::Hooks;
+pub use resources::ResourceConfig;
+pub use task::{OsConfig, TaskConfig};
+
+/// Return status of the services, with the values and, in C, the names
+/// (`E_OK`, `E_OS_ACCESS`, ...) of the OSEK specification.
+///
+/// cbindgen:rename-all=ScreamingSnakeCase
 #[repr(C)]
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum StatusType {
     EOk = 0,
-    EOsId = 1,
-    EOsNoFunc = 2,
-    EOsValue = 3,
-    EOsState = 4,
+    EOsAccess = 1,
+    EOsCallevel = 2,
+    EOsId = 3,
+    EOsLimit = 4,
+    EOsNofunc = 5,
+    EOsResource = 6,
+    EOsState = 7,
+    EOsValue = 8,
+    /// Implementation-specific: the last reset was caused by a fault, see
+    /// `GetCrashRecord`.
+    EOsSysFault = 9,
 }
-#[global_allocator]
-static HEAP: Heap = Heap::empty();
-
-const HEAP_SIZE: usize = 1024 * 8;
-static mut HEAP_MEM: [core::mem::MaybeUninit<u8>; HEAP_SIZE] =
-    [core::mem::MaybeUninit::uninit(); HEAP_SIZE];
 
-pub fn start_os() -> ! {
-    unsafe {
-        HEAP.init(
-            core::ptr::addr_of_mut!(HEAP_MEM).cast::<u8>() as usize,
-            HEAP_SIZE,
-        );
-    }
-
-    ActivateTask();
-
-    #[cfg(debug_assertions)]
-    info!("Control back to start_os");
-    loop {}
+/// OSEK conformance class, selected with the `bcc1`, `bcc2`, `ecc1` and
+/// `ecc2` features.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub enum ConformanceClass {
+    /// Basic tasks, one activation, one task per priority.
+    Bcc1,
+    /// BCC1 plus multiple activations and several tasks per priority.
+    Bcc2,
+    /// BCC1 plus extended tasks and events.
+    Ecc1,
+    /// BCC2 plus extended tasks and events.
+    Ecc2,
 }
 
-unsafe extern "C" {
-    fn Task1() -> c_void;
+/// Conformance class the kernel was built for.
+pub const CONFORMANCE_CLASS: ConformanceClass = match (
+    cfg!(feature = "multiple-activations"),
+    cfg!(feature = "events"),
+) {
+    (false, false) => ConformanceClass::Bcc1,
+    (true, false) => ConformanceClass::Bcc2,
+    (false, true) => ConformanceClass::Ecc1,
+    (true, true) => ConformanceClass::Ecc2,
+};
 
-}
+#[cfg(target_os = "none")]
+pub fn start_os(config: &'static OsConfig) -> ! {
+    rtt::init();
+    memory::init_heap(config);
 
-#[unsafe(no_mangle)]
-pub extern "C" fn ActivateTask() -> StatusType {
-    // TODO: lookup & enqueue in your scheduler
-    unsafe {
-        Task1();
-    }
-    StatusType::EOk
+    init_os(config);
+    port::start()
 }
 
-#[unsafe(no_mangle)]
-pub extern "C" fn TerminateTask() {
-    // TODO: context switch out
-}
+/// Brings the kernel up: checks and loads `config`, runs the StartupHook and
+/// lets the scheduler dispatch the autostart tasks.
+fn init_os(config: &'static OsConfig) {
+    let config = match registry::with_registered(config) {
+        Ok(config) => config,
+        Err(reason) => defmt::panic!("Invalid OS configuration: {}", reason),
+    };
+    if let Err(reason) = task::check_config(config)
+        .and_then(|()| alarms::check_config(config))
+        .and_then(|()| memory::check_config(config))
+    {
+        defmt::panic!("Invalid OS configuration: {}", reason);
+    }
+    port::init();
+    trace::init();
+    #[cfg(feature = "systemview")]
+    systemview::start(config);
+    #[cfg(feature = "stats")]
+    stats::init();
+    alarms::init(config);
+    memory::init(config);
+    #[cfg(feature = "watchdog")]
+    watchdog::start();
+    critical_section::with(|cs| task::KERNEL.borrow_ref_mut(cs).init(config));
+    let reason = reset::init(crash::init());
 
-#[unsafe(no_mangle)]
-pub extern "C" fn print(input: *const c_char) {
-    let c_str = unsafe { CStr::from_ptr(input) };
+    if let Some(hook) = config.hooks.startup {
+        hook(reason);
+    }
 
-    let r_str = match c_str.to_str() {
-        Ok(s) => s,
-        Err(_) => {
-            info!("Invalid Strings");
-            return;
-        }
-    };
-    info!("{}", r_str);
+    #[cfg(debug_assertions)]
+    info!("Starting scheduler");
+    critical_section::with(|cs| task::KERNEL.borrow_ref_mut(cs).start());
 }
 
+/// Shuts the OS down.
+///
+/// # Syntax
+/// ```ignore
+/// void ShutdownOS(StatusType Error)
+/// ```
+///
+/// # Parameters
+///
+/// * `error` (in) — Error that caused the shutdown.
+///
+/// # Description
+///
+/// Calls the ShutdownHook with `error`, then stops all OS activity. This
+/// service never returns.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn printf(str: *const c_char, mut args: ...) -> c_int {
-    use printf_compat::{format, output};
-    let mut s = String::new();
-    let bytes_written = unsafe { format(str, args.as_va_list(), output::fmt_write(&mut s)) };
-    println!("{}", s.as_str());
-    bytes_written
+pub extern "C" fn ShutdownOS(error: StatusType) -> ! {
+    #[cfg(debug_assertions)]
+    info!("Shutting down, error {}", error as u8);
+    let hook = critical_section::with(|cs| task::KERNEL.borrow_ref(cs).hooks.shutdown);
+    if let Some(hook) = hook {
+        hook(error);
+    }
+    port::shutdown()
 }
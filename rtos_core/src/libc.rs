@@ -0,0 +1,467 @@
+//! The part of the C standard library the C tasks get, declared by
+//! `tasks/string.h` and `tasks/stdlib.h`.
+//!
+//! The tasks are compiled with `-ffreestanding -fno-builtin` and linked
+//! without a C library. `memcpy`, `memmove`, `memset`, `memcmp` and
+//! `strlen` come from `compiler_builtins`, which every Rust target without
+//! an OS links, and the rest is implemented here:
+//!
+//! * `string.h`: `strnlen`, `strcmp`, `strncmp`, `strcpy`, `strncpy`,
+//!   `strcat`, `strchr`, `strrchr`, `memchr`;
+//! * `stdlib.h`: `abs`, `labs`, `atoi`, `atol`, `strtol`, `qsort`.
+//!
+//! None of them allocates or blocks, so all of them are allowed on task
+//! level and ISR level. The symbols are only exported on the target: the
+//! host simulation links the C library of the host.
+
+use core::ffi::{c_char, c_int, c_long, c_void};
+
+/// Comparison function of [`qsort`], negative, zero or positive as its
+/// first element sorts before, with or after its second one.
+pub type CompareFn = unsafe extern "C" fn(*const c_void, *const c_void) -> c_int;
+
+/// Length of `s`, at most `max`.
+///
+/// # Safety
+///
+/// `s` must be NUL-terminated or valid for `max` bytes.
+#[cfg_attr(target_os = "none", unsafe(no_mangle))]
+pub unsafe extern "C" fn strnlen(s: *const c_char, max: usize) -> usize {
+    let mut len = 0;
+    while len < max && unsafe { *s.add(len) } != 0 {
+        len += 1;
+    }
+    len
+}
+
+/// Compares `s1` and `s2` as `unsigned char`.
+///
+/// # Safety
+///
+/// Both strings must be NUL-terminated.
+#[cfg_attr(target_os = "none", unsafe(no_mangle))]
+pub unsafe extern "C" fn strcmp(s1: *const c_char, s2: *const c_char) -> c_int {
+    unsafe { strncmp(s1, s2, usize::MAX) }
+}
+
+/// Compares at most `n` characters of `s1` and `s2` as `unsigned char`.
+///
+/// # Safety
+///
+/// Both strings must be NUL-terminated or valid for `n` bytes.
+#[cfg_attr(target_os = "none", unsafe(no_mangle))]
+pub unsafe extern "C" fn strncmp(s1: *const c_char, s2: *const c_char, n: usize) -> c_int {
+    for i in 0..n {
+        let (a, b) = unsafe { (*s1.add(i) as u8, *s2.add(i) as u8) };
+        if a != b || a == 0 {
+            return c_int::from(a) - c_int::from(b);
+        }
+    }
+    0
+}
+
+/// Copies `src`, its NUL byte included, to `dst` and returns `dst`.
+///
+/// # Safety
+///
+/// `src` must be NUL-terminated, and `dst` large enough for it and not
+/// overlapping it.
+#[cfg_attr(target_os = "none", unsafe(no_mangle))]
+pub unsafe extern "C" fn strcpy(dst: *mut c_char, src: *const c_char) -> *mut c_char {
+    let mut i = 0;
+    loop {
+        let c = unsafe { *src.add(i) };
+        unsafe { *dst.add(i) = c };
+        if c == 0 {
+            return dst;
+        }
+        i += 1;
+    }
+}
+
+/// Copies at most `n` characters of `src` to `dst`, pads `dst` with NUL
+/// bytes up to `n` and returns it.
+///
+/// As in C, `dst` is not terminated if `src` is `n` characters or longer.
+///
+/// # Safety
+///
+/// `src` must be NUL-terminated or valid for `n` bytes, and `dst` valid
+/// for `n` bytes and not overlapping it.
+#[cfg_attr(target_os = "none", unsafe(no_mangle))]
+pub unsafe extern "C" fn strncpy(dst: *mut c_char, src: *const c_char, n: usize) -> *mut c_char {
+    let len = unsafe { strnlen(src, n) };
+    for i in 0..n {
+        unsafe { *dst.add(i) = if i < len { *src.add(i) } else { 0 } };
+    }
+    dst
+}
+
+/// Appends `src` to `dst` and returns `dst`.
+///
+/// # Safety
+///
+/// Both strings must be NUL-terminated, and `dst` large enough for both
+/// and not overlapping `src`.
+#[cfg_attr(target_os = "none", unsafe(no_mangle))]
+pub unsafe extern "C" fn strcat(dst: *mut c_char, src: *const c_char) -> *mut c_char {
+    unsafe { strcpy(dst.add(strnlen(dst, usize::MAX)), src) };
+    dst
+}
+
+/// First occurrence of `c` converted to `char` in `s`, its NUL byte
+/// included, or null.
+///
+/// # Safety
+///
+/// `s` must be NUL-terminated.
+#[cfg_attr(target_os = "none", unsafe(no_mangle))]
+pub unsafe extern "C" fn strchr(s: *const c_char, c: c_int) -> *mut c_char {
+    let c = c as c_char;
+    let mut p = s;
+    loop {
+        match unsafe { *p } {
+            found if found == c => return p.cast_mut(),
+            0 => return core::ptr::null_mut(),
+            _ => p = unsafe { p.add(1) },
+        }
+    }
+}
+
+/// Last occurrence of `c` converted to `char` in `s`, its NUL byte
+/// included, or null.
+///
+/// # Safety
+///
+/// `s` must be NUL-terminated.
+#[cfg_attr(target_os = "none", unsafe(no_mangle))]
+pub unsafe extern "C" fn strrchr(s: *const c_char, c: c_int) -> *mut c_char {
+    let c = c as c_char;
+    let mut last = core::ptr::null_mut();
+    let mut p = s;
+    loop {
+        let found = unsafe { *p };
+        if found == c {
+            last = p.cast_mut();
+        }
+        if found == 0 {
+            return last;
+        }
+        p = unsafe { p.add(1) };
+    }
+}
+
+/// First occurrence of `c` converted to `unsigned char` in the `n` bytes
+/// at `s`, or null.
+///
+/// # Safety
+///
+/// `s` must be valid for `n` bytes.
+#[cfg_attr(target_os = "none", unsafe(no_mangle))]
+pub unsafe extern "C" fn memchr(s: *const c_void, c: c_int, n: usize) -> *mut c_void {
+    let bytes = s.cast::<u8>();
+    for i in 0..n {
+        if unsafe { *bytes.add(i) } == c as u8 {
+            return unsafe { bytes.add(i) }.cast_mut().cast();
+        }
+    }
+    core::ptr::null_mut()
+}
+
+/// Absolute value of `n`. `INT_MIN` is returned unchanged.
+#[cfg_attr(target_os = "none", unsafe(no_mangle))]
+pub extern "C" fn abs(n: c_int) -> c_int {
+    n.wrapping_abs()
+}
+
+/// Absolute value of `n`. `LONG_MIN` is returned unchanged.
+#[cfg_attr(target_os = "none", unsafe(no_mangle))]
+pub extern "C" fn labs(n: c_long) -> c_long {
+    n.wrapping_abs()
+}
+
+/// Converts the decimal number at the start of `s`.
+///
+/// # Safety
+///
+/// `s` must be NUL-terminated.
+#[cfg_attr(target_os = "none", unsafe(no_mangle))]
+pub unsafe extern "C" fn atoi(s: *const c_char) -> c_int {
+    unsafe { atol(s) as c_int }
+}
+
+/// Converts the decimal number at the start of `s`, as [`strtol`] in base
+/// 10 does.
+///
+/// # Safety
+///
+/// `s` must be NUL-terminated.
+#[cfg_attr(target_os = "none", unsafe(no_mangle))]
+pub unsafe extern "C" fn atol(s: *const c_char) -> c_long {
+    unsafe { strtol(s, core::ptr::null_mut(), 10) }
+}
+
+/// Converts the number in base `base` at the start of `s`: leading white
+/// space, an optional sign, then digits up to the first other character.
+/// Base 16 takes an optional `0x` or `0X` prefix, and base 0 stands for
+/// 16 after such a prefix, 8 after a leading `0` and 10 otherwise. Unless
+/// `end` is null, `*end` is set past the last digit, or to `s` if there is
+/// none.
+///
+/// Out of range values saturate to `LONG_MIN` or `LONG_MAX`; there is no
+/// `errno`. A string without digits, or a base other than 0 or 2 to 36,
+/// converts to `0`.
+///
+/// # Safety
+///
+/// `s` must be NUL-terminated, and `end` null or valid for writes.
+#[cfg_attr(target_os = "none", unsafe(no_mangle))]
+pub unsafe extern "C" fn strtol(s: *const c_char, end: *mut *mut c_char, base: c_int) -> c_long {
+    let at = |p: *const u8| unsafe { *p };
+    let mut p = s.cast::<u8>();
+    let mut value = 0;
+    let mut parsed = None;
+    if base == 0 || (2..=36).contains(&base) {
+        while matches!(at(p), b' ' | b'\t' | b'\n' | b'\x0b' | b'\x0c' | b'\r') {
+            p = unsafe { p.add(1) };
+        }
+        let negative = at(p) == b'-';
+        if matches!(at(p), b'-' | b'+') {
+            p = unsafe { p.add(1) };
+        }
+        let mut base = base as u32;
+        let hex_prefix = at(p) == b'0' && matches!(at(unsafe { p.add(1) }), b'x' | b'X');
+        if hex_prefix && (base == 0 || base == 16) {
+            // Without a digit after it, the prefix is the number 0
+            parsed = Some(unsafe { p.add(1) });
+            p = unsafe { p.add(2) };
+            base = 16;
+        } else if base == 0 {
+            base = if at(p) == b'0' { 8 } else { 10 };
+        }
+        let limit = if negative {
+            c_long::MIN.unsigned_abs()
+        } else {
+            c_long::MAX.unsigned_abs()
+        };
+        let mut magnitude: Option<core::ffi::c_ulong> = Some(0);
+        while let Some(digit) = char::from(at(p)).to_digit(base) {
+            magnitude = magnitude
+                .and_then(|magnitude| magnitude.checked_mul(base.into()))
+                .and_then(|magnitude| magnitude.checked_add(digit.into()))
+                .filter(|&magnitude| magnitude <= limit);
+            p = unsafe { p.add(1) };
+            parsed = Some(p);
+        }
+        let magnitude = magnitude.unwrap_or(limit);
+        value = if negative {
+            (magnitude as c_long).wrapping_neg()
+        } else {
+            magnitude as c_long
+        };
+    }
+    if !end.is_null() {
+        unsafe { *end = parsed.unwrap_or(s.cast()).cast_mut().cast() };
+    }
+    value
+}
+
+/// Sorts the `count` elements of `size` bytes at `base` in ascending order
+/// of `compare`.
+///
+/// A heapsort: O(n log n) comparisons in the worst case, no recursion and
+/// no memory besides the stack frame. The order of equal elements is not
+/// kept.
+///
+/// # Safety
+///
+/// `base` must be valid for `count * size` bytes, and `compare` must be
+/// consistent and not modify the elements.
+#[cfg_attr(target_os = "none", unsafe(no_mangle))]
+pub unsafe extern "C" fn qsort(base: *mut c_void, count: usize, size: usize, compare: CompareFn) {
+    if count < 2 || size == 0 {
+        return;
+    }
+    let elems = Elements {
+        base: base.cast(),
+        size,
+        compare,
+    };
+    for root in (0..count / 2).rev() {
+        unsafe { elems.sift_down(root, count) };
+    }
+    for end in (1..count).rev() {
+        unsafe {
+            elems.swap(0, end);
+            elems.sift_down(0, end);
+        }
+    }
+}
+
+/// The array [`qsort`] works on.
+struct Elements {
+    base: *mut u8,
+    size: usize,
+    compare: CompareFn,
+}
+
+impl Elements {
+    unsafe fn at(&self, i: usize) -> *mut u8 {
+        unsafe { self.base.add(i * self.size) }
+    }
+
+    unsafe fn less(&self, i: usize, j: usize) -> bool {
+        unsafe { (self.compare)(self.at(i).cast(), self.at(j).cast()) < 0 }
+    }
+
+    unsafe fn swap(&self, i: usize, j: usize) {
+        unsafe { core::ptr::swap_nonoverlapping(self.at(i), self.at(j), self.size) };
+    }
+
+    /// Moves the element at `root` down the heap made of the first `end`
+    /// elements, until it is no smaller than its children.
+    unsafe fn sift_down(&self, mut root: usize, end: usize) {
+        loop {
+            let mut child = 2 * root + 1;
+            if child >= end {
+                return;
+            }
+            if child + 1 < end && unsafe { self.less(child, child + 1) } {
+                child += 1;
+            }
+            if !unsafe { self.less(root, child) } {
+                return;
+            }
+            unsafe { self.swap(root, child) };
+            root = child;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use core::{ffi::CStr, ptr};
+    use std::{ffi::CString, format};
+
+    use super::*;
+
+    #[test]
+    fn strncmp_compares_unsigned_up_to_n() {
+        unsafe {
+            assert_eq!(strncmp(c"abcd".as_ptr(), c"abcx".as_ptr(), 3), 0);
+            assert!(strncmp(c"abcd".as_ptr(), c"abcx".as_ptr(), 4) < 0);
+            assert!(strncmp(c"ab".as_ptr(), c"abc".as_ptr(), 8) < 0);
+            assert!(strncmp(c"\xff".as_ptr(), c"a".as_ptr(), 1) > 0);
+            assert_eq!(strncmp(c"a".as_ptr(), c"b".as_ptr(), 0), 0);
+        }
+    }
+
+    #[test]
+    fn strrchr_finds_the_last_occurrence() {
+        let s = c"a/b/c";
+        unsafe {
+            assert_eq!(
+                strrchr(s.as_ptr(), b'/'.into()),
+                s.as_ptr().add(3).cast_mut()
+            );
+            assert_eq!(strrchr(s.as_ptr(), 0), s.as_ptr().add(5).cast_mut());
+            assert!(strrchr(s.as_ptr(), b'x'.into()).is_null());
+        }
+    }
+
+    #[test]
+    fn atol_skips_white_space_and_takes_a_sign() {
+        unsafe {
+            assert_eq!(atol(c" \t\n-42abc".as_ptr()), -42);
+            assert_eq!(atol(c"+17".as_ptr()), 17);
+            assert_eq!(atol(c"- 1".as_ptr()), 0);
+            assert_eq!(atol(c"".as_ptr()), 0);
+            assert_eq!(atoi(c"  -2147483648".as_ptr()), c_int::MIN);
+        }
+    }
+
+    #[test]
+    fn atol_saturates_on_overflow() {
+        unsafe {
+            assert_eq!(atol(c"99999999999999999999".as_ptr()), c_long::MAX);
+            assert_eq!(atol(c"-99999999999999999999".as_ptr()), c_long::MIN);
+        }
+    }
+
+    /// `strtol` of `s` in `base`, and the number of characters it parsed.
+    fn parse(s: &CStr, base: c_int) -> (c_long, usize) {
+        let mut end = ptr::null_mut();
+        let value = unsafe { strtol(s.as_ptr(), &mut end, base) };
+        (value, unsafe { end.offset_from(s.as_ptr()) } as usize)
+    }
+
+    #[test]
+    fn strtol_parses_bases_and_sets_end() {
+        assert_eq!(parse(c"  -0x1fz", 0), (-0x1f, 7));
+        assert_eq!(parse(c"0X1F", 16), (0x1f, 4));
+        assert_eq!(parse(c"017", 0), (0o17, 3));
+        assert_eq!(parse(c"0x", 0), (0, 1));
+        assert_eq!(parse(c"1012", 2), (0b101, 3));
+        assert_eq!(parse(c"zZ", 36), (36 * 35 + 35, 2));
+        assert_eq!(parse(c"  +", 10), (0, 0));
+        assert_eq!(parse(c"12", 1), (0, 0));
+        assert_eq!(parse(c"12", 37), (0, 0));
+    }
+
+    #[test]
+    fn strtol_saturates_and_parses_all_digits() {
+        for limit in [c_long::MAX, c_long::MIN] {
+            let text = CString::new(format!("{limit}")).unwrap();
+            assert_eq!(parse(&text, 10), (limit, text.count_bytes()));
+        }
+        assert_eq!(parse(c"-99999999999999999999x", 10), (c_long::MIN, 21));
+        assert_eq!(parse(c"ffffffffffffffffffff", 16), (c_long::MAX, 20));
+    }
+
+    unsafe extern "C" fn compare_ints(a: *const c_void, b: *const c_void) -> c_int {
+        let (a, b) = unsafe { (*a.cast::<i32>(), *b.cast::<i32>()) };
+        a.cmp(&b) as c_int
+    }
+
+    #[test]
+    fn qsort_sorts_in_ascending_order() {
+        let mut values = [5, -3, 9, 0, 9, 2, -8, 1, 7, 3, 3];
+        let mut expected = values;
+        expected.sort();
+        unsafe { qsort(values.as_mut_ptr().cast(), values.len(), 4, compare_ints) };
+        assert_eq!(values, expected);
+        let mut one = [1];
+        unsafe { qsort(one.as_mut_ptr().cast(), 1, 4, compare_ints) };
+        assert_eq!(one, [1]);
+    }
+
+    #[test]
+    fn qsort_moves_whole_elements() {
+        let mut pairs = [[3u8, b'c'], [1, b'a'], [2, b'b']];
+        unsafe extern "C" fn by_key(a: *const c_void, b: *const c_void) -> c_int {
+            let (a, b) = unsafe { (*a.cast::<u8>(), *b.cast::<u8>()) };
+            c_int::from(a) - c_int::from(b)
+        }
+        unsafe { qsort(pairs.as_mut_ptr().cast(), 3, 2, by_key) };
+        assert_eq!(pairs, [[1, b'a'], [2, b'b'], [3, b'c']]);
+    }
+
+    unsafe extern "C" {
+        /// The `memmove` the tasks link: from `compiler_builtins` on the
+        /// target, from the C library of the host here.
+        fn memmove(dst: *mut c_void, src: *const c_void, n: usize) -> *mut c_void;
+    }
+
+    #[test]
+    fn memmove_handles_overlap() {
+        let mut bytes = *b"abcdefgh";
+        let base = bytes.as_mut_ptr();
+        unsafe { memmove(base.add(2).cast(), base.cast(), 5) };
+        assert_eq!(&bytes, b"ababcdeh");
+        let base = bytes.as_mut_ptr();
+        unsafe { memmove(base.cast(), base.add(3).cast(), 5) };
+        assert_eq!(&bytes, b"bcdehdeh");
+    }
+}
@@ -0,0 +1,432 @@
+//! Memory services: fixed-block pools and the heap of the global allocator,
+//! both laid out by the [`OsConfig`].
+//!
+//! A pool is a static array of equally sized blocks. Its free blocks are
+//! chained through their first word, so `AllocBlock` and `FreeBlock` take
+//! constant time whatever the state of the pool and never fragment it. Both
+//! are allowed on task level and ISR level, which the heap is not.
+
+use core::{
+    cell::{RefCell, UnsafeCell},
+    ffi::c_void,
+    mem::MaybeUninit,
+};
+
+use critical_section::Mutex;
+#[cfg(debug_assertions)]
+use defmt::info;
+#[cfg(target_os = "none")]
+use embedded_alloc::LlffHeap as Heap;
+
+use crate::{OsConfig, StatusType, hooks};
+
+/// Maximum number of pools in a configuration.
+pub const MAX_POOLS: usize = 8;
+
+pub type PoolType = usize;
+pub type BlockRefType = *mut *mut c_void;
+pub type PoolInfoRefType = *mut PoolInfoType;
+
+/// One block of a pool. The alignment makes room for the free list link
+/// and suits any type.
+#[repr(C, align(8))]
+struct Block<const SIZE: usize>([u8; SIZE]);
+
+/// Statically allocated memory of a pool: `COUNT` blocks of `SIZE` bytes,
+/// rounded up to a multiple of 8.
+pub struct PoolMemory<const SIZE: usize, const COUNT: usize>(UnsafeCell<[Block<SIZE>; COUNT]>);
+
+unsafe impl<const SIZE: usize, const COUNT: usize> Sync for PoolMemory<SIZE, COUNT> {}
+
+impl<const SIZE: usize, const COUNT: usize> PoolMemory<SIZE, COUNT> {
+    pub const fn new() -> Self {
+        PoolMemory(UnsafeCell::new([const { Block([0; SIZE]) }; COUNT]))
+    }
+
+    pub const fn region(&'static self) -> PoolRegion {
+        PoolRegion {
+            base: self.0.get().cast(),
+            block_size: size_of::<Block<SIZE>>(),
+            blocks: COUNT,
+        }
+    }
+}
+
+impl<const SIZE: usize, const COUNT: usize> Default for PoolMemory<SIZE, COUNT> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Memory region holding the blocks of a pool.
+#[derive(Clone, Copy)]
+pub struct PoolRegion {
+    pub base: *mut u8,
+    pub block_size: usize,
+    pub blocks: usize,
+}
+
+// Pool regions are only written through the free list, always from inside
+// the critical section guarding it.
+unsafe impl Send for PoolRegion {}
+unsafe impl Sync for PoolRegion {}
+
+/// Static description of a pool. Pool IDs are indices into
+/// [`OsConfig::pools`].
+#[derive(Clone, Copy)]
+pub struct PoolConfig {
+    pub name: &'static str,
+    pub memory: PoolRegion,
+}
+
+/// Statically allocated memory of the global allocator, `SIZE` bytes.
+pub struct HeapMemory<const SIZE: usize>(UnsafeCell<[MaybeUninit<u8>; SIZE]>);
+
+unsafe impl<const SIZE: usize> Sync for HeapMemory<SIZE> {}
+
+impl<const SIZE: usize> HeapMemory<SIZE> {
+    pub const fn new() -> Self {
+        HeapMemory(UnsafeCell::new([MaybeUninit::uninit(); SIZE]))
+    }
+
+    pub const fn region(&'static self) -> HeapRegion {
+        HeapRegion {
+            base: self.0.get().cast(),
+            size: SIZE,
+        }
+    }
+}
+
+impl<const SIZE: usize> Default for HeapMemory<SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Memory region handed to the global allocator.
+#[derive(Clone, Copy)]
+pub struct HeapRegion {
+    pub base: *mut u8,
+    pub size: usize,
+}
+
+// The region is only handed to the allocator, once, by `start_os`.
+unsafe impl Send for HeapRegion {}
+unsafe impl Sync for HeapRegion {}
+
+/// Usage of a pool, as returned by `GetPoolInfo`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolInfoType {
+    /// Bytes per block, rounded up to a multiple of 8.
+    pub block_size: u32,
+    pub blocks: u32,
+    /// Blocks free right now.
+    pub free: u32,
+    /// Fewest blocks ever free since `StartOS`.
+    pub min_free: u32,
+    /// `AllocBlock` calls that found the pool exhausted.
+    pub failures: u32,
+}
+
+#[derive(Clone, Copy)]
+struct PoolState {
+    memory: PoolRegion,
+    /// First free block, null when the pool is exhausted.
+    free_list: *mut u8,
+    free: usize,
+    min_free: usize,
+    failures: u32,
+}
+
+// The free list points into the static pool memory
+unsafe impl Send for PoolState {}
+
+impl PoolState {
+    const fn new() -> Self {
+        PoolState {
+            memory: PoolRegion {
+                base: core::ptr::null_mut(),
+                block_size: 0,
+                blocks: 0,
+            },
+            free_list: core::ptr::null_mut(),
+            free: 0,
+            min_free: 0,
+            failures: 0,
+        }
+    }
+
+    /// Chains all the blocks of `memory` into the free list.
+    fn init(memory: PoolRegion) -> Self {
+        let mut free_list = core::ptr::null_mut();
+        for i in (0..memory.blocks).rev() {
+            let block = unsafe { memory.base.add(i * memory.block_size) };
+            unsafe { block.cast::<*mut u8>().write(free_list) };
+            free_list = block;
+        }
+        PoolState {
+            memory,
+            free_list,
+            free: memory.blocks,
+            min_free: memory.blocks,
+            failures: 0,
+        }
+    }
+
+    fn alloc(&mut self) -> Option<*mut u8> {
+        if self.free_list.is_null() {
+            self.failures = self.failures.wrapping_add(1);
+            return None;
+        }
+        let block = self.free_list;
+        self.free_list = unsafe { block.cast::<*mut u8>().read() };
+        self.free -= 1;
+        self.min_free = self.min_free.min(self.free);
+        Some(block)
+    }
+
+    /// Whether `block` is the start of one of the blocks of the pool.
+    fn owns(&self, block: *mut u8) -> bool {
+        let offset = (block as usize).wrapping_sub(self.memory.base as usize);
+        offset < self.memory.blocks * self.memory.block_size
+            && offset.is_multiple_of(self.memory.block_size)
+    }
+
+    fn free(&mut self, block: *mut u8) -> StatusType {
+        // A pool with all its blocks free cannot take one more back
+        if !self.owns(block) || self.free == self.memory.blocks {
+            return StatusType::EOsValue;
+        }
+        unsafe { block.cast::<*mut u8>().write(self.free_list) };
+        self.free_list = block;
+        self.free += 1;
+        StatusType::EOk
+    }
+
+    fn info(&self) -> PoolInfoType {
+        PoolInfoType {
+            block_size: self.memory.block_size as u32,
+            blocks: self.memory.blocks as u32,
+            free: self.free as u32,
+            min_free: self.min_free as u32,
+            failures: self.failures,
+        }
+    }
+}
+
+struct Pools {
+    states: [PoolState; MAX_POOLS],
+    count: usize,
+    names: [&'static str; MAX_POOLS],
+}
+
+static POOLS: Mutex<RefCell<Pools>> = Mutex::new(RefCell::new(Pools {
+    states: [PoolState::new(); MAX_POOLS],
+    count: 0,
+    names: [""; MAX_POOLS],
+}));
+
+#[cfg(target_os = "none")]
+#[global_allocator]
+static HEAP: Heap = Heap::empty();
+
+/// Checks the pools of `config`.
+pub(crate) fn check_config(config: &OsConfig) -> Result<(), &'static str> {
+    if config.pools.len() > MAX_POOLS {
+        return Err("too many pools");
+    }
+    for pool in config.pools {
+        if pool.memory.block_size < size_of::<*mut u8>() {
+            return Err("pool blocks must hold a pointer");
+        }
+        if !(pool.memory.base as usize).is_multiple_of(align_of::<*mut u8>())
+            || !pool.memory.block_size.is_multiple_of(align_of::<*mut u8>())
+        {
+            return Err("pool memory is misaligned");
+        }
+    }
+    Ok(())
+}
+
+/// Hands the heap of `config` to the global allocator. Only called once,
+/// the allocator cannot be moved to other memory.
+#[cfg(target_os = "none")]
+pub(crate) fn init_heap(config: &OsConfig) {
+    if let Some(heap) = config.heap {
+        unsafe { HEAP.init(heap.base as usize, heap.size) };
+    }
+}
+
+/// Builds the free lists of all the pools of `config`, every block free.
+pub(crate) fn init(config: &OsConfig) {
+    critical_section::with(|cs| {
+        let mut pools = POOLS.borrow_ref_mut(cs);
+        pools.count = config.pools.len();
+        for (id, pool) in config.pools.iter().enumerate() {
+            pools.states[id] = PoolState::init(pool.memory);
+            pools.names[id] = pool.name;
+        }
+    });
+}
+
+/// Bytes used and free in the heap.
+#[cfg(all(target_os = "none", feature = "shell"))]
+pub(crate) fn heap_usage() -> (usize, usize) {
+    (HEAP.used(), HEAP.free())
+}
+
+/// Name and usage of pool `pool_id`.
+#[cfg(feature = "shell")]
+pub(crate) fn pool_usage(pool_id: PoolType) -> Option<(&'static str, PoolInfoType)> {
+    critical_section::with(|cs| {
+        let pools = POOLS.borrow_ref(cs);
+        (pool_id < pools.count).then(|| (pools.names[pool_id], pools.states[pool_id].info()))
+    })
+}
+
+/// Takes a block from a pool.
+///
+/// # Syntax
+/// ```ignore
+/// StatusType AllocBlock(PoolType PoolID, void **Block)
+/// ```
+///
+/// # Parameters
+///
+/// * `pool_id` (in) — Reference to the pool.
+/// * `block` (out) — Start of the block, 8-byte aligned. Unchanged if the
+///   call fails.
+///
+/// # Description
+///
+/// Takes the first block of the free list of the pool, in constant time.
+/// The content of the block is undefined.
+///
+/// # Particularities
+///
+/// Allowed on task level and ISR level. Exhaustion is reported to the
+/// ErrorHook and counted in the `failures` of `GetPoolInfo`.
+///
+/// # Status
+///
+/// * **Standard:**
+///   * `E_OK` — No error.
+///   * `E_OS_LIMIT` — No block left in the pool.
+/// * **Extended:**
+///   * `E_OS_ID` — `pool_id` is invalid.
+///
+/// # Safety
+///
+/// `block` must be valid for a write.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn AllocBlock(pool_id: PoolType, block: BlockRefType) -> StatusType {
+    let status = match alloc_block(pool_id) {
+        Ok(free) => {
+            unsafe { *block = free.cast() };
+            StatusType::EOk
+        }
+        Err(status) => status,
+    };
+    hooks::report(status)
+}
+
+fn alloc_block(pool_id: PoolType) -> Result<*mut u8, StatusType> {
+    critical_section::with(|cs| {
+        let mut pools = POOLS.borrow_ref_mut(cs);
+        if pool_id >= pools.count {
+            return Err(StatusType::EOsId);
+        }
+        pools.states[pool_id].alloc().ok_or_else(|| {
+            #[cfg(debug_assertions)]
+            info!("Pool {} exhausted", pools.names[pool_id]);
+            StatusType::EOsLimit
+        })
+    })
+}
+
+/// Gives a block back to its pool.
+///
+/// # Syntax
+/// ```ignore
+/// StatusType FreeBlock(PoolType PoolID, void *Block)
+/// ```
+///
+/// # Parameters
+///
+/// * `pool_id` (in) — Reference to the pool the block was taken from.
+/// * `block` (in) — Block returned by `AllocBlock`.
+///
+/// # Description
+///
+/// Puts the block at the head of the free list of the pool, in constant
+/// time. The block must not be used any more.
+///
+/// # Particularities
+///
+/// Allowed on task level and ISR level. A block freed twice is only
+/// caught while the pool has all its blocks free; otherwise it corrupts
+/// the pool.
+///
+/// # Status
+///
+/// * **Standard:**
+///   * `E_OK` — No error.
+/// * **Extended:**
+///   * `E_OS_ID` — `pool_id` is invalid.
+///   * `E_OS_VALUE` — `block` is not a block of the pool, or the pool has
+///     no block in use.
+///
+/// # Safety
+///
+/// `block` must come from `AllocBlock` on `pool_id`, be freed once and not
+/// be used afterwards: the free list is linked through the free blocks.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn FreeBlock(pool_id: PoolType, block: *mut c_void) -> StatusType {
+    hooks::report(critical_section::with(|cs| {
+        let mut pools = POOLS.borrow_ref_mut(cs);
+        if pool_id >= pools.count {
+            return StatusType::EOsId;
+        }
+        pools.states[pool_id].free(block.cast())
+    }))
+}
+
+/// Returns the usage of a pool.
+///
+/// # Syntax
+/// ```ignore
+/// StatusType GetPoolInfo(PoolType PoolID, PoolInfoRefType Info)
+/// ```
+///
+/// # Parameters
+///
+/// * `pool_id` (in) — Reference to the pool.
+/// * `info` (out) — Block size, number of blocks, free blocks, lowest
+///   number of free blocks and number of failed allocations.
+///
+/// # Particularities
+///
+/// Allowed on task level and ISR level.
+///
+/// # Status
+///
+/// * **Standard:**
+///   * `E_OK` — No error.
+/// * **Extended:**
+///   * `E_OS_ID` — `pool_id` is invalid.
+///
+/// # Safety
+///
+/// `info` must be valid for a write.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn GetPoolInfo(pool_id: PoolType, info: PoolInfoRefType) -> StatusType {
+    hooks::report(critical_section::with(|cs| {
+        let pools = POOLS.borrow_ref(cs);
+        if pool_id >= pools.count {
+            return StatusType::EOsId;
+        }
+        unsafe { *info = pools.states[pool_id].info() };
+        StatusType::EOk
+    }))
+}
@@ -0,0 +1,365 @@
+//! Safe Rust API over the OSEK services.
+//!
+//! The C services take raw IDs and write their results through raw
+//! pointers. This module wraps them into typed handles, [`Task`], [`Alarm`],
+//! [`Counter`], [`Resource`] and [`Event`], whose methods return
+//! `Result<_, OsError>`. Errors still go through the ErrorHook first, as
+//! for C callers, so C and Rust tasks can be mixed freely.
+//!
+//! Resources are taken with [`Resource::lock`], which returns a
+//! [`ResourceGuard`] releasing the resource when dropped. Rust scoping then
+//! keeps the nested resources released in the reverse order of their
+//! acquisition, as OSEK requires.
+//!
+//! Rust functions become task entry points with the [`task`] attribute:
+//!
+//! ```ignore
+//! use rtos_core::os::{Resource, Task, task};
+//!
+//! const LOGGER: Task = Task::new(1);
+//! const BUS: Resource = Resource::new(0);
+//!
+//! #[task]
+//! fn sampler() {
+//!     {
+//!         let _bus = BUS.lock().unwrap();
+//!         // ...
+//!     }
+//!     LOGGER.activate().unwrap();
+//! }
+//! ```
+
+use core::marker::PhantomData;
+#[cfg(feature = "events")]
+use core::ops;
+
+pub use rtos_macros::{rtos_config, task};
+
+#[cfg(feature = "events")]
+use crate::events::{self, EventMaskType};
+use crate::{
+    StatusType,
+    alarms::{self, AlarmBaseType, AlarmType, TickType},
+    resources::{self, RES_SCHEDULER, ResourceType},
+    task::{self, INVALID_TASK, KERNEL, TaskStateType, TaskType},
+};
+
+/// Error of a service, the `StatusType` it returned other than `E_OK`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub enum OsError {
+    /// `E_OS_ID`
+    Id,
+    /// `E_OS_NOFUNC`
+    NoFunc,
+    /// `E_OS_VALUE`
+    Value,
+    /// `E_OS_STATE`
+    State,
+    /// `E_OS_LIMIT`
+    Limit,
+    /// `E_OS_CALLEVEL`
+    CallLevel,
+    /// `E_OS_ACCESS`
+    Access,
+    /// `E_OS_RESOURCE`
+    Resource,
+    /// `E_OS_SYS_FAULT`
+    SysFault,
+}
+
+impl OsError {
+    /// Turns the status of a service into a `Result`.
+    pub fn check(status: StatusType) -> Result<(), OsError> {
+        match status {
+            StatusType::EOk => Ok(()),
+            StatusType::EOsId => Err(OsError::Id),
+            StatusType::EOsNofunc => Err(OsError::NoFunc),
+            StatusType::EOsValue => Err(OsError::Value),
+            StatusType::EOsState => Err(OsError::State),
+            StatusType::EOsLimit => Err(OsError::Limit),
+            StatusType::EOsCallevel => Err(OsError::CallLevel),
+            StatusType::EOsAccess => Err(OsError::Access),
+            StatusType::EOsResource => Err(OsError::Resource),
+            StatusType::EOsSysFault => Err(OsError::SysFault),
+        }
+    }
+
+    /// The status a C service returns for this error.
+    pub fn status(self) -> StatusType {
+        match self {
+            OsError::Id => StatusType::EOsId,
+            OsError::NoFunc => StatusType::EOsNofunc,
+            OsError::Value => StatusType::EOsValue,
+            OsError::State => StatusType::EOsState,
+            OsError::Limit => StatusType::EOsLimit,
+            OsError::CallLevel => StatusType::EOsCallevel,
+            OsError::Access => StatusType::EOsAccess,
+            OsError::Resource => StatusType::EOsResource,
+            OsError::SysFault => StatusType::EOsSysFault,
+        }
+    }
+}
+
+impl From<OsError> for StatusType {
+    fn from(error: OsError) -> Self {
+        error.status()
+    }
+}
+
+/// A task, by its index in [`crate::OsConfig::tasks`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub struct Task(TaskType);
+
+impl Task {
+    pub const fn new(id: TaskType) -> Self {
+        Task(id)
+    }
+
+    pub const fn id(self) -> TaskType {
+        self.0
+    }
+
+    /// The running task, `None` on ISR level or while idle. See
+    /// `GetTaskID`.
+    pub fn current() -> Option<Task> {
+        let mut id = INVALID_TASK;
+        let _ = unsafe { task::GetTaskID(&mut id) };
+        (id != INVALID_TASK).then_some(Task(id))
+    }
+
+    /// See `ActivateTask`.
+    pub fn activate(self) -> Result<(), OsError> {
+        OsError::check(task::ActivateTask(self.0))
+    }
+
+    /// Terminates the calling task and activates this one, see `ChainTask`.
+    /// Only returns on error.
+    pub fn chain(self) -> OsError {
+        match OsError::check(task::ChainTask(self.0)) {
+            Err(error) => error,
+            Ok(()) => unreachable!(),
+        }
+    }
+
+    /// See `GetTaskState`.
+    pub fn state(self) -> Result<TaskStateType, OsError> {
+        let mut state = TaskStateType::Suspended;
+        OsError::check(unsafe { task::GetTaskState(self.0, &mut state) })?;
+        Ok(state)
+    }
+
+    /// See `SetEvent`.
+    #[cfg(feature = "events")]
+    pub fn set_event(self, event: Event) -> Result<(), OsError> {
+        OsError::check(events::SetEvent(self.0, event.0))
+    }
+
+    /// The events set for this task, see `GetEvent`.
+    #[cfg(feature = "events")]
+    pub fn events(self) -> Result<Event, OsError> {
+        let mut mask = 0;
+        OsError::check(unsafe { events::GetEvent(self.0, &mut mask) })?;
+        Ok(Event(mask))
+    }
+}
+
+/// Terminates the calling task, see `TerminateTask`. Only returns on
+/// error.
+pub fn terminate_task() -> OsError {
+    match OsError::check(task::TerminateTask()) {
+        Err(error) => error,
+        Ok(()) => unreachable!(),
+    }
+}
+
+/// Rescheduling point of non-preemptable tasks, see `Schedule`.
+pub fn schedule() -> Result<(), OsError> {
+    OsError::check(task::Schedule())
+}
+
+/// An alarm, by its ID.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub struct Alarm(AlarmType);
+
+impl Alarm {
+    pub const fn new(id: AlarmType) -> Self {
+        Alarm(id)
+    }
+
+    pub const fn id(self) -> AlarmType {
+        self.0
+    }
+
+    /// See `GetAlarmBase`.
+    pub fn base(self) -> Result<AlarmBaseType, OsError> {
+        let mut base = AlarmBaseType {
+            maxallowedvalue: 0,
+            ticksperbase: 0,
+            mincycle: 0,
+        };
+        OsError::check(unsafe { alarms::GetAlarmBase(self.0, &mut base) })?;
+        Ok(base)
+    }
+
+    /// Ticks before the alarm expires, see `GetAlarm`.
+    pub fn remaining(self) -> Result<TickType, OsError> {
+        let mut ticks = 0;
+        OsError::check(unsafe { alarms::GetAlarm(self.0, &mut ticks) })?;
+        Ok(ticks)
+    }
+
+    /// Expires `increment` ticks from now, then every `cycle` ticks unless
+    /// `cycle` is `0`. See `SetRelAlarm`.
+    pub fn set_relative(self, increment: TickType, cycle: TickType) -> Result<(), OsError> {
+        OsError::check(alarms::SetRelAlarm(self.0, increment, cycle))
+    }
+
+    /// Expires when the counter reaches `start`, then every `cycle` ticks
+    /// unless `cycle` is `0`. See `SetAbsAlarm`.
+    pub fn set_absolute(self, start: TickType, cycle: TickType) -> Result<(), OsError> {
+        OsError::check(alarms::SetAbsAlarm(self.0, start, cycle))
+    }
+
+    /// See `CancelAlarm`.
+    pub fn cancel(self) -> Result<(), OsError> {
+        OsError::check(alarms::CancelAlarm(self.0))
+    }
+}
+
+/// A counter. The kernel has one, driven by the timer tick.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub struct Counter(());
+
+impl Counter {
+    /// The counter of the timer tick, which drives the alarms.
+    pub const SYSTEM: Counter = Counter(());
+
+    /// Ticks counted since `StartOS`, wrapping around.
+    pub fn value(self) -> TickType {
+        critical_section::with(|cs| KERNEL.borrow_ref(cs).ticks)
+    }
+
+    /// Ticks counted since `previous`, which is updated to the current
+    /// value.
+    pub fn elapsed(self, previous: &mut TickType) -> TickType {
+        let now = self.value();
+        let elapsed = now.wrapping_sub(*previous);
+        *previous = now;
+        elapsed
+    }
+}
+
+/// A resource, by its index in [`crate::OsConfig::resources`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub struct Resource(ResourceType);
+
+impl Resource {
+    /// `RES_SCHEDULER`, which makes the holder non-preemptable.
+    pub const SCHEDULER: Resource = Resource(RES_SCHEDULER);
+
+    pub const fn new(id: ResourceType) -> Self {
+        Resource(id)
+    }
+
+    pub const fn id(self) -> ResourceType {
+        self.0
+    }
+
+    /// Takes the resource until the guard is dropped, see `GetResource`.
+    pub fn lock(self) -> Result<ResourceGuard, OsError> {
+        OsError::check(resources::GetResource(self.0))?;
+        Ok(ResourceGuard {
+            resource: self,
+            _not_send: PhantomData,
+        })
+    }
+
+    /// Runs `f` with the resource taken.
+    pub fn with<R>(self, f: impl FnOnce() -> R) -> Result<R, OsError> {
+        let _guard = self.lock()?;
+        Ok(f())
+    }
+}
+
+/// A resource taken by [`Resource::lock`], released when dropped.
+///
+/// A release error, such as a guard dropped out of order after being moved,
+/// goes to the ErrorHook. [`ResourceGuard::release`] returns it instead.
+#[must_use = "the resource is released as soon as the guard is dropped"]
+pub struct ResourceGuard {
+    resource: Resource,
+    /// Released by the task that took it.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl ResourceGuard {
+    /// Releases the resource, see `ReleaseResource`.
+    pub fn release(self) -> Result<(), OsError> {
+        let resource = self.resource;
+        core::mem::forget(self);
+        OsError::check(resources::ReleaseResource(resource.0))
+    }
+}
+
+impl Drop for ResourceGuard {
+    fn drop(&mut self) {
+        let _ = resources::ReleaseResource(self.resource.0);
+    }
+}
+
+/// A set of events, by their mask.
+#[cfg(feature = "events")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, defmt::Format)]
+pub struct Event(EventMaskType);
+
+#[cfg(feature = "events")]
+impl Event {
+    pub const NONE: Event = Event(0);
+
+    pub const fn new(mask: EventMaskType) -> Self {
+        Event(mask)
+    }
+
+    pub const fn mask(self) -> EventMaskType {
+        self.0
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub const fn contains(self, other: Event) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Waits until one of the events is set for the calling task, and
+    /// returns those set. See `WaitEvent`.
+    pub fn wait(self) -> Result<Event, OsError> {
+        OsError::check(events::WaitEvent(self.0))?;
+        let current = Task::current().ok_or(OsError::CallLevel)?;
+        Ok(current.events()? & self)
+    }
+
+    /// Clears the events of the calling task, see `ClearEvent`.
+    pub fn clear(self) -> Result<(), OsError> {
+        OsError::check(events::ClearEvent(self.0))
+    }
+}
+
+#[cfg(feature = "events")]
+impl ops::BitOr for Event {
+    type Output = Event;
+
+    fn bitor(self, other: Event) -> Event {
+        Event(self.0 | other.0)
+    }
+}
+
+#[cfg(feature = "events")]
+impl ops::BitAnd for Event {
+    type Output = Event;
+
+    fn bitand(self, other: Event) -> Event {
+        Event(self.0 & other.0)
+    }
+}
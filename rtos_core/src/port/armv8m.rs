@@ -0,0 +1,331 @@
+//! Armv8-M Mainline (Cortex-M33) port.
+//!
+//! Tasks run in thread mode on the process stack (PSP), exceptions on the
+//! main stack. Context switches happen in PendSV, which runs at the lowest
+//! exception priority: a switch requested from a task is taken as soon as
+//! the kernel critical section is left, one requested from an ISR once all
+//! ISRs have returned.
+//!
+//! A saved context looks like this on the task stack, lowest address first:
+//!
+//! ```text
+//! r4-r11, EXC_RETURN        <- saved by PendSV
+//! [s16-s31]                 <- saved by PendSV if the task used the FPU
+//! [hardware frame]          <- r0-r3, r12, lr, pc, xPSR (+ s0-s15, FPSCR)
+//! ```
+
+use core::arch::naked_asm;
+
+use cortex_m::peripheral::{
+    SCB,
+    scb::{SystemHandler, VectActive},
+};
+
+use crate::{
+    crash::{self, CrashRecordType},
+    reset::{ResetInfoType, ResetReasonType},
+    task::{self, StackRegion, TaskEntry},
+};
+
+/// Thread mode, process stack, no FP frame, secure state.
+const EXC_RETURN_THREAD_PSP: u32 = 0xFFFF_FFFD;
+/// Thumb bit of xPSR, the only bit a fresh frame needs.
+const XPSR_THUMB: u32 = 1 << 24;
+/// Words pushed by PendSV: r4-r11 and EXC_RETURN.
+const SW_FRAME_WORDS: usize = 9;
+/// Words pushed by the hardware: r0-r3, r12, lr, pc, xPSR.
+const HW_FRAME_WORDS: usize = 8;
+/// Words added for a task that used the FPU: s16-s31 by PendSV, then s0-s15,
+/// FPSCR and a reserved word by the hardware.
+const FP_FRAME_WORDS: usize = 16 + 18;
+
+/// Smallest task stack: the context of a task preempted with its FPU state,
+/// the 8-byte alignment of the stack top, and some room for the task itself.
+pub(crate) const MIN_STACK_SIZE: usize = 256;
+const _: () = assert!((SW_FRAME_WORDS + HW_FRAME_WORDS + FP_FRAME_WORDS) * 4 + 8 < MIN_STACK_SIZE);
+
+/// Upper and lower words of the RP2350 TIMER0 raw counter, which counts
+/// microseconds.
+#[cfg(feature = "stats")]
+const TIMER0_TIMERAWH: *const u32 = 0x400b_0024 as *const u32;
+#[cfg(any(feature = "trace", feature = "systemview", feature = "stats"))]
+const TIMER0_TIMERAWL: *const u32 = 0x400b_0028 as *const u32;
+
+/// Fault status and address registers of the System Control Block.
+const CFSR: *const u32 = 0xE000_ED28 as *const u32;
+const HFSR: *const u32 = 0xE000_ED2C as *const u32;
+const MMFAR: *const u32 = 0xE000_ED34 as *const u32;
+const BFAR: *const u32 = 0xE000_ED38 as *const u32;
+const SFSR: *const u32 = 0xE000_EDE4 as *const u32;
+const SFAR: *const u32 = 0xE000_EDE8 as *const u32;
+/// MEMFAULTENA, BUSFAULTENA, USGFAULTENA and SECUREFAULTENA of SHCSR.
+const SHCSR_FAULTS_ENABLE: u32 = 0b1111 << 16;
+/// RAM a stacked frame may be read from: SRAM0-SRAM9 of the RP2350.
+const RAM: core::ops::Range<u32> = 0x2000_0000..0x2008_2000;
+
+/// POWMAN `CHIP_RESET` and the `HAD_*` bits telling the last chip reset.
+const POWMAN_CHIP_RESET: *const u32 = 0x4010_002c as *const u32;
+const HAD_GLITCH_DETECT: u32 = 1 << 26;
+const HAD_RESCUE: u32 = 1 << 21;
+const HAD_DP_RESET_REQ: u32 = 1 << 19;
+const HAD_RUN_LOW: u32 = 1 << 18;
+const HAD_BOR: u32 = 1 << 17;
+const HAD_POR: u32 = 1 << 16;
+/// WATCHDOG `REASON`, cleared by every other chip reset, and the eight
+/// scratch registers after it.
+const WATCHDOG_REASON: *const u32 = 0x400d_8008 as *const u32;
+const WATCHDOG_SCRATCH0: *const u32 = 0x400d_800c as *const u32;
+const WATCHDOG_REASON_TIMER: u32 = 1 << 0;
+const WATCHDOG_REASON_FORCE: u32 = 1 << 1;
+/// Left in `SCRATCH4` by the bootrom `reboot` function, which resets the
+/// chip with the watchdog.
+const BOOTROM_REBOOT_MAGIC: u32 = 0xb007_c0d3;
+
+const IDLE_STACK_SIZE: usize = MIN_STACK_SIZE;
+static IDLE_STACK: task::Stack<IDLE_STACK_SIZE> = task::Stack::new();
+
+/// Builds the initial context of a task on `stack` so that PendSV starts it
+/// at `entry`, and returns the stack pointer to restore.
+///
+/// # Safety
+///
+/// `stack` must not hold the context of a task that may still run.
+pub(crate) unsafe fn init_context(stack: StackRegion, entry: TaskEntry) -> usize {
+    let top = (stack.base as usize + stack.size) & !7;
+    let sp = (top - (SW_FRAME_WORDS + HW_FRAME_WORDS) * 4) as *mut u32;
+    unsafe {
+        for i in 0..SW_FRAME_WORDS + HW_FRAME_WORDS {
+            sp.add(i).write(0);
+        }
+        sp.add(SW_FRAME_WORDS - 1).write(EXC_RETURN_THREAD_PSP);
+        // Hardware frame: r0, r1, r2, r3, r12, lr, pc, xPSR
+        sp.add(SW_FRAME_WORDS + 5)
+            .write(task_exit as *const () as u32);
+        sp.add(SW_FRAME_WORDS + 6)
+            .write(entry as *const () as u32 & !1);
+        sp.add(SW_FRAME_WORDS + 7).write(XPSR_THUMB);
+    }
+    sp as usize
+}
+
+/// Builds the context of the idle loop, run whenever no task is ready.
+pub(crate) fn init_idle() -> usize {
+    unsafe { init_context(IDLE_STACK.region(), idle) }
+}
+
+/// Asks for a rescheduling at the next opportunity.
+pub(crate) fn request_switch() {
+    SCB::set_pendsv();
+}
+
+pub(crate) fn in_isr() -> bool {
+    SCB::vect_active() != VectActive::ThreadMode
+}
+
+/// Microseconds from TIMER0, for the kernel trace and SystemView.
+#[cfg(any(feature = "trace", feature = "systemview"))]
+pub(crate) fn timestamp() -> u32 {
+    // Reading TIMERAWL has no side effect, unlike TIMELR
+    unsafe { TIMER0_TIMERAWL.read_volatile() }
+}
+
+/// Microseconds from the whole 64-bit TIMER0 counter, for the statistics.
+#[cfg(feature = "stats")]
+pub(crate) fn time_us() -> u64 {
+    // Read the upper word again in case the lower one wrapped in between
+    loop {
+        let high = unsafe { TIMER0_TIMERAWH.read_volatile() };
+        let low = unsafe { TIMER0_TIMERAWL.read_volatile() };
+        if unsafe { TIMER0_TIMERAWH.read_volatile() } == high {
+            return (high as u64) << 32 | low as u64;
+        }
+    }
+}
+
+/// Prepares the exception that performs the context switches, and enables
+/// the fault handlers that would otherwise escalate to HardFault.
+pub(crate) fn init() {
+    unsafe {
+        let mut peripherals = cortex_m::Peripherals::steal();
+        peripherals.SCB.set_priority(SystemHandler::PendSV, 0xFF);
+        peripherals
+            .SCB
+            .shcsr
+            .modify(|shcsr| shcsr | SHCSR_FAULTS_ENABLE);
+    }
+}
+
+/// Reads the cause of the last chip reset from WATCHDOG and POWMAN.
+pub(crate) fn reset_info() -> ResetInfoType {
+    let chip_reset = unsafe { POWMAN_CHIP_RESET.read_volatile() };
+    let watchdog_reason = unsafe { WATCHDOG_REASON.read_volatile() };
+    let scratch = core::array::from_fn(|i| unsafe { WATCHDOG_SCRATCH0.add(i).read_volatile() });
+    let reason = if watchdog_reason & WATCHDOG_REASON_FORCE != 0
+        || watchdog_reason & WATCHDOG_REASON_TIMER != 0 && scratch[4] == BOOTROM_REBOOT_MAGIC
+    {
+        ResetReasonType::Software
+    } else if watchdog_reason & WATCHDOG_REASON_TIMER != 0 {
+        ResetReasonType::Watchdog
+    } else if chip_reset & HAD_GLITCH_DETECT != 0 {
+        ResetReasonType::GlitchDetector
+    } else if chip_reset & HAD_BOR != 0 {
+        ResetReasonType::BrownOut
+    } else if chip_reset & HAD_RUN_LOW != 0 {
+        ResetReasonType::ResetPin
+    } else if chip_reset & (HAD_DP_RESET_REQ | HAD_RESCUE) != 0 {
+        ResetReasonType::Debugger
+    } else if chip_reset & HAD_POR != 0 {
+        ResetReasonType::PowerOn
+    } else {
+        ResetReasonType::Unknown
+    };
+    ResetInfoType {
+        reason,
+        chip_reset,
+        watchdog_reason,
+        scratch,
+    }
+}
+
+/// Hands the CPU over to the scheduler for good.
+pub(crate) fn start() -> ! {
+    SCB::set_pendsv();
+    // PendSV never switches back to the boot context on the main stack
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
+
+/// Stops all OS activity.
+pub(crate) fn shutdown() -> ! {
+    cortex_m::interrupt::disable();
+    loop {
+        cortex_m::asm::wfi();
+    }
+}
+
+/// Waits for the switch away from a terminated task, which is taken as soon
+/// as interrupts are enabled again.
+pub(crate) fn leave_terminated() -> ! {
+    loop {
+        cortex_m::asm::nop();
+    }
+}
+
+/// Return address of every task: a task that returns from its entry point
+/// without calling `TerminateTask` is terminated all the same.
+extern "C" fn task_exit() -> ! {
+    task::TerminateTask();
+    leave_terminated()
+}
+
+unsafe extern "C" fn idle() {
+    loop {
+        #[cfg(feature = "low-power")]
+        if crate::power::idle().is_some() {
+            continue;
+        }
+        cortex_m::asm::wfi();
+    }
+}
+
+/// Called by PendSV with the stack pointer of the outgoing context (`0` when
+/// coming from the boot context) and returning the one to restore.
+extern "C" fn switch_context(saved_sp: usize) -> usize {
+    task::switch(saved_sp)
+}
+
+#[unsafe(naked)]
+#[unsafe(no_mangle)]
+unsafe extern "C" fn PendSV() {
+    naked_asm!(
+        "mrs r0, psp",
+        // Only contexts running on the process stack are saved
+        "tst lr, #4",
+        "beq 1f",
+        "tst lr, #0x10",
+        "it eq",
+        "vstmdbeq r0!, {{s16-s31}}",
+        "stmdb r0!, {{r4-r11, lr}}",
+        "b 2f",
+        "1:",
+        "movs r0, #0",
+        "2:",
+        "bl {switch}",
+        "ldmia r0!, {{r4-r11, lr}}",
+        "tst lr, #0x10",
+        "it eq",
+        "vldmiaeq r0!, {{s16-s31}}",
+        "msr psp, r0",
+        "bx lr",
+        switch = sym switch_context,
+    )
+}
+
+/// Called by the fault handlers with their `EXC_RETURN`, the stack pointer
+/// of the faulting context and their exception number. Saves the crash
+/// record and resets the chip.
+extern "C" fn fault(exc_return: u32, frame: *const u32, exception: u32) -> ! {
+    let mut record = CrashRecordType {
+        exception,
+        exc_return,
+        cfsr: unsafe { CFSR.read_volatile() },
+        hfsr: unsafe { HFSR.read_volatile() },
+        mmfar: unsafe { MMFAR.read_volatile() },
+        bfar: unsafe { BFAR.read_volatile() },
+        sfsr: unsafe { SFSR.read_volatile() },
+        sfar: unsafe { SFAR.read_volatile() },
+        ..CrashRecordType::default()
+    };
+    // A stacking fault leaves no frame to read
+    let frame_start = frame as u32;
+    let frame_end = frame_start + (HW_FRAME_WORDS * 4) as u32;
+    if RAM.contains(&frame_start) && frame_end <= RAM.end && frame_start.is_multiple_of(4) {
+        let stacked = unsafe { core::slice::from_raw_parts(frame, HW_FRAME_WORDS) };
+        [
+            record.r0,
+            record.r1,
+            record.r2,
+            record.r3,
+            record.r12,
+            record.lr,
+            record.pc,
+            record.xpsr,
+        ] = stacked.try_into().unwrap_or([0; HW_FRAME_WORDS]);
+        // The frame is larger with the FP registers, and padded to 8 bytes
+        // if bit 9 of the stacked xPSR says so
+        let fp_words = if exc_return & 0x10 == 0 { 18 } else { 0 };
+        let padding = (record.xpsr >> 9) & 1;
+        record.sp = frame_end + (fp_words + padding) * 4;
+        record.isr = record.xpsr & 0x1FF;
+    }
+    crash::save(record);
+    SCB::sys_reset()
+}
+
+/// Defines a fault handler passing its `EXC_RETURN`, the stack pointer of
+/// the faulting context and its exception number to [`fault`].
+macro_rules! fault_handler {
+    ($name:ident) => {
+        #[unsafe(naked)]
+        #[unsafe(no_mangle)]
+        unsafe extern "C" fn $name() -> ! {
+            naked_asm!(
+                "mov r0, lr",
+                "tst lr, #4",
+                "ite eq",
+                "mrseq r1, msp",
+                "mrsne r1, psp",
+                "mrs r2, ipsr",
+                "b {fault}",
+                fault = sym fault,
+            )
+        }
+    };
+}
+
+fault_handler!(HardFault);
+fault_handler!(MemManage);
+fault_handler!(BusFault);
+fault_handler!(UsageFault);
+fault_handler!(SecureFault);
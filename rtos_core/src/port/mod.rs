@@ -0,0 +1,25 @@
+//! Hardware-dependent part of the kernel: building task contexts, switching
+//! between them and finding out whether we run at interrupt level.
+//!
+//! Bare-metal builds use the Cortex-M33 port, host builds the simulation
+//! port that the conformance suite runs on.
+
+#[cfg(target_os = "none")]
+mod armv8m;
+#[cfg(not(target_os = "none"))]
+pub mod sim;
+
+#[cfg(target_os = "none")]
+pub(crate) use armv8m::*;
+#[cfg(all(not(target_os = "none"), feature = "stats"))]
+pub(crate) use sim::time_us;
+#[cfg(all(
+    not(target_os = "none"),
+    any(feature = "trace", feature = "systemview")
+))]
+pub(crate) use sim::timestamp;
+#[cfg(not(target_os = "none"))]
+pub(crate) use sim::{
+    MIN_STACK_SIZE, in_isr, init, init_context, init_idle, leave_terminated, request_switch,
+    reset_info, shutdown,
+};
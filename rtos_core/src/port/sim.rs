@@ -0,0 +1,296 @@
+//! Host simulation port.
+//!
+//! Every task context is a host thread, and a baton makes sure that exactly
+//! one of them runs at a time, so the kernel runs unchanged on top of it.
+//! As on the target, a context switch requested inside the kernel critical
+//! section is taken when the outermost critical section is left, and one
+//! requested from an ISR when the ISR returns.
+//!
+//! Interrupts are simulated with [`isr`]. The timer interrupt only fires
+//! while the system is idle: [`run_os`] feeds it the number of ticks asked
+//! for, one at a time, or lets the low-power idle sleep through several.
+
+extern crate std;
+
+use core::{
+    cell::Cell,
+    sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
+};
+use std::{
+    sync::{Condvar, Mutex, PoisonError},
+    thread,
+};
+
+use crate::{
+    OsConfig, alarms,
+    crash::{self, CrashRecordType},
+    registry::TaskDescriptorType,
+    reset::{ResetInfoType, ResetReasonType},
+    task::{self, StackRegion, TaskEntry},
+};
+
+/// Context of the thread calling [`run_os`], used for idle.
+const IDLE_CTX: usize = 1;
+
+static NEXT_CTX: AtomicUsize = AtomicUsize::new(IDLE_CTX + 1);
+static BATON: Mutex<usize> = Mutex::new(IDLE_CTX);
+static WAKE: Condvar = Condvar::new();
+static SWITCH_PENDING: AtomicBool = AtomicBool::new(false);
+static CS_NESTING: AtomicUsize = AtomicUsize::new(0);
+static ISR_NESTING: AtomicUsize = AtomicUsize::new(0);
+static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+static TICKS: AtomicU32 = AtomicU32::new(0);
+/// Serializes simulation runs, the kernel state being global.
+static RUN: Mutex<()> = Mutex::new(());
+/// Descriptors standing for the `.os_tasks` section of the current run.
+static TASK_SECTION: Mutex<&'static [TaskDescriptorType]> = Mutex::new(&[]);
+
+std::thread_local! {
+    static CTX: Cell<usize> = const { Cell::new(0) };
+    static SWITCHING: Cell<bool> = const { Cell::new(false) };
+}
+
+struct SimCriticalSection;
+critical_section::set_impl!(SimCriticalSection);
+
+// Only the thread holding the baton runs, so nesting is all there is to
+// track. Leaving the outermost section is where a pending switch is taken.
+unsafe impl critical_section::Impl for SimCriticalSection {
+    unsafe fn acquire() -> critical_section::RawRestoreState {
+        CS_NESTING.fetch_add(1, Ordering::SeqCst);
+    }
+
+    unsafe fn release(_: critical_section::RawRestoreState) {
+        if CS_NESTING.fetch_sub(1, Ordering::SeqCst) == 1 && ISR_NESTING.load(Ordering::SeqCst) == 0
+        {
+            switch_if_pending();
+        }
+    }
+}
+
+#[defmt::global_logger]
+struct SimLogger;
+
+// Kernel logs are dropped on the host
+unsafe impl defmt::Logger for SimLogger {
+    fn acquire() {}
+    unsafe fn flush() {}
+    unsafe fn release() {}
+    unsafe fn write(_bytes: &[u8]) {}
+}
+
+defmt::timestamp!("{=u32}", TICKS.load(Ordering::Relaxed));
+
+#[defmt::panic_handler]
+fn defmt_panic() -> ! {
+    core::panic!("defmt panic")
+}
+
+fn hand_over(ctx: usize) {
+    *BATON.lock().unwrap_or_else(PoisonError::into_inner) = ctx;
+    WAKE.notify_all();
+}
+
+fn wait_for(ctx: usize) {
+    let mut baton = BATON.lock().unwrap_or_else(PoisonError::into_inner);
+    while *baton != ctx {
+        baton = WAKE.wait(baton).unwrap_or_else(PoisonError::into_inner);
+    }
+}
+
+fn switch_if_pending() {
+    if SWITCHING.with(|switching| switching.replace(true)) {
+        return;
+    }
+    while !SHUTDOWN.load(Ordering::SeqCst) && SWITCH_PENDING.swap(false, Ordering::SeqCst) {
+        let me = CTX.with(Cell::get);
+        let next = task::switch(me);
+        if next != me {
+            hand_over(next);
+            // A terminated context is never handed the baton again
+            wait_for(me);
+        }
+    }
+    SWITCHING.with(|switching| switching.set(false));
+}
+
+/// Tasks run on host threads, so their stacks may be empty.
+pub(crate) const MIN_STACK_SIZE: usize = 0;
+
+/// Creates a fresh context running `entry`. The stack is not used, the
+/// context gets a host thread instead.
+pub(crate) unsafe fn init_context(_stack: StackRegion, entry: TaskEntry) -> usize {
+    let ctx = NEXT_CTX.fetch_add(1, Ordering::SeqCst);
+    thread::spawn(move || {
+        CTX.with(|c| c.set(ctx));
+        wait_for(ctx);
+        unsafe { entry() };
+        task_exit();
+    });
+    ctx
+}
+
+pub(crate) fn init_idle() -> usize {
+    IDLE_CTX
+}
+
+pub(crate) fn init() {}
+
+pub(crate) fn request_switch() {
+    SWITCH_PENDING.store(true, Ordering::SeqCst);
+}
+
+pub(crate) fn in_isr() -> bool {
+    ISR_NESTING.load(Ordering::SeqCst) > 0
+}
+
+pub(crate) fn leave_terminated() -> ! {
+    loop {
+        thread::park();
+    }
+}
+
+pub(crate) fn shutdown() -> ! {
+    if CTX.with(Cell::get) == IDLE_CTX {
+        std::panic!("ShutdownOS is only supported from tasks in the simulation");
+    }
+    SHUTDOWN.store(true, Ordering::SeqCst);
+    hand_over(IDLE_CTX);
+    leave_terminated()
+}
+
+fn task_exit() -> ! {
+    task::TerminateTask();
+    leave_terminated()
+}
+
+/// Microseconds since the first call, for the statistics.
+#[cfg(any(feature = "trace", feature = "systemview", feature = "stats"))]
+pub(crate) fn time_us() -> u64 {
+    static START: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+    START
+        .get_or_init(std::time::Instant::now)
+        .elapsed()
+        .as_micros() as u64
+}
+
+/// Lower word of [`time_us`], for the kernel trace and SystemView.
+#[cfg(any(feature = "trace", feature = "systemview"))]
+pub(crate) fn timestamp() -> u32 {
+    time_us() as u32
+}
+
+#[cfg(feature = "systemview")]
+static SYSVIEW_STREAM: Mutex<std::vec::Vec<u8>> = Mutex::new(std::vec::Vec::new());
+
+/// Keeps the SystemView stream in memory, see [`systemview_stream`].
+#[cfg(feature = "systemview")]
+pub(crate) fn write_systemview(bytes: &[u8]) -> bool {
+    SYSVIEW_STREAM
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .extend_from_slice(bytes);
+    true
+}
+
+/// Takes the SystemView stream written so far. Saved as an `.SVDat` file,
+/// it opens in SystemView.
+#[cfg(feature = "systemview")]
+pub fn systemview_stream() -> std::vec::Vec<u8> {
+    core::mem::take(
+        &mut *SYSVIEW_STREAM
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner),
+    )
+}
+
+/// Prints the raw standard output and the shell output on the host
+/// standard output.
+pub(crate) fn write_stdout(bytes: &[u8]) {
+    use std::io::Write;
+    let mut stdout = std::io::stdout().lock();
+    let _ = stdout.write_all(bytes);
+    let _ = stdout.flush();
+}
+
+/// Runs `f` as a Category 2 ISR interrupting the current context.
+pub fn isr(f: impl FnOnce()) {
+    ISR_NESTING.fetch_add(1, Ordering::SeqCst);
+    f();
+    if ISR_NESTING.fetch_sub(1, Ordering::SeqCst) == 1 && CS_NESTING.load(Ordering::SeqCst) == 0 {
+        switch_if_pending();
+    }
+}
+
+/// Simulates a HardFault of the running task: saves a crash record as the
+/// fault handlers of the target do, then stops the OS as their reset
+/// would. The next [`run_os`] finds the record.
+pub fn fault() -> ! {
+    crash::save(CrashRecordType {
+        exception: crash::HARD_FAULT,
+        ..CrashRecordType::default()
+    });
+    shutdown()
+}
+
+/// Each run of the simulation starts as after a power-on.
+pub(crate) fn reset_info() -> ResetInfoType {
+    ResetInfoType {
+        reason: ResetReasonType::PowerOn,
+        ..ResetInfoType::UNKNOWN
+    }
+}
+
+/// Number of timer ticks since the start of the current run, those an
+/// [`isr`] or the low-power idle delivered included.
+pub fn now() -> u32 {
+    critical_section::with(|cs| task::KERNEL.borrow_ref(cs).ticks as u32)
+}
+
+/// The `.os_tasks` section of the current run, which the host does not
+/// link.
+pub(crate) fn task_section() -> &'static [TaskDescriptorType] {
+    *TASK_SECTION.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Starts the OS with `config` and runs it until it is idle with `ticks`
+/// timer interrupts delivered, or until `ShutdownOS` is called.
+///
+/// Each call starts from a fresh kernel state.
+pub fn run_os(config: &'static OsConfig, ticks: u32) {
+    run_os_registered(config, &[], ticks);
+}
+
+/// Runs the OS as [`run_os`] does, with the tasks of `descriptors`
+/// registered as `TASK` and `#[task]` register them on the target.
+pub fn run_os_registered(
+    config: &'static OsConfig,
+    descriptors: &'static [TaskDescriptorType],
+    ticks: u32,
+) {
+    let _run = RUN.lock().unwrap_or_else(PoisonError::into_inner);
+    *TASK_SECTION.lock().unwrap_or_else(PoisonError::into_inner) = descriptors;
+    SHUTDOWN.store(false, Ordering::SeqCst);
+    SWITCH_PENDING.store(false, Ordering::SeqCst);
+    ISR_NESTING.store(0, Ordering::SeqCst);
+    TICKS.store(0, Ordering::SeqCst);
+    CTX.with(|c| c.set(IDLE_CTX));
+    hand_over(IDLE_CTX);
+
+    #[cfg(feature = "low-power")]
+    crate::power::reset();
+
+    crate::init_os(config);
+    let mut delivered = 0;
+    while delivered < ticks && !SHUTDOWN.load(Ordering::SeqCst) {
+        #[cfg(feature = "low-power")]
+        if let Some(slept @ 1..) = crate::power::idle() {
+            TICKS.fetch_add(slept as u32, Ordering::SeqCst);
+            delivered += slept as u32;
+            continue;
+        }
+        TICKS.fetch_add(1, Ordering::SeqCst);
+        delivered += 1;
+        isr(alarms::tick);
+    }
+}
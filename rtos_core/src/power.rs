@@ -0,0 +1,94 @@
+//! Low-power idle.
+//!
+//! With the `low-power` feature, the idle loop works out how many ticks it
+//! may sleep: until the first alarm expires and, with the watchdog manager,
+//! until the end of the supervision cycle. It picks the deepest
+//! [`PowerState`] whose latency is below that and hands it to
+//! [`PowerDriver::sleep`], which stops the kernel tick, arms the alarm of
+//! the always-on timer, sleeps and returns the ticks that went by as
+//! measured by the always-on timer. The kernel then catches up with those
+//! ticks as if the timer had delivered them, expiring the alarms on the way.
+//! Each tick is caught up in a critical section of its own, so that the
+//! interrupts, and the tasks the catch-up activates, do not wait for all
+//! of them.
+//!
+//! Until [`init`] is called, or while no state is worth it, the idle loop
+//! just waits for the next interrupt. The simulation port sleeps through
+//! the driver as well, between the ticks it delivers.
+
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+#[cfg(debug_assertions)]
+use defmt::info;
+
+use crate::alarms::{self, TickType};
+
+/// A low-power state of the application.
+#[derive(Clone, Copy)]
+pub struct PowerState {
+    pub name: &'static str,
+    /// Entry and wake-up latency in ticks. The state is only entered when
+    /// more ticks than this are left before the next kernel activity.
+    pub latency: TickType,
+}
+
+/// Hardware side of the low-power idle, provided by the application.
+#[derive(Clone, Copy)]
+pub struct PowerDriver {
+    /// Low-power states, shallowest first.
+    pub states: &'static [PowerState],
+    /// Enters `states[state]` until at most `ticks` ticks after the last
+    /// kernel tick, or until another interrupt, and returns the ticks
+    /// elapsed since the last kernel tick, whole ticks only. The kernel
+    /// tick must not fire meanwhile, nor for the ticks returned. Called
+    /// with interrupts masked.
+    pub sleep: fn(state: usize, ticks: TickType) -> TickType,
+}
+
+static DRIVER: Mutex<RefCell<Option<PowerDriver>>> = Mutex::new(RefCell::new(None));
+
+/// Lets the idle loop sleep through `driver`.
+pub fn init(driver: PowerDriver) {
+    critical_section::with(|cs| *DRIVER.borrow_ref_mut(cs) = Some(driver));
+}
+
+/// Forgets the driver, for the next run of the simulation.
+#[cfg(not(target_os = "none"))]
+pub(crate) fn reset() {
+    critical_section::with(|cs| *DRIVER.borrow_ref_mut(cs) = None);
+}
+
+/// Sleeps in the deepest state worth it, then catches up with the ticks
+/// slept, which it returns. Returns `None` if no state was, for the idle
+/// loop to wait for an interrupt instead. Called by the idle loop of the
+/// port.
+pub(crate) fn idle() -> Option<TickType> {
+    // Masked until the sleep is over: an interrupt taken before it would be
+    // missed by it
+    let slept = critical_section::with(|cs| {
+        let driver = (*DRIVER.borrow_ref(cs))?;
+        let budget = alarms::next_expiry();
+        #[cfg(feature = "watchdog")]
+        let budget = match (budget, crate::watchdog::next_feed()) {
+            (Some(expiry), Some(feed)) => Some(expiry.min(feed)),
+            (expiry, feed) => expiry.or(feed),
+        };
+        let budget = budget.unwrap_or(TickType::MAX);
+        let state = driver
+            .states
+            .iter()
+            .rposition(|state| state.latency < budget)?;
+        let slept = (driver.sleep)(state, budget);
+        #[cfg(debug_assertions)]
+        info!(
+            "Slept {} of {} ticks in {}",
+            slept, budget, driver.states[state].name
+        );
+        Some(slept)
+    })?;
+    for _ in 0..slept {
+        critical_section::with(|_| alarms::tick());
+    }
+    Some(slept)
+}
@@ -0,0 +1,366 @@
+//! Ready queue and scheduling decision.
+//!
+//! This module holds the one data structure every scheduling decision goes
+//! through: a priority bitmap with a FIFO of task IDs behind every priority,
+//! plus the per-task activation counters. It has no hardware dependencies,
+//! performs no allocation and owns no global state, so it can be checked in
+//! isolation with Kani (`cargo kani -p rtos_core`).
+//!
+//! Priorities follow OSEK: `0` is the lowest priority, bigger numbers win.
+
+/// Task priority. `0` is the lowest priority.
+pub type Priority = u8;
+
+/// Errors reported by the ready queue.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueueError {
+    /// The task already has its maximum number of activations recorded.
+    Limit,
+    /// The FIFO behind the requested priority has no free slot left.
+    Full,
+}
+
+/// Outcome of [`select_next`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decision {
+    /// The running task keeps the CPU.
+    Keep,
+    /// The running task is preempted by the head of the ready queue.
+    Preempt,
+    /// No task is running, the head of the ready queue gets the CPU.
+    Dispatch,
+    /// No task is running and none is ready.
+    Idle,
+}
+
+/// A ring buffer of task IDs of fixed capacity.
+#[derive(Clone, Copy)]
+struct Fifo<const DEPTH: usize> {
+    slots: [u8; DEPTH],
+    head: usize,
+    len: usize,
+}
+
+impl<const DEPTH: usize> Fifo<DEPTH> {
+    const fn new() -> Self {
+        Fifo {
+            slots: [0; DEPTH],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    fn push_back(&mut self, task: u8) -> bool {
+        if self.len == DEPTH {
+            return false;
+        }
+        self.slots[(self.head + self.len) % DEPTH] = task;
+        self.len += 1;
+        true
+    }
+
+    fn push_front(&mut self, task: u8) -> bool {
+        if self.len == DEPTH {
+            return false;
+        }
+        self.head = (self.head + DEPTH - 1) % DEPTH;
+        self.slots[self.head] = task;
+        self.len += 1;
+        true
+    }
+
+    fn pop_front(&mut self) -> Option<u8> {
+        if self.len == 0 {
+            return None;
+        }
+        let task = self.slots[self.head];
+        self.head = (self.head + 1) % DEPTH;
+        self.len -= 1;
+        Some(task)
+    }
+
+    fn count(&self, task: u8) -> usize {
+        let mut n = 0;
+        let mut i = 0;
+        while i < self.len {
+            if self.slots[(self.head + i) % DEPTH] == task {
+                n += 1;
+            }
+            i += 1;
+        }
+        n
+    }
+}
+
+/// Priority bitmap with a FIFO per priority.
+///
+/// * `PRIOS` — number of priority levels, at most 32 (one bit each).
+/// * `TASKS` — number of tasks, at most 256.
+/// * `DEPTH` — capacity of the FIFO behind each priority.
+///
+/// Every activation of a task occupies exactly one FIFO slot until the task
+/// is dispatched; the activation counter of a task counts its queued
+/// activations plus the one currently running, if any.
+pub struct ReadyQueue<const PRIOS: usize, const TASKS: usize, const DEPTH: usize> {
+    bitmap: u32,
+    fifos: [Fifo<DEPTH>; PRIOS],
+    activations: [u8; TASKS],
+}
+
+impl<const PRIOS: usize, const TASKS: usize, const DEPTH: usize> ReadyQueue<PRIOS, TASKS, DEPTH> {
+    const CHECK: () = assert!(PRIOS <= 32 && TASKS <= 256 && DEPTH > 0);
+
+    pub const fn new() -> Self {
+        let () = Self::CHECK;
+        ReadyQueue {
+            bitmap: 0,
+            fifos: [Fifo::new(); PRIOS],
+            activations: [0; TASKS],
+        }
+    }
+
+    /// Records a new activation of `task` and queues it behind the tasks
+    /// already ready at `priority`.
+    ///
+    /// Fails with [`QueueError::Limit`] once `max_activations` activations are
+    /// recorded. Nothing is changed on failure.
+    pub fn activate(
+        &mut self,
+        task: usize,
+        priority: Priority,
+        max_activations: u8,
+    ) -> Result<(), QueueError> {
+        if self.activations[task] >= max_activations {
+            return Err(QueueError::Limit);
+        }
+        self.push_back(task, priority)?;
+        self.activations[task] += 1;
+        Ok(())
+    }
+
+    /// Queues a task that became ready without a new activation (e.g. after
+    /// waiting for an event) behind the tasks already ready at `priority`.
+    pub fn release(&mut self, task: usize, priority: Priority) -> Result<(), QueueError> {
+        self.push_back(task, priority)
+    }
+
+    /// Queues a preempted task in front of the tasks ready at `priority`, so
+    /// it is the first one to resume at that level.
+    pub fn preempt(&mut self, task: usize, priority: Priority) -> Result<(), QueueError> {
+        if !self.fifos[priority as usize].push_front(task as u8) {
+            return Err(QueueError::Full);
+        }
+        self.bitmap |= 1 << priority;
+        Ok(())
+    }
+
+    /// Drops the activation of a terminating task.
+    pub fn terminate(&mut self, task: usize) {
+        self.activations[task] = self.activations[task].saturating_sub(1);
+    }
+
+    /// Number of activations recorded for `task`, including the running one.
+    pub fn activations(&self, task: usize) -> u8 {
+        self.activations[task]
+    }
+
+    /// Highest priority with at least one ready task.
+    pub fn highest_priority(&self) -> Option<Priority> {
+        if self.bitmap == 0 {
+            None
+        } else {
+            Some((31 - self.bitmap.leading_zeros()) as Priority)
+        }
+    }
+
+    /// Removes the oldest task of the highest ready priority.
+    pub fn pop(&mut self) -> Option<(usize, Priority)> {
+        let priority = self.highest_priority()?;
+        let fifo = &mut self.fifos[priority as usize];
+        let task = fifo.pop_front()?;
+        if fifo.len == 0 {
+            self.bitmap &= !(1 << priority);
+        }
+        Some((task as usize, priority))
+    }
+
+    /// Number of queued entries for `task` at `priority`.
+    pub fn queued(&self, task: usize, priority: Priority) -> usize {
+        self.fifos[priority as usize].count(task as u8)
+    }
+
+    fn push_back(&mut self, task: usize, priority: Priority) -> Result<(), QueueError> {
+        if !self.fifos[priority as usize].push_back(task as u8) {
+            return Err(QueueError::Full);
+        }
+        self.bitmap |= 1 << priority;
+        Ok(())
+    }
+}
+
+impl<const PRIOS: usize, const TASKS: usize, const DEPTH: usize> Default
+    for ReadyQueue<PRIOS, TASKS, DEPTH>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Decides who gets the CPU next.
+///
+/// `running` is the current priority of the running task, or `None` when no
+/// task is running (idle, or the running task terminated or started waiting).
+/// A ready task only preempts the running one if its priority is strictly
+/// higher; tasks of equal priority never preempt each other.
+pub fn select_next<const PRIOS: usize, const TASKS: usize, const DEPTH: usize>(
+    queue: &ReadyQueue<PRIOS, TASKS, DEPTH>,
+    running: Option<Priority>,
+) -> Decision {
+    match (running, queue.highest_priority()) {
+        (Some(current), Some(ready)) if ready > current => Decision::Preempt,
+        (Some(_), _) => Decision::Keep,
+        (None, Some(_)) => Decision::Dispatch,
+        (None, None) => Decision::Idle,
+    }
+}
+
+#[cfg(kani)]
+mod verification {
+    use super::*;
+
+    const PRIOS: usize = 4;
+    const TASKS: usize = 3;
+    const DEPTH: usize = 4;
+    type Queue = ReadyQueue<PRIOS, TASKS, DEPTH>;
+
+    fn any_task() -> usize {
+        let task: usize = kani::any();
+        kani::assume(task < TASKS);
+        task
+    }
+
+    fn any_priority() -> Priority {
+        let priority: Priority = kani::any();
+        kani::assume((priority as usize) < PRIOS);
+        priority
+    }
+
+    /// The task handed out is always one of the highest priority.
+    #[kani::proof]
+    #[kani::unwind(5)]
+    fn pop_selects_highest_priority() {
+        let mut queue = Queue::new();
+        let mut highest: Option<Priority> = None;
+        for _ in 0..3 {
+            let priority = any_priority();
+            if queue.activate(any_task(), priority, 3).is_ok() {
+                highest = Some(highest.map_or(priority, |h| h.max(priority)));
+            }
+        }
+        assert_eq!(queue.highest_priority(), highest);
+        if let Some((_, priority)) = queue.pop() {
+            assert_eq!(Some(priority), highest);
+        }
+    }
+
+    /// Tasks of one priority leave the queue in activation order, whatever
+    /// happens at the other priorities.
+    #[kani::proof]
+    #[kani::unwind(5)]
+    fn fifo_within_priority() {
+        let mut queue = Queue::new();
+        let priority = any_priority();
+        let first = any_task();
+        let second = any_task();
+        kani::assume(first != second);
+        let other = any_priority();
+        kani::assume(other < priority);
+
+        queue.activate(first, priority, 1).unwrap();
+        let _ = queue.activate(any_task(), other, 1);
+        queue.activate(second, priority, 1).unwrap();
+
+        assert_eq!(queue.pop(), Some((first, priority)));
+        assert_eq!(queue.pop(), Some((second, priority)));
+    }
+
+    /// A preempted task resumes before the tasks that were already waiting at
+    /// its priority.
+    #[kani::proof]
+    #[kani::unwind(5)]
+    fn preempted_task_resumes_first() {
+        let mut queue = Queue::new();
+        let priority = any_priority();
+        let waiting = any_task();
+        let preempted = any_task();
+        kani::assume(waiting != preempted);
+
+        queue.activate(waiting, priority, 1).unwrap();
+        queue.preempt(preempted, priority).unwrap();
+
+        assert_eq!(queue.pop(), Some((preempted, priority)));
+        assert_eq!(queue.pop(), Some((waiting, priority)));
+    }
+
+    /// Activation counters never exceed their limit, and `Limit` is reported
+    /// exactly when the limit is reached.
+    #[kani::proof]
+    #[kani::unwind(5)]
+    fn activation_count_bounded() {
+        let mut queue = Queue::new();
+        let task = any_task();
+        let priority = any_priority();
+        let max: u8 = kani::any();
+        kani::assume(max >= 1 && (max as usize) <= DEPTH);
+
+        for _ in 0..DEPTH + 1 {
+            let before = queue.activations(task);
+            let result = queue.activate(task, priority, max);
+            assert!(queue.activations(task) <= max);
+            assert_eq!(result == Err(QueueError::Limit), before == max);
+        }
+    }
+
+    /// For every task, the activation counter equals the number of queued
+    /// entries plus one if the task is running: no activation is ever lost
+    /// or duplicated by any sequence of kernel operations.
+    #[kani::proof]
+    #[kani::unwind(6)]
+    fn no_lost_activations() {
+        let mut queue = Queue::new();
+        let priority: [Priority; TASKS] = [0, 1, 1];
+        let mut running: Option<usize> = None;
+
+        for _ in 0..4 {
+            let op: u8 = kani::any();
+            match op % 3 {
+                0 => {
+                    let task = any_task();
+                    let result = queue.activate(task, priority[task], 2);
+                    assert_ne!(result, Err(QueueError::Full));
+                }
+                1 => {
+                    if let Some(task) = running {
+                        if select_next(&queue, Some(priority[task])) == Decision::Preempt {
+                            queue.preempt(task, priority[task]).unwrap();
+                            running = queue.pop().map(|(t, _)| t);
+                        }
+                    } else if select_next(&queue, None) == Decision::Dispatch {
+                        running = queue.pop().map(|(t, _)| t);
+                    }
+                }
+                _ => {
+                    if let Some(task) = running.take() {
+                        queue.terminate(task);
+                    }
+                }
+            }
+
+            for task in 0..TASKS {
+                let queued = queue.queued(task, priority[task]);
+                let on_cpu = (running == Some(task)) as usize;
+                assert_eq!(queue.activations(task) as usize, queued + on_cpu);
+            }
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Tasks registered at link time.
+//!
+//! A task defined with the `TASK(name, priority, stack)` macro of `Os.h`,
+//! or with `#[task(priority = .., stack = ..)]` in Rust, places a
+//! [`TaskDescriptorType`] in the `.os_tasks.<name>` section, which
+//! `memory.x` gathers in name order. `StartOS` appends the descriptors to
+//! the tasks of [`OsConfig::tasks`], so adding a task does not touch the
+//! kernel or the configuration, and the configured tasks keep the IDs their
+//! handles and alarm actions use.
+//!
+//! Registered tasks are given the IDs after the configured ones, in section
+//! order, and `StartOS` stores each in the [`RegisteredTask`] of its
+//! descriptor: the `OsTaskId_<name>` symbol, which C reads as
+//! `TASK_ID(name)`. Two tasks with the same name fail to link.
+
+use core::{
+    cell::UnsafeCell,
+    ffi::{CStr, c_char},
+    mem::MaybeUninit,
+};
+
+#[cfg(debug_assertions)]
+use defmt::info;
+
+use crate::{
+    OsConfig, TaskConfig,
+    os::Task,
+    ready_queue::Priority,
+    task::{INVALID_TASK, MAX_TASKS, SchedulePolicy, StackRegion, TaskEntry, TaskType},
+};
+
+/// `flags` of a task started by `StartOS`.
+pub const TASK_AUTOSTART: u8 = 1 << 0;
+/// `flags` of an extended task, which may wait for events.
+pub const TASK_EXTENDED: u8 = 1 << 1;
+/// `flags` of a non-preemptable task.
+pub const TASK_NON_PREEMPTIVE: u8 = 1 << 2;
+
+/// ID of a registered task, set by `StartOS`.
+#[repr(transparent)]
+pub struct RegisteredTask(UnsafeCell<TaskType>);
+
+// Only written by `StartOS`, before the scheduler and the interrupts.
+unsafe impl Sync for RegisteredTask {}
+
+impl RegisteredTask {
+    pub const fn new() -> Self {
+        RegisteredTask(UnsafeCell::new(INVALID_TASK))
+    }
+
+    /// Where `StartOS` stores the ID, for [`TaskDescriptorType::id`].
+    pub const fn as_ptr(&self) -> *mut TaskType {
+        self.0.get()
+    }
+
+    /// The task, whose ID is `INVALID_TASK` until `StartOS`.
+    pub fn task(&self) -> Task {
+        Task::new(unsafe { *self.0.get() })
+    }
+}
+
+impl Default for RegisteredTask {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Task placed in the `.os_tasks` section by `TASK` or `#[task]`.
+#[repr(C)]
+pub struct TaskDescriptorType {
+    /// Where `StartOS` stores the ID of the task, or null.
+    pub id: *mut TaskType,
+    /// NUL-terminated name.
+    pub name: *const c_char,
+    pub entry: TaskEntry,
+    pub priority: Priority,
+    /// Maximum number of queued activations.
+    pub activation: u8,
+    /// `TASK_AUTOSTART`, `TASK_EXTENDED` and `TASK_NON_PREEMPTIVE`.
+    pub flags: u8,
+    pub stack: *mut u8,
+    pub stack_size: usize,
+}
+
+// Descriptors are constant, their ID is only written by `StartOS` and their
+// stack is only handed to the kernel.
+unsafe impl Sync for TaskDescriptorType {}
+
+impl TaskDescriptorType {
+    fn config(&self) -> Result<TaskConfig, &'static str> {
+        let name = unsafe { CStr::from_ptr(self.name) }
+            .to_str()
+            .map_err(|_| "registered task name is not UTF-8")?;
+        Ok(TaskConfig {
+            name,
+            entry: self.entry,
+            priority: self.priority,
+            activation: self.activation,
+            autostart: self.flags & TASK_AUTOSTART != 0,
+            extended: self.flags & TASK_EXTENDED != 0,
+            schedule: if self.flags & TASK_NON_PREEMPTIVE != 0 {
+                SchedulePolicy::Non
+            } else {
+                SchedulePolicy::Full
+            },
+            internal_resource: None,
+            deadline: None,
+            stack: StackRegion {
+                base: self.stack,
+                size: self.stack_size,
+            },
+        })
+    }
+}
+
+/// The descriptors of the `.os_tasks` section, in name order.
+#[cfg(target_os = "none")]
+fn descriptors() -> &'static [TaskDescriptorType] {
+    unsafe extern "C" {
+        static __os_tasks_start: TaskDescriptorType;
+        static __os_tasks_end: TaskDescriptorType;
+    }
+    let start = &raw const __os_tasks_start;
+    let end = &raw const __os_tasks_end;
+    unsafe { core::slice::from_raw_parts(start, end.offset_from(start) as usize) }
+}
+
+/// The host simulation links no `.os_tasks` section, its runs give the
+/// descriptors.
+#[cfg(not(target_os = "none"))]
+fn descriptors() -> &'static [TaskDescriptorType] {
+    crate::port::sim::task_section()
+}
+
+/// The tasks of `config` followed by the registered ones, and the rest of
+/// `config`, built once by `StartOS`.
+struct Registered {
+    tasks: UnsafeCell<[MaybeUninit<TaskConfig>; MAX_TASKS]>,
+    config: UnsafeCell<MaybeUninit<OsConfig>>,
+}
+
+// Only written by `StartOS`, before the scheduler and the interrupts.
+unsafe impl Sync for Registered {}
+
+static REGISTERED: Registered = Registered {
+    tasks: UnsafeCell::new([const { MaybeUninit::uninit() }; MAX_TASKS]),
+    config: UnsafeCell::new(MaybeUninit::uninit()),
+};
+
+/// `config` with the registered tasks after its own, or `config` itself if
+/// no task registered.
+pub(crate) fn with_registered(
+    config: &'static OsConfig,
+) -> Result<&'static OsConfig, &'static str> {
+    let descriptors = descriptors();
+    if descriptors.is_empty() {
+        return Ok(config);
+    }
+    let count = descriptors.len() + config.tasks.len();
+    if count > MAX_TASKS {
+        return Err("too many tasks");
+    }
+    let tasks = unsafe { &mut *REGISTERED.tasks.get() };
+    for (slot, task) in tasks.iter_mut().zip(config.tasks) {
+        slot.write(*task);
+    }
+    for (id, descriptor) in (config.tasks.len()..).zip(descriptors) {
+        tasks[id].write(descriptor.config()?);
+        if !descriptor.id.is_null() {
+            unsafe { descriptor.id.write(id) };
+        }
+        #[cfg(debug_assertions)]
+        info!("Registered task {}", id);
+    }
+    let tasks = unsafe { core::slice::from_raw_parts(tasks.as_ptr().cast::<TaskConfig>(), count) };
+    let config = unsafe { &mut *REGISTERED.config.get() }.write(OsConfig { tasks, ..*config });
+    Ok(config)
+}
@@ -0,0 +1,136 @@
+//! Cause of the last reset.
+//!
+//! `StartOS` reads the reset-cause registers of the chip and the watchdog
+//! scratch registers once, before the StartupHook, which gets the reason
+//! to choose the application mode with. [`GetResetReason`] and
+//! [`GetResetInfo`] return them until the next reset.
+
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+#[cfg(debug_assertions)]
+use defmt::info;
+
+use crate::port;
+
+/// Cause of the last reset, as returned by `GetResetReason`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub enum ResetReasonType {
+    /// The chip was powered up.
+    PowerOn = 0,
+    /// The supply dropped below the brown-out threshold.
+    BrownOut = 1,
+    /// The RUN pin was driven low.
+    ResetPin = 2,
+    /// The watchdog expired.
+    Watchdog = 3,
+    /// The software asked for a reboot, through the bootrom or by
+    /// triggering the watchdog.
+    Software = 4,
+    /// A debugger reset the chip.
+    Debugger = 5,
+    /// The glitch detector fired.
+    GlitchDetector = 6,
+    /// A fault handler reset the chip, see `GetCrashRecord`.
+    Fault = 7,
+    /// None of the above was recorded.
+    Unknown = 8,
+}
+
+pub type ResetInfoRefType = *mut ResetInfoType;
+
+/// Reason of the last reset and the registers it was read from, as
+/// returned by `GetResetInfo`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ResetInfoType {
+    pub reason: ResetReasonType,
+    /// POWMAN `CHIP_RESET`, whose `HAD_*` bits tell the last chip reset.
+    pub chip_reset: u32,
+    /// WATCHDOG `REASON`, `FORCE` and `TIMER` bits.
+    pub watchdog_reason: u32,
+    /// WATCHDOG `SCRATCH0` to `SCRATCH7`, which survive all resets but
+    /// power-on and brown-out. The bootrom uses `SCRATCH4` to `SCRATCH7`.
+    pub scratch: [u32; 8],
+}
+
+impl ResetInfoType {
+    pub const UNKNOWN: ResetInfoType = ResetInfoType {
+        reason: ResetReasonType::Unknown,
+        chip_reset: 0,
+        watchdog_reason: 0,
+        scratch: [0; 8],
+    };
+}
+
+static RESET: Mutex<RefCell<ResetInfoType>> = Mutex::new(RefCell::new(ResetInfoType::UNKNOWN));
+
+/// Reads the cause of the last reset, `crashed` telling whether a crash
+/// record was found, and returns its reason. Called by `StartOS`.
+pub(crate) fn init(crashed: bool) -> ResetReasonType {
+    let mut reset = port::reset_info();
+    // A fault resets the processor only, which the chip does not record
+    if crashed {
+        reset.reason = ResetReasonType::Fault;
+    }
+    #[cfg(debug_assertions)]
+    info!(
+        "Reset reason {}, CHIP_RESET {=u32:#x}, watchdog REASON {=u32:#x}",
+        reset.reason, reset.chip_reset, reset.watchdog_reason
+    );
+    critical_section::with(|cs| *RESET.borrow_ref_mut(cs) = reset);
+    reset.reason
+}
+
+/// Returns the cause of the last reset.
+///
+/// # Syntax
+/// ```ignore
+/// ResetReasonType GetResetReason(void)
+/// ```
+///
+/// # Description
+///
+/// The reason is the one `StartOS` found, and also the one the StartupHook
+/// got.
+///
+/// # Particularities
+///
+/// Allowed on task level, ISR level and in the hook routines.
+#[unsafe(no_mangle)]
+pub extern "C" fn GetResetReason() -> ResetReasonType {
+    critical_section::with(|cs| RESET.borrow_ref(cs).reason)
+}
+
+/// Returns the cause of the last reset with the registers it was read
+/// from.
+///
+/// # Syntax
+/// ```ignore
+/// void GetResetInfo(ResetInfoRefType Info)
+/// ```
+///
+/// # Parameters
+///
+/// * `info` (out) — Reason, reset-cause registers and watchdog scratch
+///   registers, as read by `StartOS`.
+///
+/// # Description
+///
+/// The scratch registers let an application find out what it left behind
+/// before a watchdog or software reset, such as the culprit of a failed
+/// supervision.
+///
+/// # Particularities
+///
+/// Allowed on task level, ISR level and in the hook routines.
+///
+/// # Safety
+///
+/// `info` must be valid for a write.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn GetResetInfo(info: ResetInfoRefType) {
+    let reset = critical_section::with(|cs| *RESET.borrow_ref(cs));
+    unsafe { *info = reset };
+}
@@ -0,0 +1,194 @@
+use crate::{
+    StatusType, hooks, port,
+    ready_queue::Priority,
+    task::{KERNEL, Kernel, MAX_PRIORITIES, SchedulePolicy, TaskType},
+    trace::{self, TraceKind},
+};
+
+/// Maximum number of resources in a configuration, `RES_SCHEDULER` aside.
+pub const MAX_RESOURCES: usize = 16;
+
+pub type ResourceType = usize;
+
+/// Resource locking out the scheduler. Its ceiling is the highest priority.
+pub const RES_SCHEDULER: ResourceType = MAX_RESOURCES;
+
+/// Static description of a resource, as found in the OIL `RESOURCE` object.
+#[derive(Clone, Copy)]
+pub struct ResourceConfig {
+    pub name: &'static str,
+    /// Highest priority of the tasks using the resource.
+    pub ceiling: Priority,
+    /// Internal resources (`RESOURCEPROPERTY = INTERNAL`) are held by the
+    /// tasks they are assigned to while they run, and cannot be acquired
+    /// with `GetResource`.
+    pub internal: bool,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct ResourceState {
+    pub occupied: bool,
+    /// Priority of the owner before it got the resource.
+    pub saved_priority: Priority,
+    /// Resource the owner acquired before this one.
+    pub previous: Option<ResourceType>,
+}
+
+impl ResourceState {
+    pub const fn new() -> Self {
+        ResourceState {
+            occupied: false,
+            saved_priority: 0,
+            previous: None,
+        }
+    }
+}
+
+impl Kernel {
+    /// Ceiling of a standard resource, `None` if `resource_id` is invalid or
+    /// names an internal resource.
+    fn ceiling(&self, resource_id: ResourceType) -> Option<Priority> {
+        if resource_id == RES_SCHEDULER {
+            return Some((MAX_PRIORITIES - 1) as Priority);
+        }
+        self.resource_config
+            .get(resource_id)
+            .filter(|r| !r.internal)
+            .map(|r| r.ceiling)
+    }
+
+    /// Priority task `task_id` runs at while it holds its internal resource.
+    /// A non-preemptable task behaves as if it had one with the highest
+    /// ceiling.
+    pub fn internal_ceiling(&self, task_id: TaskType) -> Priority {
+        let task = &self.tasks[task_id];
+        match (task.schedule, task.internal_resource) {
+            (SchedulePolicy::Non, _) => (MAX_PRIORITIES - 1) as Priority,
+            (SchedulePolicy::Full, Some(resource)) => {
+                task.priority.max(self.resource_config[resource].ceiling)
+            }
+            (SchedulePolicy::Full, None) => task.priority,
+        }
+    }
+
+    pub fn get_resource(&mut self, resource_id: ResourceType) -> StatusType {
+        let Some(ceiling) = self.ceiling(resource_id) else {
+            return StatusType::EOsId;
+        };
+        if port::in_isr() {
+            return StatusType::EOsCallevel;
+        }
+        let Some(id) = self.running else {
+            return StatusType::EOsCallevel;
+        };
+        let state = &mut self.resources[resource_id];
+        if state.occupied || self.tasks[id].priority > ceiling {
+            return StatusType::EOsAccess;
+        }
+        let tcb = &mut self.tcbs[id];
+        state.occupied = true;
+        state.saved_priority = tcb.priority;
+        state.previous = tcb.last_resource;
+        tcb.last_resource = Some(resource_id);
+        tcb.priority = tcb.priority.max(ceiling);
+        trace::record(TraceKind::GetResource, resource_id, id as u32);
+        StatusType::EOk
+    }
+
+    pub fn release_resource(&mut self, resource_id: ResourceType) -> StatusType {
+        let Some(ceiling) = self.ceiling(resource_id) else {
+            return StatusType::EOsId;
+        };
+        if port::in_isr() {
+            return StatusType::EOsCallevel;
+        }
+        let Some(id) = self.running else {
+            return StatusType::EOsCallevel;
+        };
+        if self.tasks[id].priority > ceiling {
+            return StatusType::EOsAccess;
+        }
+        let tcb = &mut self.tcbs[id];
+        if tcb.last_resource != Some(resource_id) {
+            return StatusType::EOsNofunc;
+        }
+        let state = &mut self.resources[resource_id];
+        state.occupied = false;
+        tcb.priority = state.saved_priority;
+        tcb.last_resource = state.previous;
+        trace::record(TraceKind::ReleaseResource, resource_id, id as u32);
+        self.schedule();
+        StatusType::EOk
+    }
+}
+
+/// Enters a critical section guarded by a resource.
+///
+/// # Syntax
+/// ```ignore
+/// StatusType GetResource(ResourceType ResID)
+/// ```
+///
+/// # Parameters
+///
+/// * `resource_id` (in) — Reference to the resource.
+///
+/// # Description
+///
+/// The priority of the calling task is raised to the ceiling priority of
+/// the resource until it is released, so no other task using the resource
+/// can preempt it.
+///
+/// # Particularities
+///
+/// Nested resources must be released in reverse order. Only allowed on task
+/// level in this implementation.
+///
+/// # Status
+///
+/// * **Standard:**
+///   * `E_OK` — No error.
+/// * **Extended:**
+///   * `E_OS_ID` — `resource_id` is invalid or an internal resource.
+///   * `E_OS_ACCESS` — Resource already occupied, or the statically assigned
+///     priority of the caller is higher than the ceiling priority.
+#[unsafe(no_mangle)]
+pub extern "C" fn GetResource(resource_id: ResourceType) -> StatusType {
+    hooks::report(critical_section::with(|cs| {
+        KERNEL.borrow_ref_mut(cs).get_resource(resource_id)
+    }))
+}
+
+/// Leaves the critical section guarded by a resource.
+///
+/// # Syntax
+/// ```ignore
+/// StatusType ReleaseResource(ResourceType ResID)
+/// ```
+///
+/// # Parameters
+///
+/// * `resource_id` (in) — Reference to the resource.
+///
+/// # Description
+///
+/// The priority of the calling task goes back to what it was before the
+/// resource was acquired. A rescheduling takes place if a ready task now
+/// has a higher priority.
+///
+/// # Status
+///
+/// * **Standard:**
+///   * `E_OK` — No error.
+/// * **Extended:**
+///   * `E_OS_ID` — `resource_id` is invalid or an internal resource.
+///   * `E_OS_NOFUNC` — Resource not occupied by the caller, or another
+///     resource has to be released first.
+///   * `E_OS_ACCESS` — The statically assigned priority of the caller is
+///     higher than the ceiling priority.
+#[unsafe(no_mangle)]
+pub extern "C" fn ReleaseResource(resource_id: ResourceType) -> StatusType {
+    hooks::report(critical_section::with(|cs| {
+        KERNEL.borrow_ref_mut(cs).release_resource(resource_id)
+    }))
+}
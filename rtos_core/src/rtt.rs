@@ -0,0 +1,110 @@
+//! RTT channels shared with the debug probe, see `rtos/Embed.toml`.
+//!
+//! Up-channel 0 carries the defmt log. With the `systemview` feature,
+//! up-channel 1 carries the SystemView stream. With the `shell` feature,
+//! up-channel 2 and down-channel 0 form the shell terminal. Up-channel 3
+//! carries the raw standard output, see [`crate::stdout`]. Channels of
+//! disabled features are kept, tiny, so that the numbering never changes.
+
+use core::{
+    cell::RefCell,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use critical_section::Mutex;
+#[cfg(feature = "shell")]
+use rtt_target::DownChannel;
+use rtt_target::{ChannelMode, UpChannel, rtt_init, set_defmt_channel};
+
+const SYSVIEW_SIZE: usize = if cfg!(feature = "systemview") {
+    4096
+} else {
+    16
+};
+const TERMINAL_SIZE: usize = if cfg!(feature = "shell") { 1024 } else { 16 };
+const TERMINAL_INPUT_SIZE: usize = if cfg!(feature = "shell") { 128 } else { 16 };
+const STDOUT_SIZE: usize = 1024;
+
+static INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+static STDOUT: Mutex<RefCell<Option<UpChannel>>> = Mutex::new(RefCell::new(None));
+
+#[cfg(feature = "systemview")]
+static SYSVIEW: Mutex<RefCell<Option<UpChannel>>> = Mutex::new(RefCell::new(None));
+
+#[cfg(feature = "shell")]
+static TERMINAL: Mutex<RefCell<Option<(UpChannel, DownChannel)>>> = Mutex::new(RefCell::new(None));
+
+/// Sets the RTT control block up and routes defmt to up-channel 0.
+///
+/// Log messages sent before are lost, so applications call it first thing
+/// in `main`. `start_os` calls it as well; calls after the first do nothing.
+pub fn init() {
+    if INITIALIZED.swap(true, Ordering::AcqRel) {
+        return;
+    }
+    // Up-channels skip writes that do not fit rather than block
+    let channels = rtt_init! {
+        up: {
+            0: { size: 1024, name: "defmt" }
+            1: { size: SYSVIEW_SIZE, name: "SysView" }
+            2: { size: TERMINAL_SIZE, name: "Terminal" }
+            3: { size: STDOUT_SIZE, name: "Stdout" }
+        }
+        down: {
+            0: { size: TERMINAL_INPUT_SIZE, name: "Terminal" }
+        }
+    };
+    set_defmt_channel(channels.up.0);
+    // Text output is better cut than dropped whole
+    let mut stdout = channels.up.3;
+    stdout.set_mode(ChannelMode::NoBlockTrim);
+    critical_section::with(|cs| *STDOUT.borrow_ref_mut(cs) = Some(stdout));
+    #[cfg(feature = "systemview")]
+    critical_section::with(|cs| *SYSVIEW.borrow_ref_mut(cs) = Some(channels.up.1));
+    #[cfg(feature = "shell")]
+    {
+        let (mut output, input) = (channels.up.2, channels.down.0);
+        output.set_mode(ChannelMode::NoBlockTrim);
+        critical_section::with(|cs| *TERMINAL.borrow_ref_mut(cs) = Some((output, input)));
+    }
+}
+
+/// Writes a SystemView packet to up-channel 1, whole or not at all.
+/// Returns whether it was written.
+#[cfg(feature = "systemview")]
+pub(crate) fn write_systemview(bytes: &[u8]) -> bool {
+    critical_section::with(|cs| match SYSVIEW.borrow_ref_mut(cs).as_mut() {
+        Some(channel) => channel.write(bytes) == bytes.len(),
+        None => false,
+    })
+}
+
+/// Writes standard output to up-channel 3, as much of it as fits.
+pub(crate) fn write_stdout(bytes: &[u8]) {
+    critical_section::with(|cs| {
+        if let Some(channel) = STDOUT.borrow_ref_mut(cs).as_mut() {
+            channel.write(bytes);
+        }
+    });
+}
+
+/// Writes shell output to up-channel 2, as much of it as fits.
+#[cfg(feature = "shell")]
+pub(crate) fn write_terminal(bytes: &[u8]) {
+    critical_section::with(|cs| {
+        if let Some((output, _)) = TERMINAL.borrow_ref_mut(cs).as_mut() {
+            output.write(bytes);
+        }
+    });
+}
+
+/// Reads shell input from down-channel 0 into `buf`, returning the number
+/// of bytes read.
+#[cfg(feature = "shell")]
+pub(crate) fn read_terminal(buf: &mut [u8]) -> usize {
+    critical_section::with(|cs| match TERMINAL.borrow_ref_mut(cs).as_mut() {
+        Some((_, input)) => input.read(buf),
+        None => 0,
+    })
+}
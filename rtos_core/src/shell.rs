@@ -0,0 +1,672 @@
+//! Interactive shell for bench debugging.
+//!
+//! With the `shell` feature, the application adds a basic task with
+//! [`ShellTask`] as entry point to its configuration, usually at the lowest
+//! priority. Input reaches the shell through [`feed`], from a UART receive
+//! interrupt for instance, and through RTT down-channel 0, which [`poll`]
+//! reads. Either one activates the shell task, which handles the pending
+//! input and terminates. Output goes to RTT up-channel 2 on the target and
+//! to the standard output in the simulation, unless [`set_output`]
+//! redirects it.
+//!
+//! `help` lists the commands. C code adds its own with
+//! [`ShellRegisterCommand`].
+
+use core::{
+    cell::{Cell, RefCell},
+    ffi::{CStr, c_char, c_int},
+    fmt::{self, Write},
+    ptr,
+};
+
+use critical_section::Mutex;
+
+#[cfg(feature = "events")]
+use crate::events::{EventMaskType, SetEvent};
+#[cfg(not(target_os = "none"))]
+use crate::port::sim::write_stdout as write_terminal;
+#[cfg(target_os = "none")]
+use crate::rtt::{read_terminal, write_terminal};
+use crate::{
+    StatusType,
+    alarms::{self, AlarmType, CancelAlarm, SetRelAlarm, TickType},
+    hooks,
+    memory::{self, MAX_POOLS},
+    task::{ActivateTask, KERNEL, TaskEntry, TaskStateType, TaskType, TerminateTask},
+};
+
+/// Longest command line, longer input is ignored.
+const LINE_SIZE: usize = 80;
+/// Bytes of input waiting for the shell task.
+const INPUT_SIZE: usize = 128;
+/// Most words in a command line, the command included.
+const MAX_ARGS: usize = 8;
+/// Number of commands applications can register.
+const MAX_COMMANDS: usize = 16;
+
+const PROMPT: &str = "> ";
+
+/// Command handler registered from C. `argv` holds `argc` NUL-terminated
+/// words, the command name first, followed by a null pointer. A non-zero
+/// return value is printed as the exit status.
+pub type ShellCommandType = extern "C" fn(argc: c_int, argv: *const *const c_char) -> c_int;
+
+/// Destination of the shell output.
+pub type Output = fn(&[u8]);
+
+#[derive(Clone, Copy)]
+struct Command {
+    name: &'static str,
+    help: &'static str,
+    handler: ShellCommandType,
+}
+
+struct Builtin {
+    name: &'static str,
+    help: &'static str,
+    run: fn(&[&str]),
+}
+
+const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "help",
+        help: "list the commands",
+        run: help,
+    },
+    Builtin {
+        name: "tasks",
+        help: "list the tasks with state, priority and stack usage",
+        run: tasks,
+    },
+    Builtin {
+        name: "activate",
+        help: "<task> - activate a task, by ID or name",
+        run: activate,
+    },
+    Builtin {
+        name: "alarms",
+        help: "list the alarms with the ticks before they expire",
+        run: list_alarms,
+    },
+    Builtin {
+        name: "alarm",
+        help: "set <alarm> <increment> [cycle] | cancel <alarm>",
+        run: alarm,
+    },
+    #[cfg(feature = "events")]
+    Builtin {
+        name: "event",
+        help: "<task> <mask> - set events of an extended task",
+        run: event,
+    },
+    Builtin {
+        name: "heap",
+        help: "show the heap usage",
+        run: heap,
+    },
+    Builtin {
+        name: "pools",
+        help: "list the memory pools with their usage",
+        run: pools,
+    },
+    Builtin {
+        name: "reboot",
+        help: "reset the chip",
+        run: reboot,
+    },
+];
+
+/// Ring buffer between the input sources and the shell task.
+struct Input {
+    bytes: [u8; INPUT_SIZE],
+    head: usize,
+    len: usize,
+}
+
+impl Input {
+    const fn new() -> Self {
+        Input {
+            bytes: [0; INPUT_SIZE],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// Queues `byte`, dropping it if the buffer is full.
+    fn push(&mut self, byte: u8) {
+        if self.len < INPUT_SIZE {
+            self.bytes[(self.head + self.len) % INPUT_SIZE] = byte;
+            self.len += 1;
+        }
+    }
+
+    fn pop(&mut self) -> Option<u8> {
+        if self.len == 0 {
+            return None;
+        }
+        let byte = self.bytes[self.head];
+        self.head = (self.head + 1) % INPUT_SIZE;
+        self.len -= 1;
+        Some(byte)
+    }
+}
+
+/// Command line being typed.
+#[derive(Clone, Copy)]
+struct Line {
+    bytes: [u8; LINE_SIZE],
+    len: usize,
+    /// Whether the last byte ended a line with `\r`, so that the `\n` of a
+    /// `\r\n` pair does not end another one.
+    after_cr: bool,
+}
+
+/// What typing one byte did to the command line.
+enum Edit {
+    None,
+    Echo(u8),
+    Erase,
+    Enter(Line),
+}
+
+impl Line {
+    const fn new() -> Self {
+        Line {
+            bytes: [0; LINE_SIZE],
+            len: 0,
+            after_cr: false,
+        }
+    }
+
+    fn edit(&mut self, byte: u8) -> Edit {
+        let after_cr = core::mem::replace(&mut self.after_cr, byte == b'\r');
+        match byte {
+            b'\n' if after_cr => Edit::None,
+            b'\r' | b'\n' => {
+                let line = *self;
+                self.len = 0;
+                Edit::Enter(line)
+            }
+            // Backspace and delete
+            0x08 | 0x7F if self.len > 0 => {
+                self.len -= 1;
+                Edit::Erase
+            }
+            b' '..=b'~' if self.len < LINE_SIZE => {
+                self.bytes[self.len] = byte;
+                self.len += 1;
+                Edit::Echo(byte)
+            }
+            _ => Edit::None,
+        }
+    }
+}
+
+static INPUT: Mutex<RefCell<Input>> = Mutex::new(RefCell::new(Input::new()));
+static LINE: Mutex<RefCell<Line>> = Mutex::new(RefCell::new(Line::new()));
+static OUTPUT: Mutex<Cell<Output>> = Mutex::new(Cell::new(write_terminal));
+static COMMANDS: Mutex<RefCell<[Option<Command>; MAX_COMMANDS]>> =
+    Mutex::new(RefCell::new([None; MAX_COMMANDS]));
+
+/// Sends the shell output to `output` instead of the default terminal.
+pub fn set_output(output: Output) {
+    critical_section::with(|cs| OUTPUT.borrow(cs).set(output));
+}
+
+/// Writes `bytes` to the shell output.
+pub fn write(bytes: &[u8]) {
+    let output = critical_section::with(|cs| OUTPUT.borrow(cs).get());
+    output(bytes);
+}
+
+/// Formatter over the shell output, turning `\n` into `\r\n` for serial
+/// terminals.
+struct Out;
+
+impl Write for Out {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        write_text(text.as_bytes());
+        Ok(())
+    }
+}
+
+fn write_text(text: &[u8]) {
+    let mut lines = text.split(|&byte| byte == b'\n');
+    if let Some(first) = lines.next() {
+        write(first);
+    }
+    for line in lines {
+        write(b"\r\n");
+        write(line);
+    }
+}
+
+/// Passes `bytes` of input to the shell and activates the shell task.
+///
+/// Allowed on task level and ISR level. Input that does not fit in the
+/// buffer is dropped.
+pub fn feed(bytes: &[u8]) {
+    critical_section::with(|cs| {
+        let mut input = INPUT.borrow_ref_mut(cs);
+        for &byte in bytes {
+            input.push(byte);
+        }
+    });
+    wake();
+}
+
+/// Reads the input waiting on RTT down-channel 0, then activates the shell
+/// task if any input is pending.
+///
+/// Called periodically, from the timer ISR for instance. It also picks up
+/// input that arrived through [`feed`] while the shell task was finishing.
+pub fn poll() {
+    #[cfg(target_os = "none")]
+    {
+        let mut buf = [0; 32];
+        loop {
+            let len = read_terminal(&mut buf);
+            if len == 0 {
+                break;
+            }
+            critical_section::with(|cs| {
+                let mut input = INPUT.borrow_ref_mut(cs);
+                for &byte in &buf[..len] {
+                    input.push(byte);
+                }
+            });
+        }
+    }
+    wake();
+}
+
+/// Activates the shell task if input is pending and the task is suspended:
+/// a running shell task handles the new input before it terminates.
+fn wake() {
+    critical_section::with(|cs| {
+        if INPUT.borrow_ref(cs).len == 0 {
+            return;
+        }
+        let mut kernel = KERNEL.borrow_ref_mut(cs);
+        let shell = ShellTask as TaskEntry as usize;
+        let Some(id) = kernel
+            .tasks
+            .iter()
+            .position(|task| task.entry as usize == shell)
+        else {
+            return;
+        };
+        if kernel.tcbs[id].state == TaskStateType::Suspended {
+            kernel.activate(id);
+        }
+    });
+}
+
+/// Entry point of the shell task.
+///
+/// Handles the pending input, echoing it and running every command line it
+/// completes, then terminates. Activated by [`feed`] and [`poll`].
+#[unsafe(no_mangle)]
+pub extern "C" fn ShellTask() {
+    while let Some(byte) = critical_section::with(|cs| INPUT.borrow_ref_mut(cs).pop()) {
+        match critical_section::with(|cs| LINE.borrow_ref_mut(cs).edit(byte)) {
+            Edit::None => {}
+            Edit::Echo(byte) => write(&[byte]),
+            Edit::Erase => write(b"\x08 \x08"),
+            Edit::Enter(line) => {
+                write(b"\r\n");
+                execute(&line.bytes[..line.len]);
+                write(PROMPT.as_bytes());
+            }
+        }
+    }
+    TerminateTask();
+}
+
+/// Splits `line` into words and runs the command they name.
+fn execute(line: &[u8]) {
+    // Words are NUL-terminated in place for the C handlers
+    let mut text = [0; LINE_SIZE + 1];
+    for (dst, &src) in text.iter_mut().zip(line) {
+        *dst = if src == b' ' { 0 } else { src };
+    }
+    let mut args = [""; MAX_ARGS];
+    let mut argv = [ptr::null::<c_char>(); MAX_ARGS + 1];
+    let mut argc = 0;
+    for word in text[..line.len()]
+        .split(|&byte| byte == 0)
+        .filter(|word| !word.is_empty())
+    {
+        if argc == MAX_ARGS {
+            let _ = writeln!(Out, "too many arguments");
+            return;
+        }
+        // The line only holds printable ASCII
+        args[argc] = core::str::from_utf8(word).unwrap_or("");
+        argv[argc] = word.as_ptr().cast();
+        argc += 1;
+    }
+    let Some(&name) = args[..argc].first() else {
+        return;
+    };
+
+    if let Some(builtin) = BUILTINS.iter().find(|builtin| builtin.name == name) {
+        (builtin.run)(&args[1..argc]);
+        return;
+    }
+    let command = critical_section::with(|cs| {
+        COMMANDS
+            .borrow_ref(cs)
+            .iter()
+            .flatten()
+            .find(|command| command.name == name)
+            .copied()
+    });
+    match command {
+        Some(command) => {
+            let status = (command.handler)(argc as c_int, argv.as_ptr());
+            if status != 0 {
+                let _ = writeln!(Out, "exit status {status}");
+            }
+        }
+        None => {
+            let _ = writeln!(Out, "unknown command {name}, try help");
+        }
+    }
+}
+
+fn help(_: &[&str]) {
+    for builtin in BUILTINS {
+        let _ = writeln!(Out, "{:<10} {}", builtin.name, builtin.help);
+    }
+    let commands = critical_section::with(|cs| *COMMANDS.borrow_ref(cs));
+    for command in commands.iter().flatten() {
+        let _ = writeln!(Out, "{:<10} {}", command.name, command.help);
+    }
+}
+
+fn tasks(_: &[&str]) {
+    let _ = writeln!(Out, "ID NAME             STATE     PRIO STACK");
+    let count = critical_section::with(|cs| KERNEL.borrow_ref(cs).tasks.len());
+    for id in 0..count {
+        let (task, tcb) = critical_section::with(|cs| {
+            let kernel = KERNEL.borrow_ref(cs);
+            (kernel.tasks[id], kernel.tcbs[id])
+        });
+        let _ = writeln!(
+            Out,
+            "{:<2} {:<16} {:<9} {:<4} {}/{}",
+            id,
+            task.name,
+            state_name(tcb.state),
+            tcb.priority,
+            task.stack.used(),
+            task.stack.size
+        );
+    }
+}
+
+fn activate(args: &[&str]) {
+    let [task] = args else {
+        let _ = writeln!(Out, "usage: activate <task>");
+        return;
+    };
+    match parse_task(task) {
+        Some(id) => print_status(ActivateTask(id)),
+        None => {
+            let _ = writeln!(Out, "no task {task}");
+        }
+    }
+}
+
+fn list_alarms(_: &[&str]) {
+    let _ = writeln!(Out, "ID NAME             STATE    REMAINING CYCLE");
+    let alarms = critical_section::with(|cs| *alarms::SOFTW_ALARMS.borrow_ref(cs));
+    for (id, (alarm, config)) in alarms.iter().zip(alarms::configured()).enumerate() {
+        if alarm.active {
+            let _ = writeln!(
+                Out,
+                "{:<2} {:<16} active   {:<9} {}",
+                id,
+                config.name,
+                alarm.alarm_base.maxallowedvalue - alarm.tick,
+                alarm.cycle
+            );
+        } else {
+            let _ = writeln!(Out, "{:<2} {:<16} inactive", id, config.name);
+        }
+    }
+}
+
+fn alarm(args: &[&str]) {
+    let status = match args {
+        ["set", alarm, increment] => parse::<AlarmType>(alarm)
+            .zip(parse::<TickType>(increment))
+            .map(|(alarm, increment)| SetRelAlarm(alarm, increment, 0)),
+        ["set", alarm, increment, cycle] => parse::<AlarmType>(alarm)
+            .zip(parse::<TickType>(increment))
+            .zip(parse::<TickType>(cycle))
+            .map(|((alarm, increment), cycle)| SetRelAlarm(alarm, increment, cycle)),
+        ["cancel", alarm] => parse::<AlarmType>(alarm).map(|alarm| CancelAlarm(alarm)),
+        _ => None,
+    };
+    match status {
+        Some(status) => print_status(status),
+        None => {
+            let _ = writeln!(
+                Out,
+                "usage: alarm set <alarm> <increment> [cycle] | cancel <alarm>"
+            );
+        }
+    }
+}
+
+#[cfg(feature = "events")]
+fn event(args: &[&str]) {
+    let status = match args {
+        [task, mask] => parse_task(task)
+            .zip(parse::<EventMaskType>(mask))
+            .map(|(task, mask)| SetEvent(task, mask)),
+        _ => None,
+    };
+    match status {
+        Some(status) => print_status(status),
+        None => {
+            let _ = writeln!(Out, "usage: event <task> <mask>");
+        }
+    }
+}
+
+fn heap(_: &[&str]) {
+    #[cfg(target_os = "none")]
+    {
+        let (used, free) = memory::heap_usage();
+        let _ = writeln!(Out, "{used} bytes used, {free} bytes free");
+    }
+    #[cfg(not(target_os = "none"))]
+    {
+        let _ = writeln!(Out, "no kernel heap in the simulation");
+    }
+}
+
+fn pools(_: &[&str]) {
+    let _ = writeln!(Out, "ID NAME             SIZE BLOCKS FREE MIN  FAILED");
+    for id in 0..MAX_POOLS {
+        let Some((name, info)) = memory::pool_usage(id) else {
+            break;
+        };
+        let _ = writeln!(
+            Out,
+            "{:<2} {:<16} {:<4} {:<6} {:<4} {:<4} {}",
+            id, name, info.block_size, info.blocks, info.free, info.min_free, info.failures
+        );
+    }
+}
+
+fn reboot(_: &[&str]) {
+    let _ = writeln!(Out, "rebooting");
+    #[cfg(target_os = "none")]
+    cortex_m::peripheral::SCB::sys_reset();
+    // There is no chip to reset in the simulation, which stops instead
+    #[cfg(not(target_os = "none"))]
+    crate::ShutdownOS(StatusType::EOk);
+}
+
+/// Parses a decimal number, or a hexadecimal one with a `0x` prefix.
+fn parse<T: TryFrom<u64>>(text: &str) -> Option<T> {
+    let value = match text.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok()?,
+        None => text.parse().ok()?,
+    };
+    T::try_from(value).ok()
+}
+
+/// Finds a task by ID or by name.
+fn parse_task(text: &str) -> Option<TaskType> {
+    critical_section::with(|cs| {
+        let tasks = KERNEL.borrow_ref(cs).tasks;
+        match parse::<TaskType>(text) {
+            Some(id) => (id < tasks.len()).then_some(id),
+            None => tasks.iter().position(|task| task.name == text),
+        }
+    })
+}
+
+fn print_status(status: StatusType) {
+    let _ = writeln!(Out, "{}", status_name(status));
+}
+
+fn status_name(status: StatusType) -> &'static str {
+    match status {
+        StatusType::EOk => "E_OK",
+        StatusType::EOsId => "E_OS_ID",
+        StatusType::EOsNofunc => "E_OS_NOFUNC",
+        StatusType::EOsValue => "E_OS_VALUE",
+        StatusType::EOsState => "E_OS_STATE",
+        StatusType::EOsLimit => "E_OS_LIMIT",
+        StatusType::EOsCallevel => "E_OS_CALLEVEL",
+        StatusType::EOsAccess => "E_OS_ACCESS",
+        StatusType::EOsResource => "E_OS_RESOURCE",
+        StatusType::EOsSysFault => "E_OS_SYS_FAULT",
+    }
+}
+
+fn state_name(state: TaskStateType) -> &'static str {
+    match state {
+        TaskStateType::Running => "running",
+        TaskStateType::Waiting => "waiting",
+        TaskStateType::Ready => "ready",
+        TaskStateType::Suspended => "suspended",
+    }
+}
+
+/// Adds a command to the shell.
+///
+/// # Syntax
+/// ```ignore
+/// StatusType ShellRegisterCommand(const char *Name, const char *Help,
+///                                 ShellCommandType Handler)
+/// ```
+///
+/// # Parameters
+///
+/// * `name` (in) — Word that runs the command, a static string.
+/// * `help` (in) — Description listed by `help`, a static string.
+/// * `handler` (in) — Function run with the words of the command line.
+///
+/// # Description
+///
+/// Registers `handler` to run in the shell task whenever a command line
+/// starts with `name`. Up to 16 commands can be registered.
+///
+/// # Particularities
+///
+/// Allowed on task level and in the StartupHook.
+///
+/// # Status
+///
+/// * **Standard:**
+///   * `E_OK` — No error.
+///   * `E_OS_LIMIT` — All command slots are taken.
+/// * **Extended:**
+///   * `E_OS_VALUE` — `name` is empty, not ASCII, contains a space or is
+///     already taken.
+///
+/// # Safety
+///
+/// `name` and `help` must be null or NUL-terminated strings that stay valid
+/// and unchanged for as long as the system runs, such as string literals:
+/// the shell keeps them.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ShellRegisterCommand(
+    name: *const c_char,
+    help: *const c_char,
+    handler: ShellCommandType,
+) -> StatusType {
+    if name.is_null() || help.is_null() {
+        return hooks::report(StatusType::EOsValue);
+    }
+    let name = unsafe { CStr::from_ptr(name) }.to_str().unwrap_or("");
+    let help = unsafe { CStr::from_ptr(help) }.to_str().unwrap_or("");
+    hooks::report(register_command(name, help, handler))
+}
+
+fn register_command(
+    name: &'static str,
+    help: &'static str,
+    handler: ShellCommandType,
+) -> StatusType {
+    if name.is_empty() || !name.bytes().all(|byte| byte.is_ascii_graphic()) {
+        return StatusType::EOsValue;
+    }
+    if BUILTINS.iter().any(|builtin| builtin.name == name) {
+        return StatusType::EOsValue;
+    }
+    critical_section::with(|cs| {
+        let mut commands = COMMANDS.borrow_ref_mut(cs);
+        if commands
+            .iter()
+            .flatten()
+            .any(|command| command.name == name)
+        {
+            return StatusType::EOsValue;
+        }
+        match commands.iter_mut().find(|slot| slot.is_none()) {
+            Some(slot) => {
+                *slot = Some(Command {
+                    name,
+                    help,
+                    handler,
+                });
+                StatusType::EOk
+            }
+            None => StatusType::EOsLimit,
+        }
+    })
+}
+
+/// Writes a string to the shell output.
+///
+/// # Syntax
+/// ```ignore
+/// void ShellWrite(const char *Text)
+/// ```
+///
+/// # Parameters
+///
+/// * `text` (in) — NUL-terminated string. `\n` is sent as `\r\n`.
+///
+/// # Particularities
+///
+/// Meant for command handlers, allowed on task level and ISR level.
+///
+/// # Safety
+///
+/// `text` must be null or NUL-terminated.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ShellWrite(text: *const c_char) {
+    if text.is_null() {
+        return;
+    }
+    write_text(unsafe { CStr::from_ptr(text) }.to_bytes());
+}
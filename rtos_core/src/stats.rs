@@ -0,0 +1,503 @@
+//! Run-time statistics.
+//!
+//! With the `stats` feature, the kernel measures for every task its
+//! execution time, activations, preemptions and longest observed response
+//! time, from an activation to the matching termination, and counts its
+//! activation overruns and deadline misses. For every
+//! Category 2 ISR that calls [`crate::trace::isr_enter`] and
+//! [`crate::trace::isr_exit`], it measures the execution time, the number of
+//! runs and the longest run. It also measures the time spent idle. Times
+//! are read from the 64-bit TIMER0 counter, in microseconds.
+//!
+//! Execution time is charged to whatever owns the CPU between two trace
+//! points: a task from its start to the switch away from it, an ISR from
+//! its entry to its exit, less the ISRs nesting in it. The time the kernel
+//! spends switching is charged to the task leaving the CPU.
+//!
+//! Without the feature, neither the measurements nor the services exist.
+
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+
+use crate::{
+    StatusType, hooks, port,
+    task::{KERNEL, MAX_TASKS, QUEUE_DEPTH, TaskType},
+    trace::TraceKind,
+};
+
+/// Number of interrupts of the RP2350.
+pub const NUM_IRQS: usize = 52;
+/// Deepest ISR nesting tracked, one level per NVIC priority.
+const MAX_NESTING: usize = 16;
+
+/// Run-time statistics of a task. Times are in microseconds.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TaskStatisticsType {
+    /// Time spent running.
+    pub execution_time: u64,
+    /// Longest time from an activation to the matching termination.
+    pub response_time_max: u64,
+    /// Number of activations.
+    pub activations: u32,
+    /// Number of times a higher-priority task took the CPU.
+    pub preemptions: u32,
+    /// Number of activations rejected with `E_OS_LIMIT`.
+    pub overruns: u32,
+    /// Number of activations still pending past their deadline.
+    pub deadline_misses: u32,
+}
+
+pub type TaskStatisticsRefType = *mut TaskStatisticsType;
+
+/// Run-time statistics of a Category 2 ISR. Times are in microseconds.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IsrStatisticsType {
+    /// Time spent running, less the nested ISRs.
+    pub execution_time: u64,
+    /// Longest time from an entry to the matching exit.
+    pub duration_max: u64,
+    /// Number of runs.
+    pub activations: u32,
+}
+
+pub type IsrStatisticsRefType = *mut IsrStatisticsType;
+
+/// Statistics of the whole system. Times are in microseconds.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SystemStatisticsType {
+    /// Time since `StartOS` or the last `ResetStatistics`.
+    pub elapsed_time: u64,
+    /// Part of `elapsed_time` spent in the idle loop.
+    pub idle_time: u64,
+}
+
+pub type SystemStatisticsRefType = *mut SystemStatisticsType;
+
+/// Owner of the CPU, charged with the time that passes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Context {
+    /// Start-up, charged to nobody.
+    None,
+    Idle,
+    Task(TaskType),
+    Isr(u8),
+}
+
+struct Stats {
+    tasks: [TaskStatisticsType; MAX_TASKS],
+    isrs: [IsrStatisticsType; NUM_IRQS],
+    idle_time: u64,
+    reset_at: u64,
+    /// Start of the time not charged yet.
+    last: u64,
+    current: Context,
+    /// Contexts interrupted by the running ISRs with the entry time of
+    /// each ISR, innermost last.
+    interrupted: [(Context, u64); MAX_NESTING],
+    nesting: usize,
+    /// Times of the pending activations of each task, oldest first.
+    pending: [[u64; QUEUE_DEPTH]; MAX_TASKS],
+    pending_len: [usize; MAX_TASKS],
+}
+
+static STATS: Mutex<RefCell<Stats>> = Mutex::new(RefCell::new(Stats::new()));
+
+impl Stats {
+    const fn new() -> Self {
+        Stats {
+            tasks: [TaskStatisticsType {
+                execution_time: 0,
+                response_time_max: 0,
+                activations: 0,
+                preemptions: 0,
+                overruns: 0,
+                deadline_misses: 0,
+            }; MAX_TASKS],
+            isrs: [IsrStatisticsType {
+                execution_time: 0,
+                duration_max: 0,
+                activations: 0,
+            }; NUM_IRQS],
+            idle_time: 0,
+            reset_at: 0,
+            last: 0,
+            current: Context::None,
+            interrupted: [(Context::None, 0); MAX_NESTING],
+            nesting: 0,
+            pending: [[0; QUEUE_DEPTH]; MAX_TASKS],
+            pending_len: [0; MAX_TASKS],
+        }
+    }
+
+    /// Charges the time since the last call to the current context.
+    fn charge(&mut self, now: u64) {
+        let elapsed = now - self.last;
+        self.last = now;
+        match self.current {
+            Context::None => {}
+            Context::Idle => self.idle_time += elapsed,
+            Context::Task(id) => self.tasks[id].execution_time += elapsed,
+            Context::Isr(irq) => {
+                if let Some(isr) = self.isrs.get_mut(irq as usize) {
+                    isr.execution_time += elapsed;
+                }
+            }
+        }
+    }
+
+    fn switch_to(&mut self, context: Context, now: u64) {
+        self.charge(now);
+        self.current = context;
+    }
+
+    fn activate(&mut self, id: TaskType, now: u64) {
+        self.tasks[id].activations = self.tasks[id].activations.wrapping_add(1);
+        let len = self.pending_len[id];
+        if len < QUEUE_DEPTH {
+            self.pending[id][len] = now;
+            self.pending_len[id] = len + 1;
+        }
+    }
+
+    fn terminate(&mut self, id: TaskType, now: u64) {
+        let len = self.pending_len[id];
+        if len == 0 {
+            return;
+        }
+        let response = now - self.pending[id][0];
+        let task = &mut self.tasks[id];
+        task.response_time_max = task.response_time_max.max(response);
+        self.pending[id].copy_within(1..len, 0);
+        self.pending_len[id] = len - 1;
+    }
+
+    fn isr_enter(&mut self, irq: u8, now: u64) {
+        self.charge(now);
+        if self.nesting == MAX_NESTING {
+            return;
+        }
+        self.interrupted[self.nesting] = (self.current, now);
+        self.nesting += 1;
+        self.current = Context::Isr(irq);
+        if let Some(isr) = self.isrs.get_mut(irq as usize) {
+            isr.activations = isr.activations.wrapping_add(1);
+        }
+    }
+
+    fn isr_exit(&mut self, now: u64) {
+        self.charge(now);
+        if self.nesting == 0 {
+            return;
+        }
+        self.nesting -= 1;
+        let (interrupted, entry) = self.interrupted[self.nesting];
+        if let Context::Isr(irq) = self.current
+            && let Some(isr) = self.isrs.get_mut(irq as usize)
+        {
+            isr.duration_max = isr.duration_max.max(now - entry);
+        }
+        self.current = interrupted;
+    }
+
+    /// Clears the measurements, keeping track of what runs.
+    fn reset(&mut self, now: u64) {
+        self.charge(now);
+        self.tasks = [TaskStatisticsType::default(); MAX_TASKS];
+        self.isrs = [IsrStatisticsType::default(); NUM_IRQS];
+        self.idle_time = 0;
+        self.reset_at = now;
+    }
+}
+
+/// Starts the measurements over, at `StartOS`.
+pub(crate) fn init() {
+    critical_section::with(|cs| {
+        let now = port::time_us();
+        let mut stats = STATS.borrow_ref_mut(cs);
+        *stats = Stats::new();
+        stats.reset_at = now;
+        stats.last = now;
+    });
+}
+
+/// Accounts for a kernel trace point.
+pub(crate) fn record(kind: TraceKind, object: usize, _data: u32) {
+    critical_section::with(|cs| {
+        // Read under the lock, so that time never goes back between records
+        let now = port::time_us();
+        let mut stats = STATS.borrow_ref_mut(cs);
+        match kind {
+            TraceKind::Activate => stats.activate(object, now),
+            TraceKind::Start => stats.switch_to(Context::Task(object), now),
+            TraceKind::Preempt => {
+                let task = &mut stats.tasks[object];
+                task.preemptions = task.preemptions.wrapping_add(1);
+            }
+            TraceKind::Terminate => stats.terminate(object, now),
+            TraceKind::Overrun => {
+                let task = &mut stats.tasks[object];
+                task.overruns = task.overruns.wrapping_add(1);
+            }
+            TraceKind::DeadlineMiss => {
+                let task = &mut stats.tasks[object];
+                task.deadline_misses = task.deadline_misses.wrapping_add(1);
+            }
+            TraceKind::Idle => stats.switch_to(Context::Idle, now),
+            TraceKind::IsrEnter => stats.isr_enter(object as u8, now),
+            TraceKind::IsrExit => stats.isr_exit(now),
+            _ => {}
+        }
+    });
+}
+
+/// Statistics of task `task_id`, up to now.
+pub fn task_statistics(task_id: TaskType) -> Result<TaskStatisticsType, StatusType> {
+    critical_section::with(|cs| {
+        let now = port::time_us();
+        if task_id >= KERNEL.borrow_ref(cs).tasks.len() {
+            return Err(StatusType::EOsId);
+        }
+        let mut stats = STATS.borrow_ref_mut(cs);
+        stats.charge(now);
+        Ok(stats.tasks[task_id])
+    })
+}
+
+/// Statistics of the ISR of interrupt `irq`, up to now.
+pub fn isr_statistics(irq: u8) -> Result<IsrStatisticsType, StatusType> {
+    if irq as usize >= NUM_IRQS {
+        return Err(StatusType::EOsId);
+    }
+    critical_section::with(|cs| {
+        let now = port::time_us();
+        let mut stats = STATS.borrow_ref_mut(cs);
+        stats.charge(now);
+        Ok(stats.isrs[irq as usize])
+    })
+}
+
+/// Statistics of the whole system, up to now.
+pub fn system_statistics() -> SystemStatisticsType {
+    critical_section::with(|cs| {
+        let now = port::time_us();
+        let mut stats = STATS.borrow_ref_mut(cs);
+        stats.charge(now);
+        SystemStatisticsType {
+            elapsed_time: now - stats.reset_at,
+            idle_time: stats.idle_time,
+        }
+    })
+}
+
+/// Reads the run-time statistics of a task.
+///
+/// # Syntax
+/// ```ignore
+/// StatusType GetTaskStatistics(TaskType TaskID, TaskStatisticsRefType Stats)
+/// ```
+///
+/// # Parameters
+///
+/// * `task_id` (in) — Task reference.
+/// * `stats` (out) — Statistics of the task since `StartOS` or the last
+///   `ResetStatistics`.
+///
+/// # Description
+///
+/// Copies the execution time, longest response time and the activation,
+/// preemption, overrun and deadline miss counts of task `task_id` to
+/// `stats`. The execution time of a
+/// task in the running state includes its current run.
+///
+/// # Particularities
+///
+/// Allowed on task level, ISR level and in all hook routines.
+///
+/// # Status
+///
+/// * **Standard:**
+///   * `E_OK` — No error.
+/// * **Extended:**
+///   * `E_OS_ID` — Task `task_id` is invalid.
+///
+/// # Safety
+///
+/// `stats` must be valid for a write.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn GetTaskStatistics(
+    task_id: TaskType,
+    stats: TaskStatisticsRefType,
+) -> StatusType {
+    let status = match task_statistics(task_id) {
+        Ok(task) => {
+            unsafe { *stats = task };
+            StatusType::EOk
+        }
+        Err(status) => status,
+    };
+    hooks::report(status)
+}
+
+/// Reads the run-time statistics of a Category 2 ISR.
+///
+/// # Syntax
+/// ```ignore
+/// StatusType GetIsrStatistics(uint8 Irq, IsrStatisticsRefType Stats)
+/// ```
+///
+/// # Parameters
+///
+/// * `irq` (in) — NVIC interrupt number of the ISR.
+/// * `stats` (out) — Statistics of the ISR since `StartOS` or the last
+///   `ResetStatistics`.
+///
+/// # Description
+///
+/// Copies the execution time, longest run and run count of the ISR of
+/// interrupt `irq` to `stats`. Only ISRs that call `trace::isr_enter` and
+/// `trace::isr_exit` are measured.
+///
+/// # Particularities
+///
+/// Allowed on task level, ISR level and in all hook routines.
+///
+/// # Status
+///
+/// * **Standard:**
+///   * `E_OK` — No error.
+/// * **Extended:**
+///   * `E_OS_ID` — `irq` is not an interrupt of the RP2350.
+///
+/// # Safety
+///
+/// `stats` must be valid for a write.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn GetIsrStatistics(irq: u8, stats: IsrStatisticsRefType) -> StatusType {
+    let status = match isr_statistics(irq) {
+        Ok(isr) => {
+            unsafe { *stats = isr };
+            StatusType::EOk
+        }
+        Err(status) => status,
+    };
+    hooks::report(status)
+}
+
+/// Reads the elapsed and idle time.
+///
+/// # Syntax
+/// ```ignore
+/// StatusType GetSystemStatistics(SystemStatisticsRefType Stats)
+/// ```
+///
+/// # Parameters
+///
+/// * `stats` (out) — Time elapsed since `StartOS` or the last
+///   `ResetStatistics`, and the part of it spent idle.
+///
+/// # Description
+///
+/// The CPU load of a task over the measurement is its execution time
+/// divided by `elapsed_time`, the total load `1 - idle_time / elapsed_time`.
+///
+/// # Particularities
+///
+/// Allowed on task level, ISR level and in all hook routines.
+///
+/// # Status
+///
+/// * **Standard:**
+///   * `E_OK` — No error.
+///
+/// # Safety
+///
+/// `stats` must be valid for a write.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn GetSystemStatistics(stats: SystemStatisticsRefType) -> StatusType {
+    unsafe { *stats = system_statistics() };
+    StatusType::EOk
+}
+
+/// Starts a new measurement.
+///
+/// # Syntax
+/// ```ignore
+/// void ResetStatistics(void)
+/// ```
+///
+/// # Description
+///
+/// Clears the statistics of all tasks and ISRs and the idle time. Response
+/// times of the activations pending at the call are still measured from
+/// the activation.
+///
+/// # Particularities
+///
+/// Allowed on task level, ISR level and in all hook routines.
+#[unsafe(no_mangle)]
+pub extern "C" fn ResetStatistics() {
+    critical_section::with(|cs| STATS.borrow_ref_mut(cs).reset(port::time_us()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_is_charged_to_tasks_nested_isrs_and_idle() {
+        let mut stats = Stats::new();
+        stats.activate(0, 0);
+        stats.switch_to(Context::Task(0), 10);
+        stats.isr_enter(3, 20);
+        stats.isr_enter(4, 25);
+        stats.isr_exit(27);
+        stats.isr_exit(30);
+        stats.terminate(0, 50);
+        stats.switch_to(Context::Idle, 50);
+        stats.charge(70);
+
+        let task = stats.tasks[0];
+        assert_eq!(task.execution_time, 30);
+        assert_eq!(task.response_time_max, 50);
+        assert_eq!(task.activations, 1);
+        assert_eq!(
+            stats.isrs[3],
+            IsrStatisticsType {
+                execution_time: 8,
+                duration_max: 10,
+                activations: 1,
+            }
+        );
+        assert_eq!(
+            stats.isrs[4],
+            IsrStatisticsType {
+                execution_time: 2,
+                duration_max: 2,
+                activations: 1,
+            }
+        );
+        assert_eq!(stats.idle_time, 20);
+    }
+
+    #[test]
+    fn response_times_survive_a_reset() {
+        let mut stats = Stats::new();
+        stats.activate(1, 0);
+        stats.activate(1, 5);
+        stats.terminate(1, 20);
+        assert_eq!(stats.tasks[1].response_time_max, 20);
+        assert_eq!(stats.tasks[1].activations, 2);
+
+        stats.reset(30);
+        assert_eq!(stats.tasks[1], TaskStatisticsType::default());
+        stats.terminate(1, 40);
+        assert_eq!(stats.tasks[1].response_time_max, 35);
+        // A termination without a pending activation changes nothing
+        stats.terminate(1, 100);
+        assert_eq!(stats.tasks[1].response_time_max, 35);
+    }
+}
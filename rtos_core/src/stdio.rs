@@ -0,0 +1,336 @@
+//! The `printf` family for the C tasks.
+//!
+//! Nothing is allocated: `printf` formats into a buffer on the stack with
+//! [`stdout::write_fmt`], one defmt message per [`stdout::CHUNK_SIZE`]
+//! bytes, and the `sprintf` variants write straight into the buffer of the
+//! caller. All of them are allowed on task level and ISR level.
+
+use core::{
+    ffi::{CStr, VaList, c_char, c_int},
+    fmt,
+};
+
+use printf_compat::output;
+
+use crate::stdout;
+
+/// `EOF` of `stdio.h`.
+const EOF: c_int = -1;
+
+/// Formats into the buffer of a `sprintf` caller, keeping the first
+/// `capacity` bytes and dropping the rest.
+struct BufferWriter {
+    buf: *mut u8,
+    capacity: usize,
+    len: usize,
+}
+
+impl BufferWriter {
+    /// Terminates the string written so far.
+    fn terminate(&mut self) {
+        unsafe { self.buf.add(self.len).write(0) };
+    }
+}
+
+impl fmt::Write for BufferWriter {
+    fn write_str(&mut self, text: &str) -> fmt::Result {
+        let count = text.len().min(self.capacity - self.len);
+        unsafe {
+            core::ptr::copy_nonoverlapping(text.as_ptr(), self.buf.add(self.len), count);
+        }
+        self.len += count;
+        Ok(())
+    }
+}
+
+/// Writes a string to the standard output.
+///
+/// # Syntax
+/// ```ignore
+/// void print(const char *Text)
+/// ```
+///
+/// # Parameters
+///
+/// * `input` (in) — NUL-terminated string, written as is.
+///
+/// # Safety
+///
+/// `input` must be null or NUL-terminated.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn print(input: *const c_char) {
+    if !input.is_null() {
+        stdout::write(unsafe { CStr::from_ptr(input) }.to_bytes());
+    }
+}
+
+/// Writes formatted output to the standard output.
+///
+/// # Syntax
+/// ```ignore
+/// int printf(const char *Format, ...)
+/// ```
+///
+/// # Description
+///
+/// Returns the number of bytes written, or a negative value if `format`
+/// is invalid.
+///
+/// # Safety
+///
+/// `str` must be a NUL-terminated format string whose conversions match
+/// the arguments.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn printf(str: *const c_char, mut args: ...) -> c_int {
+    if str.is_null() {
+        return EOF;
+    }
+    let text = unsafe { output::display(str, args.as_va_list()) };
+    stdout::write_fmt(&text);
+    text.bytes_written()
+}
+
+/// Writes formatted output to a buffer of bounded size.
+///
+/// # Syntax
+/// ```ignore
+/// int snprintf(char *Buffer, size_t Size, const char *Format, ...)
+/// ```
+///
+/// # Description
+///
+/// Writes at most `size - 1` bytes to `buffer` and terminates them with a
+/// NUL byte, unless `size` is `0`. Returns the length the whole output
+/// would have, so that a result of `size` or more means it was truncated.
+///
+/// # Safety
+///
+/// `buffer` must be valid for `size` bytes, and `format` must be a
+/// NUL-terminated format string whose conversions match the arguments.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn snprintf(
+    buffer: *mut c_char,
+    size: usize,
+    format: *const c_char,
+    mut args: ...
+) -> c_int {
+    unsafe { vsnprintf(buffer, size, format, args.as_va_list()) }
+}
+
+/// [`snprintf`] with the arguments passed as a `va_list`.
+///
+/// # Syntax
+/// ```ignore
+/// int vsnprintf(char *Buffer, size_t Size, const char *Format, va_list Args)
+/// ```
+///
+/// # Safety
+///
+/// As for [`snprintf`].
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn vsnprintf(
+    buffer: *mut c_char,
+    size: usize,
+    format: *const c_char,
+    args: VaList,
+) -> c_int {
+    if format.is_null() || (buffer.is_null() && size > 0) {
+        return EOF;
+    }
+    let mut writer = BufferWriter {
+        buf: buffer.cast(),
+        capacity: size.saturating_sub(1),
+        len: 0,
+    };
+    let count = unsafe { printf_compat::format(format, args, output::fmt_write(&mut writer)) };
+    if size > 0 {
+        writer.terminate();
+    }
+    count
+}
+
+/// Writes formatted output to a buffer.
+///
+/// # Syntax
+/// ```ignore
+/// int sprintf(char *Buffer, const char *Format, ...)
+/// ```
+///
+/// # Description
+///
+/// Writes the output to `buffer` and terminates it with a NUL byte.
+/// Returns the number of bytes written, the NUL byte excluded. Prefer
+/// [`snprintf`], which cannot overrun `buffer`.
+///
+/// # Safety
+///
+/// `buffer` must be large enough for the whole output, and `format` must
+/// be a NUL-terminated format string whose conversions match the
+/// arguments.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn sprintf(
+    buffer: *mut c_char,
+    format: *const c_char,
+    mut args: ...
+) -> c_int {
+    unsafe { vsnprintf(buffer, usize::MAX, format, args.as_va_list()) }
+}
+
+/// Writes a string and a newline to the standard output.
+///
+/// # Syntax
+/// ```ignore
+/// int puts(const char *Text)
+/// ```
+///
+/// # Description
+///
+/// Returns a non-negative value on success, `EOF` if `text` is null.
+///
+/// # Safety
+///
+/// `text` must be null or NUL-terminated.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn puts(text: *const c_char) -> c_int {
+    if text.is_null() {
+        return EOF;
+    }
+    let bytes = unsafe { CStr::from_ptr(text) }.to_bytes();
+    stdout::write_line(bytes);
+    c_int::try_from(bytes.len() + 1).unwrap_or(c_int::MAX)
+}
+
+/// Writes a character to the standard output.
+///
+/// # Syntax
+/// ```ignore
+/// int putchar(int Char)
+/// ```
+///
+/// # Description
+///
+/// Writes `character` converted to `unsigned char` and returns it. With
+/// the `Defmt` backend, every character makes a defmt message of its own.
+#[unsafe(no_mangle)]
+pub extern "C" fn putchar(character: c_int) -> c_int {
+    let byte = character as u8;
+    stdout::write(&[byte]);
+    byte as c_int
+}
+
+#[cfg(test)]
+mod tests {
+    use core::ptr;
+
+    use super::*;
+    use crate::stdout::tests::capture;
+
+    /// The NUL-terminated string at the start of `buffer`.
+    fn text(buffer: &[c_char]) -> &str {
+        unsafe { CStr::from_ptr(buffer.as_ptr()) }.to_str().unwrap()
+    }
+
+    unsafe extern "C" fn format(
+        buffer: *mut c_char,
+        size: usize,
+        format: *const c_char,
+        mut args: ...
+    ) -> c_int {
+        unsafe { vsnprintf(buffer, size, format, args.as_va_list()) }
+    }
+
+    #[test]
+    fn snprintf_truncates_and_returns_the_whole_length() {
+        let mut buffer = [b'#' as c_char; 8];
+        let count = unsafe {
+            snprintf(
+                buffer.as_mut_ptr(),
+                6,
+                c"%s-%d".as_ptr(),
+                c"abc".as_ptr(),
+                42 as c_int,
+            )
+        };
+        assert_eq!(count, 6);
+        assert_eq!(text(&buffer), "abc-4");
+        assert_eq!(buffer[6], b'#' as c_char);
+    }
+
+    #[test]
+    fn snprintf_fits_exactly() {
+        let mut buffer = [b'#' as c_char; 8];
+        let count = unsafe { snprintf(buffer.as_mut_ptr(), 7, c"abc-%d".as_ptr(), 42 as c_int) };
+        assert_eq!(count, 6);
+        assert_eq!(text(&buffer), "abc-42");
+        assert_eq!(buffer[7], b'#' as c_char);
+    }
+
+    #[test]
+    fn snprintf_of_size_zero_writes_nothing() {
+        let mut buffer = [b'#' as c_char; 4];
+        let count = unsafe { snprintf(buffer.as_mut_ptr(), 0, c"%d".as_ptr(), 1234 as c_int) };
+        assert_eq!(count, 4);
+        assert_eq!(buffer, [b'#' as c_char; 4]);
+        let count = unsafe { snprintf(ptr::null_mut(), 0, c"%d".as_ptr(), 1234 as c_int) };
+        assert_eq!(count, 4);
+        let count = unsafe { snprintf(ptr::null_mut(), 4, c"%d".as_ptr(), 1234 as c_int) };
+        assert_eq!(count, EOF);
+    }
+
+    #[test]
+    fn vsnprintf_takes_a_va_list() {
+        let mut buffer = [0 as c_char; 16];
+        let count = unsafe {
+            format(
+                buffer.as_mut_ptr(),
+                buffer.len(),
+                c"%c%u %x".as_ptr(),
+                b'v' as c_int,
+                7 as c_int,
+                255 as c_int,
+            )
+        };
+        assert_eq!(count, 5);
+        assert_eq!(text(&buffer), "v7 ff");
+        let count = unsafe { format(buffer.as_mut_ptr(), 3, c"%s".as_ptr(), c"long".as_ptr()) };
+        assert_eq!(count, 4);
+        assert_eq!(text(&buffer), "lo");
+    }
+
+    #[test]
+    fn sprintf_terminates_and_returns_the_length() {
+        let mut buffer = [b'#' as c_char; 8];
+        let count = unsafe { sprintf(buffer.as_mut_ptr(), c"%03d".as_ptr(), 7 as c_int) };
+        assert_eq!(count, 3);
+        assert_eq!(text(&buffer), "007");
+        assert_eq!(buffer[4], b'#' as c_char);
+    }
+
+    #[test]
+    fn printf_writes_to_stdout() {
+        let mut count = 0;
+        let written = capture(|| {
+            count = unsafe { printf(c"%s=%d\n".as_ptr(), c"x".as_ptr(), -3 as c_int) };
+        });
+        assert_eq!(written, b"x=-3\n");
+        assert_eq!(count, 5);
+        assert_eq!(unsafe { printf(ptr::null()) }, EOF);
+    }
+
+    #[test]
+    fn puts_adds_a_newline() {
+        let mut count = 0;
+        let written = capture(|| count = unsafe { puts(c"line".as_ptr()) });
+        assert_eq!(written, b"line\n");
+        assert_eq!(count, 5);
+        assert_eq!(unsafe { puts(ptr::null()) }, EOF);
+    }
+
+    #[test]
+    fn putchar_writes_an_unsigned_char() {
+        let mut result = 0;
+        let written = capture(|| result = putchar(0x141));
+        assert_eq!(written, b"A");
+        assert_eq!(result, 0x41);
+    }
+}
@@ -0,0 +1,305 @@
+//! Standard output of the C tasks, behind `print` and `printf`.
+//!
+//! The application picks a [`Backend`] with [`set_backend`] before
+//! `start_os`. Writes never block: the RTT backends drop what does not fit
+//! in the channel, and the driver backend queues the bytes in a RAM buffer
+//! that [`flush`] hands to the driver as it makes room, dropping what does
+//! not fit in the buffer. Each write is queued whole, in a critical
+//! section, so writes from different tasks and ISRs never interleave.
+//! [`write_fmt`] formats outside of the critical section, into a
+//! [`CHUNK_SIZE`] buffer on the stack, so that a long `printf` does not
+//! hold the interrupts off: output up to that size is one write, and one
+//! defmt message, longer output a write per chunk.
+
+use core::{
+    cell::RefCell,
+    fmt::{self, Write},
+};
+
+use critical_section::Mutex;
+
+#[cfg(not(target_os = "none"))]
+use crate::port::sim::write_stdout as write_rtt;
+#[cfg(target_os = "none")]
+use crate::rtt::write_stdout as write_rtt;
+
+/// Bytes buffered for the driver backend.
+const BUFFER_SIZE: usize = 1024;
+
+/// Bytes [`write_fmt`] formats before writing them.
+pub const CHUNK_SIZE: usize = 128;
+
+/// Non-blocking write of an application driver. Writes as many bytes as
+/// fit and returns how many, `0` if it cannot take any right now.
+pub type Driver = fn(&[u8]) -> usize;
+
+/// Where the standard output goes.
+#[derive(Clone, Copy)]
+pub enum Backend {
+    /// One defmt message per write, on RTT up-channel 0. The default.
+    Defmt,
+    /// Raw bytes on RTT up-channel 3, `Stdout`.
+    Rtt,
+    /// Buffered bytes, fed to an application driver: a UART or a USB
+    /// CDC-ACM port for instance. The driver calls [`flush`] whenever it
+    /// has room again.
+    Driver(Driver),
+}
+
+struct Stdout {
+    backend: Backend,
+    buffer: [u8; BUFFER_SIZE],
+    head: usize,
+    len: usize,
+    dropped: u32,
+}
+
+impl Stdout {
+    const fn new() -> Self {
+        Stdout {
+            backend: Backend::Defmt,
+            buffer: [0; BUFFER_SIZE],
+            head: 0,
+            len: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Queues as much of `bytes` as fits.
+    fn push(&mut self, bytes: &[u8]) {
+        let room = BUFFER_SIZE - self.len;
+        let (queued, lost) = bytes.split_at(bytes.len().min(room));
+        for &byte in queued {
+            self.buffer[(self.head + self.len) % BUFFER_SIZE] = byte;
+            self.len += 1;
+        }
+        self.dropped = self.dropped.wrapping_add(lost.len() as u32);
+    }
+
+    /// Hands the queued bytes to `driver` until it is full or the buffer
+    /// is empty.
+    fn drain(&mut self, driver: Driver) {
+        while self.len > 0 {
+            let end = (self.head + self.len).min(BUFFER_SIZE);
+            let written = driver(&self.buffer[self.head..end]).min(end - self.head);
+            if written == 0 {
+                break;
+            }
+            self.head = (self.head + written) % BUFFER_SIZE;
+            self.len -= written;
+        }
+    }
+}
+
+static STDOUT: Mutex<RefCell<Stdout>> = Mutex::new(RefCell::new(Stdout::new()));
+
+/// Selects where the standard output goes. Bytes still buffered for a
+/// previous driver are dropped.
+pub fn set_backend(backend: Backend) {
+    critical_section::with(|cs| {
+        let mut stdout = STDOUT.borrow_ref_mut(cs);
+        stdout.backend = backend;
+        stdout.head = 0;
+        stdout.len = 0;
+    });
+}
+
+/// Writes `bytes` to the standard output without blocking.
+///
+/// Allowed on task level and ISR level.
+pub fn write(bytes: &[u8]) {
+    if bytes.is_empty() {
+        return;
+    }
+    critical_section::with(|cs| {
+        let mut stdout = STDOUT.borrow_ref_mut(cs);
+        match stdout.backend {
+            Backend::Defmt => defmt_line(bytes),
+            Backend::Rtt => write_rtt(bytes),
+            Backend::Driver(driver) => {
+                stdout.push(bytes);
+                stdout.drain(driver);
+            }
+        }
+    });
+}
+
+/// Writes `bytes` and a newline to the standard output, as one write.
+///
+/// Allowed on task level and ISR level.
+pub fn write_line(bytes: &[u8]) {
+    critical_section::with(|cs| {
+        let mut stdout = STDOUT.borrow_ref_mut(cs);
+        match stdout.backend {
+            Backend::Defmt => defmt_line(bytes),
+            Backend::Rtt => {
+                write_rtt(bytes);
+                write_rtt(b"\n");
+            }
+            Backend::Driver(driver) => {
+                stdout.push(bytes);
+                stdout.push(b"\n");
+                stdout.drain(driver);
+            }
+        }
+    });
+}
+
+/// Formats `text` into the standard output without blocking, a write per
+/// [`CHUNK_SIZE`] bytes, outside of the critical section.
+///
+/// Allowed on task level and ISR level.
+pub fn write_fmt(text: &dyn fmt::Display) {
+    let mut chunks = Chunks {
+        buffer: [0; CHUNK_SIZE],
+        len: 0,
+    };
+    let _ = write!(chunks, "{text}");
+    write(&chunks.buffer[..chunks.len]);
+}
+
+/// `bytes` as one defmt message, which ends the line already.
+fn defmt_line(bytes: &[u8]) {
+    match core::str::from_utf8(bytes) {
+        Ok(text) => defmt::println!("{=str}", text.trim_end_matches('\n')),
+        Err(_) => defmt::println!("{=[u8]:a}", bytes),
+    }
+}
+
+/// Formatted text, written whenever `buffer` is full. Chunks end on
+/// character boundaries, so that each one is valid UTF-8 for defmt.
+struct Chunks {
+    buffer: [u8; CHUNK_SIZE],
+    len: usize,
+}
+
+impl Write for Chunks {
+    fn write_str(&mut self, mut text: &str) -> fmt::Result {
+        while !text.is_empty() {
+            let mut count = text.len().min(CHUNK_SIZE - self.len);
+            while !text.is_char_boundary(count) {
+                count -= 1;
+            }
+            if count == 0 {
+                write(&self.buffer[..self.len]);
+                self.len = 0;
+                continue;
+            }
+            self.buffer[self.len..self.len + count].copy_from_slice(&text.as_bytes()[..count]);
+            self.len += count;
+            text = &text[count..];
+        }
+        Ok(())
+    }
+}
+
+/// Hands the buffered output to the driver backend, as much as it takes.
+///
+/// Drivers call it when they have room again, from their transmit
+/// interrupt for instance. Allowed on task level and ISR level.
+pub fn flush() {
+    critical_section::with(|cs| {
+        let mut stdout = STDOUT.borrow_ref_mut(cs);
+        if let Backend::Driver(driver) = stdout.backend {
+            stdout.drain(driver);
+        }
+    });
+}
+
+/// Number of bytes dropped so far because the buffer was full.
+pub fn dropped() -> u32 {
+    critical_section::with(|cs| STDOUT.borrow_ref(cs).dropped)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    extern crate std;
+
+    use std::{
+        format,
+        sync::{Mutex, PoisonError},
+        vec::Vec,
+    };
+
+    use super::*;
+
+    /// What the driver backend took.
+    static TAKEN: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+    /// Serializes the tests using the standard output.
+    static STDOUT_TEST: Mutex<()> = Mutex::new(());
+
+    fn take_all(bytes: &[u8]) -> usize {
+        TAKEN.lock().unwrap().extend_from_slice(bytes);
+        bytes.len()
+    }
+
+    fn take_none(_: &[u8]) -> usize {
+        0
+    }
+
+    /// What `f` wrote, through the driver backend.
+    pub(crate) fn capture(f: impl FnOnce()) -> Vec<u8> {
+        let _test = STDOUT_TEST.lock().unwrap_or_else(PoisonError::into_inner);
+        TAKEN.lock().unwrap().clear();
+        set_backend(Backend::Driver(take_all));
+        f();
+        set_backend(Backend::Defmt);
+        core::mem::take(&mut *TAKEN.lock().unwrap())
+    }
+
+    #[test]
+    fn long_output_is_written_in_chunks() {
+        static SIZES: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+        fn record(bytes: &[u8]) -> usize {
+            SIZES.lock().unwrap().push(bytes.len());
+            bytes.len()
+        }
+        let _test = STDOUT_TEST.lock().unwrap_or_else(PoisonError::into_inner);
+        set_backend(Backend::Driver(record));
+        // A two-byte character across the end of the first chunk
+        let text = format!("{}é{}", "a".repeat(CHUNK_SIZE - 1), "b".repeat(10));
+        write_fmt(&text);
+        set_backend(Backend::Defmt);
+        assert_eq!(*SIZES.lock().unwrap(), [CHUNK_SIZE - 1, 12]);
+    }
+
+    #[test]
+    fn driver_takes_writes_in_order() {
+        let taken = capture(|| {
+            write(b"abc");
+            write_line(b"def");
+            write_fmt(&format_args!("{}-{}", 1, 2));
+        });
+        assert_eq!(taken, b"abcdef\n1-2");
+    }
+
+    #[test]
+    fn full_buffer_drops_and_flush_resumes() {
+        let _test = STDOUT_TEST.lock().unwrap_or_else(PoisonError::into_inner);
+        TAKEN.lock().unwrap().clear();
+        set_backend(Backend::Driver(take_none));
+        let dropped = dropped();
+        write(&[b'x'; BUFFER_SIZE - 2]);
+        write(b"yzw");
+        assert_eq!(dropped.wrapping_add(1), super::dropped());
+        critical_section::with(|cs| STDOUT.borrow_ref_mut(cs).backend = Backend::Driver(take_all));
+        flush();
+        let taken = core::mem::take(&mut *TAKEN.lock().unwrap());
+        set_backend(Backend::Defmt);
+        assert_eq!(taken.len(), BUFFER_SIZE);
+        assert!(taken.ends_with(b"xyz"));
+    }
+
+    #[test]
+    fn new_backend_drops_what_the_previous_driver_left() {
+        let _test = STDOUT_TEST.lock().unwrap_or_else(PoisonError::into_inner);
+        TAKEN.lock().unwrap().clear();
+        set_backend(Backend::Driver(take_none));
+        write(b"abc");
+        set_backend(Backend::Driver(take_all));
+        flush();
+        write(b"d");
+        set_backend(Backend::Defmt);
+        assert_eq!(*TAKEN.lock().unwrap(), b"d");
+    }
+}
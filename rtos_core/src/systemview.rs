@@ -0,0 +1,376 @@
+//! SEGGER SystemView event stream.
+//!
+//! With the `systemview` feature, the kernel trace points (see
+//! [`crate::trace`]) are also encoded in the SystemView binary protocol and
+//! written to RTT up-channel 1, named `SysView`, next to the defmt log on
+//! up-channel 0. A capture of that channel saved as an `.SVDat` file opens
+//! in SystemView, with its timeline and CPU load views.
+//!
+//! The stream starts at `StartOS` with the system description, the ISR
+//! names given to [`set_isr_names`] and the task list. Task `n` of the
+//! configuration shows up as task ID `n + 1`, an interrupt as its exception
+//! number, that is its NVIC number plus 16.
+//!
+//! A packet that does not fit into the channel is dropped whole. The next
+//! one that fits is preceded by an overflow packet telling SystemView how
+//! many were lost.
+//!
+//! User markers are set with the `SEGGER_SYSVIEW_Mark*` services, named as
+//! in the SEGGER target sources so that instrumented C code builds
+//! unchanged.
+
+use core::{
+    cell::{Cell, RefCell},
+    ffi::{CStr, c_char},
+};
+
+use critical_section::Mutex;
+
+#[cfg(not(target_os = "none"))]
+use crate::port::sim::write_systemview as write;
+#[cfg(target_os = "none")]
+use crate::rtt::write_systemview as write;
+use crate::{OsConfig, port, task::TaskType, trace::TraceKind};
+
+// Event IDs of the SystemView protocol
+const EVTID_OVERFLOW: u32 = 1;
+const EVTID_ISR_ENTER: u32 = 2;
+const EVTID_ISR_EXIT: u32 = 3;
+const EVTID_TASK_START_EXEC: u32 = 4;
+const EVTID_TASK_STOP_EXEC: u32 = 5;
+const EVTID_TASK_START_READY: u32 = 6;
+const EVTID_TASK_STOP_READY: u32 = 7;
+const EVTID_TASK_INFO: u32 = 9;
+const EVTID_TRACE_START: u32 = 10;
+const EVTID_SYSTIME_CYCLES: u32 = 12;
+const EVTID_SYSDESC: u32 = 14;
+const EVTID_MARK_START: u32 = 15;
+const EVTID_MARK_STOP: u32 = 16;
+const EVTID_IDLE: u32 = 17;
+const EVTID_STACK_INFO: u32 = 21;
+const EVTID_INIT: u32 = 24;
+const EVTID_EX: u32 = 31;
+// Extended events, sent as the first payload word of `EVTID_EX`
+const EVTID_EX_MARK: u32 = 0;
+const EVTID_EX_NAME_MARKER: u32 = 1;
+
+/// Events from this ID on carry the length of their payload.
+const FIRST_SIZED_EVTID: u32 = 24;
+/// Longest string sent, as in the SEGGER sources.
+const MAX_STRING_LEN: usize = 128;
+/// Longest task name sent with the task list.
+const MAX_TASK_NAME_LEN: usize = 32;
+/// Longest encoding of a `u32`.
+const MAX_U32_LEN: usize = 5;
+/// Room for the event ID and the payload length in front of the payload.
+const HEADER_SIZE: usize = 4;
+/// Largest packet: two words and a string, then the timestamp delta.
+const PACKET_SIZE: usize = HEADER_SIZE + 2 * MAX_U32_LEN + 1 + MAX_STRING_LEN + MAX_U32_LEN;
+
+/// Frequency of the timestamps, TIMER0 counting microseconds.
+const SYS_FREQ_HZ: u32 = 1_000_000;
+/// RP2350 system clock, as set up by `rp235x_hal::clocks`.
+const CPU_FREQ_HZ: u32 = 150_000_000;
+/// Cortex-M exception number of NVIC interrupt 0.
+const FIRST_IRQ_EXCEPTION: u32 = 16;
+
+const SYS_DESC: &str = "O=rtos_core,D=RP2350,C=Cortex-M33";
+
+struct Stream {
+    /// Timestamp of the last packet written, packets carry the delta.
+    last_timestamp: u32,
+    /// Packets dropped since the last one written.
+    dropped: u32,
+}
+
+static STREAM: Mutex<RefCell<Stream>> = Mutex::new(RefCell::new(Stream {
+    last_timestamp: 0,
+    dropped: 0,
+}));
+
+static ISR_NAMES: Mutex<Cell<&'static [(u8, &'static str)]>> = Mutex::new(Cell::new(&[]));
+
+/// Payload of a packet being built, with room for its header in front.
+struct Packet {
+    bytes: [u8; PACKET_SIZE],
+    len: usize,
+}
+
+impl Packet {
+    fn new() -> Self {
+        Packet {
+            bytes: [0; PACKET_SIZE],
+            len: HEADER_SIZE,
+        }
+    }
+
+    fn u32(mut self, value: u32) -> Self {
+        self.len += encode(&mut self.bytes[self.len..], value);
+        self
+    }
+
+    /// Appends the concatenation of `parts`, truncated to `limit` bytes.
+    fn str(mut self, parts: &[&[u8]], limit: usize) -> Self {
+        let start = self.len;
+        self.len += 1;
+        for part in parts {
+            let len = part.len().min(limit - (self.len - start - 1));
+            self.bytes[self.len..self.len + len].copy_from_slice(&part[..len]);
+            self.len += len;
+        }
+        // Strings are at most 128 bytes, so their length fits in one byte
+        self.bytes[start] = (self.len - start - 1) as u8;
+        self
+    }
+}
+
+/// Writes `value` in 7-bit groups, least significant first, each but the
+/// last with bit 7 set. Returns the number of bytes written.
+fn encode(out: &mut [u8], mut value: u32) -> usize {
+    let mut len = 0;
+    while value > 0x7f {
+        out[len] = value as u8 | 0x80;
+        value >>= 7;
+        len += 1;
+    }
+    out[len] = value as u8;
+    len + 1
+}
+
+/// ID of a task in SystemView.
+fn task_id(id: TaskType) -> u32 {
+    id as u32 + 1
+}
+
+/// Sends a packet, preceded by an overflow packet if some were dropped.
+fn send(stream: &mut Stream, packet: Packet, event: u32) {
+    if stream.dropped > 0 {
+        let overflow = Packet::new().u32(stream.dropped);
+        if !transmit(stream, overflow, EVTID_OVERFLOW) {
+            stream.dropped += 1;
+            return;
+        }
+        stream.dropped = 0;
+    }
+    if !transmit(stream, packet, event) {
+        stream.dropped += 1;
+    }
+}
+
+/// Frames `packet` as `event` and writes it whole. Returns whether it fit.
+fn transmit(stream: &mut Stream, packet: Packet, event: u32) -> bool {
+    let mut header = [0; HEADER_SIZE];
+    let mut header_len = encode(&mut header, event);
+    if event >= FIRST_SIZED_EVTID {
+        let payload_len = (packet.len - HEADER_SIZE) as u32;
+        header_len += encode(&mut header[header_len..], payload_len);
+    }
+    let timestamp = port::timestamp();
+    let mut packet = packet.u32(timestamp.wrapping_sub(stream.last_timestamp));
+    let start = HEADER_SIZE - header_len;
+    packet.bytes[start..HEADER_SIZE].copy_from_slice(&header[..header_len]);
+    let written = write(&packet.bytes[start..packet.len]);
+    if written {
+        stream.last_timestamp = timestamp;
+    }
+    written
+}
+
+fn emit(packet: Packet, event: u32) {
+    critical_section::with(|cs| send(&mut STREAM.borrow_ref_mut(cs), packet, event));
+}
+
+/// Names the interrupts in SystemView, as pairs of an NVIC interrupt number
+/// and a name. The names are sent by the next `StartOS`.
+pub fn set_isr_names(names: &'static [(u8, &'static str)]) {
+    critical_section::with(|cs| ISR_NAMES.borrow(cs).set(names));
+}
+
+/// Starts the stream: synchronization, system description and task list.
+pub(crate) fn start(config: &OsConfig) {
+    critical_section::with(|cs| {
+        let mut stream = STREAM.borrow_ref_mut(cs);
+        *stream = Stream {
+            last_timestamp: 0,
+            dropped: 0,
+        };
+        // Ten zero bytes let SystemView find the start of the first packet
+        write(&[0; 10]);
+        send(&mut stream, Packet::new(), EVTID_TRACE_START);
+        // Task IDs are sent as they are: RAM base address 0, ID shift 0
+        let init = Packet::new()
+            .u32(SYS_FREQ_HZ)
+            .u32(CPU_FREQ_HZ)
+            .u32(0)
+            .u32(0);
+        send(&mut stream, init, EVTID_INIT);
+        let desc = Packet::new().str(&[SYS_DESC.as_bytes()], MAX_STRING_LEN);
+        send(&mut stream, desc, EVTID_SYSDESC);
+        for &(irq, name) in ISR_NAMES.borrow(cs).get() {
+            let mut digits = [0; 3];
+            let exception = FIRST_IRQ_EXCEPTION + irq as u32;
+            let number = decimal(&mut digits, exception);
+            let desc = Packet::new().str(&[b"I#", number, b"=", name.as_bytes()], MAX_STRING_LEN);
+            send(&mut stream, desc, EVTID_SYSDESC);
+        }
+        let systime = Packet::new().u32(port::timestamp());
+        send(&mut stream, systime, EVTID_SYSTIME_CYCLES);
+        for (id, task) in config.tasks.iter().enumerate() {
+            let info = Packet::new()
+                .u32(task_id(id))
+                .u32(task.priority as u32)
+                .str(&[task.name.as_bytes()], MAX_TASK_NAME_LEN);
+            send(&mut stream, info, EVTID_TASK_INFO);
+            let stack = Packet::new()
+                .u32(task_id(id))
+                .u32(task.stack.base as usize as u32)
+                .u32(task.stack.size as u32);
+            send(&mut stream, stack, EVTID_STACK_INFO);
+        }
+    });
+}
+
+/// Writes `value` below 1000 in decimal into `digits`.
+fn decimal(digits: &mut [u8; 3], value: u32) -> &[u8] {
+    let mut start = digits.len();
+    let mut value = value;
+    loop {
+        start -= 1;
+        digits[start] = b'0' + (value % 10) as u8;
+        value /= 10;
+        if value == 0 {
+            return &digits[start..];
+        }
+    }
+}
+
+/// Translates a kernel trace point into SystemView events.
+pub(crate) fn record(kind: TraceKind, object: usize, data: u32) {
+    let (packet, event) = match kind {
+        TraceKind::Activate | TraceKind::Release => {
+            (Packet::new().u32(task_id(object)), EVTID_TASK_START_READY)
+        }
+        TraceKind::Start => (Packet::new().u32(task_id(object)), EVTID_TASK_START_EXEC),
+        TraceKind::Preempt => (Packet::new(), EVTID_TASK_STOP_EXEC),
+        // A task with activations pending is ready again at once
+        TraceKind::Terminate if data == 0 => (
+            Packet::new().u32(task_id(object)).u32(0),
+            EVTID_TASK_STOP_READY,
+        ),
+        // The cause shown by SystemView is the awaited event mask
+        TraceKind::WaitEvent => (
+            Packet::new().u32(task_id(object)).u32(data),
+            EVTID_TASK_STOP_READY,
+        ),
+        TraceKind::Idle => (Packet::new(), EVTID_IDLE),
+        TraceKind::IsrEnter => (
+            Packet::new().u32(FIRST_IRQ_EXCEPTION + object as u32),
+            EVTID_ISR_ENTER,
+        ),
+        TraceKind::IsrExit => (Packet::new(), EVTID_ISR_EXIT),
+        _ => return,
+    };
+    emit(packet, event);
+}
+
+/// Starts a marker.
+///
+/// # Syntax
+/// ```ignore
+/// void SEGGER_SYSVIEW_MarkStart(unsigned MarkerId)
+/// ```
+///
+/// # Parameters
+///
+/// * `marker_id` (in) — Marker, any number chosen by the application.
+///
+/// # Description
+///
+/// SystemView shows the time from this call to the matching
+/// [`SEGGER_SYSVIEW_MarkStop`] as a measurement of the marker.
+///
+/// # Particularities
+///
+/// Allowed on task level and in ISRs.
+#[unsafe(no_mangle)]
+pub extern "C" fn SEGGER_SYSVIEW_MarkStart(marker_id: u32) {
+    emit(Packet::new().u32(marker_id), EVTID_MARK_START);
+}
+
+/// Stops a marker.
+///
+/// # Syntax
+/// ```ignore
+/// void SEGGER_SYSVIEW_MarkStop(unsigned MarkerId)
+/// ```
+///
+/// # Parameters
+///
+/// * `marker_id` (in) — Marker started by [`SEGGER_SYSVIEW_MarkStart`].
+///
+/// # Particularities
+///
+/// Allowed on task level and in ISRs.
+#[unsafe(no_mangle)]
+pub extern "C" fn SEGGER_SYSVIEW_MarkStop(marker_id: u32) {
+    emit(Packet::new().u32(marker_id), EVTID_MARK_STOP);
+}
+
+/// Sets a single point marker.
+///
+/// # Syntax
+/// ```ignore
+/// void SEGGER_SYSVIEW_Mark(unsigned MarkerId)
+/// ```
+///
+/// # Parameters
+///
+/// * `marker_id` (in) — Marker, any number chosen by the application.
+///
+/// # Particularities
+///
+/// Allowed on task level and in ISRs.
+#[unsafe(no_mangle)]
+pub extern "C" fn SEGGER_SYSVIEW_Mark(marker_id: u32) {
+    emit(Packet::new().u32(EVTID_EX_MARK).u32(marker_id), EVTID_EX);
+}
+
+/// Names a marker.
+///
+/// # Syntax
+/// ```ignore
+/// void SEGGER_SYSVIEW_NameMarker(unsigned MarkerId, const char* sName)
+/// ```
+///
+/// # Parameters
+///
+/// * `marker_id` (in) — Marker to name.
+/// * `name` (in) — Name shown by SystemView instead of the marker number,
+///   truncated to 128 bytes.
+///
+/// # Particularities
+///
+/// Allowed on task level and in ISRs. The name only reaches SystemView if
+/// the capture was running when it was sent.
+///
+/// # Safety
+///
+/// `name` must be NUL-terminated.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn SEGGER_SYSVIEW_NameMarker(marker_id: u32, name: *const c_char) {
+    let name = unsafe { CStr::from_ptr(name) };
+    send_marker_name(marker_id, name.to_bytes());
+}
+
+/// Names a marker, like [`SEGGER_SYSVIEW_NameMarker`].
+pub fn name_marker(marker_id: u32, name: &str) {
+    send_marker_name(marker_id, name.as_bytes());
+}
+
+fn send_marker_name(marker_id: u32, name: &[u8]) {
+    let packet = Packet::new()
+        .u32(EVTID_EX_NAME_MARKER)
+        .u32(marker_id)
+        .str(&[name], MAX_STRING_LEN);
+    emit(packet, EVTID_EX);
+}
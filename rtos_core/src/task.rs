@@ -0,0 +1,914 @@
+use core::cell::{RefCell, UnsafeCell};
+
+use crate::{
+    StatusType,
+    alarms::{AlarmConfig, CounterConfig, TickType},
+    hooks::{self, Hooks},
+    memory::{HeapRegion, PoolConfig},
+    port,
+    ready_queue::{Decision, Priority, ReadyQueue, select_next},
+    resources::{MAX_RESOURCES, ResourceConfig, ResourceState, ResourceType},
+    trace::{self, TraceKind},
+};
+use critical_section::Mutex;
+use defmt::info;
+
+#[cfg(feature = "events")]
+use crate::events::EventMaskType;
+
+/// Maximum number of tasks in a configuration.
+pub const MAX_TASKS: usize = 16;
+/// Number of priority levels. Valid priorities are `0..MAX_PRIORITIES`.
+pub const MAX_PRIORITIES: usize = 16;
+/// Number of ready tasks each priority level can hold. Without multiple
+/// activations, a level holds its own task and a task preempted while its
+/// priority was raised to that level by a resource.
+pub const QUEUE_DEPTH: usize = if cfg!(feature = "multiple-activations") {
+    8
+} else {
+    2
+};
+
+pub type TaskType = usize;
+pub type TaskRefType = *mut TaskType;
+pub type TaskStateRefType = *mut TaskStateType;
+
+/// Smallest stack a task may have: its initial context, and the context
+/// saved when it is preempted, must fit in it with room to spare.
+pub const MIN_STACK_SIZE: usize = port::MIN_STACK_SIZE;
+
+/// Returned by `GetTaskID` when no task is running.
+pub const INVALID_TASK: TaskType = TaskType::MAX;
+
+/// Entry point of a task.
+pub type TaskEntry = unsafe extern "C" fn();
+
+/// State of a task, `RUNNING`, `WAITING`, `READY` or `SUSPENDED` in C.
+///
+/// cbindgen:rename-all=ScreamingSnakeCase
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub enum TaskStateType {
+    Running = 0,
+    Waiting = 1,
+    Ready = 2,
+    Suspended = 3,
+}
+
+/// Memory region used as the stack of a task.
+#[derive(Clone, Copy)]
+pub struct StackRegion {
+    pub base: *mut u8,
+    pub size: usize,
+}
+
+// Stack regions are only handed to the port while building a fresh context,
+// always from inside the kernel critical section.
+unsafe impl Send for StackRegion {}
+unsafe impl Sync for StackRegion {}
+
+/// Fills an unused stack, so that the bytes a task never touched can be told
+/// apart from the others.
+#[cfg(feature = "shell")]
+const STACK_PAINT: u8 = 0xA5;
+
+#[cfg(feature = "shell")]
+impl StackRegion {
+    /// Paints the whole region. It must not hold a live context.
+    pub(crate) fn paint(&self) {
+        if self.size > 0 {
+            unsafe { core::ptr::write_bytes(self.base, STACK_PAINT, self.size) };
+        }
+    }
+
+    /// Highest number of bytes used since the region was painted. Stacks
+    /// grow down, so the paint left at the bottom was never touched.
+    pub(crate) fn used(&self) -> usize {
+        // The task may be writing its stack meanwhile
+        let untouched = (0..self.size)
+            .take_while(|&i| unsafe { self.base.add(i).read_volatile() } == STACK_PAINT)
+            .count();
+        self.size - untouched
+    }
+}
+
+/// Statically allocated task stack of `SIZE` bytes.
+#[repr(C, align(8))]
+pub struct Stack<const SIZE: usize>(UnsafeCell<[u8; SIZE]>);
+
+unsafe impl<const SIZE: usize> Sync for Stack<SIZE> {}
+
+impl<const SIZE: usize> Stack<SIZE> {
+    pub const fn new() -> Self {
+        Stack(UnsafeCell::new([0; SIZE]))
+    }
+
+    pub const fn region(&'static self) -> StackRegion {
+        StackRegion {
+            base: self.0.get().cast(),
+            size: SIZE,
+        }
+    }
+}
+
+impl<const SIZE: usize> Default for Stack<SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Preemptability of a task, the OIL `SCHEDULE` attribute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SchedulePolicy {
+    /// The task is preempted as soon as a higher-priority task is ready.
+    Full,
+    /// The task only gives up the CPU at `Schedule`, `TerminateTask` or
+    /// `WaitEvent`.
+    Non,
+}
+
+/// Static description of a task, as found in the OIL `TASK` object.
+#[derive(Clone, Copy)]
+pub struct TaskConfig {
+    pub name: &'static str,
+    pub entry: TaskEntry,
+    pub priority: Priority,
+    /// Maximum number of queued activations (`ACTIVATION` in OIL).
+    pub activation: u8,
+    pub autostart: bool,
+    /// Extended tasks may wait for events, basic tasks may not.
+    pub extended: bool,
+    pub schedule: SchedulePolicy,
+    /// Internal resource of the task, an index into
+    /// [`OsConfig::resources`]. Tasks sharing one do not preempt each other.
+    pub internal_resource: Option<ResourceType>,
+    /// Relative deadline in timer ticks, from each activation to the
+    /// matching termination. `None` if the task is not monitored.
+    pub deadline: Option<TickType>,
+    pub stack: StackRegion,
+}
+
+/// A [`TaskConfig`] for task `name`, whose name and priority also go in the
+/// picotool binary info, as `rtos_config!` and `#[task]` put them. The
+/// priority comes first, then the other fields as in the struct literal.
+///
+/// ```ignore
+/// static TASKS: [TaskConfig; 1] = [rtos_core::task_config!(Logger {
+///     priority: 1,
+///     entry: logger,
+///     activation: 1,
+///     ..
+/// })];
+/// ```
+#[macro_export]
+macro_rules! task_config {
+    ($name:ident { priority: $priority:literal, $($field:ident: $value:expr),* $(,)? }) => {{
+        $crate::os_binary_info!(
+            match ::core::ffi::CStr::from_bytes_with_nul(
+                ::core::concat!(
+                    "task ",
+                    ::core::stringify!($name),
+                    ", priority ",
+                    ::core::stringify!($priority),
+                    "\0"
+                )
+                .as_bytes(),
+            ) {
+                ::core::result::Result::Ok(feature) => feature,
+                ::core::result::Result::Err(_) => ::core::panic!("task name is not a C string"),
+            }
+        );
+        $crate::TaskConfig {
+            name: ::core::stringify!($name),
+            priority: $priority,
+            $($field: $value),*
+        }
+    }};
+}
+
+/// Static configuration of the whole OS, handed to [`crate::start_os`].
+pub struct OsConfig {
+    pub tasks: &'static [TaskConfig],
+    pub resources: &'static [ResourceConfig],
+    pub counters: &'static [CounterConfig],
+    pub alarms: &'static [AlarmConfig],
+    pub hooks: Hooks,
+    pub pools: &'static [PoolConfig],
+    /// Memory of the global allocator, `None` for no heap. Ignored by the
+    /// host simulation, which uses the allocator of the host.
+    pub heap: Option<HeapRegion>,
+}
+
+impl OsConfig {
+    /// Configuration with `tasks` and nothing else: no resources, counters,
+    /// alarms, hooks, pools or heap. The other fields are meant to be set
+    /// with the struct update syntax.
+    pub const fn new(tasks: &'static [TaskConfig]) -> Self {
+        OsConfig {
+            tasks,
+            resources: &[],
+            counters: &[],
+            alarms: &[],
+            hooks: Hooks::NONE,
+            pools: &[],
+            heap: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct Tcb {
+    pub state: TaskStateType,
+    /// Current priority, which may differ from the configured one.
+    pub priority: Priority,
+    /// Saved stack pointer, `0` while the task has no context.
+    pub sp: usize,
+    #[cfg(feature = "events")]
+    pub events: EventMaskType,
+    #[cfg(feature = "events")]
+    pub wait_mask: EventMaskType,
+    /// Resource acquired last, head of the chain of occupied resources.
+    pub last_resource: Option<ResourceType>,
+    /// Ticks at which the pending activations happened, oldest first.
+    pub activated_at: [TickType; QUEUE_DEPTH],
+    /// Whether the deadline miss of each pending activation was reported.
+    pub miss_reported: [bool; QUEUE_DEPTH],
+}
+
+impl Tcb {
+    const fn new() -> Self {
+        Tcb {
+            state: TaskStateType::Suspended,
+            priority: 0,
+            sp: 0,
+            #[cfg(feature = "events")]
+            events: 0,
+            #[cfg(feature = "events")]
+            wait_mask: 0,
+            last_resource: None,
+            activated_at: [0; QUEUE_DEPTH],
+            miss_reported: [false; QUEUE_DEPTH],
+        }
+    }
+}
+
+/// Outcome of a context switch.
+pub(crate) struct Switch {
+    /// Stack pointer of the context to restore.
+    pub sp: usize,
+    /// Task that left the CPU, if any.
+    pub left: Option<TaskType>,
+    /// Task that got the CPU, if any.
+    pub entered: Option<TaskType>,
+}
+
+pub(crate) struct Kernel {
+    pub tasks: &'static [TaskConfig],
+    pub tcbs: [Tcb; MAX_TASKS],
+    pub ready: ReadyQueue<MAX_PRIORITIES, MAX_TASKS, QUEUE_DEPTH>,
+    pub resources: [ResourceState; MAX_RESOURCES + 1],
+    pub resource_config: &'static [ResourceConfig],
+    pub hooks: Hooks,
+    /// Task in the running state, or waiting for the switch away from it.
+    pub running: Option<TaskType>,
+    /// Task whose context is on the CPU, even if it terminated.
+    pub on_cpu: Option<TaskType>,
+    /// Whether the CPU currently executes the idle context.
+    pub idle_running: bool,
+    pub idle_sp: usize,
+    /// Set once the start-up is over and tasks may be dispatched.
+    pub started: bool,
+    /// Task leaving the CPU while the PostTaskHook runs, for `GetTaskID`.
+    pub hook_task: Option<TaskType>,
+    /// Timer ticks since the start-up, the time base of the deadlines.
+    pub ticks: TickType,
+}
+
+pub(crate) static KERNEL: Mutex<RefCell<Kernel>> = Mutex::new(RefCell::new(Kernel::new()));
+
+impl Kernel {
+    const fn new() -> Self {
+        Kernel {
+            tasks: &[],
+            tcbs: [Tcb::new(); MAX_TASKS],
+            ready: ReadyQueue::new(),
+            resources: [ResourceState::new(); MAX_RESOURCES + 1],
+            resource_config: &[],
+            hooks: Hooks::NONE,
+            running: None,
+            on_cpu: None,
+            idle_running: false,
+            idle_sp: 0,
+            started: false,
+            hook_task: None,
+            ticks: 0,
+        }
+    }
+
+    pub fn init(&mut self, config: &'static OsConfig) {
+        *self = Kernel::new();
+        self.tasks = config.tasks;
+        self.resource_config = config.resources;
+        self.hooks = config.hooks;
+        self.idle_sp = port::init_idle();
+        #[cfg(feature = "shell")]
+        for task in config.tasks {
+            task.stack.paint();
+        }
+        for (id, task) in config.tasks.iter().enumerate() {
+            if task.autostart {
+                self.activate(id);
+            }
+        }
+    }
+
+    /// Ends the start-up and lets the scheduler dispatch the first task.
+    pub fn start(&mut self) {
+        self.started = true;
+        self.schedule();
+    }
+
+    pub fn activate(&mut self, task_id: TaskType) -> StatusType {
+        let Some(task) = self.tasks.get(task_id) else {
+            #[cfg(debug_assertions)]
+            info!("Invalid Task ID");
+            return StatusType::EOsId;
+        };
+        if self
+            .ready
+            .activate(task_id, task.priority, task.activation)
+            .is_err()
+        {
+            #[cfg(debug_assertions)]
+            info!("Too many activations of {}", task.name);
+            trace::record(TraceKind::Overrun, task_id, 0);
+            return StatusType::EOsLimit;
+        }
+        trace::record(TraceKind::Activate, task_id, 0);
+        let tcb = &mut self.tcbs[task_id];
+        let slot = self.ready.activations(task_id) as usize - 1;
+        tcb.activated_at[slot] = self.ticks;
+        tcb.miss_reported[slot] = false;
+        if tcb.state == TaskStateType::Suspended {
+            tcb.state = TaskStateType::Ready;
+            tcb.priority = task.priority;
+            #[cfg(feature = "events")]
+            {
+                tcb.events = 0;
+            }
+        }
+        self.schedule();
+        StatusType::EOk
+    }
+
+    /// The first pending activation past its deadline whose miss was not
+    /// reported yet, with its lateness in ticks, now marked as reported.
+    fn next_deadline_miss(&mut self) -> Option<(TaskType, TickType)> {
+        for (id, task) in self.tasks.iter().enumerate() {
+            let Some(deadline) = task.deadline else {
+                continue;
+            };
+            let pending = self.ready.activations(id) as usize;
+            let tcb = &mut self.tcbs[id];
+            for slot in 0..pending {
+                let lateness = self
+                    .ticks
+                    .wrapping_sub(tcb.activated_at[slot])
+                    .wrapping_sub(deadline);
+                if lateness >= 1 && !tcb.miss_reported[slot] {
+                    tcb.miss_reported[slot] = true;
+                    return Some((id, lateness));
+                }
+            }
+        }
+        None
+    }
+
+    pub fn terminate_running(&mut self) -> StatusType {
+        if port::in_isr() {
+            return StatusType::EOsCallevel;
+        }
+        let Some(id) = self.running else {
+            return StatusType::EOsCallevel;
+        };
+        if self.tcbs[id].last_resource.is_some() {
+            return StatusType::EOsResource;
+        }
+        let pending = self.ready.activations(id) as usize;
+        self.ready.terminate(id);
+        trace::record(TraceKind::Terminate, id, self.ready.activations(id) as u32);
+        let tcb = &mut self.tcbs[id];
+        tcb.activated_at.copy_within(1..pending, 0);
+        tcb.miss_reported.copy_within(1..pending, 0);
+        tcb.sp = 0;
+        tcb.state = if self.ready.activations(id) > 0 {
+            TaskStateType::Ready
+        } else {
+            TaskStateType::Suspended
+        };
+        self.running = None;
+        if self.started {
+            port::request_switch();
+        }
+        StatusType::EOk
+    }
+
+    /// Terminates the running task and activates `task_id` in one step. The
+    /// caller keeps running if `task_id` cannot be activated.
+    pub fn chain(&mut self, task_id: TaskType) -> StatusType {
+        let Some(task) = self.tasks.get(task_id) else {
+            return StatusType::EOsId;
+        };
+        if port::in_isr() {
+            return StatusType::EOsCallevel;
+        }
+        let Some(id) = self.running else {
+            return StatusType::EOsCallevel;
+        };
+        if self.tcbs[id].last_resource.is_some() {
+            return StatusType::EOsResource;
+        }
+        // Chaining to itself frees the activation it is about to reuse
+        if task_id != id && self.ready.activations(task_id) >= task.activation {
+            #[cfg(debug_assertions)]
+            info!("Too many activations of {}", task.name);
+            return StatusType::EOsLimit;
+        }
+        self.terminate_running();
+        self.activate(task_id)
+    }
+
+    /// Task `GetTaskID` reports: the one leaving the CPU during the
+    /// PostTaskHook, the running one otherwise.
+    pub fn task_id(&self) -> TaskType {
+        self.hook_task.or(self.running).unwrap_or(INVALID_TASK)
+    }
+
+    pub fn task_state(&self, task_id: TaskType) -> Result<TaskStateType, StatusType> {
+        if task_id >= self.tasks.len() {
+            return Err(StatusType::EOsId);
+        }
+        Ok(self.tcbs[task_id].state)
+    }
+
+    /// Rescheduling point of the `Schedule` service: the running task gives
+    /// its internal resource back, so that a higher-priority task may run.
+    pub fn reschedule(&mut self) -> StatusType {
+        if port::in_isr() {
+            return StatusType::EOsCallevel;
+        }
+        let Some(id) = self.running else {
+            return StatusType::EOsCallevel;
+        };
+        if self.tcbs[id].last_resource.is_some() {
+            return StatusType::EOsResource;
+        }
+        self.tcbs[id].priority = self.tasks[id].priority;
+        if select_next(&self.ready, self.running_priority()) == Decision::Keep {
+            // Nothing to run instead, keep the internal resource
+            self.tcbs[id].priority = self.internal_ceiling(id);
+        } else {
+            // Taken back by `dispatch` once the task runs again
+            self.schedule();
+        }
+        StatusType::EOk
+    }
+
+    /// Requests a context switch if the ready queue asks for one.
+    pub fn schedule(&self) {
+        if self.started && select_next(&self.ready, self.running_priority()) != Decision::Keep {
+            port::request_switch();
+        }
+    }
+
+    /// Priority of the running task, `None` if no task is in the running
+    /// state (it may have started waiting for an event).
+    fn running_priority(&self) -> Option<Priority> {
+        self.running
+            .filter(|&id| self.tcbs[id].state == TaskStateType::Running)
+            .map(|id| self.tcbs[id].priority)
+    }
+
+    /// Switches from the context whose stack pointer is `saved_sp` to the
+    /// next one. `saved_sp` is `0` if there is no context worth saving.
+    pub fn switch_context(&mut self, saved_sp: usize) -> Switch {
+        let left = self.on_cpu;
+        if saved_sp != 0 {
+            match self.running {
+                Some(id) => self.tcbs[id].sp = saved_sp,
+                None if self.idle_running => self.idle_sp = saved_sp,
+                // The context of a terminated task is dropped
+                None => {}
+            }
+        }
+
+        let sp = match select_next(&self.ready, self.running_priority()) {
+            Decision::Keep => {
+                return Switch {
+                    sp: saved_sp,
+                    left: None,
+                    entered: None,
+                };
+            }
+            Decision::Idle => {
+                if !self.idle_running {
+                    trace::record(TraceKind::Idle, 0, 0);
+                }
+                self.running = None;
+                self.on_cpu = None;
+                self.idle_running = true;
+                self.idle_sp
+            }
+            Decision::Preempt => {
+                if let Some(id) = self.running.take() {
+                    trace::record(TraceKind::Preempt, id, 0);
+                    let tcb = &mut self.tcbs[id];
+                    tcb.state = TaskStateType::Ready;
+                    // A slot is always free: `check_config` left room in the
+                    // FIFO of every priority a resource can raise a task to.
+                    let _ = self.ready.preempt(id, tcb.priority);
+                }
+                self.dispatch()
+            }
+            Decision::Dispatch => self.dispatch(),
+        };
+        Switch {
+            sp,
+            left,
+            entered: self.on_cpu,
+        }
+    }
+
+    fn dispatch(&mut self) -> usize {
+        let Some((id, _)) = self.ready.pop() else {
+            if !self.idle_running {
+                trace::record(TraceKind::Idle, 0, 0);
+            }
+            self.running = None;
+            self.on_cpu = None;
+            self.idle_running = true;
+            return self.idle_sp;
+        };
+        let internal_ceiling = self.internal_ceiling(id);
+        let task = &self.tasks[id];
+        let tcb = &mut self.tcbs[id];
+        if tcb.sp == 0 {
+            tcb.priority = task.priority;
+            tcb.sp = unsafe { port::init_context(task.stack, task.entry) };
+        }
+        // The internal resource is taken whenever the task gets the CPU
+        tcb.priority = tcb.priority.max(internal_ceiling);
+        tcb.state = TaskStateType::Running;
+        trace::record(TraceKind::Start, id, 0);
+        self.running = Some(id);
+        self.on_cpu = Some(id);
+        self.idle_running = false;
+        tcb.sp
+    }
+}
+
+/// Performs the context switch requested from the port, calling the
+/// PostTaskHook and PreTaskHook outside of the kernel critical section.
+pub(crate) fn switch(saved_sp: usize) -> usize {
+    let (switch, hooks) = critical_section::with(|cs| {
+        let mut kernel = KERNEL.borrow_ref_mut(cs);
+        (kernel.switch_context(saved_sp), kernel.hooks)
+    });
+    if switch.left.is_some()
+        && let Some(hook) = hooks.post_task
+    {
+        critical_section::with(|cs| KERNEL.borrow_ref_mut(cs).hook_task = switch.left);
+        hook();
+        critical_section::with(|cs| KERNEL.borrow_ref_mut(cs).hook_task = None);
+    }
+    if switch.entered.is_some()
+        && let Some(hook) = hooks.pre_task
+    {
+        hook();
+    }
+    switch.sp
+}
+
+/// Reports every activation still pending past its deadline, once, to the
+/// `deadline_miss` hook or else to the ErrorHook with `E_OS_LIMIT`, outside
+/// of the kernel critical section. Called from the timer ISR once the tick
+/// count advanced, so that a miss is known while the task is still late.
+pub(crate) fn check_deadlines() {
+    // One miss per critical section, as a hook may activate tasks or see
+    // the next tick
+    while let Some((id, lateness, hooks)) = critical_section::with(|cs| {
+        let mut kernel = KERNEL.borrow_ref_mut(cs);
+        let (id, lateness) = kernel.next_deadline_miss()?;
+        trace::record(TraceKind::DeadlineMiss, id, lateness as u32);
+        Some((id, lateness, kernel.hooks))
+    }) {
+        match hooks.deadline_miss {
+            Some(hook) => hook(id, lateness),
+            None => {
+                hooks::report(StatusType::EOsLimit);
+            }
+        }
+    }
+}
+
+/// Checks `config` against the limits of the kernel tables and against the
+/// conformance class the kernel was built for.
+pub(crate) fn check_config(config: &OsConfig) -> Result<(), &'static str> {
+    let multiple_activations = cfg!(feature = "multiple-activations");
+    if config.tasks.len() > MAX_TASKS {
+        return Err("too many tasks");
+    }
+    if config
+        .resources
+        .iter()
+        .any(|resource| resource.ceiling as usize >= MAX_PRIORITIES)
+    {
+        return Err("resource ceiling out of range");
+    }
+    let mut per_priority = [0usize; MAX_PRIORITIES];
+    for task in config.tasks {
+        if task.priority as usize >= MAX_PRIORITIES {
+            return Err("task priority out of range");
+        }
+        if task.activation == 0 {
+            return Err("task activation must be at least 1");
+        }
+        // The host simulation runs tasks on threads, with a minimum of 0
+        #[allow(clippy::absurd_extreme_comparisons)]
+        if task.stack.size < MIN_STACK_SIZE {
+            return Err("task stack smaller than MIN_STACK_SIZE");
+        }
+        if task.deadline.is_some_and(|deadline| deadline <= 0) {
+            return Err("task deadline must be positive");
+        }
+        if task.extended && !cfg!(feature = "events") {
+            return Err("extended tasks need conformance class ECC1 or ECC2");
+        }
+        if task.extended && task.activation > 1 {
+            return Err("extended tasks cannot have multiple activations");
+        }
+        if task.activation > 1 && !multiple_activations {
+            return Err("multiple activations need conformance class BCC2 or ECC2");
+        }
+        if per_priority[task.priority as usize] > 0 && !multiple_activations {
+            return Err("several tasks per priority need conformance class BCC2 or ECC2");
+        }
+        if let Some(resource) = task.internal_resource {
+            match config.resources.get(resource) {
+                Some(resource) if resource.internal => {
+                    if resource.ceiling < task.priority {
+                        return Err("internal resource ceiling below task priority");
+                    }
+                }
+                _ => return Err("task internal resource is not an internal resource"),
+            }
+        }
+        per_priority[task.priority as usize] += task.activation as usize;
+    }
+    // A task preempted while holding a resource is queued at the ceiling
+    for (priority, activations) in per_priority.iter().enumerate() {
+        let ceiling = priority == MAX_PRIORITIES - 1
+            || config
+                .resources
+                .iter()
+                .any(|resource| resource.ceiling as usize == priority);
+        if activations + ceiling as usize > QUEUE_DEPTH {
+            return Err("too many activations at one priority");
+        }
+    }
+    if config.resources.len() > MAX_RESOURCES {
+        return Err("too many resources");
+    }
+    #[cfg(debug_assertions)]
+    info!(
+        "Configuration fits conformance class {}",
+        crate::CONFORMANCE_CLASS
+    );
+    Ok(())
+}
+
+/// Activates a task.
+///
+/// # Syntax
+/// ```ignore
+/// StatusType ActivateTask(TaskType TaskID)
+/// ```
+///
+/// # Parameters
+///
+/// * `task_id` (in) — Task reference.
+///
+/// # Description
+///
+/// The task `task_id` is transferred from the suspended state into the ready
+/// state. If the task is already ready or running, the activation is queued,
+/// up to the `activation` limit of its configuration.
+///
+/// # Particularities
+///
+/// Allowed on task level and ISR level. A rescheduling takes place after
+/// the activation if the activated task has a higher priority than the
+/// running one; when called from an ISR, it is delayed until the ISR ends.
+///
+/// # Status
+///
+/// * **Standard:**
+///   * `E_OK` — No error.
+///   * `E_OS_LIMIT` — Too many activations of `task_id`.
+/// * **Extended:**
+///   * `E_OS_ID` — `task_id` is invalid.
+#[unsafe(no_mangle)]
+pub extern "C" fn ActivateTask(task_id: TaskType) -> StatusType {
+    hooks::report(critical_section::with(|cs| {
+        KERNEL.borrow_ref_mut(cs).activate(task_id)
+    }))
+}
+
+/// Terminates the calling task.
+///
+/// # Syntax
+/// ```ignore
+/// StatusType TerminateTask(void)
+/// ```
+///
+/// # Description
+///
+/// The calling task is transferred from the running state into the
+/// suspended state, or straight back to ready if further activations are
+/// queued. It is restarted from its entry point on its next dispatch.
+///
+/// # Particularities
+///
+/// This service does not return to the caller on success.
+///
+/// # Status
+///
+/// * **Extended:**
+///   * `E_OS_RESOURCE` — Task still occupies resources.
+///   * `E_OS_CALLEVEL` — Call at interrupt level.
+#[unsafe(no_mangle)]
+pub extern "C" fn TerminateTask() -> StatusType {
+    let status = critical_section::with(|cs| KERNEL.borrow_ref_mut(cs).terminate_running());
+    if status == StatusType::EOk {
+        port::leave_terminated();
+    }
+    hooks::report(status)
+}
+
+/// Rescheduling point for non-preemptable tasks.
+///
+/// # Syntax
+/// ```ignore
+/// StatusType Schedule(void)
+/// ```
+///
+/// # Description
+///
+/// If a task with a higher priority than the calling task's configured one
+/// is ready, the internal resource of the calling task is released and the
+/// higher-priority task runs. The caller resumes, with its internal resource
+/// taken again, once it is the highest-priority ready task. Otherwise the
+/// calling task simply continues.
+///
+/// # Particularities
+///
+/// Only meaningful for non-preemptable tasks and tasks with an internal
+/// resource. Allowed on task level only.
+///
+/// # Status
+///
+/// * **Standard:**
+///   * `E_OK` — No error.
+/// * **Extended:**
+///   * `E_OS_CALLEVEL` — Call at interrupt level.
+///   * `E_OS_RESOURCE` — Calling task occupies resources.
+#[unsafe(no_mangle)]
+pub extern "C" fn Schedule() -> StatusType {
+    hooks::report(critical_section::with(|cs| {
+        KERNEL.borrow_ref_mut(cs).reschedule()
+    }))
+}
+
+/// Terminates the calling task and activates another one.
+///
+/// # Syntax
+/// ```ignore
+/// StatusType ChainTask(TaskType TaskID)
+/// ```
+///
+/// # Parameters
+///
+/// * `task_id` (in) — Reference to the sequential succeeding task to be
+///   activated.
+///
+/// # Description
+///
+/// The calling task is terminated, and the task `task_id` activated, as one
+/// operation. If `task_id` is the calling task, it is activated again and
+/// queued behind the tasks ready at its priority.
+///
+/// # Particularities
+///
+/// Does not return to the caller if successful. On error, the caller keeps
+/// running and `task_id` is not activated. Only allowed on task level, and
+/// not while the caller occupies resources.
+///
+/// # Status
+///
+/// * **Standard:**
+///   * No return to the call level.
+///   * `E_OS_LIMIT` — Too many activations of `task_id`.
+/// * **Extended:**
+///   * `E_OS_ID` — `task_id` is invalid.
+///   * `E_OS_RESOURCE` — Calling task still occupies resources.
+///   * `E_OS_CALLEVEL` — Call at interrupt level.
+#[unsafe(no_mangle)]
+pub extern "C" fn ChainTask(task_id: TaskType) -> StatusType {
+    let status = critical_section::with(|cs| KERNEL.borrow_ref_mut(cs).chain(task_id));
+    if status == StatusType::EOk {
+        port::leave_terminated();
+    }
+    hooks::report(status)
+}
+
+/// Returns the task currently running.
+///
+/// # Syntax
+/// ```ignore
+/// StatusType GetTaskID(TaskRefType TaskID)
+/// ```
+///
+/// # Parameters
+///
+/// * `task_id` (out) — Reference to the task which is currently running.
+///
+/// # Description
+///
+/// Writes the task in the running state, or `INVALID_TASK` if there is
+/// none.
+///
+/// # Particularities
+///
+/// Allowed on task level, ISR level, in the ErrorHook and in the
+/// PreTaskHook and PostTaskHook, where it returns the task entering or
+/// leaving the CPU.
+///
+/// # Status
+///
+/// * **Standard:**
+///   * `E_OK` — No error.
+///
+/// # Safety
+///
+/// `task_id` must be valid for a write.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn GetTaskID(task_id: TaskRefType) -> StatusType {
+    let id = critical_section::with(|cs| KERNEL.borrow_ref(cs).task_id());
+    unsafe { *task_id = id };
+    hooks::report(StatusType::EOk)
+}
+
+/// Returns the state of a task.
+///
+/// # Syntax
+/// ```ignore
+/// StatusType GetTaskState(TaskType TaskID, TaskStateRefType State)
+/// ```
+///
+/// # Parameters
+///
+/// * `task_id` (in) — Task reference.
+/// * `state` (out) — Reference to the state of the task.
+///
+/// # Description
+///
+/// Writes the state of `task_id`: `RUNNING`, `READY`, `WAITING` or
+/// `SUSPENDED`.
+///
+/// # Particularities
+///
+/// Allowed on task level, ISR level, in the ErrorHook and in the
+/// PreTaskHook and PostTaskHook. The state may already be outdated when it
+/// is read, if the task can be preempted.
+///
+/// # Status
+///
+/// * **Standard:**
+///   * `E_OK` — No error.
+/// * **Extended:**
+///   * `E_OS_ID` — `task_id` is invalid.
+///
+/// # Safety
+///
+/// `state` must be valid for a write.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn GetTaskState(task_id: TaskType, state: TaskStateRefType) -> StatusType {
+    let status = match critical_section::with(|cs| KERNEL.borrow_ref(cs).task_state(task_id)) {
+        Ok(task_state) => {
+            unsafe { *state = task_state };
+            StatusType::EOk
+        }
+        Err(status) => status,
+    };
+    hooks::report(status)
+}
@@ -0,0 +1,174 @@
+//! Kernel event trace.
+//!
+//! With the `trace` feature, the kernel records task activation, start,
+//! preemption and termination, event set and wait, resource get and
+//! release, alarm expiry and ISR entry and exit into the [`OS_TRACE`] ring
+//! buffer. Every record carries a timestamp in microseconds, from TIMER0 on
+//! the RP2350.
+//!
+//! Writers reserve a slot with an atomic increment of the write counter, so
+//! ISRs record without entering the kernel critical section. Once the buffer
+//! is full, the oldest records are overwritten.
+//!
+//! The buffer is meant to be read from the host: dump the `OS_TRACE` symbol
+//! with the debugger and convert it with `tools/os-trace`. The layout below
+//! is what that tool expects, keep both in sync.
+//!
+//! The same trace points feed the SystemView stream with the `systemview`
+//! feature, see [`crate::systemview`], and the run-time statistics with the
+//! `stats` feature, see [`crate::stats`]. Without any of these features,
+//! recording compiles to nothing.
+
+#[cfg(feature = "trace")]
+use core::{
+    cell::UnsafeCell,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+/// Identifies a trace buffer in a memory dump: `"OSTR"` in little endian.
+pub const TRACE_MAGIC: u32 = 0x5254_534f;
+/// Number of records in the ring buffer.
+pub const TRACE_CAPACITY: usize = 1024;
+
+/// Kind of a trace record, and what its `object` and `data` fields hold.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceKind {
+    /// `object`: activated task.
+    Activate = 1,
+    /// `object`: task entering the running state.
+    Start = 2,
+    /// `object`: task leaving the CPU for a higher-priority one.
+    Preempt = 3,
+    /// `object`: terminated task, `data`: activations still pending.
+    Terminate = 4,
+    /// `object`: task starting to wait, `data`: awaited events.
+    WaitEvent = 5,
+    /// `object`: task whose events are set, `data`: events set.
+    SetEvent = 6,
+    /// `object`: resource, `data`: task getting it.
+    GetResource = 7,
+    /// `object`: resource, `data`: task releasing it.
+    ReleaseResource = 8,
+    /// `object`: expired alarm.
+    AlarmExpire = 9,
+    /// `object`: interrupt number.
+    IsrEnter = 10,
+    /// `object`: interrupt number.
+    IsrExit = 11,
+    /// `object`: task released from waiting by a set event.
+    Release = 12,
+    /// The idle loop got the CPU.
+    Idle = 13,
+    /// `object`: task activated while at its activation limit.
+    Overrun = 14,
+    /// `object`: task with an activation still pending past its deadline,
+    /// `data`: lateness in ticks.
+    DeadlineMiss = 15,
+}
+
+/// One entry of the trace, 12 bytes.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct TraceRecord {
+    pub timestamp: u32,
+    /// A [`TraceKind`], `0` for a slot never written.
+    pub kind: u8,
+    pub object: u8,
+    pub reserved: u16,
+    pub data: u32,
+}
+
+#[cfg(feature = "trace")]
+impl TraceRecord {
+    const EMPTY: TraceRecord = TraceRecord {
+        timestamp: 0,
+        kind: 0,
+        object: 0,
+        reserved: 0,
+        data: 0,
+    };
+}
+
+/// Ring buffer as laid out in memory: a header of four words followed by
+/// the records. Record `n` is stored at index `n % capacity`.
+///
+/// The header is filled in at start-up, keeping the buffer out of `.data`.
+#[cfg(feature = "trace")]
+#[repr(C)]
+pub struct TraceBuffer {
+    magic: AtomicU32,
+    capacity: AtomicU32,
+    record_size: AtomicU32,
+    /// Number of records written since start-up.
+    written: AtomicU32,
+    records: UnsafeCell<[TraceRecord; TRACE_CAPACITY]>,
+}
+
+// Each writer owns the slot it reserved
+#[cfg(feature = "trace")]
+unsafe impl Sync for TraceBuffer {}
+
+#[cfg(feature = "trace")]
+#[unsafe(no_mangle)]
+pub static OS_TRACE: TraceBuffer = TraceBuffer {
+    magic: AtomicU32::new(0),
+    capacity: AtomicU32::new(0),
+    record_size: AtomicU32::new(0),
+    written: AtomicU32::new(0),
+    records: UnsafeCell::new([TraceRecord::EMPTY; TRACE_CAPACITY]),
+};
+
+/// Empties the trace and makes it recognizable in a memory dump.
+#[cfg(feature = "trace")]
+pub(crate) fn init() {
+    OS_TRACE.written.store(0, Ordering::Relaxed);
+    OS_TRACE
+        .capacity
+        .store(TRACE_CAPACITY as u32, Ordering::Relaxed);
+    OS_TRACE
+        .record_size
+        .store(size_of::<TraceRecord>() as u32, Ordering::Relaxed);
+    OS_TRACE.magic.store(TRACE_MAGIC, Ordering::Release);
+}
+
+#[cfg(not(feature = "trace"))]
+pub(crate) fn init() {}
+
+/// Appends a record to the trace.
+#[inline(always)]
+pub(crate) fn record(kind: TraceKind, object: usize, data: u32) {
+    #[cfg(feature = "trace")]
+    write(kind, object, data);
+    #[cfg(feature = "systemview")]
+    crate::systemview::record(kind, object, data);
+    #[cfg(feature = "stats")]
+    crate::stats::record(kind, object, data);
+    #[cfg(not(any(feature = "trace", feature = "systemview", feature = "stats")))]
+    let _ = (kind, object, data);
+}
+
+#[cfg(feature = "trace")]
+fn write(kind: TraceKind, object: usize, data: u32) {
+    let slot = OS_TRACE.written.fetch_add(1, Ordering::Relaxed) as usize % TRACE_CAPACITY;
+    let record = TraceRecord {
+        timestamp: crate::port::timestamp(),
+        kind: kind as u8,
+        object: object as u8,
+        reserved: 0,
+        data,
+    };
+    unsafe { (*OS_TRACE.records.get())[slot] = record };
+}
+
+/// Records the entry into an ISR. To be called first thing in the handler.
+#[inline(always)]
+pub fn isr_enter(irq: u8) {
+    record(TraceKind::IsrEnter, irq as usize, 0);
+}
+
+/// Records the exit from an ISR. To be called last thing in the handler.
+#[inline(always)]
+pub fn isr_exit(irq: u8) {
+    record(TraceKind::IsrExit, irq as usize, 0);
+}
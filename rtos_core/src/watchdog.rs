@@ -0,0 +1,275 @@
+//! Watchdog manager with alive supervision.
+//!
+//! With the `watchdog` feature, tasks register supervised entities with
+//! [`RegisterSupervisedEntity`], each expecting between a minimum and a
+//! maximum number of alive indications per supervision cycle, and give them
+//! with [`CheckpointReached`]. At the end of every supervision cycle, the
+//! timer tick checks all the entities: while each of them got an expected
+//! number of indications, the manager feeds the hardware watchdog through
+//! the [`WatchdogDriver`] of the application. The first entity found out of
+//! bounds is handed to [`WatchdogDriver::failed`], which records it where
+//! it survives the reset, and the watchdog is never fed again.
+//!
+//! Until [`init`] is called, nothing is supervised nor fed.
+
+use core::cell::RefCell;
+
+use critical_section::Mutex;
+#[cfg(debug_assertions)]
+use defmt::info;
+
+use crate::{
+    StatusType,
+    alarms::TickType,
+    hooks,
+    task::{KERNEL, TaskType},
+};
+
+/// Maximum number of supervised entities.
+pub const MAX_SUPERVISED_ENTITIES: usize = 16;
+
+pub type SupervisedEntityType = usize;
+pub type SupervisedEntityRefType = *mut SupervisedEntityType;
+
+/// Hardware side of the watchdog manager, provided by the application.
+#[derive(Clone, Copy)]
+pub struct WatchdogDriver {
+    /// Supervision cycle in timer ticks. The hardware watchdog must not
+    /// expire within one cycle.
+    pub cycle: TickType,
+    /// Feeds the hardware watchdog.
+    pub feed: fn(),
+    /// Called once, from the timer tick, with the first entity that failed
+    /// its supervision and the task that registered it.
+    pub failed: fn(SupervisedEntityType, TaskType),
+}
+
+#[derive(Clone, Copy)]
+struct Entity {
+    min: u32,
+    max: u32,
+    /// Alive indications in the current cycle.
+    count: u32,
+    owner: TaskType,
+    /// Cleared for the cycle the entity was registered in, which it may
+    /// have joined late.
+    supervised: bool,
+}
+
+struct Manager {
+    driver: Option<WatchdogDriver>,
+    entities: [Entity; MAX_SUPERVISED_ENTITIES],
+    count: usize,
+    /// Ticks since the start of the current cycle.
+    elapsed: TickType,
+    failed: bool,
+}
+
+static MANAGER: Mutex<RefCell<Manager>> = Mutex::new(RefCell::new(Manager {
+    driver: None,
+    entities: [Entity {
+        min: 0,
+        max: 0,
+        count: 0,
+        owner: 0,
+        supervised: false,
+    }; MAX_SUPERVISED_ENTITIES],
+    count: 0,
+    elapsed: 0,
+    failed: false,
+}));
+
+/// Starts the supervision with `driver`. The hardware watchdog should
+/// already run.
+pub fn init(driver: WatchdogDriver) {
+    critical_section::with(|cs| {
+        let mut manager = MANAGER.borrow_ref_mut(cs);
+        manager.driver = Some(driver);
+        manager.elapsed = 0;
+        manager.failed = false;
+    });
+}
+
+/// Forgets the entities of a previous run. Called by `StartOS`.
+pub(crate) fn start() {
+    critical_section::with(|cs| {
+        let mut manager = MANAGER.borrow_ref_mut(cs);
+        manager.count = 0;
+        manager.elapsed = 0;
+        manager.failed = false;
+    });
+}
+
+/// Ticks until the end of the current supervision cycle, `None` if the
+/// watchdog is not fed.
+#[cfg(feature = "low-power")]
+pub(crate) fn next_feed() -> Option<TickType> {
+    critical_section::with(|cs| {
+        let manager = MANAGER.borrow_ref(cs);
+        let driver = manager.driver?;
+        (!manager.failed).then(|| driver.cycle - manager.elapsed)
+    })
+}
+
+/// Counts a timer tick, and ends the supervision cycle after
+/// [`WatchdogDriver::cycle`] of them. Called by the kernel tick.
+pub(crate) fn tick() {
+    enum Outcome {
+        Feed(fn()),
+        Failed(
+            fn(SupervisedEntityType, TaskType),
+            SupervisedEntityType,
+            TaskType,
+        ),
+    }
+    let outcome = critical_section::with(|cs| {
+        let mut manager = MANAGER.borrow_ref_mut(cs);
+        let driver = manager.driver?;
+        if manager.failed {
+            return None;
+        }
+        manager.elapsed += 1;
+        if manager.elapsed < driver.cycle {
+            return None;
+        }
+        manager.elapsed = 0;
+        let count = manager.count;
+        let culprit = manager.entities[..count].iter().position(|entity| {
+            entity.supervised && !(entity.min..=entity.max).contains(&entity.count)
+        });
+        for entity in &mut manager.entities[..count] {
+            entity.count = 0;
+            entity.supervised = true;
+        }
+        match culprit {
+            Some(id) => {
+                manager.failed = true;
+                Some(Outcome::Failed(
+                    driver.failed,
+                    id,
+                    manager.entities[id].owner,
+                ))
+            }
+            None => Some(Outcome::Feed(driver.feed)),
+        }
+    });
+    match outcome {
+        Some(Outcome::Feed(feed)) => feed(),
+        Some(Outcome::Failed(failed, id, owner)) => {
+            #[cfg(debug_assertions)]
+            info!("Supervised entity {} of task {} failed", id, owner);
+            failed(id, owner);
+        }
+        None => {}
+    }
+}
+
+/// Registers a supervised entity.
+///
+/// # Syntax
+/// ```ignore
+/// StatusType RegisterSupervisedEntity(uint32_t MinIndications,
+///                                     uint32_t MaxIndications,
+///                                     SupervisedEntityRefType Entity)
+/// ```
+///
+/// # Parameters
+///
+/// * `min` (in) — Fewest alive indications expected per supervision cycle.
+/// * `max` (in) — Most alive indications expected per supervision cycle.
+/// * `entity` (out) — Reference to the new entity, for
+///   `CheckpointReached`.
+///
+/// # Description
+///
+/// The entity is supervised from the next supervision cycle on, and stays
+/// supervised until the next reset. The calling task is recorded as its
+/// owner.
+///
+/// # Particularities
+///
+/// Allowed on task level and in the StartupHook.
+///
+/// # Status
+///
+/// * **Standard:**
+///   * `E_OK` — No error.
+///   * `E_OS_LIMIT` — All the entities are registered.
+/// * **Extended:**
+///   * `E_OS_VALUE` — `max` is `0` or below `min`.
+///
+/// # Safety
+///
+/// `entity` must be valid for a write.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn RegisterSupervisedEntity(
+    min: u32,
+    max: u32,
+    entity: SupervisedEntityRefType,
+) -> StatusType {
+    let status = match register_supervised_entity(min, max) {
+        Ok(id) => {
+            unsafe { *entity = id };
+            StatusType::EOk
+        }
+        Err(status) => status,
+    };
+    hooks::report(status)
+}
+
+fn register_supervised_entity(min: u32, max: u32) -> Result<SupervisedEntityType, StatusType> {
+    if max == 0 || max < min {
+        return Err(StatusType::EOsValue);
+    }
+    critical_section::with(|cs| {
+        let owner = KERNEL.borrow_ref(cs).task_id();
+        let mut manager = MANAGER.borrow_ref_mut(cs);
+        let id = manager.count;
+        if id == MAX_SUPERVISED_ENTITIES {
+            return Err(StatusType::EOsLimit);
+        }
+        manager.entities[id] = Entity {
+            min,
+            max,
+            count: 0,
+            owner,
+            supervised: false,
+        };
+        manager.count += 1;
+        Ok(id)
+    })
+}
+
+/// Gives an alive indication of a supervised entity.
+///
+/// # Syntax
+/// ```ignore
+/// StatusType CheckpointReached(SupervisedEntityType Entity)
+/// ```
+///
+/// # Parameters
+///
+/// * `entity` (in) — Reference to the entity.
+///
+/// # Particularities
+///
+/// Allowed on task level and ISR level.
+///
+/// # Status
+///
+/// * **Standard:**
+///   * `E_OK` — No error.
+/// * **Extended:**
+///   * `E_OS_ID` — `entity` is invalid.
+#[unsafe(no_mangle)]
+pub extern "C" fn CheckpointReached(entity: SupervisedEntityType) -> StatusType {
+    hooks::report(critical_section::with(|cs| {
+        let mut manager = MANAGER.borrow_ref_mut(cs);
+        if entity >= manager.count {
+            return StatusType::EOsId;
+        }
+        let entity = &mut manager.entities[entity];
+        entity.count = entity.count.saturating_add(1);
+        StatusType::EOk
+    }))
+}
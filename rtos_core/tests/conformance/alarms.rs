@@ -0,0 +1,284 @@
+//! Alarms driven by the system counter.
+
+use rtos_core::{
+    OsConfig, TaskConfig,
+    alarms::{
+        AlarmBaseType, AlarmConfig, CancelAlarm, CounterConfig, GetAlarm, GetAlarmBase,
+        SetAbsAlarm, SetRelAlarm,
+    },
+    sim::{now, run_os},
+    task::{ActivateTask, TerminateTask},
+};
+
+use crate::{
+    ALL, Case,
+    common::{COUNTERS, alarm, config, expect_trace, on_alarm, task, trace},
+};
+
+pub const CASES: &[Case] = &[
+    Case {
+        id: "AL.01",
+        classes: ALL,
+        requirement: "A relative alarm expires after the given increment",
+        run: relative_alarm,
+    },
+    Case {
+        id: "AL.02",
+        classes: ALL,
+        requirement: "A cyclic alarm expires again after every cycle",
+        run: cyclic_alarm,
+    },
+    Case {
+        id: "AL.03",
+        classes: ALL,
+        requirement: "CancelAlarm stops an alarm, and returns E_OS_NOFUNC if it is not in use",
+        run: cancel_alarm,
+    },
+    Case {
+        id: "AL.04",
+        classes: ALL,
+        requirement: "Setting an alarm already in use returns E_OS_STATE",
+        run: alarm_in_use,
+    },
+    Case {
+        id: "AL.05",
+        classes: ALL,
+        requirement: "Invalid alarms return E_OS_ID, out-of-range values E_OS_VALUE",
+        run: invalid_arguments,
+    },
+    Case {
+        id: "AL.06",
+        classes: ALL,
+        requirement: "GetAlarm returns the ticks left and GetAlarmBase the counter constants",
+        run: alarm_info,
+    },
+    Case {
+        id: "AL.07",
+        classes: ALL,
+        requirement: "A task activated by an alarm callback runs once the tick is handled",
+        run: callback_activates_task,
+    },
+    Case {
+        id: "AL.08",
+        classes: ALL,
+        requirement: "An absolute alarm expires when its counter reaches the start value",
+        run: absolute_alarm,
+    },
+    Case {
+        id: "AL.09",
+        classes: ALL,
+        requirement: "A relative alarm counts from its counter value, also when set again after CancelAlarm",
+        run: alarm_phase,
+    },
+];
+
+const A: usize = 0;
+
+fn expired_at() {
+    trace(format!("expired at {}", now()));
+}
+
+fn relative_alarm() -> Result<(), String> {
+    extern "C" fn a() {
+        SetRelAlarm(0, 3, 0);
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        ..task("A", a, 1)
+    }];
+    static CONFIG: OsConfig = config(&TASKS);
+    on_alarm(0, expired_at);
+    run_os(&CONFIG, 10);
+    expect_trace(&["expired at 3"])
+}
+
+fn cyclic_alarm() -> Result<(), String> {
+    extern "C" fn a() {
+        SetRelAlarm(1, 2, 10);
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        ..task("A", a, 1)
+    }];
+    static CONFIG: OsConfig = config(&TASKS);
+    on_alarm(1, expired_at);
+    run_os(&CONFIG, 25);
+    expect_trace(&["expired at 2", "expired at 12", "expired at 22"])
+}
+
+fn cancel_alarm() -> Result<(), String> {
+    extern "C" fn a() {
+        SetRelAlarm(0, 5, 0);
+        trace(format!("{:?}", CancelAlarm(0)));
+        trace(format!("{:?}", CancelAlarm(0)));
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        ..task("A", a, 1)
+    }];
+    static CONFIG: OsConfig = config(&TASKS);
+    on_alarm(0, expired_at);
+    run_os(&CONFIG, 10);
+    expect_trace(&["EOk", "EOsNofunc"])
+}
+
+fn alarm_in_use() -> Result<(), String> {
+    extern "C" fn a() {
+        trace(format!("{:?}", SetRelAlarm(0, 5, 0)));
+        trace(format!("{:?}", SetRelAlarm(0, 5, 0)));
+        trace(format!("{:?}", SetAbsAlarm(0, 5, 0)));
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        ..task("A", a, 1)
+    }];
+    static CONFIG: OsConfig = config(&TASKS);
+    on_alarm(0, expired_at);
+    run_os(&CONFIG, 10);
+    expect_trace(&["EOk", "EOsState", "EOsState", "expired at 5"])
+}
+
+fn invalid_arguments() -> Result<(), String> {
+    extern "C" fn a() {
+        trace(format!("{:?}", SetRelAlarm(9, 5, 0)));
+        trace(format!("{:?}", SetRelAlarm(0, 0, 0)));
+        trace(format!("{:?}", SetRelAlarm(0, 11, 0)));
+        trace(format!("{:?}", SetRelAlarm(0, 5, 11)));
+        trace(format!("{:?}", SetAbsAlarm(0, 11, 0)));
+        trace(format!("{:?}", SetAbsAlarm(0, -1, 0)));
+        trace(format!("{:?}", CancelAlarm(9)));
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        ..task("A", a, 1)
+    }];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&[
+        "EOsId", "EOsValue", "EOsValue", "EOsValue", "EOsValue", "EOsValue", "EOsId",
+    ])
+}
+
+fn alarm_info() -> Result<(), String> {
+    extern "C" fn a() {
+        let mut ticks = 0;
+        trace(format!("{:?}", unsafe { GetAlarm(3, &mut ticks) }));
+        SetRelAlarm(3, 5, 0);
+        let status = unsafe { GetAlarm(3, &mut ticks) };
+        trace(format!("{status:?} {ticks}"));
+        let mut base = AlarmBaseType {
+            maxallowedvalue: 0,
+            ticksperbase: 0,
+            mincycle: 0,
+        };
+        let status = unsafe { GetAlarmBase(3, &mut base) };
+        trace(format!(
+            "{status:?} {} {}",
+            base.maxallowedvalue, base.ticksperbase
+        ));
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        ..task("A", a, 1)
+    }];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["EOsNofunc", "EOk 5", "EOk 10 1"])
+}
+
+fn callback_activates_task() -> Result<(), String> {
+    extern "C" fn a() {
+        trace(format!("A at {}", now()));
+        if now() == 0 {
+            SetRelAlarm(4, 4, 0);
+        }
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        ..task("A", a, 1)
+    }];
+    static CONFIG: OsConfig = config(&TASKS);
+    on_alarm(4, || {
+        trace("callback");
+        ActivateTask(A);
+        trace("callback done");
+    });
+    run_os(&CONFIG, 10);
+    expect_trace(&["A at 0", "callback", "callback done", "A at 4"])
+}
+
+fn absolute_alarm() -> Result<(), String> {
+    extern "C" fn a() {
+        SetAbsAlarm(0, 3, 0);
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        ..task("A", a, 1)
+    }];
+    static CONFIG: OsConfig = config(&TASKS);
+    on_alarm(0, || {
+        expired_at();
+        // The counter is at 3 already, so it wraps past 10 to reach 2
+        SetAbsAlarm(1, 2, 0);
+    });
+    on_alarm(1, expired_at);
+    run_os(&CONFIG, 20);
+    expect_trace(&["expired at 3", "expired at 13"])
+}
+
+fn alarm_phase() -> Result<(), String> {
+    extern "C" fn a() {
+        SetRelAlarm(0, 3, 0);
+        SetRelAlarm(2, 5, 0);
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        ..task("A", a, 1)
+    }];
+    // A counter advancing every other timer tick
+    static COUNTERS_BY_2: [CounterConfig; 2] = [
+        COUNTERS[0],
+        CounterConfig {
+            name: "SlowCounter",
+            base: AlarmBaseType {
+                maxallowedvalue: 10,
+                ticksperbase: 2,
+                mincycle: 1,
+            },
+        },
+    ];
+    static ALARMS: [AlarmConfig; 4] = [
+        alarm("Alarm0", 0, 0),
+        alarm("Alarm1", 0, 1),
+        alarm("SlowAlarm2", 1, 2),
+        alarm("SlowAlarm3", 1, 3),
+    ];
+    static CONFIG: OsConfig = OsConfig {
+        counters: &COUNTERS_BY_2,
+        alarms: &ALARMS,
+        ..config(&TASKS)
+    };
+    // At 3 the slow counter is half-way from 1 to 2
+    on_alarm(0, || {
+        CancelAlarm(2);
+        SetRelAlarm(3, 1, 0);
+        SetRelAlarm(1, 1, 0);
+    });
+    // At 4 the slow counter reaches 2
+    on_alarm(1, || {
+        SetRelAlarm(2, 1, 0);
+    });
+    on_alarm(2, || trace(format!("SlowAlarm2 at {}", now())));
+    on_alarm(3, || trace(format!("SlowAlarm3 at {}", now())));
+    run_os(&CONFIG, 10);
+    expect_trace(&["SlowAlarm3 at 4", "SlowAlarm2 at 6"])
+}
@@ -0,0 +1,140 @@
+//! Helpers shared by the test sequences: task descriptions, the execution
+//! trace, the alarms and their callbacks.
+
+use std::sync::Mutex;
+
+use rtos_core::{
+    OsConfig, TaskConfig,
+    alarms::{AlarmAction, AlarmBaseType, AlarmConfig, CounterConfig, CounterType},
+    ready_queue::Priority,
+    task::{SchedulePolicy, StackRegion, TaskEntry},
+};
+
+/// The simulation gives every task a host thread, stacks are not used.
+pub const NO_STACK: StackRegion = StackRegion {
+    base: core::ptr::null_mut(),
+    size: 0,
+};
+
+/// A fully preemptable basic task with a single activation that is not
+/// started automatically.
+pub const fn task(name: &'static str, entry: TaskEntry, priority: Priority) -> TaskConfig {
+    TaskConfig {
+        name,
+        entry,
+        priority,
+        activation: 1,
+        autostart: false,
+        extended: false,
+        schedule: SchedulePolicy::Full,
+        internal_resource: None,
+        deadline: None,
+        stack: NO_STACK,
+    }
+}
+
+static TRACE: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Appends an event to the execution trace.
+pub fn trace(event: impl Into<String>) {
+    TRACE.lock().unwrap().push(event.into());
+}
+
+/// Compares the execution trace with `expected` and clears it.
+pub fn expect_trace(expected: &[&str]) -> Result<(), String> {
+    let trace = std::mem::take(&mut *TRACE.lock().unwrap());
+    if trace == expected {
+        Ok(())
+    } else {
+        Err(format!("expected trace {expected:?}, got {trace:?}"))
+    }
+}
+
+pub fn reset() {
+    TRACE.lock().unwrap().clear();
+    *CALLBACKS.lock().unwrap() = [None; 5];
+}
+
+type Callback = fn();
+
+static CALLBACKS: Mutex<[Option<Callback>; 5]> = Mutex::new([None; 5]);
+
+/// Runs `callback` whenever alarm `alarm` expires.
+pub fn on_alarm(alarm: usize, callback: Callback) {
+    CALLBACKS.lock().unwrap()[alarm] = Some(callback);
+}
+
+fn alarm_callback(alarm: usize) {
+    let callback = CALLBACKS.lock().unwrap()[alarm];
+    if let Some(callback) = callback {
+        callback();
+    }
+}
+
+extern "C" fn alarm_callback0() {
+    alarm_callback(0);
+}
+
+extern "C" fn alarm_callback1() {
+    alarm_callback(1);
+}
+
+extern "C" fn alarm_callback2() {
+    alarm_callback(2);
+}
+
+extern "C" fn alarm_callback3() {
+    alarm_callback(3);
+}
+
+extern "C" fn alarm_callback4() {
+    alarm_callback(4);
+}
+
+/// The system counter, 10 ticks long.
+pub static COUNTERS: [CounterConfig; 1] = [CounterConfig {
+    name: "SystemCounter",
+    base: AlarmBaseType {
+        maxallowedvalue: 10,
+        ticksperbase: 1,
+        mincycle: 10,
+    },
+}];
+
+const CALLBACKS_OF_ALARMS: [extern "C" fn(); 5] = [
+    alarm_callback0,
+    alarm_callback1,
+    alarm_callback2,
+    alarm_callback3,
+    alarm_callback4,
+];
+
+/// Alarm on `counter` whose callback runs the one given to [`on_alarm`]
+/// for `callback`.
+pub const fn alarm(name: &'static str, counter: CounterType, callback: usize) -> AlarmConfig {
+    AlarmConfig {
+        name,
+        counter,
+        action: AlarmAction::Callback(CALLBACKS_OF_ALARMS[callback]),
+        autostart: None,
+    }
+}
+
+/// Five alarms on the system counter, whose callbacks run those given to
+/// [`on_alarm`].
+pub static ALARMS: [AlarmConfig; 5] = [
+    alarm("Alarm0", 0, 0),
+    alarm("Alarm1", 0, 1),
+    alarm("Alarm2", 0, 2),
+    alarm("Alarm3", 0, 3),
+    alarm("Alarm4", 0, 4),
+];
+
+/// Configuration of `tasks` with the system counter and its alarms.
+pub const fn config(tasks: &'static [TaskConfig]) -> OsConfig {
+    OsConfig {
+        counters: &COUNTERS,
+        alarms: &ALARMS,
+        ..OsConfig::new(tasks)
+    }
+}
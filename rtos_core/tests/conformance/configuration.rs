@@ -0,0 +1,77 @@
+//! Configurations written with `rtos_config!`.
+
+use rtos_core::sim::{now, run_os};
+
+use crate::{
+    ALL, Case,
+    common::{expect_trace, trace},
+};
+
+pub const CASES: &[Case] = &[Case {
+    id: "CF.01",
+    classes: ALL,
+    requirement: "A configuration from rtos_config! runs, its handles naming its objects",
+    run: generated_config,
+}];
+
+mod generated {
+    use rtos_core::os::{rtos_config, task};
+
+    use crate::common::trace;
+
+    #[task]
+    fn logger() {
+        let bus = BUS.lock().unwrap();
+        SAMPLER.activate().unwrap();
+        trace("Logger holds Bus");
+        drop(bus);
+        trace("Logger released Bus");
+    }
+
+    #[task]
+    fn sampler() {
+        trace(format!("Sampler at {}", super::now()));
+        let _bus = BUS.lock().unwrap();
+    }
+
+    rtos_config! {
+        tasks {
+            Logger { entry: logger, priority: 1, stack: 1024, autostart: true, resources: [Bus] },
+            Sampler { entry: sampler, priority: 2, stack: 1024, resources: [Bus] },
+        }
+        counters {
+            SystemCounter { max: 10, ticks_per_base: 1, min_cycle: 5 },
+        }
+        alarms {
+            SampleAlarm {
+                counter: SystemCounter,
+                action: activate_task(Sampler),
+                alarm_time: 2,
+                cycle_time: 5,
+            },
+        }
+        resources { Bus }
+    }
+}
+
+fn generated_config() -> Result<(), String> {
+    use generated::{BUS, CONFIG, LOGGER, SAMPLE_ALARM, SAMPLER};
+
+    trace(format!(
+        "ids {} {} {} {}, ceiling {}",
+        LOGGER.id(),
+        SAMPLER.id(),
+        SAMPLE_ALARM.id(),
+        BUS.id(),
+        CONFIG.resources[BUS.id()].ceiling
+    ));
+    run_os(&CONFIG, 10);
+    expect_trace(&[
+        "ids 0 1 0 0, ceiling 2",
+        "Logger holds Bus",
+        "Sampler at 0",
+        "Logger released Bus",
+        "Sampler at 2",
+        "Sampler at 7",
+    ])
+}
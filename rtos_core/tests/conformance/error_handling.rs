@@ -0,0 +1,117 @@
+//! Error handling through the ErrorHook.
+
+use rtos_core::{
+    Hooks, OsConfig, StatusType, TaskConfig,
+    alarms::CancelAlarm,
+    sim::run_os,
+    task::{ActivateTask, TerminateTask},
+};
+
+use crate::{
+    ALL, Case,
+    common::{config, expect_trace, task, trace},
+};
+
+pub const CASES: &[Case] = &[
+    Case {
+        id: "EH.01",
+        classes: ALL,
+        requirement: "The ErrorHook is called with the status of a failing service",
+        run: error_hook_called,
+    },
+    Case {
+        id: "EH.02",
+        classes: ALL,
+        requirement: "The ErrorHook is not called for services returning E_OK",
+        run: no_error_no_hook,
+    },
+    Case {
+        id: "EH.03",
+        classes: ALL,
+        requirement: "Services failing inside the ErrorHook do not call it again",
+        run: no_recursion,
+    },
+];
+
+extern "C" fn error_hook(status: StatusType) {
+    trace(format!("ErrorHook {status:?}"));
+}
+
+const HOOKS: Hooks = Hooks {
+    error: Some(error_hook),
+    ..Hooks::NONE
+};
+
+fn error_hook_called() -> Result<(), String> {
+    extern "C" fn a() {
+        let status = ActivateTask(7);
+        trace(format!("A {status:?}"));
+        let status = CancelAlarm(0);
+        trace(format!("A {status:?}"));
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        ..task("A", a, 1)
+    }];
+    static CONFIG: OsConfig = OsConfig {
+        hooks: HOOKS,
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&[
+        "ErrorHook EOsId",
+        "A EOsId",
+        "ErrorHook EOsNofunc",
+        "A EOsNofunc",
+    ])
+}
+
+fn no_error_no_hook() -> Result<(), String> {
+    extern "C" fn a() {
+        ActivateTask(1);
+        TerminateTask();
+    }
+    extern "C" fn b() {
+        trace("B");
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a, 1)
+        },
+        task("B", b, 2),
+    ];
+    static CONFIG: OsConfig = OsConfig {
+        hooks: HOOKS,
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&["B"])
+}
+
+fn no_recursion() -> Result<(), String> {
+    extern "C" fn nested_error_hook(status: StatusType) {
+        trace(format!("ErrorHook {status:?}"));
+        let status = ActivateTask(9);
+        trace(format!("in hook {status:?}"));
+    }
+    extern "C" fn a() {
+        ActivateTask(7);
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        ..task("A", a, 1)
+    }];
+    static CONFIG: OsConfig = OsConfig {
+        hooks: Hooks {
+            error: Some(nested_error_hook),
+            ..Hooks::NONE
+        },
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&["ErrorHook EOsId", "in hook EOsId"])
+}
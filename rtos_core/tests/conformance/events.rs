@@ -0,0 +1,302 @@
+//! Event mechanism of extended tasks.
+
+use rtos_core::{
+    OsConfig, TaskConfig,
+    events::{ClearEvent, EventMaskType, GetEvent, SetEvent, WaitEvent},
+    sim::{isr, run_os},
+    task::{ActivateTask, GetTaskState, SchedulePolicy, TaskStateType, TerminateTask},
+};
+
+use crate::{
+    Case, EXTENDED,
+    common::{config, expect_trace, task, trace},
+};
+
+pub const CASES: &[Case] = &[
+    Case {
+        id: "EV.01",
+        classes: EXTENDED,
+        requirement: "WaitEvent returns at once if an awaited event is already set",
+        run: wait_already_set,
+    },
+    Case {
+        id: "EV.02",
+        classes: EXTENDED,
+        requirement: "SetEvent releases a waiting task, which preempts a lower-priority setter",
+        run: set_releases_waiter,
+    },
+    Case {
+        id: "EV.03",
+        classes: EXTENDED,
+        requirement: "SetEvent returns E_OS_ID, E_OS_ACCESS and E_OS_STATE for invalid targets",
+        run: set_invalid_target,
+    },
+    Case {
+        id: "EV.04",
+        classes: EXTENDED,
+        requirement: "GetEvent reads and ClearEvent clears the events of the caller",
+        run: get_and_clear,
+    },
+    Case {
+        id: "EV.05",
+        classes: EXTENDED,
+        requirement: "WaitEvent and ClearEvent from a basic task return E_OS_ACCESS",
+        run: basic_task_access,
+    },
+    Case {
+        id: "EV.06",
+        classes: EXTENDED,
+        requirement: "The events of an extended task are cleared when it is activated",
+        run: cleared_on_activation,
+    },
+    Case {
+        id: "EV.07",
+        classes: EXTENDED,
+        requirement: "A task released by SetEvent from an ISR runs when the ISR returns",
+        run: set_event_from_isr,
+    },
+    Case {
+        id: "EV.08",
+        classes: EXTENDED,
+        requirement: "A non-preemptable task gives the CPU to a task activated by an ISR at WaitEvent",
+        run: wait_event_rescheduling_point,
+    },
+    Case {
+        id: "EV.09",
+        classes: EXTENDED,
+        requirement: "GetTaskState returns WAITING for a task waiting for an event",
+        run: waiting_state,
+    },
+];
+
+const E: usize = 0;
+const B: usize = 1;
+
+const EVENT: EventMaskType = 0b01;
+const OTHER: EventMaskType = 0b10;
+
+fn events_of(task_id: usize) -> String {
+    let mut mask = 0;
+    let status = unsafe { GetEvent(task_id, &mut mask) };
+    format!("{status:?} {mask:#b}")
+}
+
+fn wait_already_set() -> Result<(), String> {
+    extern "C" fn e() {
+        SetEvent(E, EVENT);
+        trace(format!("{:?}", WaitEvent(EVENT | OTHER)));
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        extended: true,
+        ..task("E", e, 1)
+    }];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["EOk"])
+}
+
+fn set_releases_waiter() -> Result<(), String> {
+    extern "C" fn e() {
+        trace("E waits");
+        WaitEvent(EVENT);
+        trace("E woken");
+        TerminateTask();
+    }
+    extern "C" fn b() {
+        trace("B sets other");
+        SetEvent(E, OTHER);
+        trace("B sets event");
+        SetEvent(E, EVENT);
+        trace("B");
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            extended: true,
+            ..task("E", e, 2)
+        },
+        TaskConfig {
+            autostart: true,
+            ..task("B", b, 1)
+        },
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["E waits", "B sets other", "B sets event", "E woken", "B"])
+}
+
+fn set_invalid_target() -> Result<(), String> {
+    extern "C" fn b() {
+        trace(format!("{:?}", SetEvent(9, EVENT)));
+        trace(format!("{:?}", SetEvent(B, EVENT)));
+        trace(format!("{:?}", SetEvent(E, EVENT)));
+        TerminateTask();
+    }
+    extern "C" fn e() {
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            extended: true,
+            ..task("E", e, 2)
+        },
+        TaskConfig {
+            autostart: true,
+            ..task("B", b, 1)
+        },
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["EOsId", "EOsAccess", "EOsState"])
+}
+
+fn get_and_clear() -> Result<(), String> {
+    extern "C" fn e() {
+        SetEvent(E, EVENT | OTHER);
+        trace(events_of(E));
+        trace(format!("{:?}", ClearEvent(EVENT)));
+        trace(events_of(E));
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        extended: true,
+        ..task("E", e, 1)
+    }];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["EOk 0b11", "EOk", "EOk 0b10"])
+}
+
+fn basic_task_access() -> Result<(), String> {
+    extern "C" fn b() {
+        trace(format!("{:?}", WaitEvent(EVENT)));
+        trace(format!("{:?}", ClearEvent(EVENT)));
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        ..task("B", b, 1)
+    }];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["EOsAccess", "EOsAccess"])
+}
+
+fn cleared_on_activation() -> Result<(), String> {
+    extern "C" fn e() {
+        trace(events_of(E));
+        SetEvent(E, EVENT);
+        TerminateTask();
+    }
+    extern "C" fn b() {
+        ActivateTask(E);
+        ActivateTask(E);
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            extended: true,
+            ..task("E", e, 2)
+        },
+        TaskConfig {
+            autostart: true,
+            ..task("B", b, 1)
+        },
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["EOk 0b0", "EOk 0b0"])
+}
+
+fn set_event_from_isr() -> Result<(), String> {
+    extern "C" fn e() {
+        trace("E waits");
+        WaitEvent(EVENT);
+        trace("E woken");
+        TerminateTask();
+    }
+    extern "C" fn b() {
+        isr(|| {
+            SetEvent(E, EVENT);
+            trace("ISR end");
+        });
+        trace("B");
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            extended: true,
+            ..task("E", e, 2)
+        },
+        TaskConfig {
+            autostart: true,
+            ..task("B", b, 1)
+        },
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["E waits", "ISR end", "E woken", "B"])
+}
+
+fn wait_event_rescheduling_point() -> Result<(), String> {
+    extern "C" fn e() {
+        isr(|| {
+            ActivateTask(B);
+            trace("ISR end");
+        });
+        trace("E waits");
+        WaitEvent(EVENT);
+        trace("E woken");
+        TerminateTask();
+    }
+    extern "C" fn b() {
+        trace("B");
+        SetEvent(E, EVENT);
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            extended: true,
+            schedule: SchedulePolicy::Non,
+            ..task("E", e, 1)
+        },
+        task("B", b, 2),
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["ISR end", "E waits", "B", "E woken"])
+}
+
+fn waiting_state() -> Result<(), String> {
+    extern "C" fn e() {
+        WaitEvent(EVENT);
+        TerminateTask();
+    }
+    extern "C" fn b() {
+        let mut state = TaskStateType::Suspended;
+        let status = unsafe { GetTaskState(E, &mut state) };
+        trace(format!("{status:?} {state:?}"));
+        SetEvent(E, EVENT);
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            extended: true,
+            ..task("E", e, 2)
+        },
+        TaskConfig {
+            autostart: true,
+            ..task("B", b, 1)
+        },
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["EOk Waiting"])
+}
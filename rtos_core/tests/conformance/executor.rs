@@ -0,0 +1,164 @@
+//! Async executor of extended tasks.
+
+use core::{
+    future::poll_fn,
+    sync::atomic::{AtomicBool, Ordering},
+    task::{Poll, Waker},
+    time::Duration,
+};
+use std::sync::Mutex;
+
+use rtos_core::{
+    OsConfig, TaskConfig,
+    alarms::{AlarmAction, AlarmConfig},
+    executor::{Executor, Timer},
+    os::{Alarm, Event},
+    sim::{isr, now, run_os},
+    task::TerminateTask,
+};
+
+use crate::{
+    Case, EXTENDED,
+    common::{config, expect_trace, task, trace},
+};
+
+pub const CASES: &[Case] = &[
+    Case {
+        id: "EX.01",
+        classes: EXTENDED,
+        requirement: "A Timer completes when the alarm of its executor expires",
+        run: timer_expires,
+    },
+    Case {
+        id: "EX.02",
+        classes: EXTENDED,
+        requirement: "A waker called from an ISR makes the executor task poll again",
+        run: wake_from_isr,
+    },
+    Case {
+        id: "EX.03",
+        classes: EXTENDED,
+        requirement: "An executor started inside another one of the same task returns E_OS_STATE",
+        run: nested_executor,
+    },
+    Case {
+        id: "EX.04",
+        classes: EXTENDED,
+        requirement: "An executor woken up by several events returns E_OS_VALUE",
+        run: several_events,
+    },
+];
+
+const WAKE: Event = Event::new(0b1);
+
+/// Executor of task 0, one kernel tick a millisecond.
+static EXECUTOR: Executor = Executor::new(WAKE, Alarm::new(0), Duration::from_millis(1));
+
+/// The alarm of [`EXECUTOR`].
+static ALARMS: [AlarmConfig; 1] = [AlarmConfig {
+    name: "Wake",
+    counter: 0,
+    action: AlarmAction::SetEvent(0, WAKE.mask()),
+    autostart: None,
+}];
+
+/// Task 0, extended and started automatically, at `priority`.
+const fn executor_task(entry: extern "C" fn(), priority: u8) -> TaskConfig {
+    TaskConfig {
+        autostart: true,
+        extended: true,
+        ..task("E", entry, priority)
+    }
+}
+
+fn timer_expires() -> Result<(), String> {
+    extern "C" fn e() {
+        let result = EXECUTOR.block_on(async {
+            trace(format!("Sleep at {}", now()));
+            Timer::after(Duration::from_millis(3)).await;
+            trace(format!("Awake at {}", now()));
+        });
+        trace(format!("{result:?}"));
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [executor_task(e, 1)];
+    static CONFIG: OsConfig = OsConfig {
+        alarms: &ALARMS,
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 5);
+    expect_trace(&["Sleep at 0", "Awake at 3", "Ok(())"])
+}
+
+fn wake_from_isr() -> Result<(), String> {
+    static READY: AtomicBool = AtomicBool::new(false);
+    static WAKER: Mutex<Option<Waker>> = Mutex::new(None);
+    extern "C" fn e() {
+        READY.store(false, Ordering::SeqCst);
+        let result = EXECUTOR.block_on(poll_fn(|cx| {
+            trace("E polls");
+            if READY.load(Ordering::SeqCst) {
+                Poll::Ready(())
+            } else {
+                *WAKER.lock().unwrap() = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }));
+        trace(format!("{result:?}"));
+        TerminateTask();
+    }
+    extern "C" fn b() {
+        isr(|| {
+            READY.store(true, Ordering::SeqCst);
+            WAKER.lock().unwrap().take().unwrap().wake();
+            trace("ISR end");
+        });
+        trace("B");
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        executor_task(e, 2),
+        TaskConfig {
+            autostart: true,
+            ..task("B", b, 1)
+        },
+    ];
+    static CONFIG: OsConfig = OsConfig {
+        alarms: &ALARMS,
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&["E polls", "ISR end", "E polls", "Ok(())", "B"])
+}
+
+fn nested_executor() -> Result<(), String> {
+    extern "C" fn e() {
+        let result = EXECUTOR.block_on(async {
+            trace(format!("{:?}", EXECUTOR.block_on(async {})));
+        });
+        trace(format!("{result:?}"));
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [executor_task(e, 1)];
+    static CONFIG: OsConfig = OsConfig {
+        alarms: &ALARMS,
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&["Err(State)", "Ok(())"])
+}
+
+fn several_events() -> Result<(), String> {
+    extern "C" fn e() {
+        let executor = Executor::new(Event::new(0b11), Alarm::new(0), Duration::from_millis(1));
+        trace(format!("{:?}", executor.block_on(async {})));
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [executor_task(e, 1)];
+    static CONFIG: OsConfig = OsConfig {
+        alarms: &ALARMS,
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&["Err(Value)"])
+}
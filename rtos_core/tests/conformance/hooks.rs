@@ -0,0 +1,291 @@
+//! StartupHook, ShutdownHook, PreTaskHook and PostTaskHook.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use rtos_core::{
+    Hooks, OsConfig, ShutdownOS, StatusType, TaskConfig,
+    alarms::{self, TickType},
+    reset::ResetReasonType,
+    sim::{isr, run_os},
+    task::{ActivateTask, GetTaskID, TaskType, TerminateTask},
+};
+
+use crate::{
+    ALL, Case,
+    common::{config, expect_trace, task, trace},
+};
+
+pub const CASES: &[Case] = &[
+    Case {
+        id: "HK.01",
+        classes: ALL,
+        requirement: "The StartupHook runs before the first task",
+        run: startup_hook,
+    },
+    Case {
+        id: "HK.02",
+        classes: ALL,
+        requirement: "PreTaskHook and PostTaskHook frame every task execution",
+        run: pre_post_hooks,
+    },
+    Case {
+        id: "HK.03",
+        classes: ALL,
+        requirement: "ShutdownOS calls the ShutdownHook with its error and stops the OS",
+        run: shutdown_hook,
+    },
+    Case {
+        id: "HK.04",
+        classes: ALL,
+        requirement: "GetTaskID returns the entering task in the PreTaskHook, the leaving one in the PostTaskHook",
+        run: task_id_in_hooks,
+    },
+    Case {
+        id: "HK.05",
+        classes: ALL,
+        requirement: "The deadline miss hook runs on the first tick past the deadline, while the task still runs",
+        run: deadline_miss_hook,
+    },
+    Case {
+        id: "HK.06",
+        classes: ALL,
+        requirement: "Each late activation is reported once, with its lateness when found",
+        run: deadline_miss_once,
+    },
+];
+
+extern "C" fn b() {
+    trace("B");
+    TerminateTask();
+}
+
+fn startup_hook() -> Result<(), String> {
+    extern "C" fn startup(_: ResetReasonType) {
+        trace("StartupHook");
+    }
+    extern "C" fn a() {
+        trace("A");
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        ..task("A", a, 1)
+    }];
+    static CONFIG: OsConfig = OsConfig {
+        hooks: Hooks {
+            startup: Some(startup),
+            ..Hooks::NONE
+        },
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&["StartupHook", "A"])
+}
+
+fn pre_post_hooks() -> Result<(), String> {
+    extern "C" fn pre_task() {
+        trace("PreTaskHook");
+    }
+    extern "C" fn post_task() {
+        trace("PostTaskHook");
+    }
+    extern "C" fn a() {
+        trace("A1");
+        ActivateTask(1);
+        trace("A2");
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a, 1)
+        },
+        task("B", b, 2),
+    ];
+    static CONFIG: OsConfig = OsConfig {
+        hooks: Hooks {
+            pre_task: Some(pre_task),
+            post_task: Some(post_task),
+            ..Hooks::NONE
+        },
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&[
+        "PreTaskHook",
+        "A1",
+        "PostTaskHook",
+        "PreTaskHook",
+        "B",
+        "PostTaskHook",
+        "PreTaskHook",
+        "A2",
+        "PostTaskHook",
+    ])
+}
+
+fn shutdown_hook() -> Result<(), String> {
+    extern "C" fn shutdown(error: StatusType) {
+        trace(format!("ShutdownHook {error:?}"));
+    }
+    extern "C" fn a() {
+        trace("A");
+        ShutdownOS(StatusType::EOsLimit);
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a, 2)
+        },
+        TaskConfig {
+            autostart: true,
+            ..task("B", b, 1)
+        },
+    ];
+    static CONFIG: OsConfig = OsConfig {
+        hooks: Hooks {
+            shutdown: Some(shutdown),
+            ..Hooks::NONE
+        },
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 10);
+    expect_trace(&["A", "ShutdownHook EOsLimit"])
+}
+
+fn task_id_in_hooks() -> Result<(), String> {
+    fn running_task() -> usize {
+        let mut task_id = 0;
+        unsafe { GetTaskID(&mut task_id) };
+        task_id
+    }
+    extern "C" fn pre_task() {
+        trace(format!("PreTaskHook {}", running_task()));
+    }
+    extern "C" fn post_task() {
+        trace(format!("PostTaskHook {}", running_task()));
+    }
+    extern "C" fn a() {
+        ActivateTask(1);
+        TerminateTask();
+    }
+    extern "C" fn b() {
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a, 1)
+        },
+        task("B", b, 2),
+    ];
+    static CONFIG: OsConfig = OsConfig {
+        hooks: Hooks {
+            pre_task: Some(pre_task),
+            post_task: Some(post_task),
+            ..Hooks::NONE
+        },
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&[
+        "PreTaskHook 0",
+        "PostTaskHook 0",
+        "PreTaskHook 1",
+        "PostTaskHook 1",
+        "PreTaskHook 0",
+        "PostTaskHook 0",
+    ])
+}
+
+fn deadline_miss_hook() -> Result<(), String> {
+    extern "C" fn deadline_miss(task_id: TaskType, lateness: TickType) {
+        trace(format!("deadline miss of {task_id}, {lateness} tick late"));
+    }
+    extern "C" fn a() {
+        for tick in 1..=4 {
+            trace(format!("tick {tick}"));
+            isr(alarms::tick);
+        }
+        trace("A terminates");
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        deadline: Some(2),
+        ..task("A", a, 1)
+    }];
+    static CONFIG: OsConfig = OsConfig {
+        hooks: Hooks {
+            deadline_miss: Some(deadline_miss),
+            ..Hooks::NONE
+        },
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&[
+        "tick 1",
+        "tick 2",
+        "tick 3",
+        "deadline miss of 0, 1 tick late",
+        "tick 4",
+        "A terminates",
+    ])
+}
+
+fn deadline_miss_once() -> Result<(), String> {
+    static NESTED: AtomicBool = AtomicBool::new(false);
+    extern "C" fn deadline_miss(task_id: TaskType, lateness: TickType) {
+        trace(format!("deadline miss of {task_id}, {lateness} tick late"));
+        // A slow hook, which the next tick interrupts
+        if !NESTED.swap(true, Ordering::SeqCst) {
+            trace("tick in the hook");
+            isr(alarms::tick);
+        }
+    }
+    extern "C" fn a() {
+        for tick in 1..=5 {
+            trace(format!("tick {tick}"));
+            isr(alarms::tick);
+        }
+        trace("A terminates");
+        TerminateTask();
+    }
+    extern "C" fn b() {
+        trace("B terminates");
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            deadline: Some(2),
+            ..task("A", a, 2)
+        },
+        TaskConfig {
+            autostart: true,
+            deadline: Some(2),
+            ..task("B", b, 1)
+        },
+    ];
+    static CONFIG: OsConfig = OsConfig {
+        hooks: Hooks {
+            deadline_miss: Some(deadline_miss),
+            ..Hooks::NONE
+        },
+        ..config(&TASKS)
+    };
+    NESTED.store(false, Ordering::SeqCst);
+    run_os(&CONFIG, 0);
+    expect_trace(&[
+        "tick 1",
+        "tick 2",
+        "tick 3",
+        "deadline miss of 0, 1 tick late",
+        "tick in the hook",
+        "deadline miss of 1, 2 tick late",
+        "tick 4",
+        "tick 5",
+        "A terminates",
+        "B terminates",
+    ])
+}
@@ -0,0 +1,140 @@
+//! Interaction of Category 2 ISRs with the scheduler.
+
+use rtos_core::{
+    OsConfig, TaskConfig,
+    resources::GetResource,
+    sim::{isr, run_os},
+    task::{ActivateTask, TerminateTask},
+};
+
+use crate::{
+    ALL, Case,
+    common::{config, expect_trace, task, trace},
+};
+
+pub const CASES: &[Case] = &[
+    Case {
+        id: "IN.01",
+        classes: ALL,
+        requirement: "A task activated by an ISR is only dispatched when the ISR returns",
+        run: activation_deferred,
+    },
+    Case {
+        id: "IN.02",
+        classes: ALL,
+        requirement: "The interrupted task resumes if the ISR activated a lower-priority task",
+        run: interrupted_task_resumes,
+    },
+    Case {
+        id: "IN.03",
+        classes: ALL,
+        requirement: "Rescheduling takes place when the outermost nested ISR returns",
+        run: nested_isr,
+    },
+    Case {
+        id: "IN.04",
+        classes: ALL,
+        requirement: "TerminateTask and GetResource called from an ISR return E_OS_CALLEVEL",
+        run: callevel,
+    },
+];
+
+const B: usize = 1;
+
+extern "C" fn b() {
+    trace("B");
+    TerminateTask();
+}
+
+fn activation_deferred() -> Result<(), String> {
+    extern "C" fn a() {
+        trace("A1");
+        isr(|| {
+            ActivateTask(B);
+            trace("ISR end");
+        });
+        trace("A2");
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a, 1)
+        },
+        task("B", b, 2),
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["A1", "ISR end", "B", "A2"])
+}
+
+fn interrupted_task_resumes() -> Result<(), String> {
+    extern "C" fn a() {
+        trace("A1");
+        isr(|| {
+            ActivateTask(B);
+            trace("ISR end");
+        });
+        trace("A2");
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a, 2)
+        },
+        task("B", b, 1),
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["A1", "ISR end", "A2", "B"])
+}
+
+fn nested_isr() -> Result<(), String> {
+    extern "C" fn a() {
+        isr(|| {
+            isr(|| {
+                ActivateTask(B);
+                trace("inner ISR end");
+            });
+            trace("outer ISR end");
+        });
+        trace("A");
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a, 1)
+        },
+        task("B", b, 2),
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["inner ISR end", "outer ISR end", "B", "A"])
+}
+
+fn callevel() -> Result<(), String> {
+    extern "C" fn a() {
+        isr(|| {
+            trace(format!("{:?}", TerminateTask()));
+            trace(format!("{:?}", GetResource(0)));
+        });
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        ..task("A", a, 1)
+    }];
+    static RESOURCES: [rtos_core::ResourceConfig; 1] = [rtos_core::ResourceConfig {
+        name: "R",
+        ceiling: 1,
+        internal: false,
+    }];
+    static CONFIG: OsConfig = OsConfig {
+        resources: &RESOURCES,
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&["EOsCallevel", "EOsCallevel"])
+}
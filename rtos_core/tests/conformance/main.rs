@@ -0,0 +1,122 @@
+//! OSEK/VDX OS conformance suite, run against the host simulation port.
+//!
+//! The test sequences follow the areas of the OSEK/VDX OS test plan: task
+//! management, events, resources, scheduling policies, alarms, interrupts,
+//! error handling and hook routines, plus the memory pools, the
+//! configurations of `rtos_config!` and, with their features, the
+//! extensions of the kernel. Each one is a set of test tasks plus
+//! the trace they are expected to produce, and is tagged with the
+//! conformance classes it applies to. The runner reports pass/fail per
+//! requirement, skipping the ones outside the class the kernel was built
+//! for.
+
+mod alarms;
+mod common;
+mod configuration;
+mod error_handling;
+#[cfg(feature = "events")]
+mod events;
+#[cfg(feature = "async")]
+mod executor;
+mod hooks;
+mod interrupts;
+mod memory;
+#[cfg(feature = "low-power")]
+mod power;
+mod resources;
+mod safe_api;
+mod scheduling;
+#[cfg(feature = "shell")]
+mod shell;
+#[cfg(feature = "systemview")]
+mod systemview;
+mod task_management;
+#[cfg(feature = "watchdog")]
+mod watchdog;
+
+use std::process::ExitCode;
+
+use rtos_core::{CONFORMANCE_CLASS, ConformanceClass as Class};
+
+pub const ALL: &[Class] = &[Class::Bcc1, Class::Bcc2, Class::Ecc1, Class::Ecc2];
+/// Classes with multiple activations and several tasks per priority.
+pub const MULTIPLE: &[Class] = &[Class::Bcc2, Class::Ecc2];
+/// Classes with extended tasks and events.
+pub const EXTENDED: &[Class] = &[Class::Ecc1, Class::Ecc2];
+
+pub struct Case {
+    pub id: &'static str,
+    pub classes: &'static [Class],
+    pub requirement: &'static str,
+    pub run: fn() -> Result<(), String>,
+}
+
+fn main() -> ExitCode {
+    let mut areas: Vec<(&str, &[Case])> = vec![("Task management", task_management::CASES)];
+    #[cfg(feature = "events")]
+    areas.push(("Events", events::CASES));
+    areas.extend([
+        ("Resources", resources::CASES),
+        ("Scheduling", scheduling::CASES),
+        ("Alarms", alarms::CASES),
+        ("Interrupts", interrupts::CASES),
+        ("Error handling", error_handling::CASES),
+        ("Hook routines", hooks::CASES),
+        ("Memory pools", memory::CASES),
+        ("Safe Rust API", safe_api::CASES),
+        ("Configuration", configuration::CASES),
+    ]);
+    #[cfg(feature = "async")]
+    areas.push(("Async executor", executor::CASES));
+    #[cfg(feature = "low-power")]
+    areas.push(("Low-power idle", power::CASES));
+    #[cfg(feature = "shell")]
+    areas.push(("Shell", shell::CASES));
+    #[cfg(feature = "systemview")]
+    areas.push(("SystemView", systemview::CASES));
+    #[cfg(feature = "watchdog")]
+    areas.push(("Watchdog manager", watchdog::CASES));
+
+    let mut passed = 0;
+    let mut failed = 0;
+    let mut skipped = 0;
+    println!(
+        "OSEK/VDX OS conformance suite (host simulation, {})",
+        format!("{CONFORMANCE_CLASS:?}").to_uppercase()
+    );
+    for (area, cases) in areas {
+        println!("\n{area}");
+        for case in cases {
+            common::reset();
+            let classes = case
+                .classes
+                .iter()
+                .map(|class| format!("{class:?}").to_uppercase())
+                .collect::<Vec<_>>()
+                .join(" ");
+            if !case.classes.contains(&CONFORMANCE_CLASS) {
+                skipped += 1;
+                println!("  {:<6} SKIP  {} [{}]", case.id, case.requirement, classes);
+                continue;
+            }
+            match (case.run)() {
+                Ok(()) => {
+                    passed += 1;
+                    println!("  {:<6} PASS  {} [{}]", case.id, case.requirement, classes);
+                }
+                Err(reason) => {
+                    failed += 1;
+                    println!("  {:<6} FAIL  {} [{}]", case.id, case.requirement, classes);
+                    println!("         {reason}");
+                }
+            }
+        }
+    }
+
+    println!("\n{passed} passed, {failed} failed, {skipped} skipped");
+    if failed == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
@@ -0,0 +1,156 @@
+//! Fixed-block memory pools.
+
+use core::{ffi::c_void, ptr};
+
+use rtos_core::{
+    OsConfig, TaskConfig,
+    memory::{AllocBlock, FreeBlock, GetPoolInfo, PoolConfig, PoolInfoType, PoolMemory},
+    sim::run_os,
+    task::TerminateTask,
+};
+
+use crate::{
+    ALL, Case,
+    common::{config, expect_trace, task, trace},
+};
+
+pub const CASES: &[Case] = &[
+    Case {
+        id: "MM.01",
+        classes: ALL,
+        requirement: "AllocBlock on an exhausted pool returns E_OS_LIMIT and is counted",
+        run: exhaustion,
+    },
+    Case {
+        id: "MM.02",
+        classes: ALL,
+        requirement: "FreeBlock of a foreign or misaligned block returns E_OS_VALUE",
+        run: foreign_block,
+    },
+    Case {
+        id: "MM.03",
+        classes: ALL,
+        requirement: "Pool services on an invalid pool return E_OS_ID",
+        run: invalid_pool,
+    },
+];
+
+static SMALL: PoolMemory<16, 2> = PoolMemory::new();
+static OTHER: PoolMemory<16, 2> = PoolMemory::new();
+
+static POOLS: [PoolConfig; 2] = [
+    PoolConfig {
+        name: "Small",
+        memory: SMALL.region(),
+    },
+    PoolConfig {
+        name: "Other",
+        memory: OTHER.region(),
+    },
+];
+
+fn trace_info(pool: usize) {
+    let mut info = PoolInfoType::default();
+    let status = unsafe { GetPoolInfo(pool, &mut info) };
+    trace(format!(
+        "{status:?} free {} min {} failures {}",
+        info.free, info.min_free, info.failures
+    ));
+}
+
+fn exhaustion() -> Result<(), String> {
+    extern "C" fn a() {
+        let mut first = ptr::null_mut();
+        let mut second = ptr::null_mut();
+        let mut third = ptr::null_mut();
+        unsafe {
+            trace(format!("{:?}", AllocBlock(0, &mut first)));
+            trace(format!("{:?}", AllocBlock(0, &mut second)));
+            trace(format!("{:?}", AllocBlock(0, &mut third)));
+        }
+        trace(format!(
+            "third {}",
+            if third.is_null() { "unchanged" } else { "set" }
+        ));
+        trace_info(0);
+        unsafe { trace(format!("{:?}", FreeBlock(0, first))) };
+        trace_info(0);
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        ..task("A", a, 1)
+    }];
+    static CONFIG: OsConfig = OsConfig {
+        pools: &POOLS,
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&[
+        "EOk",
+        "EOk",
+        "EOsLimit",
+        "third unchanged",
+        "EOk free 0 min 0 failures 1",
+        "EOk",
+        "EOk free 1 min 0 failures 1",
+    ])
+}
+
+fn foreign_block() -> Result<(), String> {
+    extern "C" fn a() {
+        let mut block: *mut c_void = ptr::null_mut();
+        let mut local = 0u64;
+        unsafe {
+            AllocBlock(0, &mut block);
+            trace(format!("{:?}", FreeBlock(1, block)));
+            trace(format!("{:?}", FreeBlock(0, block.byte_add(4))));
+            trace(format!("{:?}", FreeBlock(0, (&raw mut local).cast())));
+            trace(format!("{:?}", FreeBlock(0, block)));
+            trace(format!("{:?}", FreeBlock(0, block)));
+        }
+        trace_info(0);
+        trace_info(1);
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        ..task("A", a, 1)
+    }];
+    static CONFIG: OsConfig = OsConfig {
+        pools: &POOLS,
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&[
+        "EOsValue",
+        "EOsValue",
+        "EOsValue",
+        "EOk",
+        "EOsValue",
+        "EOk free 2 min 1 failures 0",
+        "EOk free 2 min 2 failures 0",
+    ])
+}
+
+fn invalid_pool() -> Result<(), String> {
+    extern "C" fn a() {
+        let mut block: *mut c_void = ptr::null_mut();
+        unsafe {
+            trace(format!("{:?}", AllocBlock(2, &mut block)));
+            trace(format!("{:?}", FreeBlock(2, block)));
+        }
+        trace_info(2);
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        ..task("A", a, 1)
+    }];
+    static CONFIG: OsConfig = OsConfig {
+        pools: &POOLS,
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&["EOsId", "EOsId", "EOsId free 0 min 0 failures 0"])
+}
@@ -0,0 +1,70 @@
+//! Low-power idle.
+
+use rtos_core::{
+    Hooks, OsConfig, TaskConfig,
+    alarms::{AlarmAction, AlarmAutostart, AlarmConfig, TickType},
+    power::{self, PowerDriver, PowerState},
+    reset::ResetReasonType,
+    sim::{now, run_os},
+    task::TerminateTask,
+};
+
+use crate::{
+    ALL, Case,
+    common::{config, expect_trace, task, trace},
+};
+
+pub const CASES: &[Case] = &[Case {
+    id: "LP.01",
+    classes: ALL,
+    requirement: "The idle loop sleeps until the next alarm, then catches up with the ticks slept",
+    run: sleep_until_alarm,
+}];
+
+static STATES: [PowerState; 2] = [
+    PowerState {
+        name: "Nap",
+        latency: 1,
+    },
+    PowerState {
+        name: "Deep",
+        latency: 3,
+    },
+];
+
+fn sleep_until_alarm() -> Result<(), String> {
+    fn sleep(state: usize, ticks: TickType) -> TickType {
+        trace(format!("{} for {ticks} at {}", STATES[state].name, now()));
+        ticks
+    }
+    extern "C" fn startup(_: ResetReasonType) {
+        power::init(PowerDriver {
+            states: &STATES,
+            sleep,
+        });
+    }
+    extern "C" fn a() {
+        trace(format!("A at {}", now()));
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [task("A", a, 1)];
+    static ALARMS: [AlarmConfig; 1] = [AlarmConfig {
+        name: "ActivateA",
+        counter: 0,
+        action: AlarmAction::ActivateTask(0),
+        autostart: Some(AlarmAutostart {
+            alarm_time: 3,
+            cycle_time: 10,
+        }),
+    }];
+    static CONFIG: OsConfig = OsConfig {
+        alarms: &ALARMS,
+        hooks: Hooks {
+            startup: Some(startup),
+            ..Hooks::NONE
+        },
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 13);
+    expect_trace(&["Nap for 3 at 0", "A at 3", "Deep for 10 at 3", "A at 13"])
+}
@@ -0,0 +1,237 @@
+//! Resource management with the priority ceiling protocol.
+
+use rtos_core::{
+    OsConfig, ResourceConfig, TaskConfig,
+    resources::{GetResource, RES_SCHEDULER, ReleaseResource},
+    sim::run_os,
+    task::{ActivateTask, TerminateTask},
+};
+
+use crate::{
+    ALL, Case,
+    common::{config, expect_trace, task, trace},
+};
+
+pub const CASES: &[Case] = &[
+    Case {
+        id: "RS.01",
+        classes: ALL,
+        requirement: "A task holding a resource is not preempted up to the ceiling priority",
+        run: ceiling_blocks_preemption,
+    },
+    Case {
+        id: "RS.02",
+        classes: ALL,
+        requirement: "Tasks above the ceiling priority still preempt a resource holder",
+        run: above_ceiling_preempts,
+    },
+    Case {
+        id: "RS.03",
+        classes: ALL,
+        requirement: "RES_SCHEDULER prevents preemption by any task",
+        run: res_scheduler,
+    },
+    Case {
+        id: "RS.04",
+        classes: ALL,
+        requirement: "Resources are released in LIFO order, otherwise E_OS_NOFUNC",
+        run: lifo_release,
+    },
+    Case {
+        id: "RS.05",
+        classes: ALL,
+        requirement: "GetResource of an occupied resource or below the caller's priority returns E_OS_ACCESS",
+        run: get_access,
+    },
+    Case {
+        id: "RS.06",
+        classes: ALL,
+        requirement: "Resource services with an invalid resource return E_OS_ID",
+        run: invalid_resource,
+    },
+    Case {
+        id: "RS.07",
+        classes: ALL,
+        requirement: "TerminateTask while holding a resource returns E_OS_RESOURCE",
+        run: terminate_with_resource,
+    },
+];
+
+const B: usize = 1;
+
+const R: usize = 0;
+const R2: usize = 1;
+
+static RESOURCES: [ResourceConfig; 2] = [
+    ResourceConfig {
+        name: "R",
+        ceiling: 2,
+        internal: false,
+    },
+    ResourceConfig {
+        name: "R2",
+        ceiling: 2,
+        internal: false,
+    },
+];
+
+extern "C" fn b() {
+    trace("B");
+    TerminateTask();
+}
+
+fn ceiling_blocks_preemption() -> Result<(), String> {
+    extern "C" fn a() {
+        GetResource(R);
+        ActivateTask(B);
+        trace("A holds R");
+        ReleaseResource(R);
+        trace("A released R");
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a, 1)
+        },
+        task("B", b, 2),
+    ];
+    static CONFIG: OsConfig = OsConfig {
+        resources: &RESOURCES,
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&["A holds R", "B", "A released R"])
+}
+
+fn above_ceiling_preempts() -> Result<(), String> {
+    extern "C" fn a() {
+        GetResource(R);
+        ActivateTask(B);
+        trace("A holds R");
+        ReleaseResource(R);
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a, 1)
+        },
+        task("B", b, 3),
+    ];
+    static CONFIG: OsConfig = OsConfig {
+        resources: &RESOURCES,
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&["B", "A holds R"])
+}
+
+fn res_scheduler() -> Result<(), String> {
+    extern "C" fn a() {
+        GetResource(RES_SCHEDULER);
+        ActivateTask(B);
+        trace("A holds RES_SCHEDULER");
+        ReleaseResource(RES_SCHEDULER);
+        trace("A released RES_SCHEDULER");
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a, 1)
+        },
+        task("B", b, 9),
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["A holds RES_SCHEDULER", "B", "A released RES_SCHEDULER"])
+}
+
+fn lifo_release() -> Result<(), String> {
+    extern "C" fn a() {
+        trace(format!("{:?}", ReleaseResource(R)));
+        GetResource(R);
+        GetResource(R2);
+        trace(format!("{:?}", ReleaseResource(R)));
+        trace(format!("{:?}", ReleaseResource(R2)));
+        trace(format!("{:?}", ReleaseResource(R)));
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        ..task("A", a, 1)
+    }];
+    static CONFIG: OsConfig = OsConfig {
+        resources: &RESOURCES,
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&["EOsNofunc", "EOsNofunc", "EOk", "EOk"])
+}
+
+fn get_access() -> Result<(), String> {
+    extern "C" fn a() {
+        trace(format!("{:?}", GetResource(R)));
+        trace(format!("{:?}", GetResource(R)));
+        ReleaseResource(R);
+        TerminateTask();
+    }
+    extern "C" fn b() {
+        trace(format!("{:?}", GetResource(R)));
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a, 2)
+        },
+        TaskConfig {
+            autostart: true,
+            ..task("B", b, 3)
+        },
+    ];
+    static CONFIG: OsConfig = OsConfig {
+        resources: &RESOURCES,
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&["EOsAccess", "EOk", "EOsAccess"])
+}
+
+fn invalid_resource() -> Result<(), String> {
+    extern "C" fn a() {
+        trace(format!("{:?}", GetResource(5)));
+        trace(format!("{:?}", ReleaseResource(5)));
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        ..task("A", a, 1)
+    }];
+    static CONFIG: OsConfig = OsConfig {
+        resources: &RESOURCES,
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&["EOsId", "EOsId"])
+}
+
+fn terminate_with_resource() -> Result<(), String> {
+    extern "C" fn a() {
+        GetResource(R);
+        trace(format!("{:?}", TerminateTask()));
+        ReleaseResource(R);
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        ..task("A", a, 1)
+    }];
+    static CONFIG: OsConfig = OsConfig {
+        resources: &RESOURCES,
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&["EOsResource"])
+}
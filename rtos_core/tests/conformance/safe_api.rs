@@ -0,0 +1,130 @@
+//! Safe Rust API over the services.
+
+use rtos_core::{
+    OsConfig, ResourceConfig, TaskConfig,
+    os::{Alarm, Counter, Resource, Task, terminate_task},
+    sim::run_os,
+};
+
+use crate::{
+    ALL, Case,
+    common::{config, expect_trace, task, trace},
+};
+
+pub const CASES: &[Case] = &[
+    Case {
+        id: "RA.01",
+        classes: ALL,
+        requirement: "Typed handles return the status of the services as OsError",
+        run: typed_handles,
+    },
+    Case {
+        id: "RA.02",
+        classes: ALL,
+        requirement: "A ResourceGuard holds the ceiling priority until it is dropped",
+        run: resource_guard,
+    },
+];
+
+const B: Task = Task::new(1);
+
+fn typed_handles() -> Result<(), String> {
+    extern "C" fn a() {
+        trace(format!("current {:?}", Task::current().map(Task::id)));
+        trace(format!("{:?} {:?}", B.activate(), B.activate()));
+        trace(format!("{:?}", Task::new(7).activate()));
+        trace(format!("{:?}", B.state()));
+        let alarm = Alarm::new(0);
+        trace(format!("{:?}", alarm.cancel()));
+        trace(format!(
+            "{:?} {:?} {:?}",
+            alarm.set_relative(2, 0),
+            alarm.set_relative(2, 0),
+            alarm.remaining()
+        ));
+        trace(format!("{:?}", alarm.cancel()));
+        let mut previous = 0;
+        trace(format!(
+            "elapsed {}",
+            Counter::SYSTEM.elapsed(&mut previous)
+        ));
+        terminate_task();
+    }
+    extern "C" fn b() {
+        trace("B");
+        terminate_task();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a, 2)
+        },
+        task("B", b, 1),
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&[
+        "current Some(0)",
+        "Ok(()) Err(Limit)",
+        "Err(Id)",
+        "Ok(Ready)",
+        "Err(NoFunc)",
+        "Ok(()) Err(State) Ok(2)",
+        "Ok(())",
+        "elapsed 0",
+        "B",
+    ])
+}
+
+fn resource_guard() -> Result<(), String> {
+    const R: Resource = Resource::new(0);
+    extern "C" fn a() {
+        let guard = R.lock().unwrap();
+        B.activate().unwrap();
+        trace("A holds R");
+        drop(guard);
+        trace("A released R");
+        let result = R.with(|| {
+            B.activate().unwrap();
+            trace("A in R");
+        });
+        trace(format!("{result:?}"));
+        let scheduler = Resource::SCHEDULER.lock().unwrap();
+        B.activate().unwrap();
+        trace("A holds the scheduler");
+        trace(format!("{:?}", scheduler.release()));
+        terminate_task();
+    }
+    extern "C" fn b() {
+        trace("B");
+        terminate_task();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a, 1)
+        },
+        task("B", b, 2),
+    ];
+    static RESOURCES: [ResourceConfig; 1] = [ResourceConfig {
+        name: "R",
+        ceiling: 2,
+        internal: false,
+    }];
+    static CONFIG: OsConfig = OsConfig {
+        resources: &RESOURCES,
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&[
+        "A holds R",
+        "B",
+        "A released R",
+        "A in R",
+        "B",
+        "Ok(())",
+        "A holds the scheduler",
+        "B",
+        "Ok(())",
+    ])
+}
@@ -0,0 +1,277 @@
+//! Non-preemptive scheduling, internal resources and the `Schedule` service.
+
+use rtos_core::{
+    OsConfig, ResourceConfig, TaskConfig,
+    resources::{GetResource, ReleaseResource},
+    sim::{isr, run_os},
+    task::{ActivateTask, Schedule, SchedulePolicy, TerminateTask},
+};
+
+use crate::{
+    ALL, Case,
+    common::{config, expect_trace, task, trace},
+};
+
+pub const CASES: &[Case] = &[
+    Case {
+        id: "SC.01",
+        classes: ALL,
+        requirement: "A non-preemptable task is not preempted by a higher-priority task it activates",
+        run: non_preemptable_activation,
+    },
+    Case {
+        id: "SC.02",
+        classes: ALL,
+        requirement: "An activation from an ISR waits for Schedule in a non-preemptable task",
+        run: isr_activation_waits_for_schedule,
+    },
+    Case {
+        id: "SC.03",
+        classes: ALL,
+        requirement: "An activation from an ISR waits for TerminateTask in a non-preemptable task",
+        run: isr_activation_waits_for_terminate,
+    },
+    Case {
+        id: "SC.04",
+        classes: ALL,
+        requirement: "Schedule keeps the caller running if no higher-priority task is ready",
+        run: schedule_without_ready_task,
+    },
+    Case {
+        id: "SC.05",
+        classes: ALL,
+        requirement: "Tasks sharing an internal resource do not preempt each other",
+        run: internal_resource_group,
+    },
+    Case {
+        id: "SC.06",
+        classes: ALL,
+        requirement: "Schedule lets a higher-priority task of the same internal resource run",
+        run: schedule_releases_internal_resource,
+    },
+    Case {
+        id: "SC.07",
+        classes: ALL,
+        requirement: "Schedule returns E_OS_CALLEVEL from an ISR and E_OS_RESOURCE with a resource held",
+        run: schedule_errors,
+    },
+    Case {
+        id: "SC.08",
+        classes: ALL,
+        requirement: "GetResource and ReleaseResource on an internal resource return E_OS_ID",
+        run: internal_resource_not_accessible,
+    },
+];
+
+const B: usize = 1;
+const C: usize = 2;
+
+const I: usize = 0;
+const R: usize = 1;
+
+static RESOURCES: [ResourceConfig; 2] = [
+    ResourceConfig {
+        name: "I",
+        ceiling: 2,
+        internal: true,
+    },
+    ResourceConfig {
+        name: "R",
+        ceiling: 1,
+        internal: false,
+    },
+];
+
+extern "C" fn b() {
+    trace("B");
+    TerminateTask();
+}
+
+extern "C" fn c() {
+    trace("C");
+    TerminateTask();
+}
+
+fn non_preemptable_activation() -> Result<(), String> {
+    extern "C" fn a() {
+        trace("A1");
+        ActivateTask(B);
+        trace("A2");
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            schedule: SchedulePolicy::Non,
+            ..task("A", a, 1)
+        },
+        task("B", b, 2),
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["A1", "A2", "B"])
+}
+
+fn isr_activation_waits_for_schedule() -> Result<(), String> {
+    extern "C" fn a() {
+        isr(|| {
+            ActivateTask(B);
+            trace("ISR end");
+        });
+        trace("A before Schedule");
+        Schedule();
+        trace("A after Schedule");
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            schedule: SchedulePolicy::Non,
+            ..task("A", a, 1)
+        },
+        task("B", b, 2),
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["ISR end", "A before Schedule", "B", "A after Schedule"])
+}
+
+fn isr_activation_waits_for_terminate() -> Result<(), String> {
+    extern "C" fn a() {
+        isr(|| {
+            ActivateTask(B);
+            trace("ISR end");
+        });
+        trace("A");
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            schedule: SchedulePolicy::Non,
+            ..task("A", a, 1)
+        },
+        task("B", b, 2),
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["ISR end", "A", "B"])
+}
+
+fn schedule_without_ready_task() -> Result<(), String> {
+    extern "C" fn a() {
+        ActivateTask(B);
+        trace(format!("{:?}", Schedule()));
+        // The internal resource is still held
+        ActivateTask(C);
+        trace("A");
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 3] = [
+        TaskConfig {
+            autostart: true,
+            schedule: SchedulePolicy::Non,
+            ..task("A", a, 2)
+        },
+        task("B", b, 1),
+        task("C", c, 3),
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["EOk", "A", "C", "B"])
+}
+
+fn internal_resource_group() -> Result<(), String> {
+    extern "C" fn a() {
+        trace("A1");
+        ActivateTask(B);
+        ActivateTask(C);
+        trace("A2");
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 3] = [
+        TaskConfig {
+            autostart: true,
+            internal_resource: Some(I),
+            ..task("A", a, 1)
+        },
+        TaskConfig {
+            internal_resource: Some(I),
+            ..task("B", b, 2)
+        },
+        task("C", c, 3),
+    ];
+    static CONFIG: OsConfig = OsConfig {
+        resources: &RESOURCES,
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&["A1", "C", "A2", "B"])
+}
+
+fn schedule_releases_internal_resource() -> Result<(), String> {
+    extern "C" fn a() {
+        trace("A1");
+        ActivateTask(B);
+        trace("A2");
+        Schedule();
+        trace("A3");
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            internal_resource: Some(I),
+            ..task("A", a, 1)
+        },
+        TaskConfig {
+            internal_resource: Some(I),
+            ..task("B", b, 2)
+        },
+    ];
+    static CONFIG: OsConfig = OsConfig {
+        resources: &RESOURCES,
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&["A1", "A2", "B", "A3"])
+}
+
+fn schedule_errors() -> Result<(), String> {
+    extern "C" fn a() {
+        isr(|| trace(format!("{:?}", Schedule())));
+        GetResource(R);
+        trace(format!("{:?}", Schedule()));
+        ReleaseResource(R);
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        ..task("A", a, 1)
+    }];
+    static CONFIG: OsConfig = OsConfig {
+        resources: &RESOURCES,
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&["EOsCallevel", "EOsResource"])
+}
+
+fn internal_resource_not_accessible() -> Result<(), String> {
+    extern "C" fn a() {
+        trace(format!("{:?}", GetResource(I)));
+        trace(format!("{:?}", ReleaseResource(I)));
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        internal_resource: Some(I),
+        ..task("A", a, 1)
+    }];
+    static CONFIG: OsConfig = OsConfig {
+        resources: &RESOURCES,
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&["EOsId", "EOsId"])
+}
@@ -0,0 +1,74 @@
+//! Interactive shell.
+
+use core::ffi::{CStr, c_char, c_int};
+use std::sync::Mutex;
+
+use rtos_core::{
+    OsConfig, TaskConfig,
+    shell::{self, ShellRegisterCommand, ShellTask},
+    sim::run_os,
+    task::TerminateTask,
+};
+
+use crate::{
+    ALL, Case,
+    common::{config, expect_trace, task, trace},
+};
+
+pub const CASES: &[Case] = &[Case {
+    id: "SH.01",
+    classes: ALL,
+    requirement: "The shell echoes and erases input, then runs builtin and registered commands",
+    run: edit_and_dispatch,
+}];
+
+static OUTPUT: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+fn capture(bytes: &[u8]) {
+    OUTPUT.lock().unwrap().extend_from_slice(bytes);
+}
+
+fn edit_and_dispatch() -> Result<(), String> {
+    extern "C" fn echo(argc: c_int, argv: *const *const c_char) -> c_int {
+        let args = (0..argc as usize)
+            .map(|i| unsafe { CStr::from_ptr(*argv.add(i)) }.to_string_lossy())
+            .collect::<Vec<_>>();
+        trace(format!("echo {args:?}"));
+        3
+    }
+    extern "C" fn feeder() {
+        let status = unsafe { ShellRegisterCommand(c"echo".as_ptr(), c"echo".as_ptr(), echo) };
+        trace(format!("{status:?}"));
+        // Backspace and delete erase, the \n of \r\n ends no second line
+        shell::feed(b"activate Bx\x08\x7fA\r\necho  x y\r\nnope\n");
+        trace("Feeder");
+        TerminateTask();
+    }
+    extern "C" fn a() {
+        trace("A");
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 3] = [
+        task("Shell", ShellTask, 1),
+        TaskConfig {
+            autostart: true,
+            ..task("Feeder", feeder, 2)
+        },
+        task("A", a, 3),
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    shell::set_output(capture);
+    run_os(&CONFIG, 0);
+    let output = String::from_utf8(std::mem::take(&mut *OUTPUT.lock().unwrap()))
+        .map_err(|error| error.to_string())?;
+    trace(output);
+    expect_trace(&[
+        "EOk",
+        "Feeder",
+        "A",
+        r#"echo ["echo", "x", "y"]"#,
+        "activate Bx\x08 \x08\x08 \x08A\r\nE_OK\r\n> \
+         echo  x y\r\nexit status 3\r\n> \
+         nope\r\nunknown command nope, try help\r\n> ",
+    ])
+}
@@ -0,0 +1,156 @@
+//! SEGGER SystemView stream.
+
+use rtos_core::{
+    OsConfig, TaskConfig,
+    sim::{isr, run_os, systemview_stream},
+    task::{ActivateTask, TerminateTask},
+    trace,
+};
+
+use crate::{
+    ALL, Case,
+    common::{config, task},
+};
+
+pub const CASES: &[Case] = &[Case {
+    id: "SV.01",
+    classes: ALL,
+    requirement: "The SystemView stream shows the task list, a task switch and an ISR",
+    run: task_switch_and_isr,
+}];
+
+/// Reads a value written in 7-bit groups, least significant first.
+fn decode_u32(bytes: &mut &[u8]) -> Result<u32, String> {
+    let mut value = 0;
+    for shift in (0..35).step_by(7) {
+        let (&byte, rest) = bytes.split_first().ok_or("packet cut short")?;
+        *bytes = rest;
+        value |= u32::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err("value longer than 5 bytes".into())
+}
+
+fn decode_str(bytes: &mut &[u8]) -> Result<String, String> {
+    let (&len, rest) = bytes.split_first().ok_or("packet cut short")?;
+    let (text, rest) = rest
+        .split_at_checked(len.into())
+        .ok_or("string cut short")?;
+    *bytes = rest;
+    Ok(String::from_utf8_lossy(text).into_owned())
+}
+
+/// Decodes the stream from its synchronization bytes on, one line per
+/// packet: the event name and its payload, without the timestamp.
+fn decode(stream: &[u8]) -> Result<Vec<String>, String> {
+    let mut bytes = stream
+        .strip_prefix(&[0; 10])
+        .ok_or("stream does not start with 10 zero bytes")?;
+    let mut packets = Vec::new();
+    while !bytes.is_empty() {
+        let id = decode_u32(&mut bytes)?;
+        let packet = match id {
+            2 => format!("IsrEnter {}", decode_u32(&mut bytes)?),
+            3 => "IsrExit".into(),
+            4 => format!("TaskStartExec {}", decode_u32(&mut bytes)?),
+            5 => "TaskStopExec".into(),
+            6 => format!("TaskStartReady {}", decode_u32(&mut bytes)?),
+            7 => format!(
+                "TaskStopReady {} {}",
+                decode_u32(&mut bytes)?,
+                decode_u32(&mut bytes)?
+            ),
+            9 => format!(
+                "TaskInfo {} {} {}",
+                decode_u32(&mut bytes)?,
+                decode_u32(&mut bytes)?,
+                decode_str(&mut bytes)?
+            ),
+            10 => "TraceStart".into(),
+            12 => {
+                decode_u32(&mut bytes)?;
+                "SystimeCycles".into()
+            }
+            14 => format!("SysDesc {}", decode_str(&mut bytes)?),
+            17 => "Idle".into(),
+            21 => {
+                let task = decode_u32(&mut bytes)?;
+                decode_u32(&mut bytes)?;
+                format!("StackInfo {task} {}", decode_u32(&mut bytes)?)
+            }
+            24.. => {
+                let len = decode_u32(&mut bytes)? as usize;
+                let payload = bytes.get(..len).ok_or("payload cut short")?;
+                bytes = &bytes[len..];
+                if id == 24 {
+                    let mut payload = payload;
+                    format!(
+                        "Init {} {}",
+                        decode_u32(&mut payload)?,
+                        decode_u32(&mut payload)?
+                    )
+                } else {
+                    format!("Event {id}")
+                }
+            }
+            _ => return Err(format!("unexpected event {id}")),
+        };
+        decode_u32(&mut bytes)?;
+        packets.push(packet);
+    }
+    Ok(packets)
+}
+
+fn task_switch_and_isr() -> Result<(), String> {
+    extern "C" fn a() {
+        isr(|| {
+            trace::isr_enter(5);
+            ActivateTask(1);
+            trace::isr_exit(5);
+        });
+        TerminateTask();
+    }
+    extern "C" fn b() {
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a, 1)
+        },
+        task("B", b, 2),
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    // Drops what earlier cases wrote
+    systemview_stream();
+    run_os(&CONFIG, 0);
+    let packets = decode(&systemview_stream())?;
+    let expected = [
+        "TraceStart",
+        "Init 1000000 150000000",
+        "SysDesc O=rtos_core,D=RP2350,C=Cortex-M33",
+        "SystimeCycles",
+        "TaskInfo 1 1 A",
+        "StackInfo 1 0",
+        "TaskInfo 2 2 B",
+        "StackInfo 2 0",
+        "TaskStartReady 1",
+        "TaskStartExec 1",
+        "IsrEnter 21",
+        "TaskStartReady 2",
+        "IsrExit",
+        "TaskStopExec",
+        "TaskStartExec 2",
+        "TaskStopReady 2 0",
+        "TaskStartExec 1",
+        "TaskStopReady 1 0",
+        "Idle",
+    ];
+    if packets == expected {
+        Ok(())
+    } else {
+        Err(format!("expected {expected:?}, decoded {packets:?}"))
+    }
+}
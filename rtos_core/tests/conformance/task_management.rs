@@ -0,0 +1,487 @@
+//! Task management: activation, termination and scheduling order.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use rtos_core::{
+    OsConfig, TaskConfig,
+    alarms::{AlarmAction, AlarmAutostart, AlarmConfig, SetRelAlarm},
+    registry::{RegisteredTask, TASK_AUTOSTART, TaskDescriptorType},
+    resources::{GetResource, ReleaseResource},
+    sim::{isr, run_os, run_os_registered},
+    task::{
+        ActivateTask, ChainTask, GetTaskID, GetTaskState, INVALID_TASK, TaskEntry, TaskStateType,
+        TerminateTask,
+    },
+};
+
+use crate::{
+    ALL, Case, MULTIPLE,
+    common::{config, expect_trace, on_alarm, task, trace},
+};
+
+pub const CASES: &[Case] = &[
+    Case {
+        id: "TM.01",
+        classes: ALL,
+        requirement: "Activating a higher-priority task preempts the caller",
+        run: activate_preempts,
+    },
+    Case {
+        id: "TM.02",
+        classes: ALL,
+        requirement: "Activating a lower-priority task does not preempt the caller",
+        run: activate_no_preemption,
+    },
+    Case {
+        id: "TM.03",
+        classes: ALL,
+        requirement: "ActivateTask with an invalid task returns E_OS_ID",
+        run: activate_invalid,
+    },
+    Case {
+        id: "TM.04",
+        classes: ALL,
+        requirement: "Activating a task beyond its activation limit returns E_OS_LIMIT",
+        run: activate_limit,
+    },
+    Case {
+        id: "TM.05",
+        classes: MULTIPLE,
+        requirement: "Multiple activations are queued and executed one after the other",
+        run: multiple_activations,
+    },
+    Case {
+        id: "TM.06",
+        classes: MULTIPLE,
+        requirement: "Ready tasks of equal priority run in activation order",
+        run: fifo_order,
+    },
+    Case {
+        id: "TM.07",
+        classes: MULTIPLE,
+        requirement: "A preempted task resumes before the other tasks of its priority",
+        run: preempted_first,
+    },
+    Case {
+        id: "TM.08",
+        classes: ALL,
+        requirement: "A task returning from its entry function is terminated",
+        run: implicit_termination,
+    },
+    Case {
+        id: "TM.09",
+        classes: ALL,
+        requirement: "ChainTask terminates the caller and activates the succeeding task",
+        run: chain_task,
+    },
+    Case {
+        id: "TM.10",
+        classes: ALL,
+        requirement: "ChainTask to the caller itself activates it again",
+        run: chain_itself,
+    },
+    Case {
+        id: "TM.11",
+        classes: ALL,
+        requirement: "A failing ChainTask returns E_OS_ID, E_OS_LIMIT, E_OS_RESOURCE or E_OS_CALLEVEL and the caller continues",
+        run: chain_errors,
+    },
+    Case {
+        id: "TM.12",
+        classes: ALL,
+        requirement: "GetTaskID returns the running task, INVALID_TASK if there is none",
+        run: get_task_id,
+    },
+    Case {
+        id: "TM.13",
+        classes: ALL,
+        requirement: "GetTaskState returns RUNNING, READY or SUSPENDED, E_OS_ID for an invalid task",
+        run: get_task_state,
+    },
+    Case {
+        id: "TM.14",
+        classes: ALL,
+        requirement: "Registered tasks take the IDs after the configured ones, which their handles hold",
+        run: registered_after_configured,
+    },
+];
+
+const A: usize = 0;
+const B: usize = 1;
+const C: usize = 2;
+const D: usize = 3;
+
+extern "C" fn a_activates_b() {
+    trace("A1");
+    ActivateTask(B);
+    trace("A2");
+    TerminateTask();
+}
+
+extern "C" fn b() {
+    trace("B");
+    TerminateTask();
+}
+
+extern "C" fn c() {
+    trace("C");
+    TerminateTask();
+}
+
+fn state_of(task_id: usize) -> String {
+    let mut state = TaskStateType::Suspended;
+    let status = unsafe { GetTaskState(task_id, &mut state) };
+    format!("{status:?} {state:?}")
+}
+
+fn running_task() -> String {
+    let mut task_id = 0;
+    unsafe { GetTaskID(&mut task_id) };
+    if task_id == INVALID_TASK {
+        "INVALID_TASK".into()
+    } else {
+        task_id.to_string()
+    }
+}
+
+fn activate_preempts() -> Result<(), String> {
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a_activates_b, 1)
+        },
+        task("B", b, 2),
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["A1", "B", "A2"])
+}
+
+fn activate_no_preemption() -> Result<(), String> {
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a_activates_b, 2)
+        },
+        task("B", b, 1),
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["A1", "A2", "B"])
+}
+
+fn activate_invalid() -> Result<(), String> {
+    extern "C" fn a() {
+        trace(format!("{:?}", ActivateTask(7)));
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        ..task("A", a, 1)
+    }];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["EOsId"])
+}
+
+fn activate_limit() -> Result<(), String> {
+    extern "C" fn a() {
+        trace(format!("{:?}", ActivateTask(B)));
+        trace(format!("{:?}", ActivateTask(B)));
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a, 2)
+        },
+        task("B", b, 1),
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["EOk", "EOsLimit", "B"])
+}
+
+fn multiple_activations() -> Result<(), String> {
+    extern "C" fn a() {
+        for _ in 0..4 {
+            trace(format!("{:?}", ActivateTask(B)));
+        }
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a, 2)
+        },
+        TaskConfig {
+            activation: 3,
+            ..task("B", b, 1)
+        },
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["EOk", "EOk", "EOk", "EOsLimit", "B", "B", "B"])
+}
+
+fn fifo_order() -> Result<(), String> {
+    extern "C" fn a() {
+        trace("A");
+        ActivateTask(C);
+        ActivateTask(B);
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 3] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a, 1)
+        },
+        task("B", b, 1),
+        task("C", c, 1),
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["A", "C", "B"])
+}
+
+fn preempted_first() -> Result<(), String> {
+    extern "C" fn a() {
+        trace("A1");
+        ActivateTask(C);
+        ActivateTask(B);
+        trace("A2");
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 3] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a, 1)
+        },
+        task("B", b, 2),
+        task("C", c, 1),
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["A1", "B", "A2", "C"])
+}
+
+fn implicit_termination() -> Result<(), String> {
+    extern "C" fn a() {
+        trace("A");
+    }
+    extern "C" fn b() {
+        let status = ActivateTask(A);
+        trace(format!("B {status:?}"));
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a, 2)
+        },
+        TaskConfig {
+            autostart: true,
+            ..task("B", b, 1)
+        },
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["A", "A", "B EOk"])
+}
+
+fn chain_task() -> Result<(), String> {
+    extern "C" fn a() {
+        trace("A");
+        ChainTask(B);
+        trace("A after ChainTask");
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a, 2)
+        },
+        task("B", b, 1),
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&["A", "B"])
+}
+
+fn chain_itself() -> Result<(), String> {
+    static RUNS: AtomicU32 = AtomicU32::new(0);
+    extern "C" fn a() {
+        let run = RUNS.fetch_add(1, Ordering::SeqCst);
+        trace(format!("A {run}"));
+        if run < 2 {
+            ChainTask(A);
+        }
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 1] = [TaskConfig {
+        autostart: true,
+        ..task("A", a, 1)
+    }];
+    static CONFIG: OsConfig = config(&TASKS);
+    RUNS.store(0, Ordering::SeqCst);
+    run_os(&CONFIG, 0);
+    expect_trace(&["A 0", "A 1", "A 2"])
+}
+
+fn chain_errors() -> Result<(), String> {
+    extern "C" fn a() {
+        trace(format!("{:?}", ChainTask(7)));
+        ActivateTask(B);
+        trace(format!("{:?}", ChainTask(B)));
+        GetResource(0);
+        trace(format!("{:?}", ChainTask(C)));
+        ReleaseResource(0);
+        isr(|| trace(format!("{:?}", ChainTask(C))));
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 3] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a, 3)
+        },
+        task("B", b, 1),
+        task("C", c, 2),
+    ];
+    static RESOURCES: [rtos_core::ResourceConfig; 1] = [rtos_core::ResourceConfig {
+        name: "R",
+        ceiling: 3,
+        internal: false,
+    }];
+    static CONFIG: OsConfig = OsConfig {
+        resources: &RESOURCES,
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 0);
+    expect_trace(&["EOsId", "EOsLimit", "EOsResource", "EOsCallevel", "B"])
+}
+
+fn get_task_id() -> Result<(), String> {
+    extern "C" fn a() {
+        trace(format!("A: {}", running_task()));
+        ActivateTask(B);
+        isr(|| trace(format!("ISR: {}", running_task())));
+        SetRelAlarm(0, 1, 0);
+        TerminateTask();
+    }
+    extern "C" fn b() {
+        trace(format!("B: {}", running_task()));
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a, 1)
+        },
+        task("B", b, 2),
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    // The alarm expires while the system is idle
+    on_alarm(0, || trace(format!("idle: {}", running_task())));
+    run_os(&CONFIG, 1);
+    expect_trace(&["A: 0", "B: 1", "ISR: 0", "idle: INVALID_TASK"])
+}
+
+fn get_task_state() -> Result<(), String> {
+    extern "C" fn a() {
+        ActivateTask(C);
+        ActivateTask(B);
+        trace(format!("A {}", state_of(A)));
+        trace(format!("B {}", state_of(B)));
+        trace(format!("C {}", state_of(C)));
+        trace(format!("D {}", state_of(D)));
+        trace(state_of(9));
+        TerminateTask();
+    }
+    extern "C" fn d() {
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 4] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a, 3)
+        },
+        task("B", b, 2),
+        task("C", c, 1),
+        task("D", d, 0),
+    ];
+    static CONFIG: OsConfig = config(&TASKS);
+    run_os(&CONFIG, 0);
+    expect_trace(&[
+        "A EOk Running",
+        "B EOk Ready",
+        "C EOk Ready",
+        "D EOk Suspended",
+        "EOsId Suspended",
+        "B",
+        "C",
+    ])
+}
+
+fn registered_after_configured() -> Result<(), String> {
+    static R_ID: RegisteredTask = RegisteredTask::new();
+    static S_ID: RegisteredTask = RegisteredTask::new();
+    extern "C" fn a() {
+        trace(format!("A is task {}", running_task()));
+        TerminateTask();
+    }
+    extern "C" fn r() {
+        trace(format!(
+            "R is task {}, handle {}",
+            running_task(),
+            R_ID.task().id()
+        ));
+        ActivateTask(S_ID.task().id());
+        TerminateTask();
+    }
+    extern "C" fn s() {
+        trace(format!("S is task {}", running_task()));
+        TerminateTask();
+    }
+    fn running_task() -> usize {
+        let mut id = INVALID_TASK;
+        unsafe { GetTaskID(&mut id) };
+        id
+    }
+    static TASKS: [TaskConfig; 1] = [task("A", a, 1)];
+    static DESCRIPTORS: [TaskDescriptorType; 2] = [
+        TaskDescriptorType {
+            id: R_ID.as_ptr(),
+            name: c"R".as_ptr(),
+            entry: r as TaskEntry,
+            priority: 2,
+            activation: 1,
+            flags: TASK_AUTOSTART,
+            stack: core::ptr::null_mut(),
+            stack_size: 0,
+        },
+        TaskDescriptorType {
+            id: S_ID.as_ptr(),
+            name: c"S".as_ptr(),
+            entry: s as TaskEntry,
+            priority: 3,
+            activation: 1,
+            flags: 0,
+            stack: core::ptr::null_mut(),
+            stack_size: 0,
+        },
+    ];
+    static ACTIVATE_A: [AlarmConfig; 1] = [AlarmConfig {
+        name: "ActivateA",
+        counter: 0,
+        action: AlarmAction::ActivateTask(0),
+        autostart: Some(AlarmAutostart {
+            alarm_time: 2,
+            cycle_time: 0,
+        }),
+    }];
+    static CONFIG: OsConfig = OsConfig {
+        alarms: &ACTIVATE_A,
+        ..config(&TASKS)
+    };
+    run_os_registered(&CONFIG, &DESCRIPTORS, 5);
+    expect_trace(&["R is task 1, handle 1", "S is task 2", "A is task 0"])
+}
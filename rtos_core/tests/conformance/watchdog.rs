@@ -0,0 +1,92 @@
+//! Watchdog manager.
+
+use rtos_core::{
+    Hooks, OsConfig, TaskConfig,
+    alarms::{AlarmAction, AlarmAutostart, AlarmConfig, TickType},
+    reset::ResetReasonType,
+    sim::{now, run_os},
+    task::TerminateTask,
+    watchdog::{self, CheckpointReached, RegisterSupervisedEntity, WatchdogDriver},
+};
+
+use crate::{
+    ALL, Case,
+    common::{config, expect_trace, task, trace},
+};
+
+pub const CASES: &[Case] = &[Case {
+    id: "WD.01",
+    classes: ALL,
+    requirement: "A missed checkpoint is reported once, and the watchdog is not fed any more",
+    run: missed_checkpoint,
+}];
+
+fn missed_checkpoint() -> Result<(), String> {
+    fn feed() {
+        trace(format!("feed at {}", now()));
+    }
+    fn failed(entity: usize, owner: usize) {
+        trace(format!(
+            "entity {entity} of task {owner} failed at {}",
+            now()
+        ));
+    }
+    extern "C" fn startup(_: ResetReasonType) {
+        watchdog::init(WatchdogDriver {
+            cycle: 2,
+            feed,
+            failed,
+        });
+    }
+    // Registers an entity expecting one indication per cycle
+    extern "C" fn a() {
+        let mut entity = usize::MAX;
+        let status = unsafe { RegisterSupervisedEntity(1, 1, &mut entity) };
+        trace(format!("{status:?} entity {entity}"));
+        TerminateTask();
+    }
+    // Reaches the checkpoint in the first supervised cycle only
+    extern "C" fn b() {
+        trace(format!("B at {}", now()));
+        CheckpointReached(0);
+        TerminateTask();
+    }
+    static TASKS: [TaskConfig; 2] = [
+        TaskConfig {
+            autostart: true,
+            ..task("A", a, 1)
+        },
+        task("B", b, 1),
+    ];
+    static ALARMS: [AlarmConfig; 1] = [AlarmConfig {
+        name: "ActivateB",
+        counter: 0,
+        action: AlarmAction::ActivateTask(1),
+        autostart: Some(AlarmAutostart {
+            alarm_time: 3,
+            cycle_time: 0,
+        }),
+    }];
+    static CONFIG: OsConfig = OsConfig {
+        alarms: &ALARMS,
+        hooks: Hooks {
+            startup: Some(startup),
+            ..Hooks::NONE
+        },
+        ..config(&TASKS)
+    };
+    run_os(&CONFIG, 12);
+    // The driver outlives the run, the later cases run without supervision
+    watchdog::init(WatchdogDriver {
+        cycle: TickType::MAX,
+        feed: || {},
+        failed: |_, _| {},
+    });
+    expect_trace(&[
+        "EOk entity 0",
+        "feed at 2",
+        "B at 3",
+        "feed at 4",
+        "entity 0 of task 0 failed at 6",
+    ])
+}
@@ -0,0 +1,14 @@
+//! Compile-time checks of `rtos_config!` and `#[task]`: configurations
+//! that build, and one configuration per diagnostic that does not.
+//!
+//! The cases build in every conformance class: none of them uses events
+//! or trips the class checks, and none checks `MIN_STACK_SIZE`, 0 on the
+//! host. The expected diagnostics are in the `.stderr` files next to the
+//! cases; `TRYBUILD=overwrite` regenerates them.
+
+#[test]
+fn rtos_config() {
+    let cases = trybuild::TestCases::new();
+    cases.pass("tests/rtos_config/pass/*.rs");
+    cases.compile_fail("tests/rtos_config/fail/*.rs");
+}
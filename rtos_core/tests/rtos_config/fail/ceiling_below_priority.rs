@@ -0,0 +1,10 @@
+use rtos_core::os::rtos_config;
+
+extern "C" fn a() {}
+
+rtos_config! {
+    tasks { A { entry: a, priority: 3, stack: 1024, resources: [Bus] } }
+    resources { Bus { ceiling: 2 } }
+}
+
+fn main() {}
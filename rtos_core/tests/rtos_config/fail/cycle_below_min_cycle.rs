@@ -0,0 +1,13 @@
+use rtos_core::os::rtos_config;
+
+extern "C" fn a() {}
+
+rtos_config! {
+    tasks { A { entry: a, priority: 1, stack: 1024 } }
+    counters { SystemCounter { max: 100, ticks_per_base: 1, min_cycle: 10 } }
+    alarms {
+        Wake { counter: SystemCounter, action: activate_task(A), alarm_time: 1, cycle_time: 5 },
+    }
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use rtos_core::os::rtos_config;
+
+extern "C" fn a() {}
+
+rtos_config! {
+    tasks { A { entry: a, priority: 1, stack: 1024 } }
+    counters { SystemCounter { max: 100, ticks_per_base: 1 } }
+    alarms { Wake { counter: SystemCounter, action: activate_task(A), cycle_time: 10 } }
+}
+
+fn main() {}
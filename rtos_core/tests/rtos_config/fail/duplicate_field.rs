@@ -0,0 +1,9 @@
+use rtos_core::os::rtos_config;
+
+extern "C" fn a() {}
+
+rtos_config! {
+    tasks { A { entry: a, priority: 1, stack: 1024, priority: 2 } }
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use rtos_core::os::rtos_config;
+
+extern "C" fn a() {}
+
+rtos_config! {
+    tasks { A { entry: a, priority: 1, stack: 1024 } }
+    tasks { B { entry: a, priority: 2, stack: 1024 } }
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use rtos_core::os::rtos_config;
+
+extern "C" fn a() {}
+
+rtos_config! {
+    tasks {
+        A { entry: a, priority: 1, stack: 1024, events: [Ready] },
+        B { entry: a, priority: 2, stack: 1024, events: [Done] },
+    }
+    counters { SystemCounter { max: 10, ticks_per_base: 1 } }
+    alarms { Wake { counter: SystemCounter, action: set_event(A, Done) } }
+    events { Ready, Done }
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use rtos_core::os::rtos_config;
+
+extern "C" fn a() {}
+
+rtos_config! {
+    tasks { A { entry: a, priority: 1, stack: 1024, resources: [Group] } }
+    resources { Group { internal: true } }
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use rtos_core::os::rtos_config;
+
+extern "C" fn a() {}
+
+rtos_config! {
+    counters { SystemCounter { max: 10, ticks_per_base: 1 } }
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use rtos_core::os::rtos_config;
+
+extern "C" fn a() {}
+
+rtos_config! {
+    tasks {
+        SensorTask { entry: a, priority: 1, stack: 1024 },
+        Sensor_Task { entry: a, priority: 2, stack: 1024 },
+    }
+}
+
+fn main() {}
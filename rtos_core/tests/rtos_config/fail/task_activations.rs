@@ -0,0 +1,6 @@
+use rtos_core::os::task;
+
+#[task(priority = 1, stack = 1024, activation = 9)]
+fn sampler() {}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use rtos_core::os::task;
+
+#[task]
+fn sampler(channel: u8) {
+    let _ = channel;
+}
+
+fn main() {}
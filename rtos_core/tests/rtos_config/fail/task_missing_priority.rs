@@ -0,0 +1,6 @@
+use rtos_core::os::task;
+
+#[task(stack = 1024)]
+fn sampler() {}
+
+fn main() {}
@@ -0,0 +1,27 @@
+use rtos_core::os::rtos_config;
+
+extern "C" fn a() {}
+
+rtos_config! {
+    tasks {
+        T0 { entry: a, priority: 0, stack: 1024 },
+        T1 { entry: a, priority: 1, stack: 1024 },
+        T2 { entry: a, priority: 2, stack: 1024 },
+        T3 { entry: a, priority: 3, stack: 1024 },
+        T4 { entry: a, priority: 4, stack: 1024 },
+        T5 { entry: a, priority: 5, stack: 1024 },
+        T6 { entry: a, priority: 6, stack: 1024 },
+        T7 { entry: a, priority: 7, stack: 1024 },
+        T8 { entry: a, priority: 8, stack: 1024 },
+        T9 { entry: a, priority: 9, stack: 1024 },
+        T10 { entry: a, priority: 10, stack: 1024 },
+        T11 { entry: a, priority: 11, stack: 1024 },
+        T12 { entry: a, priority: 12, stack: 1024 },
+        T13 { entry: a, priority: 13, stack: 1024 },
+        T14 { entry: a, priority: 14, stack: 1024 },
+        T15 { entry: a, priority: 15, stack: 1024 },
+        T16 { entry: a, priority: 15, stack: 1024 },
+    }
+}
+
+fn main() {}
@@ -0,0 +1,71 @@
+use rtos_core::{
+    Hooks,
+    os::{rtos_config, task},
+    task::SchedulePolicy,
+};
+
+#[task]
+fn sampler() {}
+
+#[task]
+fn logger() -> ! {
+    loop {}
+}
+
+extern "C" fn tick() {}
+
+const HOOKS: Hooks = Hooks::NONE;
+
+rtos_config! {
+    os { hooks: HOOKS }
+    tasks {
+        Sampler { entry: sampler, priority: 3, stack: 1024, resources: [Bus], deadline: 5 },
+        Logger {
+            entry: logger,
+            priority: 1,
+            stack: 2048,
+            autostart: true,
+            schedule: Non,
+            internal_resource: Group,
+        },
+    }
+    counters {
+        SystemCounter { max: 1000, ticks_per_base: 1, min_cycle: 10 },
+    }
+    alarms {
+        SampleAlarm {
+            counter: SystemCounter,
+            action: activate_task(Sampler),
+            alarm_time: 10,
+            cycle_time: 100,
+        },
+        TickAlarm { counter: SystemCounter, action: callback(tick) },
+    }
+    resources { Bus, Group { internal: true, ceiling: 2 } }
+}
+
+fn main() {
+    assert_eq!((SAMPLER.id(), LOGGER.id()), (0, 1));
+    assert_eq!((SAMPLE_ALARM.id(), TICK_ALARM.id()), (0, 1));
+    assert_eq!((BUS.id(), GROUP.id()), (0, 1));
+
+    let [sampler, logger] = CONFIG.tasks else {
+        panic!("expected two tasks");
+    };
+    assert_eq!((sampler.name, sampler.priority), ("Sampler", 3));
+    assert_eq!(sampler.deadline, Some(5));
+    assert_eq!(sampler.stack.size, 1024);
+    assert!(logger.autostart && !logger.extended);
+    assert!(matches!(logger.schedule, SchedulePolicy::Non));
+    assert_eq!(logger.internal_resource, Some(GROUP.id()));
+
+    // The ceiling of Bus is the priority of Sampler, the only task using it
+    assert_eq!(CONFIG.resources[BUS.id()].ceiling, 3);
+    assert_eq!(CONFIG.resources[GROUP.id()].ceiling, 2);
+    assert!(CONFIG.resources[GROUP.id()].internal);
+
+    assert_eq!(CONFIG.counters[0].base.mincycle, 10);
+    let autostart = CONFIG.alarms[SAMPLE_ALARM.id()].autostart.unwrap();
+    assert_eq!((autostart.alarm_time, autostart.cycle_time), (10, 100));
+    assert!(CONFIG.alarms[TICK_ALARM.id()].autostart.is_none());
+}
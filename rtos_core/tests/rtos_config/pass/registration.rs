@@ -0,0 +1,8 @@
+use rtos_core::os::{Task, task};
+
+#[task(priority = 2, stack = 1024, autostart = true, schedule = Non)]
+fn blink() {}
+
+fn main() {
+    let _: Task = BLINK.task();
+}
@@ -0,0 +1,996 @@
+//! `rtos_config!`: parsing, checks and generation of the kernel tables.
+
+use std::ffi::CString;
+
+use proc_macro2::{Literal, Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{
+    Error, Expr, ExprLit, Ident, Lit, Result, Token, braced,
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    token,
+};
+
+/// Width of `EventMaskType`.
+const MAX_EVENTS: usize = 32;
+
+/// `key: value`
+struct Field {
+    key: Ident,
+    value: Expr,
+}
+
+impl Parse for Field {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let key = input.parse()?;
+        input.parse::<Token![:]>()?;
+        let value = input.parse()?;
+        Ok(Field { key, value })
+    }
+}
+
+fn parse_fields(input: ParseStream) -> Result<Vec<Field>> {
+    let content;
+    braced!(content in input);
+    Ok(Punctuated::<Field, Token![,]>::parse_terminated(&content)?
+        .into_iter()
+        .collect())
+}
+
+/// `Name { key: value, .. }`, or just `Name` if every field is optional.
+struct Object {
+    name: Ident,
+    fields: Vec<Field>,
+}
+
+impl Parse for Object {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let name = input.parse()?;
+        let fields = if input.peek(token::Brace) {
+            parse_fields(input)?
+        } else {
+            Vec::new()
+        };
+        Ok(Object { name, fields })
+    }
+}
+
+/// The sections of the macro input, each one optional but `tasks`.
+pub struct Input {
+    os: Vec<Field>,
+    tasks: Option<Vec<Object>>,
+    counters: Vec<Object>,
+    alarms: Vec<Object>,
+    resources: Vec<Object>,
+    events: Vec<Object>,
+}
+
+impl Parse for Input {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut sections = Input {
+            os: Vec::new(),
+            tasks: None,
+            counters: Vec::new(),
+            alarms: Vec::new(),
+            resources: Vec::new(),
+            events: Vec::new(),
+        };
+        let mut seen: Vec<String> = Vec::new();
+        while !input.is_empty() {
+            let kind: Ident = input.parse()?;
+            let name = kind.to_string();
+            if seen.contains(&name) {
+                return Err(Error::new(
+                    kind.span(),
+                    format!("duplicate `{name}` section"),
+                ));
+            }
+            seen.push(name.clone());
+            if name == "os" {
+                sections.os = parse_fields(input)?;
+                continue;
+            }
+            let content;
+            braced!(content in input);
+            let objects: Vec<Object> = Punctuated::<Object, Token![,]>::parse_terminated(&content)?
+                .into_iter()
+                .collect();
+            match name.as_str() {
+                "tasks" => sections.tasks = Some(objects),
+                "counters" => sections.counters = objects,
+                "alarms" => sections.alarms = objects,
+                "resources" => sections.resources = objects,
+                "events" => sections.events = objects,
+                _ => {
+                    return Err(Error::new(
+                        kind.span(),
+                        "expected `os`, `tasks`, `counters`, `alarms`, `resources` or `events`",
+                    ));
+                }
+            }
+        }
+        Ok(sections)
+    }
+}
+
+/// Fields of one object, checked against the keys its kind allows.
+struct Fields<'a> {
+    kind: &'static str,
+    name: &'a Ident,
+    fields: &'a [Field],
+}
+
+impl<'a> Fields<'a> {
+    fn new(
+        kind: &'static str,
+        name: &'a Ident,
+        fields: &'a [Field],
+        allowed: &[&str],
+    ) -> Result<Self> {
+        for (i, field) in fields.iter().enumerate() {
+            let key = field.key.to_string();
+            if !allowed.contains(&key.as_str()) {
+                return Err(Error::new(
+                    field.key.span(),
+                    format!("unknown {kind} field `{key}`, expected one of {allowed:?}"),
+                ));
+            }
+            if fields[..i].iter().any(|other| other.key == field.key) {
+                return Err(Error::new(
+                    field.key.span(),
+                    format!("duplicate field `{key}`"),
+                ));
+            }
+        }
+        Ok(Fields { kind, name, fields })
+    }
+
+    fn get(&self, key: &str) -> Option<&'a Expr> {
+        self.fields
+            .iter()
+            .find(|field| field.key == key)
+            .map(|field| &field.value)
+    }
+
+    fn required(&self, key: &str) -> Result<&'a Expr> {
+        self.get(key).ok_or_else(|| {
+            Error::new(
+                self.name.span(),
+                format!("{} `{}` needs a `{key}`", self.kind, self.name),
+            )
+        })
+    }
+}
+
+pub(crate) fn int(expr: &Expr, min: i64, max: i64) -> Result<i64> {
+    let value = match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(lit), ..
+        }) => lit.base10_parse::<i64>()?,
+        _ => return Err(Error::new(expr.span(), "expected an integer literal")),
+    };
+    if value < min || value > max {
+        return Err(Error::new(
+            expr.span(),
+            format!("{value} is out of range {min}..={max}"),
+        ));
+    }
+    Ok(value)
+}
+
+pub(crate) fn boolean(expr: &Expr) -> Result<bool> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Bool(lit),
+            ..
+        }) => Ok(lit.value),
+        _ => Err(Error::new(expr.span(), "expected `true` or `false`")),
+    }
+}
+
+pub(crate) fn ident(expr: &Expr) -> Result<&Ident> {
+    match expr {
+        Expr::Path(path) if path.qself.is_none() => path
+            .path
+            .get_ident()
+            .ok_or_else(|| Error::new(expr.span(), "expected a name")),
+        _ => Err(Error::new(expr.span(), "expected a name")),
+    }
+}
+
+fn idents(expr: &Expr) -> Result<Vec<&Ident>> {
+    match expr {
+        Expr::Array(array) => array.elems.iter().map(ident).collect(),
+        _ => Err(Error::new(
+            expr.span(),
+            "expected a list of names, `[A, B]`",
+        )),
+    }
+}
+
+/// Index of the object called `name`, an error naming the missing `kind`
+/// otherwise.
+fn find(objects: &[Object], kind: &str, name: &Ident) -> Result<usize> {
+    objects
+        .iter()
+        .position(|object| object.name == *name)
+        .ok_or_else(|| Error::new(name.span(), format!("no {kind} `{name}` is configured")))
+}
+
+/// `SensorTask` becomes `SENSOR_TASK`, the name of its handle.
+fn constant_name(name: &Ident) -> Ident {
+    let mut constant = String::new();
+    let mut after_lower = false;
+    for c in name.to_string().chars() {
+        if c.is_uppercase() && after_lower {
+            constant.push('_');
+        }
+        after_lower = c.is_lowercase() || c.is_ascii_digit();
+        constant.extend(c.to_uppercase());
+    }
+    Ident::new(&constant, name.span())
+}
+
+struct Counter {
+    max: i64,
+    ticks_per_base: i64,
+    min_cycle: i64,
+}
+
+struct Task<'a> {
+    entry: &'a Expr,
+    priority: i64,
+    activation: i64,
+    autostart: bool,
+    schedule: Ident,
+    stack: i64,
+    resources: Vec<usize>,
+    internal_resource: Option<usize>,
+    events: Vec<usize>,
+    deadline: Option<i64>,
+}
+
+struct Resource<'a> {
+    internal: bool,
+    ceiling: Option<(i64, &'a Expr)>,
+}
+
+enum Action<'a> {
+    ActivateTask(usize),
+    SetEvent(usize, usize),
+    Callback(&'a Expr),
+}
+
+struct Alarm<'a> {
+    counter: usize,
+    action: Action<'a>,
+    autostart: Option<(i64, i64)>,
+}
+
+/// Checks `input` and expands it into the configuration tables and the
+/// handles of the objects.
+pub fn expand(input: Input) -> Result<TokenStream> {
+    let Some(task_objects) = &input.tasks else {
+        return Err(Error::new(
+            Span::call_site(),
+            "the configuration needs a `tasks` section",
+        ));
+    };
+    check_names(&input)?;
+
+    let counters = input
+        .counters
+        .iter()
+        .map(|object| {
+            let fields = Fields::new(
+                "counter",
+                &object.name,
+                &object.fields,
+                &["max", "ticks_per_base", "min_cycle"],
+            )?;
+            let max = int(fields.required("max")?, 1, i32::MAX.into())?;
+            let ticks_per_base = int(fields.required("ticks_per_base")?, 1, i32::MAX.into())?;
+            let min_cycle = match fields.get("min_cycle") {
+                Some(expr) => int(expr, 1, max)?,
+                None => 1,
+            };
+            Ok(Counter {
+                max,
+                ticks_per_base,
+                min_cycle,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if let Some(event) = input.events.get(MAX_EVENTS) {
+        return Err(Error::new(
+            event.name.span(),
+            format!("at most {MAX_EVENTS} events fit an event mask"),
+        ));
+    }
+    for event in &input.events {
+        Fields::new("event", &event.name, &event.fields, &[])?;
+    }
+
+    let resources = input
+        .resources
+        .iter()
+        .map(|object| {
+            let fields = Fields::new(
+                "resource",
+                &object.name,
+                &object.fields,
+                &["internal", "ceiling"],
+            )?;
+            Ok(Resource {
+                internal: fields
+                    .get("internal")
+                    .map(boolean)
+                    .transpose()?
+                    .unwrap_or(false),
+                ceiling: match fields.get("ceiling") {
+                    Some(expr) => Some((int(expr, 0, u8::MAX.into())?, expr)),
+                    None => None,
+                },
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let tasks = task_objects
+        .iter()
+        .map(|object| task(&input, &resources, object))
+        .collect::<Result<Vec<_>>>()?;
+
+    let ceilings = ceilings(&input, &resources, &tasks)?;
+
+    let alarms = input
+        .alarms
+        .iter()
+        .map(|object| alarm(&input, &counters, &tasks, object))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(generate(
+        &input,
+        task_objects,
+        &tasks,
+        &counters,
+        &resources,
+        &ceilings,
+        &alarms,
+    ))
+}
+
+/// Every object gets a handle named after it, so names are unique across
+/// the sections.
+fn check_names(input: &Input) -> Result<()> {
+    let objects = input
+        .tasks
+        .iter()
+        .flatten()
+        .chain(&input.counters)
+        .chain(&input.alarms)
+        .chain(&input.resources)
+        .chain(&input.events)
+        .collect::<Vec<_>>();
+    for (i, object) in objects.iter().enumerate() {
+        let constant = constant_name(&object.name);
+        if let Some(other) = objects[..i]
+            .iter()
+            .find(|other| constant_name(&other.name) == constant)
+        {
+            return Err(Error::new(
+                object.name.span(),
+                format!("`{}` clashes with `{}`", object.name, other.name),
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn task<'a>(input: &Input, resources: &[Resource], object: &'a Object) -> Result<Task<'a>> {
+    let fields = Fields::new(
+        "task",
+        &object.name,
+        &object.fields,
+        &[
+            "entry",
+            "priority",
+            "activation",
+            "autostart",
+            "schedule",
+            "stack",
+            "resources",
+            "internal_resource",
+            "events",
+            "deadline",
+        ],
+    )?;
+    let activation = match fields.get("activation") {
+        Some(expr) => int(expr, 1, u8::MAX.into())?,
+        None => 1,
+    };
+    let schedule = match fields.get("schedule") {
+        Some(expr) => {
+            let schedule = ident(expr)?;
+            if schedule != "Full" && schedule != "Non" {
+                return Err(Error::new(schedule.span(), "expected `Full` or `Non`"));
+            }
+            schedule.clone()
+        }
+        None => format_ident!("Full"),
+    };
+    let task_resources = match fields.get("resources") {
+        Some(expr) => idents(expr)?
+            .into_iter()
+            .map(|name| {
+                let id = find(&input.resources, "resource", name)?;
+                if resources[id].internal {
+                    return Err(Error::new(
+                        name.span(),
+                        format!("`{name}` is internal, assign it with `internal_resource`"),
+                    ));
+                }
+                Ok(id)
+            })
+            .collect::<Result<Vec<_>>>()?,
+        None => Vec::new(),
+    };
+    let internal_resource = match fields.get("internal_resource") {
+        Some(expr) => {
+            let name = ident(expr)?;
+            let id = find(&input.resources, "resource", name)?;
+            if !resources[id].internal {
+                return Err(Error::new(
+                    name.span(),
+                    format!("`{name}` is not declared `internal: true`"),
+                ));
+            }
+            Some(id)
+        }
+        None => None,
+    };
+    let events = match fields.get("events") {
+        Some(expr) => idents(expr)?
+            .into_iter()
+            .map(|name| find(&input.events, "event", name))
+            .collect::<Result<Vec<_>>>()?,
+        None => Vec::new(),
+    };
+    if !events.is_empty() && activation > 1 {
+        return Err(Error::new(
+            fields.required("activation")?.span(),
+            "extended tasks cannot have multiple activations",
+        ));
+    }
+    Ok(Task {
+        entry: fields.required("entry")?,
+        priority: int(fields.required("priority")?, 0, u8::MAX.into())?,
+        activation,
+        autostart: fields
+            .get("autostart")
+            .map(boolean)
+            .transpose()?
+            .unwrap_or(false),
+        schedule,
+        stack: int(fields.required("stack")?, 1, u32::MAX.into())?,
+        resources: task_resources,
+        internal_resource,
+        events,
+        deadline: fields
+            .get("deadline")
+            .map(|expr| int(expr, 1, i32::MAX.into()))
+            .transpose()?,
+    })
+}
+
+/// Ceiling of every resource: the highest priority of the tasks using it,
+/// unless a higher one is given.
+fn ceilings(input: &Input, resources: &[Resource], tasks: &[Task]) -> Result<Vec<i64>> {
+    let task_objects = input.tasks.as_deref().unwrap_or_default();
+    resources
+        .iter()
+        .zip(&input.resources)
+        .enumerate()
+        .map(|(id, (resource, object))| {
+            let users = tasks.iter().zip(task_objects).filter(|(task, _)| {
+                task.internal_resource == Some(id) || task.resources.contains(&id)
+            });
+            let highest = users.max_by_key(|(task, _)| task.priority);
+            match (resource.ceiling, highest) {
+                (Some((ceiling, expr)), Some((task, task_object))) if ceiling < task.priority => {
+                    Err(Error::new(
+                        expr.span(),
+                        format!(
+                            "ceiling {ceiling} of `{}` is below the priority {} of task `{}`",
+                            object.name, task.priority, task_object.name
+                        ),
+                    ))
+                }
+                (Some((ceiling, _)), _) => Ok(ceiling),
+                (None, Some((task, _))) => Ok(task.priority),
+                (None, None) => Err(Error::new(
+                    object.name.span(),
+                    format!("no task uses `{}`, give it a `ceiling`", object.name),
+                )),
+            }
+        })
+        .collect()
+}
+
+fn alarm<'a>(
+    input: &Input,
+    counters: &[Counter],
+    tasks: &[Task],
+    object: &'a Object,
+) -> Result<Alarm<'a>> {
+    let fields = Fields::new(
+        "alarm",
+        &object.name,
+        &object.fields,
+        &["counter", "action", "alarm_time", "cycle_time"],
+    )?;
+    let counter = find(
+        &input.counters,
+        "counter",
+        ident(fields.required("counter")?)?,
+    )?;
+    let action = action(input, tasks, fields.required("action")?)?;
+    let base = &counters[counter];
+    let autostart = match (fields.get("alarm_time"), fields.get("cycle_time")) {
+        (Some(alarm_time), cycle_time) => {
+            let alarm_time = int(alarm_time, 1, base.max)?;
+            let cycle_time = match cycle_time {
+                Some(expr) => {
+                    let cycle_time = int(expr, 0, base.max)?;
+                    if cycle_time != 0 && cycle_time < base.min_cycle {
+                        return Err(Error::new(
+                            expr.span(),
+                            format!(
+                                "cycle {cycle_time} is below the `min_cycle` {} of counter `{}`",
+                                base.min_cycle, input.counters[counter].name
+                            ),
+                        ));
+                    }
+                    cycle_time
+                }
+                None => 0,
+            };
+            Some((alarm_time, cycle_time))
+        }
+        (None, Some(cycle_time)) => {
+            return Err(Error::new(
+                cycle_time.span(),
+                "a `cycle_time` needs an `alarm_time`",
+            ));
+        }
+        (None, None) => None,
+    };
+    Ok(Alarm {
+        counter,
+        action,
+        autostart,
+    })
+}
+
+/// `activate_task(Task)`, `set_event(Task, Event)` or `callback(function)`.
+fn action<'a>(input: &Input, tasks: &[Task], expr: &'a Expr) -> Result<Action<'a>> {
+    let expected = || {
+        Error::new(
+            expr.span(),
+            "expected `activate_task(Task)`, `set_event(Task, Event)` or `callback(function)`",
+        )
+    };
+    let Expr::Call(call) = expr else {
+        return Err(expected());
+    };
+    let args: Vec<&Expr> = call.args.iter().collect();
+    let task_objects = input.tasks.as_deref().unwrap_or_default();
+    match (ident(&call.func)?.to_string().as_str(), args.as_slice()) {
+        ("activate_task", [task]) => Ok(Action::ActivateTask(find(
+            task_objects,
+            "task",
+            ident(task)?,
+        )?)),
+        ("set_event", [task, event]) => {
+            let task_name = ident(task)?;
+            let event_name = ident(event)?;
+            let task = find(task_objects, "task", task_name)?;
+            let event = find(&input.events, "event", event_name)?;
+            if !tasks[task].events.contains(&event) {
+                return Err(Error::new(
+                    event_name.span(),
+                    format!("task `{task_name}` does not list `{event_name}` in its `events`"),
+                ));
+            }
+            Ok(Action::SetEvent(task, event))
+        }
+        ("callback", [callback]) => Ok(Action::Callback(callback)),
+        _ => Err(expected()),
+    }
+}
+
+fn generate(
+    input: &Input,
+    task_objects: &[Object],
+    tasks: &[Task],
+    counters: &[Counter],
+    resources: &[Resource],
+    ceilings: &[i64],
+    alarms: &[Alarm],
+) -> TokenStream {
+    let task_configs = tasks.iter().zip(task_objects).map(|(task, object)| {
+        let name = object.name.to_string();
+        let entry = task.entry;
+        let priority = task.priority as u8;
+        let activation = task.activation as u8;
+        let autostart = task.autostart;
+        let extended = !task.events.is_empty();
+        let schedule = &task.schedule;
+        let internal_resource = match task.internal_resource {
+            Some(id) => quote!(::core::option::Option::Some(#id)),
+            None => quote!(::core::option::Option::None),
+        };
+        let deadline = match task.deadline {
+            Some(deadline) => {
+                let deadline = deadline as i32;
+                quote!(::core::option::Option::Some(#deadline))
+            }
+            None => quote!(::core::option::Option::None),
+        };
+        let stack = task.stack as usize;
+        quote! {
+            ::rtos_core::TaskConfig {
+                name: #name,
+                entry: #entry as ::rtos_core::task::TaskEntry,
+                priority: #priority,
+                activation: #activation,
+                autostart: #autostart,
+                extended: #extended,
+                schedule: ::rtos_core::task::SchedulePolicy::#schedule,
+                internal_resource: #internal_resource,
+                deadline: #deadline,
+                stack: {
+                    static STACK: ::rtos_core::task::Stack<#stack> =
+                        ::rtos_core::task::Stack::new();
+                    STACK.region()
+                },
+            }
+        }
+    });
+
+    let resource_configs = resources.iter().zip(&input.resources).zip(ceilings).map(
+        |((resource, object), ceiling)| {
+            let name = object.name.to_string();
+            let ceiling = *ceiling as u8;
+            let internal = resource.internal;
+            quote! {
+                ::rtos_core::ResourceConfig {
+                    name: #name,
+                    ceiling: #ceiling,
+                    internal: #internal,
+                }
+            }
+        },
+    );
+
+    let counter_configs = counters
+        .iter()
+        .zip(&input.counters)
+        .map(|(counter, object)| {
+            let name = object.name.to_string();
+            let max = counter.max as i32;
+            let ticks_per_base = counter.ticks_per_base as i32;
+            let min_cycle = counter.min_cycle as i32;
+            quote! {
+                ::rtos_core::alarms::CounterConfig {
+                    name: #name,
+                    base: ::rtos_core::alarms::AlarmBaseType {
+                        maxallowedvalue: #max,
+                        ticksperbase: #ticks_per_base,
+                        mincycle: #min_cycle,
+                    },
+                }
+            }
+        });
+
+    let alarm_configs = alarms.iter().zip(&input.alarms).map(|(alarm, object)| {
+        let name = object.name.to_string();
+        let counter = alarm.counter;
+        let action = match alarm.action {
+            Action::ActivateTask(task) => {
+                quote!(::rtos_core::alarms::AlarmAction::ActivateTask(#task))
+            }
+            Action::SetEvent(task, event) => {
+                let mask = 1u32 << event;
+                quote!(::rtos_core::alarms::AlarmAction::SetEvent(#task, #mask))
+            }
+            Action::Callback(callback) => {
+                quote!(::rtos_core::alarms::AlarmAction::Callback(#callback))
+            }
+        };
+        let autostart = match alarm.autostart {
+            Some((alarm_time, cycle_time)) => {
+                let alarm_time = alarm_time as i32;
+                let cycle_time = cycle_time as i32;
+                quote! {
+                    ::core::option::Option::Some(::rtos_core::alarms::AlarmAutostart {
+                        alarm_time: #alarm_time,
+                        cycle_time: #cycle_time,
+                    })
+                }
+            }
+            None => quote!(::core::option::Option::None),
+        };
+        quote! {
+            ::rtos_core::alarms::AlarmConfig {
+                name: #name,
+                counter: #counter,
+                action: #action,
+                autostart: #autostart,
+            }
+        }
+    });
+
+    let os = |key: &str, default: TokenStream| {
+        input
+            .os
+            .iter()
+            .find(|field| field.key == key)
+            .map_or(default, |field| {
+                let value = &field.value;
+                quote!(#value)
+            })
+    };
+    let hooks = os("hooks", quote!(::rtos_core::Hooks::NONE));
+    let pools = os("pools", quote!(&[]));
+    let heap = os("heap", quote!(::core::option::Option::None));
+
+    let handles = |objects: &[Object], ty: TokenStream| -> Vec<TokenStream> {
+        objects
+            .iter()
+            .enumerate()
+            .map(|(id, object)| {
+                let constant = constant_name(&object.name);
+                quote!(pub const #constant: ::rtos_core::os::#ty = ::rtos_core::os::#ty::new(#id);)
+            })
+            .collect()
+    };
+    let task_handles = handles(task_objects, quote!(Task));
+    let alarm_handles = handles(&input.alarms, quote!(Alarm));
+    let resource_handles = handles(&input.resources, quote!(Resource));
+    let event_handles = input.events.iter().enumerate().map(|(bit, object)| {
+        let constant = constant_name(&object.name);
+        let mask = 1u32 << bit;
+        quote!(pub const #constant: ::rtos_core::os::Event = ::rtos_core::os::Event::new(#mask);)
+    });
+
+    let features = binary_info(
+        tasks
+            .iter()
+            .zip(task_objects)
+            .map(|(task, object)| format!("task {}, priority {}", object.name, task.priority))
+            .chain(alarms.iter().zip(&input.alarms).map(|(alarm, object)| {
+                format!(
+                    "alarm {}, counter {}",
+                    object.name, input.counters[alarm.counter].name
+                )
+            })),
+    );
+
+    let num_tasks = tasks.len();
+    let num_resources = input.resources.len();
+    let num_alarms = alarms.len();
+    let num_counters = counters.len();
+    let extended = tasks.iter().any(|task| !task.events.is_empty());
+    let multiple_activations = tasks.iter().any(|task| task.activation > 1)
+        || tasks.iter().enumerate().any(|(i, task)| {
+            tasks[..i]
+                .iter()
+                .any(|other| other.priority == task.priority)
+        });
+    let (priorities, priority_errors): (Vec<_>, Vec<_>) = tasks
+        .iter()
+        .zip(task_objects)
+        .map(|(task, object)| (task.priority, format!("priority of task `{}`", object.name)))
+        .chain(
+            ceilings
+                .iter()
+                .zip(&input.resources)
+                .map(|(ceiling, object)| {
+                    (*ceiling, format!("ceiling of resource `{}`", object.name))
+                }),
+        )
+        .map(|(priority, what)| (priority as usize, format!("{what} out of range")))
+        .unzip();
+    // Activations queued per priority, plus the slot of a task preempted
+    // while holding a resource with that ceiling
+    let mut per_priority: Vec<(i64, usize)> = Vec::new();
+    for task in tasks {
+        match per_priority
+            .iter_mut()
+            .find(|(priority, _)| *priority == task.priority)
+        {
+            Some((_, activations)) => *activations += task.activation as usize,
+            None => per_priority.push((task.priority, task.activation as usize)),
+        }
+    }
+    let queued = per_priority.iter().map(|(priority, activations)| {
+        let at_ceiling = ceilings.contains(priority);
+        let priority = *priority as usize;
+        let error = format!("too many activations at priority {priority}");
+        quote! {
+            let ceiling = #at_ceiling || #priority == ::rtos_core::task::MAX_PRIORITIES - 1;
+            let queued = #activations + ceiling as usize;
+            assert!(queued <= ::rtos_core::task::QUEUE_DEPTH, #error);
+        }
+    });
+    let (stacks, stack_errors): (Vec<_>, Vec<_>) = tasks
+        .iter()
+        .zip(task_objects)
+        .map(|(task, object)| {
+            (
+                task.stack as usize,
+                format!(
+                    "stack of task `{}` smaller than MIN_STACK_SIZE",
+                    object.name
+                ),
+            )
+        })
+        .unzip();
+
+    quote! {
+        pub static CONFIG: ::rtos_core::OsConfig = ::rtos_core::OsConfig {
+            tasks: &[#(#task_configs),*],
+            resources: &[#(#resource_configs),*],
+            counters: &[#(#counter_configs),*],
+            alarms: &[#(#alarm_configs),*],
+            hooks: #hooks,
+            pools: #pools,
+            heap: #heap,
+        };
+
+        #(#task_handles)*
+        #(#alarm_handles)*
+        #(#resource_handles)*
+        #(#event_handles)*
+
+        #features
+
+        // Limits of the kernel and of its conformance class
+        const _: () = {
+            use ::rtos_core::{CONFORMANCE_CLASS, ConformanceClass};
+            assert!(#num_tasks <= ::rtos_core::task::MAX_TASKS, "too many tasks");
+            assert!(
+                #num_resources <= ::rtos_core::resources::MAX_RESOURCES,
+                "too many resources"
+            );
+            assert!(#num_alarms <= ::rtos_core::alarms::MAX_ALARMS, "too many alarms");
+            assert!(
+                #num_counters <= ::rtos_core::alarms::MAX_COUNTERS,
+                "too many counters"
+            );
+            #(assert!(
+                #priorities < ::rtos_core::task::MAX_PRIORITIES,
+                #priority_errors
+            );)*
+            #(#queued)*
+            #(assert!(#stacks >= ::rtos_core::task::MIN_STACK_SIZE, #stack_errors);)*
+            assert!(
+                !#extended
+                    || matches!(CONFORMANCE_CLASS, ConformanceClass::Ecc1 | ConformanceClass::Ecc2),
+                "extended tasks need conformance class ECC1 or ECC2"
+            );
+            assert!(
+                !#multiple_activations
+                    || matches!(CONFORMANCE_CLASS, ConformanceClass::Bcc2 | ConformanceClass::Ecc2),
+                "multiple activations and several tasks per priority need conformance class \
+                 BCC2 or ECC2"
+            );
+        };
+    }
+}
+
+/// The picotool binary info entries of `features`, program feature strings.
+pub fn binary_info(features: impl IntoIterator<Item = String>) -> TokenStream {
+    let features = features.into_iter().map(|feature| {
+        Literal::c_string(&CString::new(feature).expect("object names have no NUL"))
+    });
+    quote!(::rtos_core::os_binary_info!(#(#features),*);)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Input {
+        syn::parse_str(input).unwrap()
+    }
+
+    fn error(input: &str) -> String {
+        match syn::parse_str::<Input>(input).and_then(expand) {
+            Ok(_) => panic!("`{input}` was accepted"),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    #[test]
+    fn sections() {
+        let input = parse(
+            "os { hooks: HOOKS }
+             tasks { A { entry: a, priority: 1, stack: 1024 }, B { entry: b, priority: 2, stack: 512 } }
+             resources { Bus, Group { internal: true } }
+             events { Ready }",
+        );
+        assert_eq!(input.os.len(), 1);
+        let tasks = input.tasks.as_deref().unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[1].name, "B");
+        assert_eq!(tasks[1].fields.len(), 3);
+        assert_eq!(tasks[1].fields[2].key, "stack");
+        assert!(input.resources[0].fields.is_empty());
+        assert_eq!(input.resources[1].fields[0].key, "internal");
+        assert_eq!(input.events.len(), 1);
+        assert!(input.counters.is_empty() && input.alarms.is_empty());
+    }
+
+    #[test]
+    fn malformed_sections() {
+        assert!(syn::parse_str::<Input>("tasks { A { priority 1 } }").is_err());
+        assert!(syn::parse_str::<Input>("tasks A").is_err());
+        assert_eq!(error("tasks {} tasks {}"), "duplicate `tasks` section");
+        assert_eq!(
+            error("counters {}"),
+            "the configuration needs a `tasks` section"
+        );
+    }
+
+    #[test]
+    fn task_fields() {
+        let input = parse(
+            "tasks {
+                 A { entry: a, priority: 2, stack: 1024, activation: 3, schedule: Non },
+                 B { entry: b, priority: 1, stack: 512, resources: [Bus], deadline: 10 },
+             }
+             resources { Bus }",
+        );
+        let resources = [Resource {
+            internal: false,
+            ceiling: None,
+        }];
+        let objects = input.tasks.as_deref().unwrap();
+        let a = task(&input, &resources, &objects[0]).unwrap();
+        assert_eq!((a.priority, a.activation, a.stack), (2, 3, 1024));
+        assert_eq!(a.schedule, "Non");
+        assert!(!a.autostart && a.deadline.is_none());
+        let b = task(&input, &resources, &objects[1]).unwrap();
+        assert_eq!(b.resources, [0]);
+        assert_eq!(b.deadline, Some(10));
+        assert_eq!(ceilings(&input, &resources, &[a, b]).unwrap(), [1]);
+    }
+
+    #[test]
+    fn rejected_values() {
+        assert_eq!(
+            error("tasks { A { entry: a, priority: 256, stack: 1024 } }"),
+            "256 is out of range 0..=255"
+        );
+        assert_eq!(
+            error("tasks { A { entry: a, priority: 1, stack: 1024, activation: 0 } }"),
+            "0 is out of range 1..=255"
+        );
+        assert_eq!(
+            error("tasks { A { entry: a, priority: 1, stack: 1024, deadline: 0 } }"),
+            "0 is out of range 1..=2147483647"
+        );
+    }
+
+    #[test]
+    fn constant_names() {
+        let name = |name: &str| constant_name(&Ident::new(name, Span::call_site())).to_string();
+        assert_eq!(name("SensorTask"), "SENSOR_TASK");
+        assert_eq!(name("Task1Init"), "TASK1_INIT");
+        assert_eq!(name("ADC"), "ADC");
+        assert_eq!(name("bus"), "BUS");
+    }
+}
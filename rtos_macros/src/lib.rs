@@ -0,0 +1,282 @@
+//! Procedural macros of `rtos_core`, re-exported by it.
+
+mod config;
+
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::{
+    Error, Expr, Ident, ItemFn, MetaNameValue, ReturnType, Token, Type, Visibility,
+    parse_macro_input, punctuated::Punctuated, spanned::Spanned,
+};
+
+/// Turns a Rust function into a task entry point, for
+/// `TaskConfig::entry`.
+///
+/// The function takes no arguments and returns `()` or `!`. It gets the C
+/// calling convention the kernel starts tasks with; its name, visibility
+/// and attributes are kept. Returning from it terminates the task, as
+/// `TerminateTask` would.
+///
+/// ```ignore
+/// #[task]
+/// fn blink() {
+///     LED.toggle();
+/// }
+///
+/// TaskConfig { name: "Blink", entry: blink, .. }
+/// ```
+///
+/// With arguments, the task registers itself at link time instead, as
+/// `TASK(name, priority, stack)` does in C, and needs no `TaskConfig`.
+/// `priority` and `stack` in bytes are required; `activation` (1),
+/// `autostart` (`false`), `extended` (`false`) and `schedule` (`Full` or
+/// `Non`) are optional. `StartOS` gives the task an ID after those of
+/// `OsConfig::tasks`, which the `RegisteredTask` named after the function
+/// in upper case holds, and C reads as `TASK_ID(name)`. Two tasks with the
+/// same name fail to link.
+///
+/// ```ignore
+/// #[task(priority = 2, stack = 1024, autostart = true)]
+/// fn blink() {
+///     LED.toggle();
+/// }
+///
+/// BLINK.task().activate()?;
+/// ```
+#[proc_macro_attribute]
+pub fn task(args: TokenStream, item: TokenStream) -> TokenStream {
+    let args =
+        parse_macro_input!(args with Punctuated::<MetaNameValue, Token![,]>::parse_terminated);
+    let entry = parse_macro_input!(item as ItemFn);
+    let ident = entry.sig.ident.clone();
+    let vis = entry.vis.clone();
+    let tokens = task_entry(entry).and_then(|entry| {
+        if args.is_empty() {
+            return Ok(entry);
+        }
+        let registration = registration(&ident, &vis, &args)?;
+        Ok(quote!(#entry #registration))
+    });
+    match tokens {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.into_compile_error().into(),
+    }
+}
+
+fn task_entry(mut entry: ItemFn) -> syn::Result<proc_macro2::TokenStream> {
+    let sig = &entry.sig;
+    if let Some(abi) = &sig.abi {
+        return Err(Error::new(abi.span(), "a task must not specify an ABI"));
+    }
+    if let Some(asyncness) = &sig.asyncness {
+        return Err(Error::new(asyncness.span(), "a task cannot be async"));
+    }
+    if !sig.generics.params.is_empty() || sig.generics.where_clause.is_some() {
+        return Err(Error::new(sig.generics.span(), "a task cannot be generic"));
+    }
+    if !sig.inputs.is_empty() {
+        return Err(Error::new(sig.inputs.span(), "a task takes no arguments"));
+    }
+    if sig.variadic.is_some() {
+        return Err(Error::new(sig.span(), "a task takes no arguments"));
+    }
+    let never = match &sig.output {
+        ReturnType::Default => false,
+        ReturnType::Type(_, ty) => match ty.as_ref() {
+            Type::Never(_) => true,
+            Type::Tuple(tuple) if tuple.elems.is_empty() => false,
+            ty => {
+                return Err(Error::new(ty.span(), "a task returns `()` or `!`"));
+            }
+        },
+    };
+    if !never {
+        entry.sig.abi = Some(syn::parse_quote!(extern "C"));
+        return Ok(quote!(#entry));
+    }
+    // `TaskEntry` returns `()`, so the body moves into an inner function
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
+    } = entry;
+    let ident = &sig.ident;
+    let unsafety = &sig.unsafety;
+    let call = match unsafety {
+        Some(_) => quote!(unsafe { #ident() }),
+        None => quote!(#ident()),
+    };
+    Ok(quote! {
+        #(#attrs)*
+        #vis #unsafety extern "C" fn #ident() {
+            #[inline(always)]
+            #sig #block
+            #call
+        }
+    })
+}
+
+/// The descriptor of task `ident` in the `.os_tasks` section, its stack,
+/// its ID, exported as `OsTaskId_<ident>` so that duplicate names fail to
+/// link, and its picotool binary info entry.
+fn registration(
+    ident: &Ident,
+    vis: &Visibility,
+    args: &Punctuated<MetaNameValue, Token![,]>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    const KEYS: [&str; 6] = [
+        "priority",
+        "stack",
+        "activation",
+        "autostart",
+        "extended",
+        "schedule",
+    ];
+    let mut values: [Option<&Expr>; KEYS.len()] = [None; KEYS.len()];
+    for arg in args {
+        let Some(index) = KEYS.iter().position(|key| arg.path.is_ident(key)) else {
+            return Err(Error::new(
+                arg.path.span(),
+                format!("expected one of {}", KEYS.join(", ")),
+            ));
+        };
+        if values[index].replace(&arg.value).is_some() {
+            return Err(Error::new(arg.path.span(), "duplicate argument"));
+        }
+    }
+    let [priority, stack, activation, autostart, extended, schedule] = values;
+    fn required<'a>(value: Option<&'a Expr>, key: &str) -> syn::Result<&'a Expr> {
+        value.ok_or_else(|| Error::new(Span::call_site(), format!("#[task] needs `{key}`")))
+    }
+    let priority = config::int(required(priority, "priority")?, 0, u8::MAX.into())? as u8;
+    let stack = config::int(required(stack, "stack")?, 1, u32::MAX.into())? as usize;
+    let activation = match activation {
+        Some(expr) => config::int(expr, 1, u8::MAX.into())? as u8,
+        None => 1,
+    };
+    let mut flags = Vec::new();
+    if autostart.map(config::boolean).transpose()? == Some(true) {
+        flags.push(quote!(::rtos_core::registry::TASK_AUTOSTART));
+    }
+    if extended.map(config::boolean).transpose()? == Some(true) {
+        flags.push(quote!(::rtos_core::registry::TASK_EXTENDED));
+    }
+    if let Some(schedule) = schedule {
+        let schedule = config::ident(schedule)?;
+        if schedule == "Non" {
+            flags.push(quote!(::rtos_core::registry::TASK_NON_PREEMPTIVE));
+        } else if schedule != "Full" {
+            return Err(Error::new(schedule.span(), "expected `Full` or `Non`"));
+        }
+    }
+    let features = config::binary_info([format!("task {ident}, priority {priority}")]);
+    let name = format!("{ident}\0");
+    let section = format!(".os_tasks.{ident}");
+    let id_symbol = format!("OsTaskId_{ident}");
+    let handle = format_ident!("{}", ident.to_string().to_uppercase());
+    let stack_error = format!("stack of task `{ident}` smaller than MIN_STACK_SIZE");
+    let activation_error = format!("activation of task `{ident}` exceeds QUEUE_DEPTH");
+    Ok(quote! {
+        #[unsafe(export_name = #id_symbol)]
+        #vis static #handle: ::rtos_core::registry::RegisteredTask =
+            ::rtos_core::registry::RegisteredTask::new();
+
+        const _: () = {
+            assert!(#stack >= ::rtos_core::task::MIN_STACK_SIZE, #stack_error);
+            assert!(
+                #activation as usize <= ::rtos_core::task::QUEUE_DEPTH,
+                #activation_error
+            );
+
+            static STACK: ::rtos_core::task::Stack<#stack> = ::rtos_core::task::Stack::new();
+
+            #[used]
+            #[unsafe(link_section = #section)]
+            static DESCRIPTOR: ::rtos_core::registry::TaskDescriptorType =
+                ::rtos_core::registry::TaskDescriptorType {
+                    id: #handle.as_ptr(),
+                    name: #name.as_ptr().cast(),
+                    entry: #ident as ::rtos_core::task::TaskEntry,
+                    priority: #priority,
+                    activation: #activation,
+                    flags: 0 #(| #flags)*,
+                    stack: STACK.region().base,
+                    stack_size: #stack,
+                };
+
+            #features
+        };
+    })
+}
+
+/// Static OS configuration, checked at compile time.
+///
+/// Expands to `pub static CONFIG: OsConfig` and to a constant handle for
+/// every task, alarm, resource and event, named after it in upper case:
+/// task `SensorTask` becomes `SENSOR_TASK: os::Task`. Objects refer to each
+/// other by name; IDs, event masks, resource ceilings and stacks are worked
+/// out by the macro. Only `tasks` is required. With the `binary-info`
+/// feature of `rtos_core`, the names of the tasks, with their priorities,
+/// and of the alarms, with their counters, go in the picotool binary info.
+///
+/// ```ignore
+/// rtos_config! {
+///     os { hooks: HOOKS, heap: Some(HEAP.region()) }
+///     tasks {
+///         Sampler { entry: sampler, priority: 2, stack: 1024, resources: [Bus] },
+///         Logger {
+///             entry: logger,
+///             priority: 1,
+///             stack: 2048,
+///             autostart: true,
+///             events: [DataReady],
+///         },
+///     }
+///     counters {
+///         SystemCounter { max: 1000, ticks_per_base: 1, min_cycle: 10 },
+///     }
+///     alarms {
+///         SampleAlarm {
+///             counter: SystemCounter,
+///             action: activate_task(Sampler),
+///             alarm_time: 10,
+///             cycle_time: 100,
+///         },
+///     }
+///     resources { Bus }
+///     events { DataReady }
+/// }
+/// ```
+///
+/// Fields, with their defaults:
+///
+/// * `os`: `hooks` (`Hooks::NONE`), `pools` (`&[]`) and `heap` (`None`),
+///   any expressions.
+/// * tasks: `entry`, `priority` and `stack` in bytes, then `activation`
+///   (1), `autostart` (`false`), `schedule` (`Full` or `Non`), `resources`,
+///   `internal_resource`, `events`, whose presence makes the task extended,
+///   and `deadline` in ticks.
+/// * counters: `max`, `ticks_per_base` and `min_cycle` (1).
+/// * alarms: `counter`, `action`, one of `activate_task(Task)`,
+///   `set_event(Task, Event)` and `callback(function)`, and `alarm_time`
+///   and `cycle_time` (0) for an alarm started by `StartOS`.
+/// * resources: `internal` (`false`) and `ceiling`, by default the highest
+///   priority of the tasks using the resource.
+/// * events: none, masks are given in declaration order.
+///
+/// Rejected at compile time, as `StartOS` would: references to missing
+/// objects, a ceiling below the priority of a task using the resource, an
+/// alarm setting an event its task does not wait for, times and cycles
+/// outside the bounds of the counter, stacks below `MIN_STACK_SIZE`, more
+/// activations at one priority than `QUEUE_DEPTH` holds, and
+/// configurations beyond the kernel tables or its conformance class.
+#[proc_macro]
+pub fn rtos_config(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as config::Input);
+    match config::expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.into_compile_error().into(),
+    }
+}
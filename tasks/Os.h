@@ -0,0 +1,1938 @@
+#ifndef OS_H
+#define OS_H
+
+#pragma once
+
+#include <stdarg.h>
+#include <stddef.h>
+#include <stdint.h>
+int vsnprintf(char *buffer, uintptr_t size, const char *format, va_list args);
+
+
+#define ABI_SIZE_STATUS_TYPE 1
+
+#define ABI_SIZE_TASK_STATE_TYPE 1
+
+#define ABI_SIZE_RESET_REASON_TYPE 1
+
+#define ABI_SIZE_ALARM_BASE_TYPE 12
+
+#define ABI_ALIGN_ALARM_BASE_TYPE 4
+
+#define ABI_SIZE_CRASH_RECORD_TYPE 80
+
+#define ABI_ALIGN_CRASH_RECORD_TYPE 4
+
+#define ABI_SIZE_POOL_INFO_TYPE 20
+
+#define ABI_ALIGN_POOL_INFO_TYPE 4
+
+#define ABI_SIZE_RESET_INFO_TYPE 44
+
+#define ABI_ALIGN_RESET_INFO_TYPE 4
+
+#define ABI_SIZE_TASK_STATISTICS_TYPE 32
+
+#define ABI_ALIGN_TASK_STATISTICS_TYPE 8
+
+#define ABI_SIZE_ISR_STATISTICS_TYPE 24
+
+#define ABI_ALIGN_ISR_STATISTICS_TYPE 8
+
+#define ABI_SIZE_SYSTEM_STATISTICS_TYPE 16
+
+#define ABI_ALIGN_SYSTEM_STATISTICS_TYPE 8
+
+#define ABI_SIZE_TASK_DESCRIPTOR_TYPE 24
+
+#define ABI_ALIGN_TASK_DESCRIPTOR_TYPE 4
+
+#define ABI_OFFSET_ALARM_BASE_TYPE_MAXALLOWEDVALUE 0
+
+#define ABI_OFFSET_ALARM_BASE_TYPE_TICKSPERBASE 4
+
+#define ABI_OFFSET_ALARM_BASE_TYPE_MINCYCLE 8
+
+#define ABI_OFFSET_CRASH_RECORD_TYPE_EXCEPTION 0
+
+#define ABI_OFFSET_CRASH_RECORD_TYPE_R0 4
+
+#define ABI_OFFSET_CRASH_RECORD_TYPE_R1 8
+
+#define ABI_OFFSET_CRASH_RECORD_TYPE_R2 12
+
+#define ABI_OFFSET_CRASH_RECORD_TYPE_R3 16
+
+#define ABI_OFFSET_CRASH_RECORD_TYPE_R12 20
+
+#define ABI_OFFSET_CRASH_RECORD_TYPE_LR 24
+
+#define ABI_OFFSET_CRASH_RECORD_TYPE_PC 28
+
+#define ABI_OFFSET_CRASH_RECORD_TYPE_XPSR 32
+
+#define ABI_OFFSET_CRASH_RECORD_TYPE_SP 36
+
+#define ABI_OFFSET_CRASH_RECORD_TYPE_EXC_RETURN 40
+
+#define ABI_OFFSET_CRASH_RECORD_TYPE_CFSR 44
+
+#define ABI_OFFSET_CRASH_RECORD_TYPE_HFSR 48
+
+#define ABI_OFFSET_CRASH_RECORD_TYPE_MMFAR 52
+
+#define ABI_OFFSET_CRASH_RECORD_TYPE_BFAR 56
+
+#define ABI_OFFSET_CRASH_RECORD_TYPE_SFSR 60
+
+#define ABI_OFFSET_CRASH_RECORD_TYPE_SFAR 64
+
+#define ABI_OFFSET_CRASH_RECORD_TYPE_TASK 68
+
+#define ABI_OFFSET_CRASH_RECORD_TYPE_ISR 72
+
+#define ABI_OFFSET_CRASH_RECORD_TYPE_TICKS 76
+
+#define ABI_OFFSET_POOL_INFO_TYPE_BLOCK_SIZE 0
+
+#define ABI_OFFSET_POOL_INFO_TYPE_BLOCKS 4
+
+#define ABI_OFFSET_POOL_INFO_TYPE_FREE 8
+
+#define ABI_OFFSET_POOL_INFO_TYPE_MIN_FREE 12
+
+#define ABI_OFFSET_POOL_INFO_TYPE_FAILURES 16
+
+#define ABI_OFFSET_RESET_INFO_TYPE_REASON 0
+
+#define ABI_OFFSET_RESET_INFO_TYPE_CHIP_RESET 4
+
+#define ABI_OFFSET_RESET_INFO_TYPE_WATCHDOG_REASON 8
+
+#define ABI_OFFSET_RESET_INFO_TYPE_SCRATCH 12
+
+#define ABI_OFFSET_TASK_DESCRIPTOR_TYPE_ID 0
+
+#define ABI_OFFSET_TASK_DESCRIPTOR_TYPE_NAME 4
+
+#define ABI_OFFSET_TASK_DESCRIPTOR_TYPE_ENTRY 8
+
+#define ABI_OFFSET_TASK_DESCRIPTOR_TYPE_PRIORITY 12
+
+#define ABI_OFFSET_TASK_DESCRIPTOR_TYPE_ACTIVATION 13
+
+#define ABI_OFFSET_TASK_DESCRIPTOR_TYPE_FLAGS 14
+
+#define ABI_OFFSET_TASK_DESCRIPTOR_TYPE_STACK 16
+
+#define ABI_OFFSET_TASK_DESCRIPTOR_TYPE_STACK_SIZE 20
+
+#define ABI_OFFSET_TASK_STATISTICS_TYPE_EXECUTION_TIME 0
+
+#define ABI_OFFSET_TASK_STATISTICS_TYPE_RESPONSE_TIME_MAX 8
+
+#define ABI_OFFSET_TASK_STATISTICS_TYPE_ACTIVATIONS 16
+
+#define ABI_OFFSET_TASK_STATISTICS_TYPE_PREEMPTIONS 20
+
+#define ABI_OFFSET_TASK_STATISTICS_TYPE_OVERRUNS 24
+
+#define ABI_OFFSET_TASK_STATISTICS_TYPE_DEADLINE_MISSES 28
+
+#define ABI_OFFSET_ISR_STATISTICS_TYPE_EXECUTION_TIME 0
+
+#define ABI_OFFSET_ISR_STATISTICS_TYPE_DURATION_MAX 8
+
+#define ABI_OFFSET_ISR_STATISTICS_TYPE_ACTIVATIONS 16
+
+#define ABI_OFFSET_SYSTEM_STATISTICS_TYPE_ELAPSED_TIME 0
+
+#define ABI_OFFSET_SYSTEM_STATISTICS_TYPE_IDLE_TIME 8
+
+/**
+ * Maximum number of alarms in a configuration.
+ */
+#define MAX_ALARMS 16
+
+/**
+ * Maximum number of counters in a configuration.
+ */
+#define MAX_COUNTERS 8
+
+/**
+ * Exception numbers of the faults.
+ */
+#define HARD_FAULT 3
+
+#define MEM_MANAGE_FAULT 4
+
+#define BUS_FAULT 5
+
+#define USAGE_FAULT 6
+
+#define SECURE_FAULT 7
+
+/**
+ * Maximum number of pools in a configuration.
+ */
+#define MAX_POOLS 8
+
+/**
+ * `flags` of a task started by `StartOS`.
+ */
+#define TASK_AUTOSTART (1 << 0)
+
+/**
+ * `flags` of an extended task, which may wait for events.
+ */
+#define TASK_EXTENDED (1 << 1)
+
+/**
+ * `flags` of a non-preemptable task.
+ */
+#define TASK_NON_PREEMPTIVE (1 << 2)
+
+/**
+ * Maximum number of resources in a configuration, `RES_SCHEDULER` aside.
+ */
+#define MAX_RESOURCES 16
+
+/**
+ * Number of interrupts of the RP2350.
+ */
+#define NUM_IRQS 52
+
+/**
+ * Maximum number of tasks in a configuration.
+ */
+#define MAX_TASKS 16
+
+/**
+ * Number of priority levels. Valid priorities are `0..MAX_PRIORITIES`.
+ */
+#define MAX_PRIORITIES 16
+
+
+
+/**
+ * Maximum number of supervised entities.
+ */
+#define MAX_SUPERVISED_ENTITIES 16
+
+/**
+ * Return status of the services, with the values and, in C, the names
+ * (`E_OK`, `E_OS_ACCESS`, ...) of the OSEK specification.
+ *
+ */
+typedef enum StatusType {
+  E_OK = 0,
+  E_OS_ACCESS = 1,
+  E_OS_CALLEVEL = 2,
+  E_OS_ID = 3,
+  E_OS_LIMIT = 4,
+  E_OS_NOFUNC = 5,
+  E_OS_RESOURCE = 6,
+  E_OS_STATE = 7,
+  E_OS_VALUE = 8,
+  /**
+   * Implementation-specific: the last reset was caused by a fault, see
+   * `GetCrashRecord`.
+   */
+  E_OS_SYS_FAULT = 9,
+} StatusType;
+
+/**
+ * Cause of the last reset, as returned by `GetResetReason`.
+ */
+typedef enum ResetReasonType {
+  /**
+   * The chip was powered up.
+   */
+  PowerOn = 0,
+  /**
+   * The supply dropped below the brown-out threshold.
+   */
+  BrownOut = 1,
+  /**
+   * The RUN pin was driven low.
+   */
+  ResetPin = 2,
+  /**
+   * The watchdog expired.
+   */
+  Watchdog = 3,
+  /**
+   * The software asked for a reboot, through the bootrom or by
+   * triggering the watchdog.
+   */
+  Software = 4,
+  /**
+   * A debugger reset the chip.
+   */
+  Debugger = 5,
+  /**
+   * The glitch detector fired.
+   */
+  GlitchDetector = 6,
+  /**
+   * A fault handler reset the chip, see `GetCrashRecord`.
+   */
+  Fault = 7,
+  /**
+   * None of the above was recorded.
+   */
+  Unknown = 8,
+} ResetReasonType;
+
+/**
+ * State of a task, `RUNNING`, `WAITING`, `READY` or `SUSPENDED` in C.
+ *
+ */
+typedef enum TaskStateType {
+  RUNNING = 0,
+  WAITING = 1,
+  READY = 2,
+  SUSPENDED = 3,
+} TaskStateType;
+
+/**
+ * Hook routines of the application, as selected by the `USE*HOOK`
+ * attributes of the OIL `OS` object. `None` disables a hook.
+ */
+typedef struct Hooks Hooks;
+
+typedef uintptr_t AlarmType;
+
+typedef int32_t TickType;
+
+typedef struct AlarmBaseType {
+  TickType maxallowedvalue;
+  TickType ticksperbase;
+  TickType mincycle;
+} AlarmBaseType;
+
+typedef struct AlarmBaseType *AlarmBaseRefType;
+
+typedef TickType *TickRefType;
+
+typedef uintptr_t TaskType;
+
+/**
+ * What a fault left behind, as returned by `GetCrashRecord`.
+ */
+typedef struct CrashRecordType {
+  /**
+   * Exception number of the fault, `HARD_FAULT` to `SECURE_FAULT`.
+   */
+  uint32_t exception;
+  /**
+   * Registers stacked on exception entry, `0` if the stack pointer was
+   * unusable.
+   */
+  uint32_t r0;
+  uint32_t r1;
+  uint32_t r2;
+  uint32_t r3;
+  uint32_t r12;
+  uint32_t lr;
+  /**
+   * Faulting instruction, or the one after it for imprecise faults.
+   */
+  uint32_t pc;
+  uint32_t xpsr;
+  /**
+   * Stack pointer of the faulting context before the exception entry.
+   */
+  uint32_t sp;
+  /**
+   * `EXC_RETURN` of the fault, telling the stack and mode of the
+   * faulting context.
+   */
+  uint32_t exc_return;
+  /**
+   * Configurable, HardFault and SecureFault status registers and the
+   * fault addresses they validate.
+   */
+  uint32_t cfsr;
+  uint32_t hfsr;
+  uint32_t mmfar;
+  uint32_t bfar;
+  uint32_t sfsr;
+  uint32_t sfar;
+  /**
+   * Task running when the fault happened, `INVALID_TASK` if none.
+   */
+  TaskType task;
+  /**
+   * Exception number of the interrupted context, `0` in thread mode.
+   */
+  uint32_t isr;
+  /**
+   * Timer ticks since the start-up when the fault happened.
+   */
+  TickType ticks;
+} CrashRecordType;
+
+typedef struct CrashRecordType *CrashRecordRefType;
+
+typedef uint32_t EventMaskType;
+
+typedef EventMaskType *EventMaskRefType;
+
+typedef uintptr_t PoolType;
+
+typedef void **BlockRefType;
+
+/**
+ * Usage of a pool, as returned by `GetPoolInfo`.
+ */
+typedef struct PoolInfoType {
+  /**
+   * Bytes per block, rounded up to a multiple of 8.
+   */
+  uint32_t block_size;
+  uint32_t blocks;
+  /**
+   * Blocks free right now.
+   */
+  uint32_t free;
+  /**
+   * Fewest blocks ever free since `StartOS`.
+   */
+  uint32_t min_free;
+  /**
+   * `AllocBlock` calls that found the pool exhausted.
+   */
+  uint32_t failures;
+} PoolInfoType;
+
+typedef struct PoolInfoType *PoolInfoRefType;
+
+/**
+ * Reason of the last reset and the registers it was read from, as
+ * returned by `GetResetInfo`.
+ */
+typedef struct ResetInfoType {
+  enum ResetReasonType reason;
+  /**
+   * POWMAN `CHIP_RESET`, whose `HAD_*` bits tell the last chip reset.
+   */
+  uint32_t chip_reset;
+  /**
+   * WATCHDOG `REASON`, `FORCE` and `TIMER` bits.
+   */
+  uint32_t watchdog_reason;
+  /**
+   * WATCHDOG `SCRATCH0` to `SCRATCH7`, which survive all resets but
+   * power-on and brown-out. The bootrom uses `SCRATCH4` to `SCRATCH7`.
+   */
+  uint32_t scratch[8];
+} ResetInfoType;
+
+typedef struct ResetInfoType *ResetInfoRefType;
+
+typedef uintptr_t ResourceType;
+
+/**
+ * Command handler registered from C. `argv` holds `argc` NUL-terminated
+ * words, the command name first, followed by a null pointer. A non-zero
+ * return value is printed as the exit status.
+ */
+typedef int (*ShellCommandType)(int argc, const char *const *argv);
+
+/**
+ * Run-time statistics of a task. Times are in microseconds.
+ */
+typedef struct TaskStatisticsType {
+  /**
+   * Time spent running.
+   */
+  uint64_t execution_time;
+  /**
+   * Longest time from an activation to the matching termination.
+   */
+  uint64_t response_time_max;
+  /**
+   * Number of activations.
+   */
+  uint32_t activations;
+  /**
+   * Number of times a higher-priority task took the CPU.
+   */
+  uint32_t preemptions;
+  /**
+   * Number of activations rejected with `E_OS_LIMIT`.
+   */
+  uint32_t overruns;
+  /**
+   * Number of activations still pending past their deadline.
+   */
+  uint32_t deadline_misses;
+} TaskStatisticsType;
+
+typedef struct TaskStatisticsType *TaskStatisticsRefType;
+
+/**
+ * Run-time statistics of a Category 2 ISR. Times are in microseconds.
+ */
+typedef struct IsrStatisticsType {
+  /**
+   * Time spent running, less the nested ISRs.
+   */
+  uint64_t execution_time;
+  /**
+   * Longest time from an entry to the matching exit.
+   */
+  uint64_t duration_max;
+  /**
+   * Number of runs.
+   */
+  uint32_t activations;
+} IsrStatisticsType;
+
+typedef struct IsrStatisticsType *IsrStatisticsRefType;
+
+/**
+ * Statistics of the whole system. Times are in microseconds.
+ */
+typedef struct SystemStatisticsType {
+  /**
+   * Time since `StartOS` or the last `ResetStatistics`.
+   */
+  uint64_t elapsed_time;
+  /**
+   * Part of `elapsed_time` spent in the idle loop.
+   */
+  uint64_t idle_time;
+} SystemStatisticsType;
+
+typedef struct SystemStatisticsType *SystemStatisticsRefType;
+
+typedef TaskType *TaskRefType;
+
+typedef enum TaskStateType *TaskStateRefType;
+
+typedef uintptr_t SupervisedEntityType;
+
+typedef SupervisedEntityType *SupervisedEntityRefType;
+
+/**
+ * Entry point of a task.
+ */
+typedef void (*TaskEntry)(void);
+
+/**
+ * Task priority. `0` is the lowest priority.
+ */
+typedef uint8_t Priority;
+
+/**
+ * Task placed in the `.os_tasks` section by `TASK` or `#[task]`.
+ */
+typedef struct TaskDescriptorType {
+  /**
+   * Where `StartOS` stores the ID of the task, or null.
+   */
+  TaskType *id;
+  /**
+   * NUL-terminated name.
+   */
+  const char *name;
+  TaskEntry entry;
+  Priority priority;
+  /**
+   * Maximum number of queued activations.
+   */
+  uint8_t activation;
+  /**
+   * `TASK_AUTOSTART`, `TASK_EXTENDED` and `TASK_NON_PREEMPTIVE`.
+   */
+  uint8_t flags;
+  uint8_t *stack;
+  uintptr_t stack_size;
+} TaskDescriptorType;
+
+
+
+/**
+ * Resource locking out the scheduler. Its ceiling is the highest priority.
+ */
+#define RES_SCHEDULER MAX_RESOURCES
+
+
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Shuts the OS down.
+ *
+ * # Syntax
+ * ```ignore
+ * void ShutdownOS(StatusType Error)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `error` (in) — Error that caused the shutdown.
+ *
+ * # Description
+ *
+ * Calls the ShutdownHook with `error`, then stops all OS activity. This
+ * service never returns.
+ */
+void ShutdownOS(enum StatusType error);
+
+/**
+ * Reads the alarm base characteristics.
+ *
+ * # Syntax
+ * ```ignore
+ * StatusType GetAlarmBase(AlarmType AlarmID, AlarmBaseRefType Info)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `alarm_id` (in) — Reference to the alarm.
+ * * `info` (out) — Reference to a structure with constants of the alarm base.
+ *
+ * # Description
+ *
+ * This service reads the alarm base characteristics.
+ * The return value `info` is a structure of type [`AlarmBaseType`]
+ * that contains the alarm base information.
+ *
+ * # Particularities
+ *
+ * Allowed on task level, ISR, and in several hook routines
+ *
+ * # Status
+ *
+ * * **Standard:**
+ *   * `E_OK` — No error.
+ * * **Extended:**
+ *   * `E_OS_ID` — Alarm `alarm_id` is invalid.
+ *
+ * # Safety
+ *
+ * `info` must be valid for a write.
+ */
+enum StatusType GetAlarmBase(AlarmType alarm_id, AlarmBaseRefType info);
+
+/**
+ * Returns the relative value in ticks before an alarm expires.
+ *
+ * # Syntax
+ * ```ignore
+ * StatusType GetAlarm(AlarmType AlarmID, TickRefType Tick)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `alarm_id` (in) — Reference to an alarm.
+ * * `tick` (out) — Relative value in ticks before the alarm `alarm_id` expires.
+ *   If the alarm is not in use, the value of `tick` is undefined.
+ *
+ * # Description
+ *
+ * This service returns the relative value in ticks before the alarm `alarm_id`
+ * expires.
+ *
+ * # Particularities
+ *
+ * * If `alarm_id` is not in use, `tick` is undefined.
+ * * Allowed on task level, ISR, and in several hook routines.
+ *
+ * # Status
+ *
+ * * **Standard:**
+ *   * `E_OK` — No error.
+ *   * `E_OS_NOFUNC` — Alarm `alarm_id` is not used.
+ *
+ * # Safety
+ *
+ * `tick` must be valid for a write.
+ */
+enum StatusType GetAlarm(AlarmType alarm_id, TickRefType tick);
+
+enum StatusType SetRelAlarm(AlarmType alarm_id, TickType increment, TickType cycle);
+
+/**
+ *
+ * # Parameters
+ * * `alarm_id` - Reference to the alarm element.
+ * * `start` - Absolute value in ticks when the alarm should expire.
+ * * `cycle` - Cycle value for cyclic alarms. Must be `0` for single alarms.
+ *
+ * # Behavior
+ * - Occupies the alarm specified by `alarm_id`.
+ * - When the counter reaches `start`, the assigned task is activated, the assigned
+ *   event (for extended tasks) is set, or the alarm callback routine is called.
+ * - If `start` is very close to the current counter value, the alarm may expire
+ *   immediately, possibly before the function returns.
+ * - If `start` was already passed before the system call, the alarm will expire
+ *   only after the counter overflows and reaches `start` again.
+ * - If `cycle` is nonzero, the alarm is automatically re-scheduled after expiry
+ *   with a relative value of `cycle`.
+ *
+ * # Particularities
+ * - The alarm must not already be in use. To change values of an active alarm,
+ *   cancel it first with `CancelAlarm`.
+ * - Allowed at task level and in ISRs, but not in hook routines.
+ *
+ * # Return Values
+ * * `E_OK` - No error.
+ * * `E_OS_STATE` - Alarm already in use.
+ * * `E_OS_ID` - Invalid `alarm_id`.
+ * * `E_OS_VALUE` -
+ *   - `start` is outside counter limits (`< 0` or `> maxallowedvalue`).
+ *   - `cycle` is nonzero but outside counter limits (`< mincycle` or `> maxallowedvalue`).
+ *
+ */
+enum StatusType SetAbsAlarm(AlarmType alarm_id, TickType start, TickType cycle);
+
+enum StatusType CancelAlarm(AlarmType alarm_id);
+
+/**
+ * Returns the crash record left by a fault before the last reset.
+ *
+ * # Syntax
+ * ```ignore
+ * StatusType GetCrashRecord(CrashRecordRefType Record)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `record` (out) — Exception, stacked registers, fault status registers,
+ *   running task and tick count of the fault.
+ *
+ * # Description
+ *
+ * The record is the one `StartOS` found, and stays available until the
+ * next reset.
+ *
+ * # Particularities
+ *
+ * Allowed on task level, ISR level and in the hook routines, the
+ * ErrorHook reporting `E_OS_SYS_FAULT` included.
+ *
+ * # Status
+ *
+ * * **Standard:**
+ *   * `E_OK` — No error.
+ *   * `E_OS_NOFUNC` — The last reset was not caused by a fault.
+ *
+ * # Safety
+ *
+ * `record` must be valid for a write.
+ */
+enum StatusType GetCrashRecord(CrashRecordRefType record);
+
+/**
+ * Sets events of an extended task.
+ *
+ * # Syntax
+ * ```ignore
+ * StatusType SetEvent(TaskType TaskID, EventMaskType Mask)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `task_id` (in) — Reference to the task for which one or several events
+ *   are to be set.
+ * * `mask` (in) — Mask of the events to be set.
+ *
+ * # Description
+ *
+ * The events of task `task_id` are set according to `mask`. If the task
+ * was waiting for at least one of them, it is transferred into the ready
+ * state.
+ *
+ * # Particularities
+ *
+ * Allowed on task level and ISR level, but not in hook routines.
+ *
+ * # Status
+ *
+ * * **Standard:**
+ *   * `E_OK` — No error.
+ * * **Extended:**
+ *   * `E_OS_ID` — `task_id` is invalid.
+ *   * `E_OS_ACCESS` — `task_id` is not an extended task.
+ *   * `E_OS_STATE` — Task `task_id` is in the suspended state.
+ */
+enum StatusType SetEvent(TaskType task_id, EventMaskType mask);
+
+/**
+ * Clears events of the calling task.
+ *
+ * # Syntax
+ * ```ignore
+ * StatusType ClearEvent(EventMaskType Mask)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `mask` (in) — Mask of the events to be cleared.
+ *
+ * # Particularities
+ *
+ * Restricted to extended tasks.
+ *
+ * # Status
+ *
+ * * **Standard:**
+ *   * `E_OK` — No error.
+ * * **Extended:**
+ *   * `E_OS_ACCESS` — Call not from an extended task.
+ *   * `E_OS_CALLEVEL` — Call at interrupt level.
+ */
+enum StatusType ClearEvent(EventMaskType mask);
+
+/**
+ * Reads the current events of an extended task.
+ *
+ * # Syntax
+ * ```ignore
+ * StatusType GetEvent(TaskType TaskID, EventMaskRefType Event)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `task_id` (in) — Task whose event mask is to be returned.
+ * * `event` (out) — Reference to the memory of the return data.
+ *
+ * # Particularities
+ *
+ * Allowed on task level, ISR level and in several hook routines.
+ *
+ * # Status
+ *
+ * * **Standard:**
+ *   * `E_OK` — No error.
+ * * **Extended:**
+ *   * `E_OS_ID` — `task_id` is invalid.
+ *   * `E_OS_ACCESS` — `task_id` is not an extended task.
+ *   * `E_OS_STATE` — Task `task_id` is in the suspended state.
+ *
+ * # Safety
+ *
+ * `event` must be valid for a write.
+ */
+enum StatusType GetEvent(TaskType task_id, EventMaskRefType event);
+
+/**
+ * Waits until at least one of the events in `mask` is set.
+ *
+ * # Syntax
+ * ```ignore
+ * StatusType WaitEvent(EventMaskType Mask)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `mask` (in) — Mask of the events waited for.
+ *
+ * # Description
+ *
+ * The calling task is transferred into the waiting state, unless at least
+ * one of the events in `mask` is already set. The task returns to the
+ * ready state as soon as one of them is set.
+ *
+ * # Particularities
+ *
+ * Restricted to extended tasks. A rescheduling takes place if the task
+ * starts waiting, its internal resource being released until it runs again.
+ *
+ * # Status
+ *
+ * * **Standard:**
+ *   * `E_OK` — No error.
+ * * **Extended:**
+ *   * `E_OS_ACCESS` — Call not from an extended task.
+ *   * `E_OS_RESOURCE` — Calling task occupies resources.
+ *   * `E_OS_CALLEVEL` — Call at interrupt level.
+ */
+enum StatusType WaitEvent(EventMaskType mask);
+
+/**
+ * Takes a block from a pool.
+ *
+ * # Syntax
+ * ```ignore
+ * StatusType AllocBlock(PoolType PoolID, void **Block)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `pool_id` (in) — Reference to the pool.
+ * * `block` (out) — Start of the block, 8-byte aligned. Unchanged if the
+ *   call fails.
+ *
+ * # Description
+ *
+ * Takes the first block of the free list of the pool, in constant time.
+ * The content of the block is undefined.
+ *
+ * # Particularities
+ *
+ * Allowed on task level and ISR level. Exhaustion is reported to the
+ * ErrorHook and counted in the `failures` of `GetPoolInfo`.
+ *
+ * # Status
+ *
+ * * **Standard:**
+ *   * `E_OK` — No error.
+ *   * `E_OS_LIMIT` — No block left in the pool.
+ * * **Extended:**
+ *   * `E_OS_ID` — `pool_id` is invalid.
+ *
+ * # Safety
+ *
+ * `block` must be valid for a write.
+ */
+enum StatusType AllocBlock(PoolType pool_id, BlockRefType block);
+
+/**
+ * Gives a block back to its pool.
+ *
+ * # Syntax
+ * ```ignore
+ * StatusType FreeBlock(PoolType PoolID, void *Block)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `pool_id` (in) — Reference to the pool the block was taken from.
+ * * `block` (in) — Block returned by `AllocBlock`.
+ *
+ * # Description
+ *
+ * Puts the block at the head of the free list of the pool, in constant
+ * time. The block must not be used any more.
+ *
+ * # Particularities
+ *
+ * Allowed on task level and ISR level. A block freed twice is only
+ * caught while the pool has all its blocks free; otherwise it corrupts
+ * the pool.
+ *
+ * # Status
+ *
+ * * **Standard:**
+ *   * `E_OK` — No error.
+ * * **Extended:**
+ *   * `E_OS_ID` — `pool_id` is invalid.
+ *   * `E_OS_VALUE` — `block` is not a block of the pool, or the pool has
+ *     no block in use.
+ *
+ * # Safety
+ *
+ * `block` must come from `AllocBlock` on `pool_id`, be freed once and not
+ * be used afterwards: the free list is linked through the free blocks.
+ */
+enum StatusType FreeBlock(PoolType pool_id, void *block);
+
+/**
+ * Returns the usage of a pool.
+ *
+ * # Syntax
+ * ```ignore
+ * StatusType GetPoolInfo(PoolType PoolID, PoolInfoRefType Info)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `pool_id` (in) — Reference to the pool.
+ * * `info` (out) — Block size, number of blocks, free blocks, lowest
+ *   number of free blocks and number of failed allocations.
+ *
+ * # Particularities
+ *
+ * Allowed on task level and ISR level.
+ *
+ * # Status
+ *
+ * * **Standard:**
+ *   * `E_OK` — No error.
+ * * **Extended:**
+ *   * `E_OS_ID` — `pool_id` is invalid.
+ *
+ * # Safety
+ *
+ * `info` must be valid for a write.
+ */
+enum StatusType GetPoolInfo(PoolType pool_id, PoolInfoRefType info);
+
+/**
+ * Returns the cause of the last reset.
+ *
+ * # Syntax
+ * ```ignore
+ * ResetReasonType GetResetReason(void)
+ * ```
+ *
+ * # Description
+ *
+ * The reason is the one `StartOS` found, and also the one the StartupHook
+ * got.
+ *
+ * # Particularities
+ *
+ * Allowed on task level, ISR level and in the hook routines.
+ */
+enum ResetReasonType GetResetReason(void);
+
+/**
+ * Returns the cause of the last reset with the registers it was read
+ * from.
+ *
+ * # Syntax
+ * ```ignore
+ * void GetResetInfo(ResetInfoRefType Info)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `info` (out) — Reason, reset-cause registers and watchdog scratch
+ *   registers, as read by `StartOS`.
+ *
+ * # Description
+ *
+ * The scratch registers let an application find out what it left behind
+ * before a watchdog or software reset, such as the culprit of a failed
+ * supervision.
+ *
+ * # Particularities
+ *
+ * Allowed on task level, ISR level and in the hook routines.
+ *
+ * # Safety
+ *
+ * `info` must be valid for a write.
+ */
+void GetResetInfo(ResetInfoRefType info);
+
+/**
+ * Enters a critical section guarded by a resource.
+ *
+ * # Syntax
+ * ```ignore
+ * StatusType GetResource(ResourceType ResID)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `resource_id` (in) — Reference to the resource.
+ *
+ * # Description
+ *
+ * The priority of the calling task is raised to the ceiling priority of
+ * the resource until it is released, so no other task using the resource
+ * can preempt it.
+ *
+ * # Particularities
+ *
+ * Nested resources must be released in reverse order. Only allowed on task
+ * level in this implementation.
+ *
+ * # Status
+ *
+ * * **Standard:**
+ *   * `E_OK` — No error.
+ * * **Extended:**
+ *   * `E_OS_ID` — `resource_id` is invalid or an internal resource.
+ *   * `E_OS_ACCESS` — Resource already occupied, or the statically assigned
+ *     priority of the caller is higher than the ceiling priority.
+ */
+enum StatusType GetResource(ResourceType resource_id);
+
+/**
+ * Leaves the critical section guarded by a resource.
+ *
+ * # Syntax
+ * ```ignore
+ * StatusType ReleaseResource(ResourceType ResID)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `resource_id` (in) — Reference to the resource.
+ *
+ * # Description
+ *
+ * The priority of the calling task goes back to what it was before the
+ * resource was acquired. A rescheduling takes place if a ready task now
+ * has a higher priority.
+ *
+ * # Status
+ *
+ * * **Standard:**
+ *   * `E_OK` — No error.
+ * * **Extended:**
+ *   * `E_OS_ID` — `resource_id` is invalid or an internal resource.
+ *   * `E_OS_NOFUNC` — Resource not occupied by the caller, or another
+ *     resource has to be released first.
+ *   * `E_OS_ACCESS` — The statically assigned priority of the caller is
+ *     higher than the ceiling priority.
+ */
+enum StatusType ReleaseResource(ResourceType resource_id);
+
+/**
+ * Entry point of the shell task.
+ *
+ * Handles the pending input, echoing it and running every command line it
+ * completes, then terminates. Activated by [`feed`] and [`poll`].
+ */
+void ShellTask(void);
+
+/**
+ * Adds a command to the shell.
+ *
+ * # Syntax
+ * ```ignore
+ * StatusType ShellRegisterCommand(const char *Name, const char *Help,
+ *                                 ShellCommandType Handler)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `name` (in) — Word that runs the command, a static string.
+ * * `help` (in) — Description listed by `help`, a static string.
+ * * `handler` (in) — Function run with the words of the command line.
+ *
+ * # Description
+ *
+ * Registers `handler` to run in the shell task whenever a command line
+ * starts with `name`. Up to 16 commands can be registered.
+ *
+ * # Particularities
+ *
+ * Allowed on task level and in the StartupHook.
+ *
+ * # Status
+ *
+ * * **Standard:**
+ *   * `E_OK` — No error.
+ *   * `E_OS_LIMIT` — All command slots are taken.
+ * * **Extended:**
+ *   * `E_OS_VALUE` — `name` is empty, not ASCII, contains a space or is
+ *     already taken.
+ *
+ * # Safety
+ *
+ * `name` and `help` must be null or NUL-terminated strings that stay valid
+ * and unchanged for as long as the system runs, such as string literals:
+ * the shell keeps them.
+ */
+enum StatusType ShellRegisterCommand(const char *name, const char *help, ShellCommandType handler);
+
+/**
+ * Writes a string to the shell output.
+ *
+ * # Syntax
+ * ```ignore
+ * void ShellWrite(const char *Text)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `text` (in) — NUL-terminated string. `\n` is sent as `\r\n`.
+ *
+ * # Particularities
+ *
+ * Meant for command handlers, allowed on task level and ISR level.
+ *
+ * # Safety
+ *
+ * `text` must be null or NUL-terminated.
+ */
+void ShellWrite(const char *text);
+
+/**
+ * Reads the run-time statistics of a task.
+ *
+ * # Syntax
+ * ```ignore
+ * StatusType GetTaskStatistics(TaskType TaskID, TaskStatisticsRefType Stats)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `task_id` (in) — Task reference.
+ * * `stats` (out) — Statistics of the task since `StartOS` or the last
+ *   `ResetStatistics`.
+ *
+ * # Description
+ *
+ * Copies the execution time, longest response time and the activation,
+ * preemption, overrun and deadline miss counts of task `task_id` to
+ * `stats`. The execution time of a
+ * task in the running state includes its current run.
+ *
+ * # Particularities
+ *
+ * Allowed on task level, ISR level and in all hook routines.
+ *
+ * # Status
+ *
+ * * **Standard:**
+ *   * `E_OK` — No error.
+ * * **Extended:**
+ *   * `E_OS_ID` — Task `task_id` is invalid.
+ *
+ * # Safety
+ *
+ * `stats` must be valid for a write.
+ */
+enum StatusType GetTaskStatistics(TaskType task_id, TaskStatisticsRefType stats);
+
+/**
+ * Reads the run-time statistics of a Category 2 ISR.
+ *
+ * # Syntax
+ * ```ignore
+ * StatusType GetIsrStatistics(uint8 Irq, IsrStatisticsRefType Stats)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `irq` (in) — NVIC interrupt number of the ISR.
+ * * `stats` (out) — Statistics of the ISR since `StartOS` or the last
+ *   `ResetStatistics`.
+ *
+ * # Description
+ *
+ * Copies the execution time, longest run and run count of the ISR of
+ * interrupt `irq` to `stats`. Only ISRs that call `trace::isr_enter` and
+ * `trace::isr_exit` are measured.
+ *
+ * # Particularities
+ *
+ * Allowed on task level, ISR level and in all hook routines.
+ *
+ * # Status
+ *
+ * * **Standard:**
+ *   * `E_OK` — No error.
+ * * **Extended:**
+ *   * `E_OS_ID` — `irq` is not an interrupt of the RP2350.
+ *
+ * # Safety
+ *
+ * `stats` must be valid for a write.
+ */
+enum StatusType GetIsrStatistics(uint8_t irq, IsrStatisticsRefType stats);
+
+/**
+ * Reads the elapsed and idle time.
+ *
+ * # Syntax
+ * ```ignore
+ * StatusType GetSystemStatistics(SystemStatisticsRefType Stats)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `stats` (out) — Time elapsed since `StartOS` or the last
+ *   `ResetStatistics`, and the part of it spent idle.
+ *
+ * # Description
+ *
+ * The CPU load of a task over the measurement is its execution time
+ * divided by `elapsed_time`, the total load `1 - idle_time / elapsed_time`.
+ *
+ * # Particularities
+ *
+ * Allowed on task level, ISR level and in all hook routines.
+ *
+ * # Status
+ *
+ * * **Standard:**
+ *   * `E_OK` — No error.
+ *
+ * # Safety
+ *
+ * `stats` must be valid for a write.
+ */
+enum StatusType GetSystemStatistics(SystemStatisticsRefType stats);
+
+/**
+ * Starts a new measurement.
+ *
+ * # Syntax
+ * ```ignore
+ * void ResetStatistics(void)
+ * ```
+ *
+ * # Description
+ *
+ * Clears the statistics of all tasks and ISRs and the idle time. Response
+ * times of the activations pending at the call are still measured from
+ * the activation.
+ *
+ * # Particularities
+ *
+ * Allowed on task level, ISR level and in all hook routines.
+ */
+void ResetStatistics(void);
+
+/**
+ * Writes a string to the standard output.
+ *
+ * # Syntax
+ * ```ignore
+ * void print(const char *Text)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `input` (in) — NUL-terminated string, written as is.
+ *
+ * # Safety
+ *
+ * `input` must be null or NUL-terminated.
+ */
+void print(const char *input);
+
+/**
+ * Writes formatted output to the standard output.
+ *
+ * # Syntax
+ * ```ignore
+ * int printf(const char *Format, ...)
+ * ```
+ *
+ * # Description
+ *
+ * Returns the number of bytes written, or a negative value if `format`
+ * is invalid.
+ *
+ * # Safety
+ *
+ * `str` must be a NUL-terminated format string whose conversions match
+ * the arguments.
+ */
+int printf(const char *str, ...);
+
+/**
+ * Writes formatted output to a buffer of bounded size.
+ *
+ * # Syntax
+ * ```ignore
+ * int snprintf(char *Buffer, size_t Size, const char *Format, ...)
+ * ```
+ *
+ * # Description
+ *
+ * Writes at most `size - 1` bytes to `buffer` and terminates them with a
+ * NUL byte, unless `size` is `0`. Returns the length the whole output
+ * would have, so that a result of `size` or more means it was truncated.
+ *
+ * # Safety
+ *
+ * `buffer` must be valid for `size` bytes, and `format` must be a
+ * NUL-terminated format string whose conversions match the arguments.
+ */
+int snprintf(char *buffer, uintptr_t size, const char *format, ...);
+
+/**
+ * Writes formatted output to a buffer.
+ *
+ * # Syntax
+ * ```ignore
+ * int sprintf(char *Buffer, const char *Format, ...)
+ * ```
+ *
+ * # Description
+ *
+ * Writes the output to `buffer` and terminates it with a NUL byte.
+ * Returns the number of bytes written, the NUL byte excluded. Prefer
+ * [`snprintf`], which cannot overrun `buffer`.
+ *
+ * # Safety
+ *
+ * `buffer` must be large enough for the whole output, and `format` must
+ * be a NUL-terminated format string whose conversions match the
+ * arguments.
+ */
+int sprintf(char *buffer, const char *format, ...);
+
+/**
+ * Writes a string and a newline to the standard output.
+ *
+ * # Syntax
+ * ```ignore
+ * int puts(const char *Text)
+ * ```
+ *
+ * # Description
+ *
+ * Returns a non-negative value on success, `EOF` if `text` is null.
+ *
+ * # Safety
+ *
+ * `text` must be null or NUL-terminated.
+ */
+int puts(const char *text);
+
+/**
+ * Writes a character to the standard output.
+ *
+ * # Syntax
+ * ```ignore
+ * int putchar(int Char)
+ * ```
+ *
+ * # Description
+ *
+ * Writes `character` converted to `unsigned char` and returns it. With
+ * the `Defmt` backend, every character makes a defmt message of its own.
+ */
+int putchar(int character);
+
+/**
+ * Starts a marker.
+ *
+ * # Syntax
+ * ```ignore
+ * void SEGGER_SYSVIEW_MarkStart(unsigned MarkerId)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `marker_id` (in) — Marker, any number chosen by the application.
+ *
+ * # Description
+ *
+ * SystemView shows the time from this call to the matching
+ * [`SEGGER_SYSVIEW_MarkStop`] as a measurement of the marker.
+ *
+ * # Particularities
+ *
+ * Allowed on task level and in ISRs.
+ */
+void SEGGER_SYSVIEW_MarkStart(uint32_t marker_id);
+
+/**
+ * Stops a marker.
+ *
+ * # Syntax
+ * ```ignore
+ * void SEGGER_SYSVIEW_MarkStop(unsigned MarkerId)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `marker_id` (in) — Marker started by [`SEGGER_SYSVIEW_MarkStart`].
+ *
+ * # Particularities
+ *
+ * Allowed on task level and in ISRs.
+ */
+void SEGGER_SYSVIEW_MarkStop(uint32_t marker_id);
+
+/**
+ * Sets a single point marker.
+ *
+ * # Syntax
+ * ```ignore
+ * void SEGGER_SYSVIEW_Mark(unsigned MarkerId)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `marker_id` (in) — Marker, any number chosen by the application.
+ *
+ * # Particularities
+ *
+ * Allowed on task level and in ISRs.
+ */
+void SEGGER_SYSVIEW_Mark(uint32_t marker_id);
+
+/**
+ * Names a marker.
+ *
+ * # Syntax
+ * ```ignore
+ * void SEGGER_SYSVIEW_NameMarker(unsigned MarkerId, const char* sName)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `marker_id` (in) — Marker to name.
+ * * `name` (in) — Name shown by SystemView instead of the marker number,
+ *   truncated to 128 bytes.
+ *
+ * # Particularities
+ *
+ * Allowed on task level and in ISRs. The name only reaches SystemView if
+ * the capture was running when it was sent.
+ *
+ * # Safety
+ *
+ * `name` must be NUL-terminated.
+ */
+void SEGGER_SYSVIEW_NameMarker(uint32_t marker_id, const char *name);
+
+/**
+ * Activates a task.
+ *
+ * # Syntax
+ * ```ignore
+ * StatusType ActivateTask(TaskType TaskID)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `task_id` (in) — Task reference.
+ *
+ * # Description
+ *
+ * The task `task_id` is transferred from the suspended state into the ready
+ * state. If the task is already ready or running, the activation is queued,
+ * up to the `activation` limit of its configuration.
+ *
+ * # Particularities
+ *
+ * Allowed on task level and ISR level. A rescheduling takes place after
+ * the activation if the activated task has a higher priority than the
+ * running one; when called from an ISR, it is delayed until the ISR ends.
+ *
+ * # Status
+ *
+ * * **Standard:**
+ *   * `E_OK` — No error.
+ *   * `E_OS_LIMIT` — Too many activations of `task_id`.
+ * * **Extended:**
+ *   * `E_OS_ID` — `task_id` is invalid.
+ */
+enum StatusType ActivateTask(TaskType task_id);
+
+/**
+ * Terminates the calling task.
+ *
+ * # Syntax
+ * ```ignore
+ * StatusType TerminateTask(void)
+ * ```
+ *
+ * # Description
+ *
+ * The calling task is transferred from the running state into the
+ * suspended state, or straight back to ready if further activations are
+ * queued. It is restarted from its entry point on its next dispatch.
+ *
+ * # Particularities
+ *
+ * This service does not return to the caller on success.
+ *
+ * # Status
+ *
+ * * **Extended:**
+ *   * `E_OS_RESOURCE` — Task still occupies resources.
+ *   * `E_OS_CALLEVEL` — Call at interrupt level.
+ */
+enum StatusType TerminateTask(void);
+
+/**
+ * Rescheduling point for non-preemptable tasks.
+ *
+ * # Syntax
+ * ```ignore
+ * StatusType Schedule(void)
+ * ```
+ *
+ * # Description
+ *
+ * If a task with a higher priority than the calling task's configured one
+ * is ready, the internal resource of the calling task is released and the
+ * higher-priority task runs. The caller resumes, with its internal resource
+ * taken again, once it is the highest-priority ready task. Otherwise the
+ * calling task simply continues.
+ *
+ * # Particularities
+ *
+ * Only meaningful for non-preemptable tasks and tasks with an internal
+ * resource. Allowed on task level only.
+ *
+ * # Status
+ *
+ * * **Standard:**
+ *   * `E_OK` — No error.
+ * * **Extended:**
+ *   * `E_OS_CALLEVEL` — Call at interrupt level.
+ *   * `E_OS_RESOURCE` — Calling task occupies resources.
+ */
+enum StatusType Schedule(void);
+
+/**
+ * Terminates the calling task and activates another one.
+ *
+ * # Syntax
+ * ```ignore
+ * StatusType ChainTask(TaskType TaskID)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `task_id` (in) — Reference to the sequential succeeding task to be
+ *   activated.
+ *
+ * # Description
+ *
+ * The calling task is terminated, and the task `task_id` activated, as one
+ * operation. If `task_id` is the calling task, it is activated again and
+ * queued behind the tasks ready at its priority.
+ *
+ * # Particularities
+ *
+ * Does not return to the caller if successful. On error, the caller keeps
+ * running and `task_id` is not activated. Only allowed on task level, and
+ * not while the caller occupies resources.
+ *
+ * # Status
+ *
+ * * **Standard:**
+ *   * No return to the call level.
+ *   * `E_OS_LIMIT` — Too many activations of `task_id`.
+ * * **Extended:**
+ *   * `E_OS_ID` — `task_id` is invalid.
+ *   * `E_OS_RESOURCE` — Calling task still occupies resources.
+ *   * `E_OS_CALLEVEL` — Call at interrupt level.
+ */
+enum StatusType ChainTask(TaskType task_id);
+
+/**
+ * Returns the task currently running.
+ *
+ * # Syntax
+ * ```ignore
+ * StatusType GetTaskID(TaskRefType TaskID)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `task_id` (out) — Reference to the task which is currently running.
+ *
+ * # Description
+ *
+ * Writes the task in the running state, or `INVALID_TASK` if there is
+ * none.
+ *
+ * # Particularities
+ *
+ * Allowed on task level, ISR level, in the ErrorHook and in the
+ * PreTaskHook and PostTaskHook, where it returns the task entering or
+ * leaving the CPU.
+ *
+ * # Status
+ *
+ * * **Standard:**
+ *   * `E_OK` — No error.
+ *
+ * # Safety
+ *
+ * `task_id` must be valid for a write.
+ */
+enum StatusType GetTaskID(TaskRefType task_id);
+
+/**
+ * Returns the state of a task.
+ *
+ * # Syntax
+ * ```ignore
+ * StatusType GetTaskState(TaskType TaskID, TaskStateRefType State)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `task_id` (in) — Task reference.
+ * * `state` (out) — Reference to the state of the task.
+ *
+ * # Description
+ *
+ * Writes the state of `task_id`: `RUNNING`, `READY`, `WAITING` or
+ * `SUSPENDED`.
+ *
+ * # Particularities
+ *
+ * Allowed on task level, ISR level, in the ErrorHook and in the
+ * PreTaskHook and PostTaskHook. The state may already be outdated when it
+ * is read, if the task can be preempted.
+ *
+ * # Status
+ *
+ * * **Standard:**
+ *   * `E_OK` — No error.
+ * * **Extended:**
+ *   * `E_OS_ID` — `task_id` is invalid.
+ *
+ * # Safety
+ *
+ * `state` must be valid for a write.
+ */
+enum StatusType GetTaskState(TaskType task_id, TaskStateRefType state);
+
+/**
+ * Registers a supervised entity.
+ *
+ * # Syntax
+ * ```ignore
+ * StatusType RegisterSupervisedEntity(uint32_t MinIndications,
+ *                                     uint32_t MaxIndications,
+ *                                     SupervisedEntityRefType Entity)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `min` (in) — Fewest alive indications expected per supervision cycle.
+ * * `max` (in) — Most alive indications expected per supervision cycle.
+ * * `entity` (out) — Reference to the new entity, for
+ *   `CheckpointReached`.
+ *
+ * # Description
+ *
+ * The entity is supervised from the next supervision cycle on, and stays
+ * supervised until the next reset. The calling task is recorded as its
+ * owner.
+ *
+ * # Particularities
+ *
+ * Allowed on task level and in the StartupHook.
+ *
+ * # Status
+ *
+ * * **Standard:**
+ *   * `E_OK` — No error.
+ *   * `E_OS_LIMIT` — All the entities are registered.
+ * * **Extended:**
+ *   * `E_OS_VALUE` — `max` is `0` or below `min`.
+ *
+ * # Safety
+ *
+ * `entity` must be valid for a write.
+ */
+enum StatusType RegisterSupervisedEntity(uint32_t min,
+                                         uint32_t max,
+                                         SupervisedEntityRefType entity);
+
+/**
+ * Gives an alive indication of a supervised entity.
+ *
+ * # Syntax
+ * ```ignore
+ * StatusType CheckpointReached(SupervisedEntityType Entity)
+ * ```
+ *
+ * # Parameters
+ *
+ * * `entity` (in) — Reference to the entity.
+ *
+ * # Particularities
+ *
+ * Allowed on task level and ISR level.
+ *
+ * # Status
+ *
+ * * **Standard:**
+ *   * `E_OK` — No error.
+ * * **Extended:**
+ *   * `E_OS_ID` — `entity` is invalid.
+ */
+enum StatusType CheckpointReached(SupervisedEntityType entity);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+/* Returned by GetTaskID when no task is running. */
+#define INVALID_TASK ((TaskType)UINTPTR_MAX)
+
+/*
+ * Entry points, named after the object as the configuration refers to them
+ * by symbol. An ISR(name) is found by the vector table, so its name is that
+ * of the interrupt, e.g. ISR(IO_IRQ_BANK0). TASK is below.
+ */
+#ifdef __cplusplus
+#define OS_C_LINKAGE extern "C"
+#define OS_C_EXTERN extern "C"
+#define OS_STATIC_ASSERT static_assert
+#else
+#define OS_C_LINKAGE
+#define OS_C_EXTERN extern
+#define OS_STATIC_ASSERT _Static_assert
+#endif
+#define ISR(name) OS_C_LINKAGE void name(void)
+#define ALARMCALLBACK(name) OS_C_LINKAGE void name(void)
+
+/*
+ * Declarations of objects of the configuration. Tasks are declared by their
+ * entry point and, for registered tasks, TASK_ID; the IDs of the other
+ * objects are constants of the application headers, so the macros declare
+ * nothing but take a `;`.
+ */
+#define DeclareTask(name)                                                      \
+  OS_TASK_ENTRY(name);                                                         \
+  OS_C_EXTERN TaskType OsTaskId_##name
+#define DeclareAlarm(name) typedef AlarmType DeclareAlarm_##name
+#define DeclareResource(name) typedef ResourceType DeclareResource_##name
+#define DeclareEvent(name) typedef EventMaskType DeclareEvent_##name
+
+/*
+ * Puts the string `feature` in the picotool binary info as a program
+ * feature, as os_binary_info! does in Rust: an entry of the Pico SDK layout
+ * and its address in the .bi_entries section.
+ */
+typedef struct OsBinaryInfoString {
+  uint16_t type;
+  uint16_t tag;
+  uint32_t id;
+  const char *value;
+} OsBinaryInfoString;
+#define OS_BINARY_INFO(name, feature)                                          \
+  static const OsBinaryInfoString OsBinaryInfo_##name = {                      \
+      6 /* ID and string */, 0x5052 /* "RP" */, 0xa1f4b453 /* feature */,      \
+      (feature)};                                                              \
+  __attribute__((used, section(".bi_entries")))                                \
+  static const OsBinaryInfoString *const OsBinaryInfoEntry_##name =            \
+      &OsBinaryInfo_##name
+
+/*
+ * TASK(name) is the entry point of a task of OsConfig::tasks.
+ *
+ * TASK(name, priority, stack_size) and TASK(name, priority, stack_size,
+ * flags) define task `name` and register it at link time, without a
+ * TaskConfig: its priority, stack size in bytes and TASK_* flags. StartOS
+ * gives registered tasks the IDs after those of OsConfig::tasks, in name
+ * order, and TASK_ID(name) is the ID of one, INVALID_TASK before StartOS.
+ * Two tasks with the same name fail to link. The name and priority go in
+ * the picotool binary info.
+ *
+ *   TASK(Sampler, 2, 1024, TASK_AUTOSTART) { ... }
+ *   ActivateTask(TASK_ID(Sampler));
+ */
+#define TASK(...)                                                              \
+  OS_TASK_SELECT(__VA_ARGS__, OS_TASK, OS_TASK_DEFAULT, OS_TASK_ARGUMENTS,     \
+                 OS_TASK_ENTRY, )                                              \
+  (__VA_ARGS__)
+#define OS_TASK_SELECT(_1, _2, _3, _4, macro, ...) macro
+#define OS_TASK_ARGUMENTS(...)                                                 \
+  OS_STATIC_ASSERT(0, "TASK takes a name, or a name, a priority, a stack "     \
+                      "size and optional flags")
+#define OS_TASK_ENTRY(name) OS_C_LINKAGE void name(void)
+#define OS_TASK_DEFAULT(name, priority, stack_size)                            \
+  OS_TASK(name, priority, stack_size, 0)
+#define OS_TASK(name, priority, stack_size, flags)                             \
+  OS_TASK_ENTRY(name);                                                         \
+  OS_C_EXTERN TaskType OsTaskId_##name;                                        \
+  TaskType OsTaskId_##name = INVALID_TASK;                                     \
+  static uint64_t OsTaskStack_##name[((stack_size) + 7) / 8];                  \
+  __attribute__((used, section(".os_tasks." #name)))                           \
+  static const TaskDescriptorType OsTask_##name = {                            \
+      &OsTaskId_##name, #name, name, (priority), 1, (flags),                   \
+      (uint8_t *)OsTaskStack_##name, sizeof(OsTaskStack_##name)};              \
+  OS_BINARY_INFO(name, "task " #name ", priority " #priority);                 \
+  OS_TASK_ENTRY(name)
+#define TASK_ID(name) OsTaskId_##name
+
+#if defined(__arm__)
+#ifdef __cplusplus
+#define OS_ABI_CHECK(type, size, align)                                        \
+  static_assert(sizeof(type) == (size) && alignof(type) == (align),            \
+                #type " does not match the kernel ABI")
+#define OS_ABI_OFFSET(type, field, offset)                                     \
+  static_assert(offsetof(type, field) == (offset),                             \
+                #type "." #field " does not match the kernel ABI")
+#else
+#define OS_ABI_CHECK(type, size, align)                                        \
+  _Static_assert(sizeof(type) == (size) && _Alignof(type) == (align),          \
+                 #type " does not match the kernel ABI")
+#define OS_ABI_OFFSET(type, field, offset)                                     \
+  _Static_assert(offsetof(type, field) == (offset),                            \
+                 #type "." #field " does not match the kernel ABI")
+#endif
+
+/* Enums must be compiled with -fshort-enums, the default of arm-none-eabi */
+OS_ABI_CHECK(StatusType, ABI_SIZE_STATUS_TYPE, 1);
+OS_ABI_CHECK(TaskStateType, ABI_SIZE_TASK_STATE_TYPE, 1);
+OS_ABI_CHECK(ResetReasonType, ABI_SIZE_RESET_REASON_TYPE, 1);
+
+OS_ABI_CHECK(AlarmBaseType, ABI_SIZE_ALARM_BASE_TYPE, ABI_ALIGN_ALARM_BASE_TYPE);
+OS_ABI_CHECK(CrashRecordType, ABI_SIZE_CRASH_RECORD_TYPE, ABI_ALIGN_CRASH_RECORD_TYPE);
+OS_ABI_CHECK(PoolInfoType, ABI_SIZE_POOL_INFO_TYPE, ABI_ALIGN_POOL_INFO_TYPE);
+OS_ABI_CHECK(ResetInfoType, ABI_SIZE_RESET_INFO_TYPE, ABI_ALIGN_RESET_INFO_TYPE);
+OS_ABI_CHECK(TaskStatisticsType, ABI_SIZE_TASK_STATISTICS_TYPE,
+             ABI_ALIGN_TASK_STATISTICS_TYPE);
+OS_ABI_CHECK(IsrStatisticsType, ABI_SIZE_ISR_STATISTICS_TYPE,
+             ABI_ALIGN_ISR_STATISTICS_TYPE);
+OS_ABI_CHECK(SystemStatisticsType, ABI_SIZE_SYSTEM_STATISTICS_TYPE,
+             ABI_ALIGN_SYSTEM_STATISTICS_TYPE);
+OS_ABI_CHECK(TaskDescriptorType, ABI_SIZE_TASK_DESCRIPTOR_TYPE,
+             ABI_ALIGN_TASK_DESCRIPTOR_TYPE);
+
+OS_ABI_OFFSET(AlarmBaseType, maxallowedvalue, ABI_OFFSET_ALARM_BASE_TYPE_MAXALLOWEDVALUE);
+OS_ABI_OFFSET(AlarmBaseType, ticksperbase, ABI_OFFSET_ALARM_BASE_TYPE_TICKSPERBASE);
+OS_ABI_OFFSET(AlarmBaseType, mincycle, ABI_OFFSET_ALARM_BASE_TYPE_MINCYCLE);
+
+OS_ABI_OFFSET(CrashRecordType, exception, ABI_OFFSET_CRASH_RECORD_TYPE_EXCEPTION);
+OS_ABI_OFFSET(CrashRecordType, r0, ABI_OFFSET_CRASH_RECORD_TYPE_R0);
+OS_ABI_OFFSET(CrashRecordType, r1, ABI_OFFSET_CRASH_RECORD_TYPE_R1);
+OS_ABI_OFFSET(CrashRecordType, r2, ABI_OFFSET_CRASH_RECORD_TYPE_R2);
+OS_ABI_OFFSET(CrashRecordType, r3, ABI_OFFSET_CRASH_RECORD_TYPE_R3);
+OS_ABI_OFFSET(CrashRecordType, r12, ABI_OFFSET_CRASH_RECORD_TYPE_R12);
+OS_ABI_OFFSET(CrashRecordType, lr, ABI_OFFSET_CRASH_RECORD_TYPE_LR);
+OS_ABI_OFFSET(CrashRecordType, pc, ABI_OFFSET_CRASH_RECORD_TYPE_PC);
+OS_ABI_OFFSET(CrashRecordType, xpsr, ABI_OFFSET_CRASH_RECORD_TYPE_XPSR);
+OS_ABI_OFFSET(CrashRecordType, sp, ABI_OFFSET_CRASH_RECORD_TYPE_SP);
+OS_ABI_OFFSET(CrashRecordType, exc_return, ABI_OFFSET_CRASH_RECORD_TYPE_EXC_RETURN);
+OS_ABI_OFFSET(CrashRecordType, cfsr, ABI_OFFSET_CRASH_RECORD_TYPE_CFSR);
+OS_ABI_OFFSET(CrashRecordType, hfsr, ABI_OFFSET_CRASH_RECORD_TYPE_HFSR);
+OS_ABI_OFFSET(CrashRecordType, mmfar, ABI_OFFSET_CRASH_RECORD_TYPE_MMFAR);
+OS_ABI_OFFSET(CrashRecordType, bfar, ABI_OFFSET_CRASH_RECORD_TYPE_BFAR);
+OS_ABI_OFFSET(CrashRecordType, sfsr, ABI_OFFSET_CRASH_RECORD_TYPE_SFSR);
+OS_ABI_OFFSET(CrashRecordType, sfar, ABI_OFFSET_CRASH_RECORD_TYPE_SFAR);
+OS_ABI_OFFSET(CrashRecordType, task, ABI_OFFSET_CRASH_RECORD_TYPE_TASK);
+OS_ABI_OFFSET(CrashRecordType, isr, ABI_OFFSET_CRASH_RECORD_TYPE_ISR);
+OS_ABI_OFFSET(CrashRecordType, ticks, ABI_OFFSET_CRASH_RECORD_TYPE_TICKS);
+
+OS_ABI_OFFSET(PoolInfoType, block_size, ABI_OFFSET_POOL_INFO_TYPE_BLOCK_SIZE);
+OS_ABI_OFFSET(PoolInfoType, blocks, ABI_OFFSET_POOL_INFO_TYPE_BLOCKS);
+OS_ABI_OFFSET(PoolInfoType, free, ABI_OFFSET_POOL_INFO_TYPE_FREE);
+OS_ABI_OFFSET(PoolInfoType, min_free, ABI_OFFSET_POOL_INFO_TYPE_MIN_FREE);
+OS_ABI_OFFSET(PoolInfoType, failures, ABI_OFFSET_POOL_INFO_TYPE_FAILURES);
+
+OS_ABI_OFFSET(ResetInfoType, reason, ABI_OFFSET_RESET_INFO_TYPE_REASON);
+OS_ABI_OFFSET(ResetInfoType, chip_reset, ABI_OFFSET_RESET_INFO_TYPE_CHIP_RESET);
+OS_ABI_OFFSET(ResetInfoType, watchdog_reason, ABI_OFFSET_RESET_INFO_TYPE_WATCHDOG_REASON);
+OS_ABI_OFFSET(ResetInfoType, scratch, ABI_OFFSET_RESET_INFO_TYPE_SCRATCH);
+
+OS_ABI_OFFSET(TaskDescriptorType, id, ABI_OFFSET_TASK_DESCRIPTOR_TYPE_ID);
+OS_ABI_OFFSET(TaskDescriptorType, name, ABI_OFFSET_TASK_DESCRIPTOR_TYPE_NAME);
+OS_ABI_OFFSET(TaskDescriptorType, entry, ABI_OFFSET_TASK_DESCRIPTOR_TYPE_ENTRY);
+OS_ABI_OFFSET(TaskDescriptorType, priority, ABI_OFFSET_TASK_DESCRIPTOR_TYPE_PRIORITY);
+OS_ABI_OFFSET(TaskDescriptorType, activation, ABI_OFFSET_TASK_DESCRIPTOR_TYPE_ACTIVATION);
+OS_ABI_OFFSET(TaskDescriptorType, flags, ABI_OFFSET_TASK_DESCRIPTOR_TYPE_FLAGS);
+OS_ABI_OFFSET(TaskDescriptorType, stack, ABI_OFFSET_TASK_DESCRIPTOR_TYPE_STACK);
+OS_ABI_OFFSET(TaskDescriptorType, stack_size, ABI_OFFSET_TASK_DESCRIPTOR_TYPE_STACK_SIZE);
+
+OS_ABI_OFFSET(TaskStatisticsType, execution_time, ABI_OFFSET_TASK_STATISTICS_TYPE_EXECUTION_TIME);
+OS_ABI_OFFSET(TaskStatisticsType, response_time_max, ABI_OFFSET_TASK_STATISTICS_TYPE_RESPONSE_TIME_MAX);
+OS_ABI_OFFSET(TaskStatisticsType, activations, ABI_OFFSET_TASK_STATISTICS_TYPE_ACTIVATIONS);
+OS_ABI_OFFSET(TaskStatisticsType, preemptions, ABI_OFFSET_TASK_STATISTICS_TYPE_PREEMPTIONS);
+OS_ABI_OFFSET(TaskStatisticsType, overruns, ABI_OFFSET_TASK_STATISTICS_TYPE_OVERRUNS);
+OS_ABI_OFFSET(TaskStatisticsType, deadline_misses, ABI_OFFSET_TASK_STATISTICS_TYPE_DEADLINE_MISSES);
+
+OS_ABI_OFFSET(IsrStatisticsType, execution_time, ABI_OFFSET_ISR_STATISTICS_TYPE_EXECUTION_TIME);
+OS_ABI_OFFSET(IsrStatisticsType, duration_max, ABI_OFFSET_ISR_STATISTICS_TYPE_DURATION_MAX);
+OS_ABI_OFFSET(IsrStatisticsType, activations, ABI_OFFSET_ISR_STATISTICS_TYPE_ACTIVATIONS);
+
+OS_ABI_OFFSET(SystemStatisticsType, elapsed_time, ABI_OFFSET_SYSTEM_STATISTICS_TYPE_ELAPSED_TIME);
+OS_ABI_OFFSET(SystemStatisticsType, idle_time, ABI_OFFSET_SYSTEM_STATISTICS_TYPE_IDLE_TIME);
+
+#undef OS_ABI_OFFSET
+#undef OS_ABI_CHECK
+#endif
+
+#endif /* OS_H */
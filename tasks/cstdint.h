@@ -1,9 +0,0 @@
-#ifndef STDINT_H_
-#define STDINT_H_
-
-typedef unsigned int   uint32_t;
-typedef signed int     int32_t;
-typedef unsigned long  uintptr_t;
-
-
-#endif // STDINT_H_
@@ -0,0 +1,15 @@
+/*
+ * Boolean type for the C tasks. `bool` is one byte, as Rust's.
+ */
+#ifndef STDBOOL_H_
+#define STDBOOL_H_
+
+#ifndef __cplusplus
+#define bool _Bool
+#define true 1
+#define false 0
+#endif
+
+#define __bool_true_false_are_defined 1
+
+#endif // STDBOOL_H_
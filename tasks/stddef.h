@@ -0,0 +1,24 @@
+/*
+ * Common definitions for the C tasks, with the types of the compiler
+ * target as in stdint.h.
+ */
+#ifndef STDDEF_H_
+#define STDDEF_H_
+
+typedef __SIZE_TYPE__ size_t;
+typedef __PTRDIFF_TYPE__ ptrdiff_t;
+#ifndef __cplusplus
+typedef __WCHAR_TYPE__ wchar_t;
+#endif
+
+#ifndef NULL
+#ifdef __cplusplus
+#define NULL 0
+#else
+#define NULL ((void *)0)
+#endif
+#endif
+
+#define offsetof(type, member) __builtin_offsetof(type, member)
+
+#endif // STDDEF_H_
@@ -0,0 +1,26 @@
+/*
+ * The stdlib.h subset of the C tasks, implemented in Rust by
+ * rtos_core/src/libc.rs. There is no malloc: tasks use static memory.
+ */
+#ifndef STDLIB_H_
+#define STDLIB_H_
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+int abs(int n);
+long labs(long n);
+int atoi(const char *s);
+long atol(const char *s);
+long strtol(const char *s, char **end, int base);
+void qsort(void *base, size_t count, size_t size,
+           int (*compare)(const void *, const void *));
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // STDLIB_H_
@@ -0,0 +1,37 @@
+/*
+ * String and memory functions for the C tasks, implemented in Rust.
+ *
+ * memcpy, memmove, memset, memcmp and strlen come from compiler_builtins,
+ * the others from rtos_core/src/libc.rs. All of them may be called on task
+ * level and ISR level.
+ */
+#ifndef STRING_H_
+#define STRING_H_
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+void *memcpy(void *dst, const void *src, size_t n);
+void *memmove(void *dst, const void *src, size_t n);
+void *memset(void *s, int c, size_t n);
+int memcmp(const void *s1, const void *s2, size_t n);
+void *memchr(const void *s, int c, size_t n);
+
+size_t strlen(const char *s);
+size_t strnlen(const char *s, size_t max);
+int strcmp(const char *s1, const char *s2);
+int strncmp(const char *s1, const char *s2, size_t n);
+char *strcpy(char *dst, const char *src);
+char *strncpy(char *dst, const char *src, size_t n);
+char *strcat(char *dst, const char *src);
+char *strchr(const char *s, int c);
+char *strrchr(const char *s, int c);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif // STRING_H_
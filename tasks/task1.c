@@ -1,6 +1,6 @@
 #include "task1.h"
 
-void Task1(void){
+TASK(Task1, 1, 2048, TASK_AUTOSTART){
     printf("Hello World! %d" , 69);
     TerminateTask();
 }
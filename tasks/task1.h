@@ -1,7 +1,9 @@
 #ifndef TASK1_H_
 #define TASK1_H_
 
-#include "rtos_core.h"
+#include "Os.h"
 
+/* Registered by TASK in task1.c; other tasks activate it with TASK_ID(Task1) */
+DeclareTask(Task1);
 
 #endif // TASK1_H_
@@ -0,0 +1,367 @@
+//! Converts a dump of the `rtos_core` kernel trace (the `OS_TRACE` symbol,
+//! see `rtos_core/src/trace.rs`) to the Chrome trace event format, which
+//! Perfetto and `chrome://tracing` open as a timeline.
+//!
+//! Usage:
+//!
+//! ```text
+//! os-trace <dump.bin> [-o <trace.json>] [--tasks <name,...>] [--resources <name,...>]
+//! ```
+//!
+//! The dump may be the buffer alone or a larger RAM image containing it.
+//! Task and resource names are given in ID order; IDs are shown otherwise.
+
+use std::{env, fmt::Write as _, fs, process::ExitCode};
+
+/// `"OSTR"` in little endian, as written by the kernel.
+const TRACE_MAGIC: u32 = 0x5254_534f;
+const HEADER_SIZE: usize = 16;
+const RECORD_SIZE: usize = 12;
+
+/// Thread IDs of the tracks that are not tasks.
+const ALARM_TRACK: u32 = 900;
+const ISR_TRACK: u32 = 1000;
+
+struct Record {
+    timestamp: u32,
+    kind: u8,
+    object: u8,
+    data: u32,
+}
+
+struct Options {
+    input: String,
+    output: Option<String>,
+    tasks: Vec<String>,
+    resources: Vec<String>,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut args = env::args().skip(1);
+    let mut options = Options {
+        input: String::new(),
+        output: None,
+        tasks: Vec::new(),
+        resources: Vec::new(),
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {arg}"));
+        match arg.as_str() {
+            "-o" | "--output" => options.output = Some(value()?),
+            "--tasks" => options.tasks = value()?.split(',').map(str::to_owned).collect(),
+            "--resources" => options.resources = value()?.split(',').map(str::to_owned).collect(),
+            _ if options.input.is_empty() && !arg.starts_with('-') => options.input = arg,
+            _ => return Err(format!("unexpected argument {arg}")),
+        }
+    }
+    if options.input.is_empty() {
+        return Err("no input dump given".into());
+    }
+    Ok(options)
+}
+
+fn word(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+/// Finds the trace buffer in `dump` and returns its records, oldest first.
+fn read_records(dump: &[u8]) -> Result<Vec<Record>, String> {
+    let start = (0..dump.len().saturating_sub(HEADER_SIZE - 1))
+        .step_by(4)
+        .find(|&offset| word(dump, offset) == TRACE_MAGIC)
+        .ok_or("no trace buffer in the dump")?;
+    let capacity = word(dump, start + 4) as usize;
+    let record_size = word(dump, start + 8) as usize;
+    let written = word(dump, start + 12) as usize;
+    if record_size != RECORD_SIZE {
+        return Err(format!("unsupported record size {record_size}"));
+    }
+    let records = &dump[start + HEADER_SIZE..];
+    if capacity == 0 || records.len() < capacity * RECORD_SIZE {
+        return Err("the dump ends before the trace buffer".into());
+    }
+
+    // Once the buffer wrapped, the oldest record follows the newest one
+    let (first, count) = if written > capacity {
+        (written % capacity, capacity)
+    } else {
+        (0, written)
+    };
+    Ok((0..count)
+        .map(|i| {
+            let offset = (first + i) % capacity * RECORD_SIZE;
+            Record {
+                timestamp: word(records, offset),
+                kind: records[offset + 4],
+                object: records[offset + 5],
+                data: word(records, offset + 8),
+            }
+        })
+        .filter(|record| record.kind != 0)
+        .collect())
+}
+
+fn name(names: &[String], prefix: &str, id: u8) -> String {
+    names
+        .get(id as usize)
+        .cloned()
+        .unwrap_or_else(|| format!("{prefix} {id}"))
+}
+
+/// Writes one trace event. `extra` holds additional members, each starting
+/// with a comma.
+fn event(json: &mut String, ph: &str, name: &str, tid: u32, ts: u64, extra: &str) {
+    if !json.ends_with('[') {
+        json.push(',');
+    }
+    let _ = write!(
+        json,
+        "\n{{\"name\":{},\"ph\":\"{ph}\",\"pid\":1,\"tid\":{tid},\"ts\":{ts}{extra}}}",
+        quote(name)
+    );
+}
+
+fn instant(json: &mut String, name: &str, tid: u32, ts: u64) {
+    event(json, "i", name, tid, ts, ",\"s\":\"t\"");
+}
+
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(quoted, "\\u{:04x}", c as u32);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn to_json(records: &[Record], options: &Options) -> String {
+    let task = |id: u8| name(&options.tasks, "Task", id);
+    let resource = |id: u8| name(&options.resources, "Resource", id);
+    let mut json = String::from("{\"traceEvents\":[");
+    let mut tracks = Vec::new();
+
+    // Timestamps are 32-bit microseconds, unwrap them
+    let mut high = 0u64;
+    let mut last = 0u32;
+    for record in records {
+        if record.timestamp < last {
+            high += 1 << 32;
+        }
+        last = record.timestamp;
+        let ts = high + record.timestamp as u64;
+        let object = record.object;
+        let tid = object as u32;
+        match record.kind {
+            1..=6 | 12 | 14 | 15 => tracks.push((tid, task(object))),
+            7 | 8 => tracks.push((record.data, task(record.data as u8))),
+            _ => {}
+        }
+
+        match record.kind {
+            1 => instant(&mut json, "ActivateTask", tid, ts),
+            2 => event(&mut json, "B", &task(object), tid, ts, ""),
+            3 => {
+                event(&mut json, "E", &task(object), tid, ts, "");
+                instant(&mut json, "Preempted", tid, ts);
+            }
+            4 => {
+                event(&mut json, "E", &task(object), tid, ts, "");
+                instant(&mut json, "TerminateTask", tid, ts);
+            }
+            5 => {
+                event(&mut json, "E", &task(object), tid, ts, "");
+                instant(&mut json, &format!("WaitEvent {:#x}", record.data), tid, ts);
+            }
+            6 => instant(&mut json, &format!("SetEvent {:#x}", record.data), tid, ts),
+            7 | 8 => {
+                let service = if record.kind == 7 {
+                    "GetResource"
+                } else {
+                    "ReleaseResource"
+                };
+                let label = format!("{service} {}", resource(object));
+                instant(&mut json, &label, record.data, ts);
+            }
+            9 => {
+                instant(&mut json, &format!("Alarm {object}"), ALARM_TRACK, ts);
+                tracks.push((ALARM_TRACK, "Alarms".into()));
+            }
+            10 | 11 => {
+                let isr = format!("ISR {object}");
+                let ph = if record.kind == 10 { "B" } else { "E" };
+                event(&mut json, ph, &isr, ISR_TRACK + tid, ts, "");
+                tracks.push((ISR_TRACK + tid, isr));
+            }
+            12 => instant(&mut json, "Released", tid, ts),
+            // Idle shows as the gaps between task slices
+            13 => {}
+            14 => instant(&mut json, "ActivationOverrun", tid, ts),
+            15 => {
+                let label = format!("DeadlineMiss +{} ticks", record.data);
+                instant(&mut json, &label, tid, ts);
+            }
+            kind => eprintln!("skipping record of unknown kind {kind}"),
+        }
+    }
+
+    tracks.sort();
+    tracks.dedup();
+    for (tid, track) in tracks {
+        let args = format!(",\"args\":{{\"name\":{}}}", quote(&track));
+        event(&mut json, "M", "thread_name", tid, 0, &args);
+    }
+    json.push_str("\n]}\n");
+    json
+}
+
+fn run() -> Result<(), String> {
+    let options = parse_args()?;
+    let dump = fs::read(&options.input).map_err(|e| format!("{}: {e}", options.input))?;
+    let records = read_records(&dump)?;
+    let json = to_json(&records, &options);
+    match &options.output {
+        Some(path) => fs::write(path, json).map_err(|e| format!("{path}: {e}"))?,
+        None => print!("{json}"),
+    }
+    eprintln!("{} records converted", records.len());
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("os-trace: {error}");
+            eprintln!(
+                "usage: os-trace <dump.bin> [-o <trace.json>] [--tasks <name,...>] [--resources <name,...>]"
+            );
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A trace buffer of `capacity` records, `written` of them written so
+    /// far, holding `records` from its first slot on.
+    fn buffer(capacity: u32, written: u32, records: &[(u32, u8, u8, u32)]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for word in [TRACE_MAGIC, capacity, RECORD_SIZE as u32, written] {
+            bytes.extend(word.to_le_bytes());
+        }
+        for &(timestamp, kind, object, data) in records {
+            bytes.extend(timestamp.to_le_bytes());
+            bytes.extend([kind, object, 0, 0]);
+            bytes.extend(data.to_le_bytes());
+        }
+        bytes.resize(HEADER_SIZE + capacity as usize * RECORD_SIZE, 0);
+        bytes
+    }
+
+    fn timestamps(records: &[Record]) -> Vec<u32> {
+        records.iter().map(|record| record.timestamp).collect()
+    }
+
+    fn options(tasks: &[&str]) -> Options {
+        Options {
+            input: String::new(),
+            output: None,
+            tasks: tasks.iter().map(|&task| task.to_owned()).collect(),
+            resources: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn finds_the_buffer_in_a_ram_image() {
+        let mut dump = vec![0xaa; 8];
+        dump.extend(buffer(4, 2, &[(10, 2, 1, 0), (20, 4, 1, 7)]));
+        dump.extend([0xbb; 12]);
+        let records = read_records(&dump).unwrap();
+        assert_eq!(timestamps(&records), [10, 20]);
+        assert_eq!(
+            (records[1].kind, records[1].object, records[1].data),
+            (4, 1, 7)
+        );
+    }
+
+    #[test]
+    fn rejects_a_dump_without_magic() {
+        let mut dump = buffer(2, 0, &[]);
+        dump[0] ^= 1;
+        assert_eq!(
+            read_records(&dump).err().unwrap(),
+            "no trace buffer in the dump"
+        );
+    }
+
+    #[test]
+    fn rejects_another_record_size() {
+        let mut dump = buffer(2, 0, &[]);
+        dump[8] = 16;
+        assert_eq!(
+            read_records(&dump).err().unwrap(),
+            "unsupported record size 16"
+        );
+    }
+
+    #[test]
+    fn rejects_a_truncated_buffer() {
+        let mut dump = buffer(4, 0, &[]);
+        dump.truncate(HEADER_SIZE + 3 * RECORD_SIZE);
+        assert_eq!(
+            read_records(&dump).err().unwrap(),
+            "the dump ends before the trace buffer"
+        );
+    }
+
+    #[test]
+    fn reads_a_wrapped_buffer_oldest_first() {
+        // Records 3 and 4 overwrote records 0 and 1
+        let dump = buffer(3, 5, &[(3, 1, 0, 0), (4, 1, 0, 0), (2, 1, 0, 0)]);
+        assert_eq!(timestamps(&read_records(&dump).unwrap()), [2, 3, 4]);
+        let dump = buffer(3, 6, &[(3, 1, 0, 0), (4, 1, 0, 0), (5, 1, 0, 0)]);
+        assert_eq!(timestamps(&read_records(&dump).unwrap()), [3, 4, 5]);
+    }
+
+    #[test]
+    fn writes_chrome_trace_events() {
+        let dump = buffer(4, 3, &[(10, 2, 0, 0), (20, 4, 0, 0), (30, 15, 1, 2)]);
+        let records = read_records(&dump).unwrap();
+        let json = to_json(&records, &options(&["Logger"]));
+        assert_eq!(
+            json,
+            concat!(
+                "{\"traceEvents\":[",
+                "\n{\"name\":\"Logger\",\"ph\":\"B\",\"pid\":1,\"tid\":0,\"ts\":10},",
+                "\n{\"name\":\"Logger\",\"ph\":\"E\",\"pid\":1,\"tid\":0,\"ts\":20},",
+                "\n{\"name\":\"TerminateTask\",\"ph\":\"i\",\"pid\":1,\"tid\":0,\"ts\":20,\"s\":\"t\"},",
+                "\n{\"name\":\"DeadlineMiss +2 ticks\",\"ph\":\"i\",\"pid\":1,\"tid\":1,\"ts\":30,\"s\":\"t\"},",
+                "\n{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":1,\"tid\":0,\"ts\":0,\"args\":{\"name\":\"Logger\"}},",
+                "\n{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":1,\"tid\":1,\"ts\":0,\"args\":{\"name\":\"Task 1\"}}",
+                "\n]}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn unwraps_the_timestamps() {
+        let dump = buffer(2, 2, &[(0xffff_fff0, 1, 0, 0), (0x10, 1, 0, 0)]);
+        let json = to_json(&read_records(&dump).unwrap(), &options(&[]));
+        assert!(json.contains("\"ts\":4294967280,"));
+        assert!(json.contains("\"ts\":4294967312,"));
+    }
+
+    #[test]
+    fn quotes_names() {
+        assert_eq!(quote("a\"b\\c\n"), "\"a\\\"b\\\\c\\u000a\"");
+    }
+}